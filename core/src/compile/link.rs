@@ -1,6 +1,7 @@
 use synth_gen::prelude::{Generator, GeneratorState};
 
 use std::cell::RefCell;
+use std::collections::BTreeSet;
 use std::iter::IntoIterator;
 use std::ops::Range;
 use std::rc::Rc;
@@ -221,10 +222,69 @@ where
 /// [`Recorder`](Recorder).
 pub struct TapeView<Y, R>(pub(super) TapeViewImpl<Y, R>);
 
+impl<Y, R> TapeView<Y, R> {
+    /// Reads the values recorded into the slice as a whole instead of replaying them.
+    pub fn into_values(self) -> TapeValues<Y, R> {
+        TapeValues {
+            slice: self.0.slice,
+            read: 0,
+            next_start: 0,
+            starts: Vec::new(),
+            values: Vec::new(),
+        }
+    }
+}
+
+/// A reader of the values recorded into the buffer managed by a [`Recorder`](Recorder), for
+/// nodes that need random access to them rather than replaying them in order like a
+/// [`TapeView`](TapeView) does.
+pub struct TapeValues<Y, R> {
+    slice: SliceRef<Y, R>,
+    read: usize,
+    next_start: usize,
+    /// The index into the buffer of the first state of each value.
+    starts: Vec<usize>,
+    values: Vec<R>,
+}
+
+impl<Y, R> TapeValues<Y, R>
+where
+    R: Clone,
+{
+    fn sync(&mut self) {
+        let tape = (*self.slice.tape).borrow();
+        while let Some(state) = tape.get(self.read) {
+            self.read += 1;
+            if let GeneratorState::Complete(value) = state {
+                self.starts.push(self.next_start);
+                self.values.push(value.clone());
+                self.next_start = self.read;
+            }
+        }
+    }
+
+    /// Every value recorded so far, oldest first.
+    pub fn all(&mut self) -> &[R] {
+        self.sync();
+        &self.values
+    }
+
+    /// The values recorded since the slice was last reset, that is the values generated by the
+    /// current generation of the [`Ordered`](Ordered) node the slice is scoped to.
+    pub fn current(&mut self) -> &[R] {
+        self.sync();
+        let start = (*self.slice.tape).borrow().new_range(self.slice.index).start;
+        let first = self.starts.partition_point(|value_start| *value_start < start);
+        &self.values[first..]
+    }
+}
+
 pub(super) struct OrderedImpl<G, Y, R> {
     is_complete: bool,
-    scope: Vec<SliceRef<Y, R>>,
+    scope: Vec<(String, SliceRef<Y, R>)>,
     children: Vec<(String, Recorder<G, Y, R>)>,
+    targets: BTreeSet<String>,
+    frozen: BTreeSet<String>,
     src: G,
 }
 
@@ -237,10 +297,17 @@ where
 
     fn next<RR: Rng>(&mut self, rng: &mut RR) -> GeneratorState<Self::Yield, Self::Return> {
         if !self.is_complete {
-            self.scope.iter_mut().for_each(|slice| slice.reset());
-            self.children.iter_mut().for_each(|(_, recorder)| {
-                recorder.complete(rng);
-            });
+            let frozen = &self.frozen;
+            self.scope
+                .iter_mut()
+                .filter(|(child, _)| !frozen.contains(child))
+                .for_each(|(_, slice)| slice.reset());
+            self.children
+                .iter_mut()
+                .filter(|(child, _)| !frozen.contains(child))
+                .for_each(|(_, recorder)| {
+                    recorder.complete(rng);
+                });
             self.is_complete = true;
         }
         let state = self.src.next(rng);
@@ -260,15 +327,20 @@ where
 pub struct Ordered<G, Y, R>(pub(super) OrderedImpl<G, Y, R>);
 
 impl<G, Y, R> Ordered<G, Y, R> {
-    pub(super) fn new<S, C>(scope: S, children: C, src: G) -> Self
+    /// Each slice of `scope` is paired with the name of the child it records values of. The
+    /// children named in `targets` are those referred to by other children.
+    pub(super) fn new<S, C, T>(scope: S, children: C, targets: T, src: G) -> Self
     where
-        S: IntoIterator<Item = SliceRef<Y, R>>,
+        S: IntoIterator<Item = (String, SliceRef<Y, R>)>,
         C: IntoIterator<Item = (String, Recorder<G, Y, R>)>,
+        T: IntoIterator<Item = String>,
     {
         Self(OrderedImpl {
             is_complete: false,
             scope: scope.into_iter().collect(),
             children: children.into_iter().collect(),
+            targets: targets.into_iter().collect(),
+            frozen: BTreeSet::new(),
             src,
         })
     }
@@ -319,6 +391,22 @@ impl<G, Y, R> Link<G, Y, R> {
             _ => None,
         }
     }
+
+    /// Stops generating new values for the child `name` of an ordered node: from then on, the
+    /// nodes referring to it only ever see the values it generated last.
+    pub fn freeze(&mut self, name: &str) {
+        if let Self::Ordered(Ordered(OrderedImpl { frozen, .. })) = self {
+            frozen.insert(name.to_string());
+        }
+    }
+
+    /// Whether the child `name` of an ordered node is referred to by any of its other children.
+    pub fn is_target(&self, name: &str) -> bool {
+        match self {
+            Self::Ordered(Ordered(OrderedImpl { targets, .. })) => targets.contains(name),
+            _ => false,
+        }
+    }
 }
 
 /// A trait for types that can own an inner [`Link`](Link).
@@ -33,7 +33,7 @@ pub mod address;
 pub use address::Address;
 
 pub mod link;
-pub use link::{FromLink, Link};
+pub use link::{FromLink, Link, TapeValues};
 use link::{GeneratorRecorder, GeneratorSliceRef, Ordered, Recorder};
 
 use crate::graph::Graph;
@@ -50,6 +50,12 @@ pub trait Compiler<'a> {
 
     /// Access the built value of the node at address `field`.
     fn get<S: Into<Address>>(&mut self, field: S) -> Result<Graph>;
+
+    /// Access the content of the node at address `field` as it is written in the schema, if this
+    /// compiler knows about it.
+    fn content<S: Into<Address>>(&self, _field: S) -> Option<&'a Content> {
+        None
+    }
 }
 
 pub trait Compile {
@@ -78,7 +84,9 @@ impl<'a> NamespaceCompiler<'a> {
     }
 
     pub fn compile(mut self) -> Result<Graph> {
+        let root = *self.state.source();
         let crawler = Crawler {
+            root,
             state: &mut self.state,
             symbols: &mut self.vtable,
             position: Address::new_root(),
@@ -105,6 +113,9 @@ impl<'a> NamespaceCompiler<'a> {
                         anyhow!("undefined reference to `{}` from `{}`", target, address)
                     })?;
                     if !is_built {
+                        self.state.project(target.clone()).with_context(|| {
+                            anyhow!("undefined reference to `{}` from `{}`", target, address)
+                        })?;
                         debug!("direct reference to `{}`", target);
                         targets.insert(target.clone());
                     }
@@ -183,6 +194,7 @@ impl<'a> NamespaceCompiler<'a> {
             let vtable = &mut self.vtable;
             let mut children = BTreeMap::new();
             let content_compiler = ContentCompiler {
+                root,
                 scope: address.clone(),
                 state,
                 children: &mut children,
@@ -195,21 +207,34 @@ impl<'a> NamespaceCompiler<'a> {
 
             if let Some(local_table) = vtable.get(&address) {
                 // `node` must be wrapped in `Ordered`
+                // Local addresses start with the name of the child they are found in
                 let mut scope = local_table
-                    .values()
-                    .map(|factory| factory.get_source().unwrap())
+                    .iter()
+                    .map(|(local, factory)| {
+                        let child = local.iter().next().unwrap_or_default().to_string();
+                        (child, factory.get_source().unwrap())
+                    })
+                    .collect::<Vec<_>>();
+                let targets = scope
+                    .iter()
+                    .map(|(child, _)| child.clone())
                     .collect::<Vec<_>>();
                 let mut ordered_children = Vec::new();
                 for child in state.scope().iter_ordered() {
                     let (recorder, slice_ref) = children.remove(child).unwrap();
-                    scope.push(slice_ref);
+                    scope.push((child.to_string(), slice_ref));
                     ordered_children.push((child.to_string(), recorder));
                 }
                 for (child, (recorder, slice_ref)) in children.into_iter() {
-                    scope.push(slice_ref);
+                    scope.push((child.clone(), slice_ref));
                     ordered_children.push((child, recorder));
                 }
-                node = Graph::from_link(Link::Ordered(Ordered::new(scope, ordered_children, node)));
+                node = Graph::from_link(Link::Ordered(Ordered::new(
+                    scope,
+                    ordered_children,
+                    targets,
+                    node,
+                )));
             }
 
             let artifact = if vtable.targetted(&address) {
@@ -234,6 +259,7 @@ impl<'a> NamespaceCompiler<'a> {
 }
 
 pub struct ContentCompiler<'c, 'a: 'c> {
+    root: Source<'a>,
     scope: Address,
     state: &'c mut CompilerState<'a, Graph>,
     children: &'c mut BTreeMap<String, (GeneratorRecorder<Graph>, GeneratorSliceRef<Graph>)>,
@@ -282,9 +308,14 @@ impl<'c, 'a: 'c> Compiler<'a> for ContentCompiler<'c, 'a> {
         })?;
         Ok(Graph::from_link(Link::View(view)))
     }
+
+    fn content<S: Into<Address>>(&self, field: S) -> Option<&'a Content> {
+        self.root.find(&field.into()).ok()
+    }
 }
 
 pub struct Crawler<'t, 'a> {
+    root: Source<'a>,
     state: &'t mut CompilerState<'a, Graph>,
     symbols: &'t mut Symbols,
     position: Address,
@@ -294,6 +325,7 @@ impl<'t, 'a: 't> Crawler<'t, 'a> {
     fn as_at(&mut self, field: &str, content: &'a Content) -> Crawler<'_, 'a> {
         let position = self.position.clone().into_at(field);
         Crawler {
+            root: self.root,
             state: self.state.entry(field).or_init(content),
             symbols: self.symbols,
             position,
@@ -328,6 +360,10 @@ impl<'t, 'a: 't> Compiler<'a> for Crawler<'t, 'a> {
         self.state.refs_mut().insert(target);
         Ok(Graph::dummy())
     }
+
+    fn content<S: Into<Address>>(&self, field: S) -> Option<&'a Content> {
+        self.root.find(&field.into()).ok()
+    }
 }
 
 #[cfg(test)]
@@ -9,7 +9,7 @@ use super::link::{Recorder, SliceRef, TapeView};
 use super::Address;
 use super::{FromLink, Link};
 
-use crate::schema::{Content, Namespace};
+use crate::schema::{Content, Find, Namespace};
 
 /// A holder struct for the compiler's internal state of the children of a given node.
 ///
@@ -141,6 +141,7 @@ impl<'a, G: Generator> Iterator for IntoIter<'a, G> {
     }
 }
 
+#[derive(Clone, Copy)]
 pub enum Source<'a> {
     Namespace(&'a Namespace),
     Content(&'a Content),
@@ -180,6 +181,20 @@ impl<'a> Source<'a> {
             )),
         }
     }
+
+    /// The content found at `address`, relative to this source.
+    pub fn find(&self, address: &Address) -> Result<&'a Content> {
+        let mut chunks = address.iter();
+        match *self {
+            Self::Namespace(namespace) => {
+                let collection = chunks
+                    .next()
+                    .ok_or_else(|| failed!(target: Release, "expected a collection name"))?;
+                namespace.get_collection(collection)?.find(chunks)
+            }
+            Self::Content(content) => content.find(chunks),
+        }
+    }
 }
 
 pub(super) enum Artifact<G, Y, R> {
@@ -468,8 +483,8 @@ where
         self.locals.get(to)
     }
 
-    pub(super) fn values(&self) -> impl Iterator<Item = &ReferenceFactory<G>> {
-        self.locals.values()
+    pub(super) fn iter(&self) -> impl Iterator<Item = (&Address, &ReferenceFactory<G>)> {
+        self.locals.iter()
     }
 
     fn get_mut(&mut self, to: &Address) -> Option<&mut ReferenceFactory<G>> {
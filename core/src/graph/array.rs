@@ -1,5 +1,7 @@
 use super::prelude::*;
 
+use super::{unsigned_from_ok, RecordedValues};
+
 use synth_gen::value::Seq;
 
 use std::cell::RefCell;
@@ -45,3 +47,59 @@ impl ArrayNode {
         Self(inner)
     }
 }
+
+/// A node that generates, for each value generated by its parent in the current generation, an
+/// array of `length` elements of `content` where the fields in `fields` are set to that value.
+/// The arrays of all the parent's values are then concatenated.
+pub struct ChildrenNode {
+    parents: RecordedValues,
+    length: Box<Graph>,
+    content: Box<Graph>,
+    fields: Vec<String>,
+}
+
+impl ChildrenNode {
+    pub fn new(
+        parents: RecordedValues,
+        length: Graph,
+        content: Graph,
+        fields: Vec<String>,
+    ) -> Self {
+        Self {
+            parents,
+            length: Box::new(length),
+            content: Box::new(content),
+            fields,
+        }
+    }
+
+    fn children<R: Rng>(&mut self, rng: &mut R) -> Result<Value, Error> {
+        let parents = self.parents.current().to_vec();
+        let mut children = Vec::new();
+
+        for parent in parents {
+            let parent = parent?;
+            let length = unsigned_from_ok(self.length.complete(rng))?;
+            for _ in 0..length {
+                let mut child = self.content.complete(rng)?;
+                if let Value::Object(fields) = &mut child {
+                    for field in &self.fields {
+                        fields.insert(field.clone(), parent.clone());
+                    }
+                }
+                children.push(child);
+            }
+        }
+
+        Ok(Value::Array(children))
+    }
+}
+
+impl Generator for ChildrenNode {
+    type Yield = Token;
+    type Return = Result<Value, Error>;
+
+    fn next<R: Rng>(&mut self, rng: &mut R) -> GeneratorState<Self::Yield, Self::Return> {
+        GeneratorState::Complete(self.children(rng))
+    }
+}
@@ -0,0 +1,534 @@
+use super::prelude::*;
+
+use crate::schema::content::expr::NOW_FORMAT;
+use crate::schema::{BinaryOp, ChronoValue, ChronoValueAndFormat, Expr, Function, UnaryOp};
+
+use chrono::{DateTime, Datelike, Duration, FixedOffset, NaiveDate, NaiveTime, Timelike};
+use std::cmp::Ordering;
+use std::sync::Arc;
+
+/// 2^63: whole floats fit in an `i64` if they are at least its opposite and below it.
+const I64_BOUND: f64 = 9_223_372_036_854_775_808.0;
+
+/// A node that evaluates an [`Expr`](crate::schema::Expr) over the values generated by the
+/// nodes it references.
+pub struct ExprNode {
+    expr: Expr,
+    args: Vec<Graph>,
+    now: Option<DateTime<FixedOffset>>,
+}
+
+impl ExprNode {
+    pub fn new(expr: Expr, args: Vec<Graph>, now: Option<DateTime<FixedOffset>>) -> Self {
+        Self { expr, args, now }
+    }
+}
+
+impl Generator for ExprNode {
+    type Yield = Token;
+    type Return = Result<Value, Error>;
+
+    fn next<R: Rng>(&mut self, rng: &mut R) -> GeneratorState<Self::Yield, Self::Return> {
+        let value = self
+            .args
+            .iter_mut()
+            .map(|arg| arg.complete(rng))
+            .collect::<Result<Vec<_>, _>>()
+            .and_then(|args| eval(&self.expr, &args, self.now));
+        GeneratorState::Complete(value)
+    }
+}
+
+macro_rules! expr_error {
+    ($($arg:tt)*) => {
+        failed_crate!(target: Release, "expression error: {}", format!($($arg)*))
+    };
+}
+
+fn eval(expr: &Expr, args: &[Value], now: Option<DateTime<FixedOffset>>) -> Result<Value, Error> {
+    match expr {
+        Expr::Null => Ok(Value::Null(())),
+        Expr::Bool(b) => Ok(Value::Bool(*b)),
+        Expr::Int(i) => Ok(Value::Number(Number::I64(*i))),
+        Expr::Float(x) => Ok(Value::Number((*x).into())),
+        Expr::String(s) => Ok(Value::String(s.clone())),
+        Expr::Ref(index) => Ok(args[*index].clone()),
+        Expr::Unary(op, operand) => unary(*op, eval(operand, args, now)?),
+        Expr::Binary(op, lhs, rhs) => binary(*op, eval(lhs, args, now)?, eval(rhs, args, now)?),
+        Expr::Call(function, call_args) => call(
+            *function,
+            call_args
+                .iter()
+                .map(|arg| eval(arg, args, now))
+                .collect::<Result<Vec<_>, _>>()?,
+            now,
+        ),
+    }
+}
+
+/// A numerical operand: integers stay integers for as long as possible.
+#[derive(Clone, Copy)]
+enum Num {
+    Int(i64),
+    Float(f64),
+}
+
+impl Num {
+    fn as_f64(self) -> f64 {
+        match self {
+            Self::Int(i) => i as f64,
+            Self::Float(x) => x,
+        }
+    }
+}
+
+impl From<Num> for Value {
+    fn from(num: Num) -> Self {
+        match num {
+            Num::Int(i) => Value::Number(Number::I64(i)),
+            Num::Float(x) => Value::Number(x.into()),
+        }
+    }
+}
+
+fn as_num(value: &Value) -> Option<Num> {
+    match value {
+        Value::Number(number) => Some(match *number {
+            Number::I8(i) => Num::Int(i as i64),
+            Number::I16(i) => Num::Int(i as i64),
+            Number::I32(i) => Num::Int(i as i64),
+            Number::I64(i) => Num::Int(i),
+            Number::U8(u) => Num::Int(u as i64),
+            Number::U16(u) => Num::Int(u as i64),
+            Number::U32(u) => Num::Int(u as i64),
+            Number::U64(u) => i64::try_from(u).map_or(Num::Float(u as f64), Num::Int),
+            Number::I128(i) => i64::try_from(i).map_or(Num::Float(i as f64), Num::Int),
+            Number::U128(u) => i64::try_from(u).map_or(Num::Float(u as f64), Num::Int),
+            Number::F32(x) => Num::Float(x.into_inner() as f64),
+            Number::F64(x) => Num::Float(x.into_inner()),
+        }),
+        _ => None,
+    }
+}
+
+pub(super) fn as_f64(value: &Value) -> Option<f64> {
+    as_num(value).map(Num::as_f64)
+}
+
+fn num(function: &str, value: &Value) -> Result<Num, Error> {
+    as_num(value)
+        .ok_or_else(|| expr_error!("`{}` expects a number, found '{}'", function, value.type_()))
+}
+
+fn date_time<'v>(function: &str, value: &'v Value) -> Result<&'v ChronoValueAndFormat, Error> {
+    match value {
+        Value::DateTime(date_time) => Ok(date_time),
+        otherwise => Err(expr_error!(
+            "`{}` expects a date_time, found '{}'",
+            function,
+            otherwise.type_()
+        )),
+    }
+}
+
+fn boolean(op: &str, value: &Value) -> Result<bool, Error> {
+    match value {
+        Value::Bool(b) => Ok(*b),
+        otherwise => Err(expr_error!(
+            "`{}` expects a bool, found '{}'",
+            op,
+            otherwise.type_()
+        )),
+    }
+}
+
+fn unary(op: UnaryOp, value: Value) -> Result<Value, Error> {
+    match op {
+        UnaryOp::Neg => match num("-", &value)? {
+            Num::Int(i) => i
+                .checked_neg()
+                .map(|i| Num::Int(i).into())
+                .ok_or_else(|| expr_error!("overflow when negating {}", i)),
+            Num::Float(x) => Ok(Num::Float(-x).into()),
+        },
+        UnaryOp::Not => Ok(Value::Bool(!boolean("!", &value)?)),
+    }
+}
+
+fn arithmetic(
+    symbol: &str,
+    lhs: &Value,
+    rhs: &Value,
+    int: fn(i64, i64) -> Option<i64>,
+    float: fn(f64, f64) -> f64,
+) -> Result<Value, Error> {
+    match (num(symbol, lhs)?, num(symbol, rhs)?) {
+        (Num::Int(l), Num::Int(r)) => int(l, r)
+            .map(|i| Num::Int(i).into())
+            .ok_or_else(|| expr_error!("overflow when evaluating {} {} {}", l, symbol, r)),
+        (l, r) => Ok(Num::Float(float(l.as_f64(), r.as_f64())).into()),
+    }
+}
+
+/// Compares two values of the same type. Integers and floats can be compared with each other.
+fn compare(lhs: &Value, rhs: &Value) -> Option<Ordering> {
+    match (lhs, rhs) {
+        (Value::Null(_), Value::Null(_)) => Some(Ordering::Equal),
+        (Value::Bool(l), Value::Bool(r)) => l.partial_cmp(r),
+        (Value::String(l), Value::String(r)) => l.partial_cmp(r),
+        (Value::DateTime(l), Value::DateTime(r)) => l.value.partial_cmp(&r.value),
+        (Value::Number(_), Value::Number(_)) => match (as_num(lhs)?, as_num(rhs)?) {
+            (Num::Int(l), Num::Int(r)) => l.partial_cmp(&r),
+            (l, r) => l.as_f64().partial_cmp(&r.as_f64()),
+        },
+        _ => None,
+    }
+}
+
+fn ordering(symbol: &str, lhs: &Value, rhs: &Value) -> Result<Ordering, Error> {
+    compare(lhs, rhs).ok_or_else(|| {
+        expr_error!(
+            "cannot compare '{}' and '{}' with `{}`",
+            lhs.type_(),
+            rhs.type_(),
+            symbol
+        )
+    })
+}
+
+fn binary(op: BinaryOp, lhs: Value, rhs: Value) -> Result<Value, Error> {
+    match op {
+        BinaryOp::Add => match (lhs, rhs) {
+            (Value::String(l), Value::String(r)) => Ok(Value::String(l + &r)),
+            (lhs, rhs) => arithmetic("+", &lhs, &rhs, i64::checked_add, |l, r| l + r),
+        },
+        BinaryOp::Sub => arithmetic("-", &lhs, &rhs, i64::checked_sub, |l, r| l - r),
+        BinaryOp::Mul => arithmetic("*", &lhs, &rhs, i64::checked_mul, |l, r| l * r),
+        BinaryOp::Div => {
+            let divisor = num("/", &rhs)?.as_f64();
+            if divisor == 0.0 {
+                return Err(expr_error!("division by zero"));
+            }
+            Ok(Num::Float(num("/", &lhs)?.as_f64() / divisor).into())
+        }
+        BinaryOp::Rem => {
+            if num("%", &rhs)?.as_f64() == 0.0 {
+                return Err(expr_error!("division by zero"));
+            }
+            arithmetic("%", &lhs, &rhs, i64::checked_rem, |l, r| l % r)
+        }
+        BinaryOp::Eq => Ok(Value::Bool(compare(&lhs, &rhs) == Some(Ordering::Equal))),
+        BinaryOp::Ne => Ok(Value::Bool(compare(&lhs, &rhs) != Some(Ordering::Equal))),
+        BinaryOp::Lt => Ok(Value::Bool(ordering("<", &lhs, &rhs)?.is_lt())),
+        BinaryOp::Le => Ok(Value::Bool(ordering("<=", &lhs, &rhs)?.is_le())),
+        BinaryOp::Gt => Ok(Value::Bool(ordering(">", &lhs, &rhs)?.is_gt())),
+        BinaryOp::Ge => Ok(Value::Bool(ordering(">=", &lhs, &rhs)?.is_ge())),
+        BinaryOp::And => Ok(Value::Bool(boolean("&&", &lhs)? && boolean("&&", &rhs)?)),
+        BinaryOp::Or => Ok(Value::Bool(boolean("||", &lhs)? || boolean("||", &rhs)?)),
+    }
+}
+
+fn extract(
+    function: Function,
+    value: &Value,
+    date: fn(&NaiveDate) -> u32,
+    time: fn(&NaiveTime) -> u32,
+) -> Result<Value, Error> {
+    let name = function.name();
+    let component = match (&date_time(name, value)?.value, function) {
+        (ChronoValue::NaiveDate(d), Function::Year | Function::Month | Function::Day) => date(d),
+        (ChronoValue::NaiveTime(t), Function::Hour | Function::Minute | Function::Second) => {
+            time(t)
+        }
+        (ChronoValue::NaiveDateTime(dt), _) => match function {
+            Function::Year | Function::Month | Function::Day => date(&dt.date()),
+            _ => time(&dt.time()),
+        },
+        (ChronoValue::DateTime(dt), _) => match function {
+            Function::Year | Function::Month | Function::Day => date(&dt.naive_local().date()),
+            _ => time(&dt.time()),
+        },
+        (value, _) => {
+            return Err(expr_error!(
+                "`{}` is not defined for a {}",
+                name,
+                value.type_()
+            ))
+        }
+    };
+    Ok(Num::Int(component as i64).into())
+}
+
+fn add_duration(
+    function: Function,
+    args: &[Value],
+    unit: fn(i64) -> Duration,
+) -> Result<Value, Error> {
+    let name = function.name();
+    let date_time = date_time(name, &args[0])?;
+    let amount = num(name, &args[1])?;
+    let duration = match amount {
+        Num::Int(i) => unit(i),
+        Num::Float(x) => Duration::milliseconds((x * unit(1).num_milliseconds() as f64) as i64),
+    };
+    Ok(Value::DateTime(ChronoValueAndFormat {
+        value: date_time.value.clone() + duration,
+        format: date_time.format.clone(),
+    }))
+}
+
+fn between(function: Function, args: &[Value]) -> Result<Duration, Error> {
+    let name = function.name();
+    let (from, to) = (
+        &date_time(name, &args[0])?.value,
+        &date_time(name, &args[1])?.value,
+    );
+    match (from, to) {
+        (ChronoValue::NaiveDate(l), ChronoValue::NaiveDate(r)) => Ok(*r - *l),
+        (ChronoValue::NaiveTime(l), ChronoValue::NaiveTime(r)) => Ok(*r - *l),
+        (ChronoValue::NaiveDateTime(l), ChronoValue::NaiveDateTime(r)) => Ok(*r - *l),
+        (ChronoValue::DateTime(l), ChronoValue::DateTime(r)) => Ok(*r - *l),
+        _ => Err(expr_error!(
+            "`{}` expects two date_times of the same type, found a {} and a {}",
+            name,
+            from.type_(),
+            to.type_()
+        )),
+    }
+}
+
+fn round(function: Function, value: &Value, op: fn(f64) -> f64) -> Result<Value, Error> {
+    match num(function.name(), value)? {
+        Num::Int(i) => Ok(Num::Int(i).into()),
+        Num::Float(x) => {
+            let rounded = op(x);
+            if (-I64_BOUND..I64_BOUND).contains(&rounded) {
+                Ok(Num::Int(rounded as i64).into())
+            } else {
+                Err(expr_error!(
+                    "{}({}) is out of range for an integer",
+                    function.name(),
+                    x
+                ))
+            }
+        }
+    }
+}
+
+fn call(
+    function: Function,
+    args: Vec<Value>,
+    now: Option<DateTime<FixedOffset>>,
+) -> Result<Value, Error> {
+    let name = function.name();
+    match function {
+        Function::Min | Function::Max => {
+            let mut args = args.into_iter();
+            let mut out = args.next().unwrap();
+            for arg in args {
+                let ordering = ordering(name, &arg, &out)?;
+                if (function == Function::Min && ordering.is_lt())
+                    || (function == Function::Max && ordering.is_gt())
+                {
+                    out = arg;
+                }
+            }
+            Ok(out)
+        }
+        Function::Round => match args.get(1) {
+            None => round(function, &args[0], f64::round),
+            Some(digits) => {
+                let digits = match num(name, digits)? {
+                    Num::Int(i) => i as i32,
+                    Num::Float(_) => return Err(expr_error!("`round` expects integer digits")),
+                };
+                let factor = 10f64.powi(digits);
+                Ok(Num::Float((num(name, &args[0])?.as_f64() * factor).round() / factor).into())
+            }
+        },
+        Function::Floor => round(function, &args[0], f64::floor),
+        Function::Ceil => round(function, &args[0], f64::ceil),
+        Function::Abs => match num(name, &args[0])? {
+            Num::Int(i) => i
+                .checked_abs()
+                .map(|i| Num::Int(i).into())
+                .ok_or_else(|| expr_error!("overflow when evaluating abs({})", i)),
+            Num::Float(x) => Ok(Num::Float(x.abs()).into()),
+        },
+        Function::Concat => args
+            .into_iter()
+            .map(|arg| match arg {
+                Value::Null(_) => Ok(String::new()),
+                Value::Bool(b) => Ok(b.to_string()),
+                otherwise => String::try_from(otherwise),
+            })
+            .collect::<Result<String, Error>>()
+            .map(Value::String),
+        Function::If => {
+            let mut args = args.into_iter();
+            let condition = boolean(name, &args.next().unwrap())?;
+            let (then, otherwise) = (args.next().unwrap(), args.next().unwrap());
+            Ok(if condition { then } else { otherwise })
+        }
+        Function::Now => Ok(Value::DateTime(ChronoValueAndFormat {
+            value: ChronoValue::DateTime(now.ok_or_else(|| expr_error!("`now` is not set"))?),
+            format: Arc::from(NOW_FORMAT),
+        })),
+        Function::Year => extract(function, &args[0], |d| d.year() as u32, |_| 0),
+        Function::Month => extract(function, &args[0], |d| d.month(), |_| 0),
+        Function::Day => extract(function, &args[0], |d| d.day(), |_| 0),
+        Function::Hour => extract(function, &args[0], |_| 0, |t| t.hour()),
+        Function::Minute => extract(function, &args[0], |_| 0, |t| t.minute()),
+        Function::Second => extract(function, &args[0], |_| 0, |t| t.second()),
+        Function::AddDays => add_duration(function, &args, Duration::days),
+        Function::AddHours => add_duration(function, &args, Duration::hours),
+        Function::AddMinutes => add_duration(function, &args, Duration::minutes),
+        Function::AddSeconds => add_duration(function, &args, Duration::seconds),
+        Function::DaysBetween => Ok(Num::Int(between(function, &args)?.num_days()).into()),
+        Function::SecondsBetween => Ok(Num::Int(between(function, &args)?.num_seconds()).into()),
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::graph::{Graph, Value};
+    use crate::tests::complete;
+
+    use synth_gen::prelude::*;
+
+    const NOW: &str = "2021-06-01T12:00:00+0000";
+
+    fn try_compile(expr: &str, now: Option<&str>) -> anyhow::Result<Graph> {
+        try_generator!({
+            "type": "object",
+            "a": {
+                "type": "object",
+                "price": 12.5,
+                "quantity": 4,
+                "discount": 0.2,
+                "huge": 1e20,
+                "name": {
+                    "type": "string",
+                    "pattern": "widget"
+                },
+                "date": {
+                    "type": "date_time",
+                    "format": "%Y-%m-%d",
+                    "begin": "2021-02-27",
+                    "end": "2021-02-27"
+                }
+            },
+            "result": {
+                "type": "expr",
+                "expr": expr,
+                "now": now
+            }
+        })
+    }
+
+    fn evaluate(expr: &str) -> Value {
+        let value = complete(try_compile(expr, Some(NOW)).unwrap()).unwrap();
+        value.as_object().unwrap().get("result").unwrap().clone()
+    }
+
+    fn evaluate_err(expr: &str) -> bool {
+        complete(try_compile(expr, Some(NOW)).unwrap()).is_err()
+    }
+
+    fn compile_err(expr: &str) -> bool {
+        try_compile(expr, None).is_err()
+    }
+
+    #[test]
+    fn arithmetic() {
+        assert_eq!(
+            evaluate("@a.price * @a.quantity * (1 - @a.discount)"),
+            Value::Number(40.0.into())
+        );
+        assert_eq!(
+            evaluate("@a.quantity * 3 - 2"),
+            Value::Number(Number::I64(10))
+        );
+        assert_eq!(evaluate("@a.quantity / 8"), Value::Number(0.5.into()));
+        assert_eq!(evaluate("-@a.quantity % 3"), Value::Number(Number::I64(-1)));
+        assert_eq!(
+            evaluate("round(@a.price / 3, 2)"),
+            Value::Number(4.17.into())
+        );
+        assert_eq!(evaluate("round(@a.price)"), Value::Number(Number::I64(13)));
+        assert_eq!(
+            evaluate("max(@a.quantity, @a.price, 7)"),
+            Value::Number(12.5.into())
+        );
+        assert_eq!(
+            evaluate("min(@a.quantity, @a.price, 7)"),
+            Value::Number(Number::U64(4))
+        );
+    }
+
+    #[test]
+    fn strings_and_comparisons() {
+        assert_eq!(
+            evaluate("@a.name + \"-\" + concat(@a.quantity, 'x')"),
+            Value::String("widget-4x".to_string())
+        );
+        assert_eq!(evaluate("@a.quantity >= 4.0"), Value::Bool(true));
+        assert_eq!(
+            evaluate("@a.name == 'widget' && !(@a.price < 10)"),
+            Value::Bool(true)
+        );
+        assert_eq!(
+            evaluate("if(@a.quantity > 10, 'bulk', 'retail')"),
+            Value::String("retail".to_string())
+        );
+    }
+
+    #[test]
+    fn dates() {
+        assert_eq!(evaluate("year(@a.date)"), Value::Number(Number::I64(2021)));
+        assert_eq!(
+            evaluate("month(add_days(@a.date, 2))"),
+            Value::Number(Number::I64(3))
+        );
+        assert_eq!(
+            evaluate("days_between(@a.date, add_days(@a.date, @a.quantity))"),
+            Value::Number(Number::I64(4))
+        );
+        assert_eq!(
+            String::try_from(evaluate("add_days(@a.date, 2)")).unwrap(),
+            "2021-03-01"
+        );
+        assert_eq!(
+            evaluate("year(now) - year(@a.date)"),
+            Value::Number(Number::I64(0))
+        );
+        assert_eq!(
+            String::try_from(evaluate("add_hours(now, 1)")).unwrap(),
+            "2021-06-01T13:00:00+0000"
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert!(compile_err("@a.name * 2"));
+        assert!(compile_err("-@a.name"));
+        assert!(compile_err("@a.quantity < @a.name"));
+        assert!(compile_err("max(@a.quantity, @a.date)"));
+        assert!(compile_err("year(@a.quantity)"));
+        assert!(compile_err("if(@a.quantity, 1, 2)"));
+        assert!(compile_err("year(now)"));
+        assert!(!compile_err("@a.name + @a.name + concat(@a.quantity)"));
+
+        assert!(evaluate_err("@a.quantity / 0"));
+        assert!(evaluate_err("floor(@a.huge)"));
+        assert!(evaluate_err("round(-@a.huge)"));
+        assert!(try_generator!({
+            "type": "object",
+            "result": {
+                "type": "expr",
+                "expr": "@missing.field + 1"
+            }
+        })
+        .is_err());
+    }
+}
@@ -0,0 +1,66 @@
+use super::prelude::*;
+
+use std::collections::BTreeMap;
+
+/// A node that picks one row of another collection with `row` and projects some of its fields,
+/// so that all of them come from the same row.
+///
+/// While `row` has not generated any row to pick from, every projected field is `null`.
+pub struct LookupNode {
+    row: Box<Graph>,
+    /// The name of each projected field and the field of the row it is projected from.
+    fields: Vec<(String, String)>,
+}
+
+impl LookupNode {
+    pub fn new(row: Graph, fields: Vec<(String, String)>) -> Self {
+        Self {
+            row: Box::new(row),
+            fields,
+        }
+    }
+
+    fn lookup<R: Rng>(&mut self, rng: &mut R) -> Result<Value, Error> {
+        let row = self.row.complete(rng)?;
+        let projected = match &row {
+            Value::Null(_) => self
+                .fields
+                .iter()
+                .map(|(name, _)| (name.clone(), Value::Null(())))
+                .collect::<BTreeMap<_, _>>(),
+            Value::Object(row) => self
+                .fields
+                .iter()
+                .map(|(name, field)| {
+                    row.get(field)
+                        .cloned()
+                        .map(|value| (name.clone(), value))
+                        .ok_or_else(|| {
+                            failed_crate!(
+                                target: Release,
+                                "the row picked by a lookup has no field '{}'",
+                                field
+                            )
+                        })
+                })
+                .collect::<Result<BTreeMap<_, _>, Error>>()?,
+            other => {
+                return Err(failed_crate!(
+                    target: Release,
+                    "a lookup can only pick rows that are objects, found a {}",
+                    other.type_()
+                ))
+            }
+        };
+        Ok(Value::Object(projected))
+    }
+}
+
+impl Generator for LookupNode {
+    type Yield = Token;
+    type Return = Result<Value, Error>;
+
+    fn next<R: Rng>(&mut self, rng: &mut R) -> GeneratorState<Self::Yield, Self::Return> {
+        GeneratorState::Complete(self.lookup(rng))
+    }
+}
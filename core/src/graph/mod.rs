@@ -5,8 +5,9 @@ use anyhow::{Context, Result};
 use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, Utc};
 use sqlx::mysql::MySqlTypeInfo;
 use sqlx::postgres::{PgArgumentBuffer, PgTypeInfo};
+use sqlx::sqlite::{SqliteArgumentValue, SqliteTypeInfo};
 use sqlx::{encode::IsNull, Encode, Type};
-use sqlx::{MySql, Postgres};
+use sqlx::{MySql, Postgres, Sqlite};
 
 use synth_gen::prelude::*;
 use synth_gen::value::{Token, Tokenizer};
@@ -29,8 +30,8 @@ pub use date_time::{DateTimeNode, RandomDateTime};
 
 pub mod number;
 pub use number::{
-    Incrementing, NumberNode, RandomF64, RandomI64, RandomU64, StandardFloatRangeStep,
-    StandardIntRangeStep,
+    BoundedDistribution, BoundedNumber, Incrementing, NumberDistribution, NumberNode, RandomF64,
+    RandomI64, RandomU64, StandardFloatRangeStep, StandardIntRangeStep,
 };
 
 pub mod boolean;
@@ -40,7 +41,7 @@ pub mod iter;
 pub use iter::IterNode;
 
 pub mod array;
-pub use array::ArrayNode;
+pub use array::{ArrayNode, ChildrenNode};
 
 pub mod object;
 pub use object::{KeyValueOrNothing, ObjectNode};
@@ -48,6 +49,21 @@ pub use object::{KeyValueOrNothing, ObjectNode};
 pub mod unique;
 pub use unique::UniqueNode;
 
+pub mod expr;
+pub use expr::ExprNode;
+
+pub mod switch;
+pub use switch::SwitchNode;
+
+pub mod sample;
+pub use sample::{SampleNode, Sampling};
+
+pub mod lookup;
+pub use lookup::LookupNode;
+
+pub mod permutation;
+pub use permutation::PermutationNode;
+
 pub mod one_of;
 pub(crate) mod series;
 
@@ -367,8 +383,19 @@ impl Type<MySql> for Value {
     }
 }
 
+impl Type<Sqlite> for Value {
+    fn type_info() -> SqliteTypeInfo {
+        <String as Type<Sqlite>>::type_info()
+    }
+
+    fn compatible(_ty: &SqliteTypeInfo) -> bool {
+        unreachable!("This should never happen. Please reach out to https://github.com/getsynth/synth/issues if it does.")
+    }
+}
+
 impl Value {
-    fn to_postgres_string(&self) -> String {
+    /// The text representation Postgres parses values from, e.g. `{1, 2}` for arrays.
+    pub fn to_postgres_string(&self) -> String {
         match self {
             Self::Array(arr) => {
                 let (typ, _) = self.get_postgres_type();
@@ -391,7 +418,7 @@ impl Value {
                 Number::F64(f64) => (*f64).to_string(),
                 _ => num.to_string(),
             },
-            Self::String(str) => format!("\"{}\"", str),
+            Self::String(str) => format!("\"{}\"", str.replace('\\', "\\\\").replace('"', "\\\"")),
             Self::DateTime(date) => date.format_to_string(),
             Self::Object(_) => {
                 serde_json::to_string(&json::synth_val_to_json(self.clone())).unwrap()
@@ -596,6 +623,62 @@ impl Encode<'_, MySql> for Value {
     }
 }
 
+/// SQLite has no arrays or objects, so those are stored as JSON text. Integers that don't fit an
+/// `INTEGER` are stored as text too.
+impl<'q> Encode<'q, Sqlite> for Value {
+    fn encode_by_ref(&self, buf: &mut Vec<SqliteArgumentValue<'q>>) -> IsNull {
+        match self {
+            Value::Null(_) => IsNull::Yes,
+            Value::Bool(b) => <bool as Encode<'_, Sqlite>>::encode_by_ref(b, buf),
+            Value::Number(num) => match *num {
+                Number::I8(i) => <i8 as Encode<'_, Sqlite>>::encode_by_ref(&i, buf),
+                Number::I16(i) => <i16 as Encode<'_, Sqlite>>::encode_by_ref(&i, buf),
+                Number::I32(i) => <i32 as Encode<'_, Sqlite>>::encode_by_ref(&i, buf),
+                Number::I64(i) => <i64 as Encode<'_, Sqlite>>::encode_by_ref(&i, buf),
+                Number::I128(i) => encode_sqlite_integer(i, buf),
+                Number::U8(i) => <i16 as Encode<'_, Sqlite>>::encode_by_ref(&(i as i16), buf),
+                Number::U16(i) => <i32 as Encode<'_, Sqlite>>::encode_by_ref(&(i as i32), buf),
+                Number::U32(i) => <i64 as Encode<'_, Sqlite>>::encode_by_ref(&(i as i64), buf),
+                Number::U64(i) => encode_sqlite_integer(i, buf),
+                Number::U128(i) => encode_sqlite_integer(i, buf),
+                Number::F32(f) => <f32 as Encode<'_, Sqlite>>::encode_by_ref(&f, buf),
+                Number::F64(f) => <f64 as Encode<'_, Sqlite>>::encode_by_ref(&f, buf),
+            },
+            Value::String(s) => <String as Encode<'_, Sqlite>>::encode_by_ref(s, buf),
+            Value::DateTime(ChronoValueAndFormat { value, .. }) => match value {
+                ChronoValue::NaiveDate(nd) => {
+                    <NaiveDate as Encode<'_, Sqlite>>::encode_by_ref(nd, buf)
+                }
+                ChronoValue::NaiveTime(nt) => {
+                    <NaiveTime as Encode<'_, Sqlite>>::encode_by_ref(nt, buf)
+                }
+                ChronoValue::NaiveDateTime(ndt) => {
+                    <NaiveDateTime as Encode<'_, Sqlite>>::encode_by_ref(ndt, buf)
+                }
+                ChronoValue::DateTime(dt) => {
+                    <DateTime<FixedOffset> as Encode<'_, Sqlite>>::encode_by_ref(dt, buf)
+                }
+            },
+            Value::Object(_) | Value::Array(_) => {
+                let s = json::synth_val_to_json(self.clone()).to_string();
+                <String as Encode<'_, Sqlite>>::encode(s, buf)
+            }
+        }
+    }
+}
+
+/// Binds `i` as an `INTEGER` if it fits one, or as its decimal text otherwise.
+fn encode_sqlite_integer<I>(i: I, buf: &mut Vec<SqliteArgumentValue<'_>>) -> IsNull
+where
+    I: Copy + ToString,
+    i64: TryFrom<I>,
+{
+    match i64::try_from(i) {
+        Ok(int) => <i64 as Encode<'_, Sqlite>>::encode_by_ref(&int, buf),
+        Err(_) => <String as Encode<'_, Sqlite>>::encode(i.to_string(), buf),
+    }
+}
+
 #[allow(unused)]
 impl Value {
     pub fn is_null(&self) -> bool {
@@ -782,6 +865,11 @@ derive_generator!(
         Link(Box<LinkNode>),
         Hidden(Box<Graph>),
         Iter(IterNode),
+        Expr(ExprNode),
+        Switch(SwitchNode),
+        Sample(SampleNode),
+        Children(ChildrenNode),
+        Lookup(LookupNode),
     }
 );
 
@@ -799,6 +887,9 @@ impl Graph {
     }
 }
 
+/// The values recorded for a node referred to by others, see [`Graph::into_recorded_values`].
+pub type RecordedValues = crate::compile::TapeValues<Token, Result<Value, Error>>;
+
 enum LinkNodeState {
     YieldFrom,
     Yield(Token),
@@ -879,6 +970,34 @@ impl Graph {
             _ => None,
         }
     }
+
+    /// Turns a reference to a node, as returned by [`Compiler::get`](crate::compile::Compiler::get),
+    /// into a reader of all the values recorded for that node. Any other graph is returned as is.
+    pub fn into_recorded_values(self) -> std::result::Result<RecordedValues, Self> {
+        match self {
+            Self::Link(link_node) => match *link_node {
+                LinkNode(Link::View(view), _) => Ok(view.into_values()),
+                link_node => Err(Self::Link(Box::new(link_node))),
+            },
+            otherwise => Err(otherwise),
+        }
+    }
+
+    /// Stops generating new values for the collection `name`, while the collections referring to
+    /// it keep referring to the values it generated last.
+    pub fn freeze(&mut self, name: &str) {
+        if let Self::Link(box LinkNode(link, _)) = self {
+            link.freeze(name)
+        }
+    }
+
+    /// Whether the collection `name` is referred to by other collections.
+    pub fn is_referred_to(&self, name: &str) -> bool {
+        match self {
+            Self::Link(box LinkNode(link, _)) => link.is_target(name),
+            _ => false,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -1250,4 +1369,24 @@ pub mod tests {
             }
         }
     }
+
+    #[test]
+    fn encode_sqlite_integers() {
+        let encode = |number: Number| {
+            let mut buf = Vec::new();
+            <Value as Encode<'_, Sqlite>>::encode_by_ref(&Value::Number(number), &mut buf);
+            match buf.as_slice() {
+                [SqliteArgumentValue::Int64(i)] => i.to_string(),
+                [SqliteArgumentValue::Text(text)] => format!("'{}'", text),
+                otherwise => panic!("unexpected arguments: {:?}", otherwise),
+            }
+        };
+
+        assert_eq!(encode(Number::U64(42)), "42");
+        assert_eq!(encode(Number::U64(u64::MAX)), "'18446744073709551615'");
+        assert_eq!(encode(Number::I128(-42)), "-42");
+        assert_eq!(encode(Number::I128(i128::MIN)), format!("'{}'", i128::MIN));
+        assert_eq!(encode(Number::U128(7)), "7");
+        assert_eq!(encode(Number::U128(u128::MAX)), format!("'{}'", u128::MAX));
+    }
 }
@@ -290,6 +290,232 @@ macro_rules! standard_float_range_step_impl {
 standard_float_range_step_impl! { f32 }
 standard_float_range_step_impl! { f64 }
 
+/// A continuous or discrete probability distribution over `f64`, backing the
+/// `normal`, `log_normal`, `exponential`, `gamma` and `zipf` number variants.
+#[derive(Clone)]
+pub enum NumberDistribution {
+    Normal(rand_distr::Normal<f64>),
+    LogNormal(rand_distr::LogNormal<f64>),
+    Exponential(rand_distr::Exp<f64>),
+    Gamma(rand_distr::Gamma<f64>),
+    Zipf(rand_distr::Zipf<f64>),
+}
+
+impl NumberDistribution {
+    pub fn normal(mean: f64, std_dev: f64) -> anyhow::Result<Self> {
+        if std_dev < 0. {
+            return Err(anyhow!(
+                "normal distribution with std_dev={} is invalid, use a non-negative value instead",
+                std_dev
+            ));
+        }
+        rand_distr::Normal::new(mean, std_dev)
+            .map(Self::Normal)
+            .map_err(|err| {
+                anyhow!(
+                    "normal distribution with mean={} std_dev={} is invalid: {}",
+                    mean,
+                    std_dev,
+                    err
+                )
+            })
+    }
+
+    pub fn log_normal(mu: f64, sigma: f64) -> anyhow::Result<Self> {
+        if sigma < 0. {
+            return Err(anyhow!("log-normal distribution with sigma={} is invalid, use a non-negative value instead", sigma));
+        }
+        rand_distr::LogNormal::new(mu, sigma)
+            .map(Self::LogNormal)
+            .map_err(|err| {
+                anyhow!(
+                    "log-normal distribution with mu={} sigma={} is invalid: {}",
+                    mu,
+                    sigma,
+                    err
+                )
+            })
+    }
+
+    pub fn exponential(rate: f64) -> anyhow::Result<Self> {
+        rand_distr::Exp::new(rate)
+            .map(Self::Exponential)
+            .map_err(|err| {
+                anyhow!(
+                    "exponential distribution with rate={} is invalid: {}",
+                    rate,
+                    err
+                )
+            })
+    }
+
+    pub fn gamma(shape: f64, scale: f64) -> anyhow::Result<Self> {
+        rand_distr::Gamma::new(shape, scale)
+            .map(Self::Gamma)
+            .map_err(|err| {
+                anyhow!(
+                    "gamma distribution with shape={} scale={} is invalid: {}",
+                    shape,
+                    scale,
+                    err
+                )
+            })
+    }
+
+    pub fn zipf(n: u64, exponent: f64) -> anyhow::Result<Self> {
+        rand_distr::Zipf::new(n, exponent)
+            .map(Self::Zipf)
+            .map_err(|err| {
+                anyhow!(
+                    "zipf distribution with n={} exponent={} is invalid: {}",
+                    n,
+                    exponent,
+                    err
+                )
+            })
+    }
+}
+
+impl Distribution<f64> for NumberDistribution {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> f64 {
+        match self {
+            Self::Normal(dist) => dist.sample(rng),
+            Self::LogNormal(dist) => dist.sample(rng),
+            Self::Exponential(dist) => dist.sample(rng),
+            Self::Gamma(dist) => dist.sample(rng),
+            Self::Zipf(dist) => dist.sample(rng),
+        }
+    }
+}
+
+/// Number primitives that samples of a [`NumberDistribution`] can be converted to.
+pub trait BoundedNumber: Copy {
+    const MIN: f64;
+    const MAX: f64;
+
+    /// Whether samples have to be rounded to the nearest integer.
+    const INTEGRAL: bool;
+
+    /// Convert a sample, which is guaranteed to be within `[Self::MIN, Self::MAX]`.
+    fn from_sample(sample: f64) -> Self;
+}
+
+macro_rules! bounded_number_impl {
+    { $($target:ty: $integral:literal,)* } => {
+        $(
+            impl BoundedNumber for $target {
+                const MIN: f64 = <$target>::MIN as f64;
+                const MAX: f64 = <$target>::MAX as f64;
+                const INTEGRAL: bool = $integral;
+
+                fn from_sample(sample: f64) -> Self {
+                    sample as $target
+                }
+            }
+        )*
+    }
+}
+
+bounded_number_impl! {
+    u32: true,
+    u64: true,
+    i32: true,
+    i64: true,
+    f32: false,
+    f64: false,
+}
+
+/// Maximum number of times a sample falling outside of the bounds of a
+/// [`BoundedDistribution`] is redrawn before generation fails. It is only reached
+/// when the bounds hold a tiny fraction of the distribution.
+const MAX_RESAMPLES: usize = 1000;
+
+/// A [`NumberDistribution`] truncated (or clamped) to `[low, high]` and to the
+/// range representable by `N`. Samples are rounded to the nearest integer when
+/// `N` is an integer type.
+///
+/// Truncated samples are redrawn until they fall within the bounds, and fail
+/// after [`MAX_RESAMPLES`] draws rather than being moved to a bound.
+pub struct BoundedDistribution<N> {
+    dist: NumberDistribution,
+    low: f64,
+    high: f64,
+    clamp: bool,
+    _marker: std::marker::PhantomData<N>,
+}
+
+impl<N: BoundedNumber> BoundedDistribution<N> {
+    pub fn new(
+        dist: NumberDistribution,
+        low: Option<f64>,
+        high: Option<f64>,
+        clamp: bool,
+    ) -> anyhow::Result<Self> {
+        let low = low.map_or(N::MIN, |low| low.max(N::MIN));
+        let high = high.map_or(N::MAX, |high| high.min(N::MAX));
+        let (low, high) = if N::INTEGRAL {
+            (low.ceil(), high.floor())
+        } else {
+            (low, high)
+        };
+        if low.is_nan() || high.is_nan() || low > high {
+            return Err(anyhow!(
+                "distribution bounds low={} high={} are empty",
+                low,
+                high
+            ));
+        }
+        Ok(Self {
+            dist,
+            low,
+            high,
+            clamp,
+            _marker: std::marker::PhantomData,
+        })
+    }
+
+    fn round(sample: f64) -> f64 {
+        if N::INTEGRAL {
+            sample.round()
+        } else {
+            sample
+        }
+    }
+
+    fn contains(&self, sample: f64) -> bool {
+        self.low <= sample && sample <= self.high
+    }
+}
+
+impl<N: BoundedNumber> Generator for BoundedDistribution<N> {
+    type Yield = N;
+
+    type Return = Result<Never, Error>;
+
+    fn next<R: Rng>(&mut self, rng: &mut R) -> GeneratorState<Self::Yield, Self::Return> {
+        let mut sample = Self::round(self.dist.sample(rng));
+        if self.clamp {
+            return GeneratorState::Yielded(N::from_sample(sample.clamp(self.low, self.high)));
+        }
+
+        let mut resamples = 0;
+        while !self.contains(sample) {
+            if resamples == MAX_RESAMPLES {
+                return GeneratorState::Complete(Err(failed_crate!(
+                    target: Release,
+                    "{} samples in a row of the distribution fell outside of low={} high={}: widen the bounds or set 'clamp' to true",
+                    MAX_RESAMPLES + 1,
+                    self.low,
+                    self.high
+                )));
+            }
+            sample = Self::round(self.dist.sample(rng));
+            resamples += 1;
+        }
+        GeneratorState::Yielded(N::from_sample(sample))
+    }
+}
+
 pub struct Incrementing<N = i64> {
     count: N,
     step: N,
@@ -383,6 +609,7 @@ macro_rules! number_node {
                 $(
                     $incrementing:ident as $new_incrementing:ident
                 )?,
+                $distribution:ident as $new_distribution:ident,
             ) for $ty:ty,
 	    )*
     } => {
@@ -403,6 +630,7 @@ macro_rules! number_node {
                     $constant(OnceInfallible<Yield<$ty>>),
                     $($categorical(OnceInfallible<Random<$ty, Categorical<$ty>>>),)?
                     $($incrementing(TryOnce<Incrementing<$ty>>),)?
+                    $distribution(TryOnce<BoundedDistribution<$ty>>),
                 }
             }
 
@@ -427,6 +655,10 @@ macro_rules! number_node {
                         Self::$incrementing(incr.try_once())
                     }
                 )?
+
+                pub fn $new_distribution(dist: BoundedDistribution<$ty>) -> Self {
+                    Self::$distribution(dist.try_once())
+                }
             }
 
             impl From<$rand> for NumberNode {
@@ -444,32 +676,38 @@ number_node!(
         U64Constant as constant,
         U64Categorical as categorical,
         Incrementing as incrementing,
+        U64Distribution as distribution,
     ) for u64,
     RandomI64 (
         I64Range<StandardIntRangeStep<u64, i128>> as range,
         I64Constant as constant,
         I64Categorical as categorical,
         Incrementing as incrementing,
+        I64Distribution as distribution,
     ) for i64,
     RandomF64 (
         F64Range<StandardFloatRangeStep<f64>> as range,
         F64Constant as constant,,,
+        F64Distribution as distribution,
     ) for f64,
     RandomU32 (
         U32Range<StandardIntRangeStep<u32, u32>> as range,
         U32Constant as constant,
         U32Categorical as categorical,
         Incrementing as incrementing,
+        U32Distribution as distribution,
     ) for u32,
     RandomI32 (
         I32Range<StandardIntRangeStep<u32, i64>> as range,
         I32Constant as constant,
         I32Categorical as categorical,
         Incrementing as incrementing,
+        I32Distribution as distribution,
     ) for i32,
     RandomF32 (
         F32Range<StandardFloatRangeStep<f32>> as range,
         F32Constant as constant,,,
+        F32Distribution as distribution,
     ) for f32,
 );
 
@@ -494,4 +732,19 @@ pub mod test {
 
         assert!(incrementing.next(&mut rng).into_complete().is_err())
     }
+
+    #[test]
+    fn test_bounds_out_of_distribution() {
+        let normal = || NumberDistribution::normal(0., 1.).unwrap();
+        let mut rng = OsRng::default();
+
+        let mut clamped =
+            BoundedDistribution::<i64>::new(normal(), Some(100.), None, true).unwrap();
+        assert_eq!(clamped.next(&mut rng).into_yielded().unwrap(), 100);
+
+        // Samples are never moved to the bounds of a truncated distribution
+        let mut truncated =
+            BoundedDistribution::<i64>::new(normal(), Some(100.), None, false).unwrap();
+        assert!(truncated.next(&mut rng).into_complete().is_err());
+    }
 }
@@ -1,6 +1,6 @@
 use super::prelude::*;
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashSet};
 use synth_gen::value::Map;
 
 pub struct KeyValueOrNothing {
@@ -8,6 +8,7 @@ pub struct KeyValueOrNothing {
     p: f64,
     active: bool,
     pub hidden: bool,
+    pub flatten: bool,
     pub key: String,
 }
 
@@ -41,10 +42,18 @@ impl KeyValueOrNothing {
             p: freq,
             active: false,
             hidden: is_hidden,
+            flatten: false,
             key: key.to_string(),
         }
     }
 
+    /// Merges the fields of the object generated for this key into the object it is a field of,
+    /// rather than keeping them under the key.
+    pub fn flattened(mut self) -> Self {
+        self.flatten = true;
+        self
+    }
+
     pub fn always(key: &str, content: Graph, is_hidden: bool) -> Self {
         Self::new_with(key, content, 1.0, is_hidden)
     }
@@ -54,11 +63,29 @@ impl KeyValueOrNothing {
     }
 }
 
-pub struct ObjectNode(Map<Chain<KeyValueOrNothing>>);
+pub struct ObjectNode {
+    inner: Map<Chain<KeyValueOrNothing>>,
+    hidden_fields: HashSet<String>,
+    flattened_fields: HashSet<String>,
+}
 
 impl FromIterator<KeyValueOrNothing> for ObjectNode {
     fn from_iter<T: IntoIterator<Item = KeyValueOrNothing>>(iter: T) -> Self {
-        Self(Chain::from_iter(iter).into_map(None))
+        let fields = iter.into_iter().collect::<Vec<_>>();
+        let keys_where = |filter: fn(&KeyValueOrNothing) -> bool| -> HashSet<String> {
+            fields
+                .iter()
+                .filter(|field| filter(field))
+                .map(|field| field.key.clone())
+                .collect()
+        };
+        let hidden_fields = keys_where(|field| field.hidden);
+        let flattened_fields = keys_where(|field| field.flatten);
+        Self {
+            inner: Chain::from_iter(fields).into_map(None),
+            hidden_fields,
+            flattened_fields,
+        }
     }
 }
 
@@ -68,25 +95,24 @@ impl Generator for ObjectNode {
     type Return = Result<Value, Error>;
 
     fn next<R: Rng>(&mut self, rng: &mut R) -> GeneratorState<Self::Yield, Self::Return> {
-        let hidden_fields: std::collections::HashSet<String> = self
-            .0
-            .inner
-            .inner
-            .inners
-            .iter()
-            .filter(|&p| p.hidden)
-            .map(|p| p.key.clone())
-            .collect();
-
-        self.0.next(rng).map_complete(|kv| {
-            kv.into_iter()
-                .filter(|p| match p {
-                    Some((k, _)) => !hidden_fields.contains(k),
-                    _ => true,
-                })
-                .filter_map(|m_kv| m_kv.map(|(k, vr)| vr.map(|v| (k, v))))
-                .collect::<Result<BTreeMap<_, _>, Error>>()
-                .map(|hm| hm.into())
+        let hidden_fields = &self.hidden_fields;
+        let flattened_fields = &self.flattened_fields;
+        self.inner.next(rng).map_complete(|kv| {
+            let mut object = BTreeMap::new();
+            for (k, vr) in kv.into_iter().flatten() {
+                if hidden_fields.contains(&k) {
+                    continue;
+                }
+                match vr? {
+                    // `ObjectContent` makes sure the fields of a flattened object are not
+                    // already fields of this one
+                    Value::Object(fields) if flattened_fields.contains(&k) => object.extend(fields),
+                    v => {
+                        object.insert(k, v);
+                    }
+                }
+            }
+            Ok(object.into())
         })
     }
 }
@@ -0,0 +1,356 @@
+//! Generates the values of a finite space in a random order without repeating any, by enumerating
+//! the space and going through a random permutation of its indices.
+//!
+//! The permutation is a Feistel network over the smallest power of 4 not below the size of the
+//! space, with cycle walking to stay within the space, so that nothing but its keys needs to be
+//! kept in memory.
+use super::prelude::*;
+
+use anyhow::Context;
+use regex_syntax::hir::{self, Hir, HirKind, RepetitionKind, RepetitionRange};
+
+use std::collections::HashSet;
+
+const ROUNDS: usize = 6;
+
+/// The number of repetitions `*` and `+` stand for at most in a pattern, as for the `pattern`
+/// generator.
+const MAX_REPEAT: u32 = 32;
+
+/// A random permutation of `0..size`.
+pub struct Permutation {
+    size: u128,
+    half_bits: u32,
+    keys: [u64; ROUNDS],
+}
+
+impl Permutation {
+    pub fn new<R: Rng>(size: u128, rng: &mut R) -> Self {
+        let bits = 128 - size.saturating_sub(1).leading_zeros();
+        Self {
+            size,
+            half_bits: ((bits + 1) / 2).max(1),
+            keys: rng.gen(),
+        }
+    }
+
+    pub fn apply(&self, index: u128) -> u128 {
+        // The network permutes a domain at most 4 times as large as `0..size`, so that this takes
+        // at most a few rounds on average
+        let mut permuted = self.feistel(index);
+        while permuted >= self.size {
+            permuted = self.feistel(permuted);
+        }
+        permuted
+    }
+
+    fn feistel(&self, value: u128) -> u128 {
+        let mask = (1u128 << self.half_bits) - 1;
+        let (mut left, mut right) = (value >> self.half_bits, value & mask);
+        for key in self.keys {
+            let next = left ^ (mix(right as u64 ^ key) as u128 & mask);
+            left = right;
+            right = next;
+        }
+        (left << self.half_bits) | right
+    }
+}
+
+/// The finalizer of SplitMix64.
+fn mix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+/// A finite space of values, each of which has an index in `0..size`.
+pub enum Space {
+    /// The numbers `low + index * step`, made into numbers of the right type with `to_number`.
+    Integers {
+        low: i128,
+        step: i128,
+        size: u128,
+        to_number: fn(i128) -> Number,
+    },
+    /// The strings matching a pattern.
+    Pattern(Pattern),
+}
+
+impl Space {
+    pub fn size(&self) -> u128 {
+        match self {
+            Self::Integers { size, .. } => *size,
+            Self::Pattern(pattern) => pattern.size(),
+        }
+    }
+
+    fn value(&self, index: u128) -> Value {
+        match self {
+            Self::Integers {
+                low,
+                step,
+                to_number,
+                ..
+            } => Value::Number(to_number(low + index as i128 * step)),
+            Self::Pattern(pattern) => {
+                let mut string = String::new();
+                pattern.write(index, &mut string);
+                Value::String(string)
+            }
+        }
+    }
+}
+
+/// The strings matching a regular expression, for the expressions where every string matches
+/// in only one way.
+///
+/// Sizes saturate at `u128::MAX`, in which case only the first `u128::MAX` strings are indexed.
+pub enum Pattern {
+    Literal(String),
+    /// Sorted and disjoint ranges of code points, none of which contains a surrogate.
+    Class(Vec<(u32, u32)>),
+    /// All but the last part have a fixed length.
+    Concat(Vec<Pattern>),
+    /// The inner pattern has a fixed length.
+    Repeat {
+        inner: Box<Pattern>,
+        min: u32,
+        max: u32,
+    },
+    /// Distinct literals.
+    Alternation(Vec<String>),
+}
+
+impl Pattern {
+    pub fn parse(pattern: &str) -> anyhow::Result<Self> {
+        let hir = regex_syntax::ParserBuilder::new()
+            .build()
+            .parse(pattern)
+            .with_context(|| anyhow!("invalid pattern '{}'", pattern))?;
+        Self::from_hir(&hir).with_context(|| {
+            anyhow!(
+                "cannot enumerate the strings matching the pattern '{}'",
+                pattern
+            )
+        })
+    }
+
+    fn from_hir(hir: &Hir) -> anyhow::Result<Self> {
+        let pattern = match hir.kind() {
+            HirKind::Empty | HirKind::Anchor(_) => Self::Literal(String::new()),
+            HirKind::Literal(hir::Literal::Unicode(c)) => Self::Literal(c.to_string()),
+            HirKind::Literal(hir::Literal::Byte(_)) | HirKind::Class(hir::Class::Bytes(_)) => {
+                return Err(anyhow!("patterns matching bytes are not supported"))
+            }
+            HirKind::WordBoundary(_) => return Err(anyhow!("word boundaries are not supported")),
+            HirKind::Class(hir::Class::Unicode(class)) => {
+                let mut ranges = Vec::new();
+                for range in class.iter() {
+                    let (start, end) = (range.start() as u32, range.end() as u32);
+                    // Code points between 0xD800 and 0xDFFF are not chars
+                    if start < 0xD800 && end > 0xDFFF {
+                        ranges.push((start, 0xD7FF));
+                        ranges.push((0xE000, end));
+                    } else {
+                        ranges.push((start, end));
+                    }
+                }
+                Self::Class(ranges)
+            }
+            HirKind::Group(group) => Self::from_hir(&group.hir)?,
+            HirKind::Concat(hirs) => {
+                let parts = hirs
+                    .iter()
+                    .map(Self::from_hir)
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+                let init = parts.split_last().map_or(&[][..], |(_, init)| init);
+                if init.iter().any(|part| part.fixed_len().is_none()) {
+                    return Err(anyhow!(
+                        "only the last part of a pattern can match strings of different lengths"
+                    ));
+                }
+                Self::Concat(parts)
+            }
+            HirKind::Repetition(repetition) => {
+                let (min, max) = match &repetition.kind {
+                    RepetitionKind::ZeroOrOne => (0, 1),
+                    RepetitionKind::ZeroOrMore => (0, MAX_REPEAT),
+                    RepetitionKind::OneOrMore => (1, MAX_REPEAT),
+                    RepetitionKind::Range(RepetitionRange::Exactly(n)) => (*n, *n),
+                    RepetitionKind::Range(RepetitionRange::AtLeast(n)) => {
+                        (*n, n.saturating_add(MAX_REPEAT))
+                    }
+                    RepetitionKind::Range(RepetitionRange::Bounded(m, n)) => (*m, *n),
+                };
+                let inner = Self::from_hir(&repetition.hir)?;
+                let unique = match inner.fixed_len() {
+                    Some(len) => len > 0 || min == max,
+                    None => min == max && max <= 1,
+                };
+                if !unique {
+                    return Err(anyhow!(
+                        "only patterns matching strings of a single, non-zero length can be repeated"
+                    ));
+                }
+                Self::Repeat {
+                    inner: Box::new(inner),
+                    min,
+                    max,
+                }
+            }
+            HirKind::Alternation(hirs) => {
+                let mut literals = Vec::new();
+                for hir in hirs {
+                    match Self::from_hir(hir)? {
+                        Self::Literal(literal) => literals.push(literal),
+                        Self::Concat(parts) => {
+                            let mut literal = String::new();
+                            for part in parts {
+                                match part {
+                                    Self::Literal(part) => literal.push_str(&part),
+                                    _ => {
+                                        return Err(anyhow!(
+                                            "only alternations of literals are supported"
+                                        ))
+                                    }
+                                }
+                            }
+                            literals.push(literal)
+                        }
+                        _ => return Err(anyhow!("only alternations of literals are supported")),
+                    }
+                }
+                if literals.iter().collect::<HashSet<_>>().len() != literals.len() {
+                    return Err(anyhow!("alternations cannot match the same string twice"));
+                }
+                Self::Alternation(literals)
+            }
+        };
+        Ok(pattern)
+    }
+
+    /// The length in chars of every string matching this, if they all have the same.
+    fn fixed_len(&self) -> Option<usize> {
+        match self {
+            Self::Literal(literal) => Some(literal.chars().count()),
+            Self::Class(_) => Some(1),
+            Self::Concat(parts) => parts.iter().map(Self::fixed_len).sum(),
+            Self::Repeat { inner, min, max } if min == max => {
+                inner.fixed_len().map(|len| len * *min as usize)
+            }
+            Self::Repeat { .. } => None,
+            Self::Alternation(literals) => {
+                let mut lens = literals.iter().map(|literal| literal.chars().count());
+                let len = lens.next().unwrap_or_default();
+                lens.all(|other| other == len).then_some(len)
+            }
+        }
+    }
+
+    pub fn size(&self) -> u128 {
+        match self {
+            Self::Literal(_) => 1,
+            Self::Class(ranges) => ranges
+                .iter()
+                .map(|(start, end)| (end - start + 1) as u128)
+                .sum(),
+            Self::Concat(parts) => parts
+                .iter()
+                .fold(1, |size, part| size.saturating_mul(part.size())),
+            Self::Repeat { inner, min, max } => {
+                let inner = inner.size();
+                (*min..=*max).fold(0, |size, times| {
+                    size.saturating_add(saturating_pow(inner, times))
+                })
+            }
+            Self::Alternation(literals) => literals.len() as u128,
+        }
+    }
+
+    fn write(&self, mut index: u128, out: &mut String) {
+        match self {
+            Self::Literal(literal) => out.push_str(literal),
+            Self::Class(ranges) => {
+                for (start, end) in ranges {
+                    let len = (end - start + 1) as u128;
+                    if index < len {
+                        out.push(char::from_u32(start + index as u32).unwrap());
+                        return;
+                    }
+                    index -= len;
+                }
+            }
+            Self::Concat(parts) => {
+                for part in parts {
+                    let size = part.size();
+                    part.write(index % size, out);
+                    index /= size;
+                }
+            }
+            Self::Repeat { inner, min, max } => {
+                let size = inner.size();
+                for times in *min..=*max {
+                    let of_times = saturating_pow(size, times);
+                    if index < of_times {
+                        for _ in 0..times {
+                            inner.write(index % size, out);
+                            index /= size;
+                        }
+                        return;
+                    }
+                    index -= of_times;
+                }
+            }
+            Self::Alternation(literals) => out.push_str(&literals[index as usize]),
+        }
+    }
+}
+
+fn saturating_pow(base: u128, exp: u32) -> u128 {
+    (0..exp).fold(1, |pow: u128, _| pow.saturating_mul(base))
+}
+
+/// A node that generates every value of a space once, in a random order, then fails.
+pub struct PermutationNode {
+    space: Space,
+    permutation: Option<Permutation>,
+    next: u128,
+}
+
+impl PermutationNode {
+    pub fn new(space: Space) -> Self {
+        Self {
+            space,
+            permutation: None,
+            next: 0,
+        }
+    }
+
+    fn permuted<R: Rng>(&mut self, rng: &mut R) -> Result<Value, Error> {
+        let size = self.space.size();
+        if self.next >= size {
+            return Err(failed_crate!(
+                target: Release,
+                "Could not generate enough unique values from generator: all of its {} values have been generated",
+                size
+            ));
+        }
+
+        // The permutation is drawn from the first generation's rng, so that it depends on the seed
+        let permutation = self
+            .permutation
+            .get_or_insert_with(|| Permutation::new(size, rng));
+        let index = permutation.apply(self.next);
+        self.next += 1;
+        Ok(self.space.value(index))
+    }
+}
+
+impl Generator for PermutationNode {
+    type Yield = Token;
+    type Return = Result<Value, Error>;
+
+    fn next<R: Rng>(&mut self, rng: &mut R) -> GeneratorState<Self::Yield, Self::Return> {
+        GeneratorState::Complete(self.permuted(rng))
+    }
+}
@@ -0,0 +1,142 @@
+use super::prelude::*;
+
+use super::expr::as_f64;
+use super::RecordedValues;
+
+/// How a [`SampleNode`] picks one of the values generated so far.
+pub enum Sampling {
+    /// Every value is equally likely to be picked.
+    Uniform,
+    /// The frequency of a value is inversely proportional to its rank (the first value generated
+    /// having rank 1) to the power `exponent`.
+    Zipf {
+        exponent: f64,
+        /// The distribution of the ranks, along with the number of values it was built for.
+        distribution: Option<(usize, rand_distr::Zipf<f64>)>,
+    },
+    /// The frequency of a value is proportional to the value generated alongside it by another
+    /// node.
+    Weighted {
+        weights: RecordedValues,
+        cumulative: Vec<f64>,
+    },
+}
+
+impl Sampling {
+    pub fn zipf(exponent: f64) -> Self {
+        Self::Zipf {
+            exponent,
+            distribution: None,
+        }
+    }
+
+    pub fn weighted(weights: RecordedValues) -> Self {
+        Self::Weighted {
+            weights,
+            cumulative: Vec::new(),
+        }
+    }
+}
+
+/// A node that picks its values at random from every value generated so far by the node it
+/// refers to, rather than going through them in order.
+///
+/// Nothing is picked from while the node referred to has not generated any value, in which case
+/// the node generates `null`.
+///
+/// Every value generated by the node referred to (and every weight) is kept for the whole
+/// generation, as any of them can be picked again later, so the memory used grows with the
+/// number of values referred to.
+pub struct SampleNode {
+    values: RecordedValues,
+    sampling: Sampling,
+}
+
+impl SampleNode {
+    pub fn new(values: RecordedValues, sampling: Sampling) -> Self {
+        Self { values, sampling }
+    }
+
+    fn sample<R: Rng>(&mut self, rng: &mut R) -> Result<Value, Error> {
+        let values = self.values.all();
+        if values.is_empty() {
+            return Ok(Value::Null(()));
+        }
+
+        let index = match &mut self.sampling {
+            Sampling::Uniform => rng.gen_range(0..values.len()),
+            Sampling::Zipf {
+                exponent,
+                distribution,
+            } => {
+                let rank: f64 = zipf(values.len(), *exponent, distribution)?.sample(rng);
+                rank as usize - 1
+            }
+            Sampling::Weighted {
+                weights,
+                cumulative,
+            } => {
+                let weights = weights.all();
+                let len = values.len().min(weights.len());
+                while cumulative.len() < len {
+                    let weight = match &weights[cumulative.len()] {
+                        Ok(Value::Null(_)) => 0.0,
+                        Ok(value) => {
+                            as_f64(value)
+                                .filter(|weight| *weight >= 0.0)
+                                .ok_or_else(|| {
+                                    failed_crate!(
+                                        target: Release,
+                                        "weights must be non-negative numbers, found a {}",
+                                        value.type_()
+                                    )
+                                })?
+                        }
+                        Err(err) => return Err(err.clone()),
+                    };
+                    cumulative.push(cumulative.last().copied().unwrap_or_default() + weight);
+                }
+
+                match cumulative[..len].last() {
+                    Some(total) if *total > 0.0 => {
+                        let at = rng.gen::<f64>() * total;
+                        cumulative[..len]
+                            .partition_point(|sum| *sum <= at)
+                            .min(len - 1)
+                    }
+                    _ => return Ok(Value::Null(())),
+                }
+            }
+        };
+
+        values[index].clone()
+    }
+}
+
+/// The distribution of the ranks of `len` values, which is only built again once new values
+/// were generated.
+fn zipf(
+    len: usize,
+    exponent: f64,
+    distribution: &mut Option<(usize, rand_distr::Zipf<f64>)>,
+) -> Result<rand_distr::Zipf<f64>, Error> {
+    match distribution {
+        Some((built_for, zipf)) if *built_for == len => Ok(*zipf),
+        _ => {
+            let zipf = rand_distr::Zipf::new(len as u64, exponent).map_err(
+                |err| failed_crate!(target: Release, "invalid zipf exponent {}: {}", exponent, err),
+            )?;
+            *distribution = Some((len, zipf));
+            Ok(zipf)
+        }
+    }
+}
+
+impl Generator for SampleNode {
+    type Yield = Token;
+    type Return = Result<Value, Error>;
+
+    fn next<R: Rng>(&mut self, rng: &mut R) -> GeneratorState<Self::Yield, Self::Return> {
+        GeneratorState::Complete(self.sample(rng))
+    }
+}
@@ -0,0 +1,126 @@
+use super::prelude::*;
+
+use super::json::synth_val_to_json;
+
+/// A node that generates with the branch whose case matches the value of `on`, or with `default`
+/// if none does.
+pub struct SwitchNode {
+    on: Box<Graph>,
+    cases: Vec<(serde_json::Value, Graph)>,
+    default: Box<Graph>,
+    active: Option<usize>,
+}
+
+impl SwitchNode {
+    pub fn new(on: Graph, cases: Vec<(serde_json::Value, Graph)>, default: Graph) -> Self {
+        Self {
+            on: Box::new(on),
+            cases,
+            default: Box::new(default),
+            active: None,
+        }
+    }
+
+    fn branch(&mut self, idx: usize) -> &mut Graph {
+        match self.cases.get_mut(idx) {
+            Some((_, graph)) => graph,
+            None => &mut self.default,
+        }
+    }
+}
+
+impl Generator for SwitchNode {
+    type Yield = Token;
+    type Return = Result<Value, Error>;
+
+    fn next<R: Rng>(&mut self, rng: &mut R) -> GeneratorState<Self::Yield, Self::Return> {
+        let idx = match self.active {
+            Some(idx) => idx,
+            None => {
+                let on = match self.on.complete(rng) {
+                    Ok(value) => synth_val_to_json(value),
+                    Err(err) => return GeneratorState::Complete(Err(err)),
+                };
+                let idx = self
+                    .cases
+                    .iter()
+                    .position(|(when, _)| *when == on)
+                    .unwrap_or(self.cases.len());
+                self.active = Some(idx);
+                idx
+            }
+        };
+
+        let state = self.branch(idx).next(rng);
+        if state.is_complete() {
+            self.active = None;
+        }
+        state
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::graph::Value;
+    use crate::tests::complete;
+
+    #[test]
+    fn switch_on_sibling() {
+        let generator = generator!({
+            "type": "object",
+            "orders": {
+                "type": "array",
+                "length": 64,
+                "content": {
+                    "type": "object",
+                    "status": {
+                        "type": "string",
+                        "categorical": {
+                            "pending": 1,
+                            "shipped": 1,
+                            "returned": 1
+                        }
+                    },
+                    "shipped_at": {
+                        "type": "switch",
+                        "on": "orders.content.status",
+                        "cases": [
+                            {
+                                "when": "shipped",
+                                "content": {
+                                    "type": "date_time",
+                                    "format": "%Y-%m-%d",
+                                    "begin": "2021-01-01",
+                                    "end": "2021-12-31"
+                                }
+                            },
+                            {
+                                "when": "returned",
+                                "content": {
+                                    "type": "string",
+                                    "pattern": "returned"
+                                }
+                            }
+                        ]
+                    }
+                }
+            }
+        });
+
+        let value = complete(generator).unwrap();
+        let orders = value.as_object().unwrap().get("orders").unwrap();
+        for order in orders.as_array().unwrap() {
+            let order = order.as_object().unwrap();
+            let shipped_at = order.get("shipped_at").unwrap();
+            match order.get("status").unwrap() {
+                Value::String(status) if status == "shipped" => {
+                    assert!(matches!(shipped_at, Value::DateTime(_)))
+                }
+                Value::String(status) if status == "returned" => {
+                    assert_eq!(*shipped_at, Value::String("returned".to_string()))
+                }
+                _ => assert_eq!(*shipped_at, Value::Null(())),
+            }
+        }
+    }
+}
@@ -1,7 +1,9 @@
 use crate::graph::prelude::{Error, Rng, Token, TryFilterMap, TryGeneratorExt, Value};
+use crate::graph::PermutationNode;
 use crate::Graph;
 
-use std::collections::HashMap;
+use std::collections::hash_map::RandomState;
+use std::collections::{HashMap, HashSet};
 use std::hash::{BuildHasher, Hash, Hasher};
 
 const MAX_RETRIES: usize = 64;
@@ -14,6 +16,7 @@ derive_generator! {
     return Result<Value, Error>,
     pub enum UniqueNode {
         Hash(ValueFilter),
+        Permutation(PermutationNode),
     }
 }
 
@@ -44,15 +47,75 @@ impl UniqueNode {
         };
         Self::Hash(Box::new(inner).try_filter_map(Box::new(filter)))
     }
+
+    /// Generates objects again while their values for one of the sets of `fields` were already
+    /// generated. As for the unique keys of SQL, values of a set including a `null` never repeat
+    /// earlier ones.
+    pub fn together(inner: Graph, fields: Vec<Vec<String>>) -> Self {
+        let state = RandomState::new();
+        let mut seen: Vec<HashSet<u64>> = vec![HashSet::new(); fields.len()];
+        let mut retries = 0;
+        let filter = move |value: Value| {
+            let object = match &value {
+                Value::Object(object) => object,
+                _ => return Ok(Some(value)),
+            };
+
+            let hashes: Vec<Option<u64>> = fields
+                .iter()
+                .map(|fields| {
+                    let mut hasher = state.build_hasher();
+                    for field in fields {
+                        match object.get(field) {
+                            None | Some(Value::Null(_)) => return None,
+                            Some(value) => value.hash(&mut hasher),
+                        }
+                    }
+                    Some(hasher.finish())
+                })
+                .collect();
+
+            let repeated = hashes
+                .iter()
+                .zip(&seen)
+                .any(|(hash, seen)| matches!(hash, Some(hash) if seen.contains(hash)));
+            if !repeated {
+                for (hash, seen) in hashes.into_iter().zip(&mut seen) {
+                    seen.extend(hash);
+                }
+                retries = 0;
+                Ok(Some(value))
+            } else if retries < MAX_RETRIES {
+                retries += 1;
+                Ok(None)
+            } else {
+                Err(failed_crate!(
+                    target: Release,
+                    "Could not generate enough unique values for the fields {}: \
+                    try reducing the number of values generated",
+                    fields
+                        .iter()
+                        .map(|fields| format!("({})", fields.join(", ")))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ))
+            }
+        };
+        Self::Hash(Box::new(inner).try_filter_map(Box::new(filter)))
+    }
 }
 
 #[cfg(test)]
 pub mod tests {
     use super::*;
     use crate::graph::{
-        prelude::{Generator, GeneratorExt},
-        Graph, NumberNode, RandFaker, RandomString, RandomU64, RangeStep, StringNode,
+        permutation::{Pattern, Space},
+        prelude::{Generator, GeneratorExt, Number},
+        Graph, KeyValueOrNothing, NumberNode, ObjectNode, RandFaker, RandomString, RandomU64,
+        RangeStep, StringNode,
     };
+    use rand::SeedableRng;
+    use std::collections::HashSet;
 
     const NUM_GENERATED: usize = 1024;
 
@@ -86,4 +149,101 @@ pub mod tests {
 
         assert!(output.iter().any(Result::is_err));
     }
+
+    #[test]
+    fn unique_node_permutation() {
+        let integers = || Space::Integers {
+            low: 3,
+            step: 2,
+            size: 500,
+            to_number: |n| Number::from(n as u64),
+        };
+        let output = UniqueNode::Permutation(PermutationNode::new(integers()))
+            .repeat(500)
+            .complete(&mut rand::rngs::StdRng::seed_from_u64(7))
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        let expected: HashSet<_> = (0..500)
+            .map(|i| Value::Number(Number::from(3 + 2 * i as u64)))
+            .collect();
+        assert_eq!(output.iter().cloned().collect::<HashSet<_>>(), expected);
+
+        // The same seed gives the same permutation
+        let again = UniqueNode::Permutation(PermutationNode::new(integers()))
+            .repeat(500)
+            .complete(&mut rand::rngs::StdRng::seed_from_u64(7))
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(output, again);
+
+        let mut rng = rand::thread_rng();
+        let output = UniqueNode::Permutation(PermutationNode::new(integers()))
+            .repeat(501)
+            .complete(&mut rng);
+        assert!(output[..500].iter().all(Result::is_ok));
+        assert!(output[500].is_err());
+
+        let pattern = Pattern::parse("[A-C]{2}-(ab|cd)[0-9]{0,2}").unwrap();
+        assert_eq!(pattern.size(), 9 * 2 * 111);
+        let output = UniqueNode::Permutation(PermutationNode::new(Space::Pattern(pattern)))
+            .repeat(9 * 2 * 111)
+            .complete(&mut rng)
+            .into_iter()
+            .collect::<Result<HashSet<_>, _>>()
+            .unwrap();
+        assert_eq!(output.len(), 9 * 2 * 111);
+        let regex = regex::Regex::new("^[A-C]{2}-(ab|cd)[0-9]{0,2}$").unwrap();
+        for value in output {
+            match value {
+                Value::String(string) => assert!(regex.is_match(&string)),
+                other => panic!("expected a string, found {:?}", other),
+            }
+        }
+
+        assert!(Pattern::parse("[a-z]+[0-9]").is_err());
+        assert!(Pattern::parse("(ab|ab)").is_err());
+        assert!(Pattern::parse("(a|bc)+").is_err());
+    }
+
+    #[test]
+    fn unique_node_together() {
+        let pairs = || {
+            let digit = || {
+                Graph::Number(NumberNode::from(
+                    RandomU64::range(RangeStep::new(0, 8, 1)).unwrap(),
+                ))
+            };
+            Graph::Object(ObjectNode::from_iter(vec![
+                KeyValueOrNothing::always("a", digit(), false),
+                KeyValueOrNothing::always("b", digit(), false),
+                KeyValueOrNothing::always("c", digit(), false),
+            ]))
+        };
+        let together = vec![vec!["a".to_string(), "b".to_string()]];
+
+        let mut rng = rand::thread_rng();
+        let output = UniqueNode::together(pairs(), together.clone())
+            .repeat(32)
+            .complete(&mut rng)
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let unique_pairs = output
+            .iter()
+            .map(|value| match value {
+                Value::Object(object) => (object["a"].clone(), object["b"].clone()),
+                other => panic!("expected an object, found {:?}", other),
+            })
+            .collect::<HashSet<_>>();
+        assert_eq!(unique_pairs.len(), 32);
+
+        // There are only 64 distinct pairs
+        let output = UniqueNode::together(pairs(), together)
+            .repeat(65)
+            .complete(&mut rng);
+        assert!(output.iter().any(Result::is_err));
+    }
 }
@@ -1,7 +1,7 @@
 use super::prelude::*;
 use crate::graph::prelude::content::number::number_content::U64;
 use crate::graph::prelude::VariantContent;
-use crate::schema::{number_content, NumberContent, RangeStep};
+use crate::schema::{number_content, FieldRef, NumberContent, RangeStep, SameAsContent};
 use serde::de;
 use std::fmt;
 
@@ -12,6 +12,14 @@ pub struct ArrayContent {
     #[serde(default)]
     pub length: Box<Content>,
     pub content: Box<Content>,
+    /// The number of elements to generate for the collection this array is the top-level node of,
+    /// unless it is overridden by `--size`. Ignored anywhere else.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<usize>,
+    /// If set, `length` elements are generated for each value generated for `parent` instead,
+    /// with the fields of `content` referring to `parent` set to that value.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent: Option<FieldRef>,
 }
 
 lazy_static! {
@@ -29,6 +37,8 @@ impl<'de> Deserialize<'de> for ArrayContent {
         enum Field {
             Length,
             Content,
+            Size,
+            Parent,
         }
 
         struct ArrayVisitor;
@@ -46,6 +56,8 @@ impl<'de> Deserialize<'de> for ArrayContent {
             {
                 let mut length = None;
                 let mut content = None;
+                let mut size = None;
+                let mut parent = None;
 
                 while let Some(key) = access.next_key()? {
                     match key {
@@ -63,6 +75,20 @@ impl<'de> Deserialize<'de> for ArrayContent {
 
                             content = Some(access.next_value()?);
                         }
+                        Field::Size => {
+                            if size.is_some() {
+                                return Err(de::Error::duplicate_field("size"));
+                            }
+
+                            size = Some(access.next_value()?);
+                        }
+                        Field::Parent => {
+                            if parent.is_some() {
+                                return Err(de::Error::duplicate_field("parent"));
+                            }
+
+                            parent = Some(access.next_value()?);
+                        }
                     }
                 }
 
@@ -126,11 +152,13 @@ impl<'de> Deserialize<'de> for ArrayContent {
                 Ok(ArrayContent {
                     length: Box::new(length),
                     content: Box::new(content),
+                    size,
+                    parent,
                 })
             }
         }
 
-        const FIELDS: &[&str] = &["length", "content"];
+        const FIELDS: &[&str] = &["length", "content", "size", "parent"];
         deserializer.deserialize_struct("ArrayContent", FIELDS, ArrayVisitor)
     }
 }
@@ -142,15 +170,67 @@ impl ArrayContent {
                 RangeStep::new(1, 2, 1),
             )))),
             content: Box::new(content),
+            size: None,
+            parent: None,
+        }
+    }
+}
+
+impl ArrayContent {
+    /// The fields of `content` referring to `parent`, which are set to the value of the parent of
+    /// each element.
+    fn parent_fields(&self, parent: &FieldRef) -> Result<Vec<String>> {
+        let fields: Vec<String> = match self.content.as_ref() {
+            Content::Object(object) => object
+                .fields
+                .iter()
+                .filter(|(_, field)| {
+                    matches!(
+                        field,
+                        Content::SameAs(SameAsContent { ref_, sample: None }) if ref_ == parent
+                    )
+                })
+                .map(|(name, _)| name.clone())
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        if fields.is_empty() {
+            Err(failed!(
+                target: Release,
+                "the content of an array with a parent must be an object with a field referring to its parent: try adding '\"{}\": \"@{}\"' to the content",
+                parent.last(),
+                parent
+            ))
+        } else {
+            Ok(fields)
         }
     }
 }
 
 impl Compile for ArrayContent {
     fn compile<'a, C: Compiler<'a>>(&'a self, mut compiler: C) -> Result<Graph> {
-        let length = compiler.build("length", self.length.as_ref())?.into_size();
+        let parent = match &self.parent {
+            Some(parent) => parent,
+            None => {
+                let length = compiler.build("length", self.length.as_ref())?.into_size();
+                let content = compiler.build("content", &self.content)?;
+                return Ok(Graph::Array(ArrayNode::new_with(length, content)));
+            }
+        };
+
+        let fields = self.parent_fields(parent)?;
+        let parents = compiler.get(parent.clone())?;
+        let length = compiler.build("length", self.length.as_ref())?;
         let content = compiler.build("content", &self.content)?;
-        Ok(Graph::Array(ArrayNode::new_with(length, content)))
+        match parents.into_recorded_values() {
+            Ok(parents) => Ok(Graph::Children(ChildrenNode::new(
+                parents, length, content, fields,
+            ))),
+            // Only the references of a graph being built can be read from, not those returned
+            // while looking for its dependencies
+            Err(parents) => Ok(parents),
+        }
     }
 }
 
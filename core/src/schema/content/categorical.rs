@@ -48,6 +48,15 @@ impl<T: CategoricalType> Categorical<T> {
         };
         self.total += 1;
     }
+
+    /// Forgets how often each value was seen, keeping the values, so that the values pushed next
+    /// weigh them. Values which aren't pushed again are no longer sampled.
+    pub fn clear_occurrences(&mut self) {
+        for occurrences in self.seen.values_mut() {
+            *occurrences = 0;
+        }
+        self.total = 0;
+    }
 }
 
 /// This struct purely serves as an intermediary to check invariants in the
@@ -106,6 +115,12 @@ pub mod tests {
         assert_eq!(categorical.seen.get("a").unwrap(), &6);
         assert_eq!(categorical.seen.get("b").unwrap(), &11);
         assert_eq!(categorical.total, 17);
+
+        categorical.clear_occurrences();
+        categorical.push("b".to_string());
+        assert_eq!(categorical.seen.get("a").unwrap(), &0);
+        assert_eq!(categorical.seen.get("b").unwrap(), &1);
+        assert_eq!(categorical.total, 1);
     }
 
     #[test]
@@ -0,0 +1,845 @@
+//! A small expression language to compute a value from the values of other fields.
+//!
+//! ```text
+//! expr    := or
+//! or      := and ("||" and)*
+//! and     := compare ("&&" compare)*
+//! compare := sum (("==" | "!=" | "<" | "<=" | ">" | ">=") sum)?
+//! sum     := product (("+" | "-") product)*
+//! product := unary (("*" | "/" | "%") unary)*
+//! unary   := ("-" | "!") unary | primary
+//! primary := number | string | "true" | "false" | "null" | "now"
+//!          | "@" field_ref | function "(" (expr ("," expr)*)? ")" | "(" expr ")"
+//! ```
+//!
+//! References to other fields (`@users.content.birth_date`) are written as in `same_as` and are
+//! compiled with [`Compiler::get`](crate::compile::Compiler::get).
+//!
+//! `now` stands for the instant set by the `now` field of the content, so that the values
+//! generated from it don't depend on when they are generated.
+use super::prelude::*;
+
+use crate::schema::FieldRef;
+
+use std::hash::Hasher;
+
+/// The format of `now`, both in the schema and in the values it evaluates to.
+pub const NOW_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%z";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UnaryOp {
+    Neg,
+    Not,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    And,
+    Or,
+}
+
+impl BinaryOp {
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Self::Add => "+",
+            Self::Sub => "-",
+            Self::Mul => "*",
+            Self::Div => "/",
+            Self::Rem => "%",
+            Self::Eq => "==",
+            Self::Ne => "!=",
+            Self::Lt => "<",
+            Self::Le => "<=",
+            Self::Gt => ">",
+            Self::Ge => ">=",
+            Self::And => "&&",
+            Self::Or => "||",
+        }
+    }
+}
+
+macro_rules! expr_functions {
+    {
+        $($variant:ident($name:literal, $min:literal..=$max:expr),)*
+    } => {
+        #[derive(Debug, Clone, Copy, PartialEq)]
+        pub enum Function {
+            $($variant,)*
+        }
+
+        impl Function {
+            fn from_name(name: &str) -> Option<Self> {
+                match name {
+                    $($name => Some(Self::$variant),)*
+                    _ => None,
+                }
+            }
+
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $(Self::$variant => $name,)*
+                }
+            }
+
+            fn arity(&self) -> (usize, usize) {
+                match self {
+                    $(Self::$variant => ($min, $max),)*
+                }
+            }
+        }
+    };
+}
+
+expr_functions! {
+    Min("min", 1..=usize::MAX),
+    Max("max", 1..=usize::MAX),
+    Round("round", 1..=2),
+    Floor("floor", 1..=1),
+    Ceil("ceil", 1..=1),
+    Abs("abs", 1..=1),
+    Concat("concat", 1..=usize::MAX),
+    If("if", 3..=3),
+    Now("now", 0..=0),
+    Year("year", 1..=1),
+    Month("month", 1..=1),
+    Day("day", 1..=1),
+    Hour("hour", 1..=1),
+    Minute("minute", 1..=1),
+    Second("second", 1..=1),
+    AddDays("add_days", 2..=2),
+    AddHours("add_hours", 2..=2),
+    AddMinutes("add_minutes", 2..=2),
+    AddSeconds("add_seconds", 2..=2),
+    DaysBetween("days_between", 2..=2),
+    SecondsBetween("seconds_between", 2..=2),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+    /// The value of the field at this index of [`ExprContent::refs`].
+    Ref(usize),
+    Unary(UnaryOp, Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    Call(Function, Vec<Expr>),
+}
+
+#[derive(Debug, Clone)]
+pub struct ExprContent {
+    source: String,
+    expr: Expr,
+    refs: Vec<FieldRef>,
+    now: Option<DateTime<FixedOffset>>,
+}
+
+impl ExprContent {
+    pub fn parse(source: &str) -> Result<Self> {
+        let mut parser = ExprParser {
+            tokens: lex(source)?,
+            position: 0,
+            refs: Vec::new(),
+        };
+
+        let expr = parser.parse_expr()?;
+        if let Some(token) = parser.peek() {
+            return Err(failed!(
+                target: Release,
+                "unexpected `{}` in expression `{}`",
+                token,
+                source
+            ));
+        }
+
+        Ok(Self {
+            source: source.to_string(),
+            expr,
+            refs: parser.refs,
+            now: None,
+        })
+    }
+
+    /// Sets the instant `now` stands for in the expression.
+    pub fn with_now(mut self, now: DateTime<FixedOffset>) -> Self {
+        self.now = Some(now);
+        self
+    }
+
+    pub fn expr(&self) -> &Expr {
+        &self.expr
+    }
+
+    /// The fields referenced by the expression, in the order of their first appearance.
+    pub fn refs(&self) -> &[FieldRef] {
+        &self.refs
+    }
+}
+
+impl PartialEq for ExprContent {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source && self.now == other.now
+    }
+}
+
+impl Hash for ExprContent {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.source.hash(state);
+        self.now.hash(state);
+    }
+}
+
+impl Serialize for ExprContent {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeStruct;
+        let mut s = serializer.serialize_struct("ExprContent", 2)?;
+        s.serialize_field("expr", &self.source)?;
+        if let Some(now) = &self.now {
+            s.serialize_field("now", &now.format(NOW_FORMAT).to_string())?;
+        }
+        s.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for ExprContent {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct ExprSource {
+            expr: String,
+            #[serde(default)]
+            now: Option<String>,
+        }
+
+        let ExprSource { expr, now } = ExprSource::deserialize(deserializer)?;
+        let content = Self::parse(&expr).map_err(D::Error::custom)?;
+        match now {
+            Some(now) => DateTime::parse_from_str(&now, NOW_FORMAT)
+                .map(|now| content.with_now(now))
+                .map_err(|err| {
+                    D::Error::custom(format!(
+                        "invalid `now` '{}': {} (expected the format '{}')",
+                        now, err, NOW_FORMAT
+                    ))
+                }),
+            None => Ok(content),
+        }
+    }
+}
+
+impl Compile for ExprContent {
+    fn compile<'a, C: Compiler<'a>>(&'a self, mut compiler: C) -> Result<Graph> {
+        let types = self
+            .refs
+            .iter()
+            .map(|ref_| {
+                compiler
+                    .content(ref_.clone())
+                    .map_or(ExprType::Unknown, ExprType::of)
+            })
+            .collect::<Vec<_>>();
+        self.check(&self.expr, &types)
+            .with_context(|| anyhow!("in expression `{}`", self.source))?;
+
+        let args = self
+            .refs
+            .iter()
+            .map(|ref_| compiler.get(ref_.clone()))
+            .collect::<Result<Vec<_>>>()?;
+        let node = ExprNode::new(self.expr.clone(), args, self.now);
+        Ok(Graph::Expr(node))
+    }
+}
+
+/// The type of the values an expression evaluates to, as far as it is known before generating
+/// them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ExprType {
+    Null,
+    Bool,
+    Number,
+    String,
+    DateTime,
+    /// Fields that are computed from others, or that can take values of different types, are only
+    /// checked when generating.
+    Unknown,
+}
+
+impl ExprType {
+    fn of(content: &Content) -> Self {
+        match content {
+            Content::Null(_) => Self::Null,
+            Content::Bool(_) => Self::Bool,
+            Content::Number(_) => Self::Number,
+            Content::String(_) => Self::String,
+            Content::DateTime(_) => Self::DateTime,
+            Content::Unique(unique) => Self::of(&unique.content),
+            Content::Hidden(hidden) => Self::of(&hidden.content),
+            Content::OneOf(one_of) => one_of
+                .variants
+                .iter()
+                .map(|variant| Self::of(&variant.content))
+                .reduce(Self::join)
+                .unwrap_or(Self::Unknown),
+            _ => Self::Unknown,
+        }
+    }
+
+    /// The type of a value which is either of type `self` or of type `other`.
+    fn join(self, other: Self) -> Self {
+        if self == other {
+            self
+        } else {
+            Self::Unknown
+        }
+    }
+
+    /// Whether values of these types may be compared to each other.
+    fn is_comparable_with(self, other: Self) -> bool {
+        self == other || self == Self::Unknown || other == Self::Unknown
+    }
+}
+
+impl Display for ExprType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Null => write!(f, "null"),
+            Self::Bool => write!(f, "bool"),
+            Self::Number => write!(f, "number"),
+            Self::String => write!(f, "string"),
+            Self::DateTime => write!(f, "date_time"),
+            Self::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
+/// Checks that `found` is `expected`, if it is known, for the operator or function `name`.
+fn expect_type(name: &str, expected: ExprType, found: ExprType) -> Result<()> {
+    if found == expected || found == ExprType::Unknown {
+        Ok(())
+    } else {
+        Err(failed!(
+            target: Release,
+            "`{}` expects a {}, found a {}",
+            name,
+            expected,
+            found
+        ))
+    }
+}
+
+fn expect_comparable(name: &str, lhs: ExprType, rhs: ExprType) -> Result<()> {
+    if lhs.is_comparable_with(rhs) {
+        Ok(())
+    } else {
+        Err(failed!(
+            target: Release,
+            "cannot compare a {} and a {} with `{}`",
+            lhs,
+            rhs,
+            name
+        ))
+    }
+}
+
+impl ExprContent {
+    /// Infers the type of `expr` from the types of the fields it references, failing if an
+    /// operator or a function is given operands it isn't defined for.
+    fn check(&self, expr: &Expr, refs: &[ExprType]) -> Result<ExprType> {
+        match expr {
+            Expr::Null => Ok(ExprType::Null),
+            Expr::Bool(_) => Ok(ExprType::Bool),
+            Expr::Int(_) | Expr::Float(_) => Ok(ExprType::Number),
+            Expr::String(_) => Ok(ExprType::String),
+            Expr::Ref(index) => Ok(refs[*index]),
+            Expr::Unary(UnaryOp::Neg, operand) => {
+                expect_type("-", ExprType::Number, self.check(operand, refs)?)?;
+                Ok(ExprType::Number)
+            }
+            Expr::Unary(UnaryOp::Not, operand) => {
+                expect_type("!", ExprType::Bool, self.check(operand, refs)?)?;
+                Ok(ExprType::Bool)
+            }
+            Expr::Binary(op, lhs, rhs) => {
+                let (lhs, rhs) = (self.check(lhs, refs)?, self.check(rhs, refs)?);
+                let symbol = op.symbol();
+                match op {
+                    BinaryOp::Add if lhs == ExprType::String && rhs == ExprType::String => {
+                        Ok(ExprType::String)
+                    }
+                    // `+` joins strings too, so it is only known to be numerical once either side
+                    // is known not to be a string
+                    BinaryOp::Add
+                        if matches!(
+                            (lhs, rhs),
+                            (
+                                ExprType::String | ExprType::Unknown,
+                                ExprType::String | ExprType::Unknown
+                            )
+                        ) =>
+                    {
+                        Ok(ExprType::Unknown)
+                    }
+                    BinaryOp::Add
+                    | BinaryOp::Sub
+                    | BinaryOp::Mul
+                    | BinaryOp::Div
+                    | BinaryOp::Rem => {
+                        expect_type(symbol, ExprType::Number, lhs)?;
+                        expect_type(symbol, ExprType::Number, rhs)?;
+                        Ok(ExprType::Number)
+                    }
+                    BinaryOp::Eq | BinaryOp::Ne => Ok(ExprType::Bool),
+                    BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge => {
+                        expect_comparable(symbol, lhs, rhs)?;
+                        Ok(ExprType::Bool)
+                    }
+                    BinaryOp::And | BinaryOp::Or => {
+                        expect_type(symbol, ExprType::Bool, lhs)?;
+                        expect_type(symbol, ExprType::Bool, rhs)?;
+                        Ok(ExprType::Bool)
+                    }
+                }
+            }
+            Expr::Call(function, args) => {
+                let name = function.name();
+                let args = args
+                    .iter()
+                    .map(|arg| self.check(arg, refs))
+                    .collect::<Result<Vec<_>>>()?;
+                match function {
+                    Function::Min | Function::Max => {
+                        let known = args
+                            .iter()
+                            .copied()
+                            .find(|arg| *arg != ExprType::Unknown)
+                            .unwrap_or(ExprType::Unknown);
+                        for arg in &args {
+                            expect_comparable(name, known, *arg)?;
+                        }
+                        Ok(args.into_iter().reduce(ExprType::join).unwrap())
+                    }
+                    Function::Round | Function::Floor | Function::Ceil | Function::Abs => {
+                        for arg in args {
+                            expect_type(name, ExprType::Number, arg)?;
+                        }
+                        Ok(ExprType::Number)
+                    }
+                    Function::Concat => Ok(ExprType::String),
+                    Function::If => {
+                        expect_type(name, ExprType::Bool, args[0])?;
+                        Ok(args[1].join(args[2]))
+                    }
+                    Function::Now => match self.now {
+                        Some(_) => Ok(ExprType::DateTime),
+                        None => Err(failed!(
+                            target: Release,
+                            "`now` is not set: add a `now` field with the instant it stands for, formatted as '{}'",
+                            NOW_FORMAT
+                        )),
+                    },
+                    Function::Year
+                    | Function::Month
+                    | Function::Day
+                    | Function::Hour
+                    | Function::Minute
+                    | Function::Second => {
+                        expect_type(name, ExprType::DateTime, args[0])?;
+                        Ok(ExprType::Number)
+                    }
+                    Function::AddDays
+                    | Function::AddHours
+                    | Function::AddMinutes
+                    | Function::AddSeconds => {
+                        expect_type(name, ExprType::DateTime, args[0])?;
+                        expect_type(name, ExprType::Number, args[1])?;
+                        Ok(ExprType::DateTime)
+                    }
+                    Function::DaysBetween | Function::SecondsBetween => {
+                        expect_type(name, ExprType::DateTime, args[0])?;
+                        expect_type(name, ExprType::DateTime, args[1])?;
+                        Ok(ExprType::Number)
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum ExprToken {
+    Int(i64),
+    Float(f64),
+    String(String),
+    Ident(String),
+    Ref(FieldRef),
+    Symbol(&'static str),
+}
+
+impl Display for ExprToken {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Int(i) => write!(f, "{}", i),
+            Self::Float(x) => write!(f, "{}", x),
+            Self::String(s) => write!(f, "\"{}\"", s),
+            Self::Ident(ident) => write!(f, "{}", ident),
+            Self::Ref(ref_) => write!(f, "@{}", ref_),
+            Self::Symbol(symbol) => write!(f, "{}", symbol),
+        }
+    }
+}
+
+const SYMBOLS: [&str; 17] = [
+    "==", "!=", "<=", ">=", "&&", "||", "<", ">", "+", "-", "*", "/", "%", "!", "(", ")", ",",
+];
+
+fn lex(source: &str) -> Result<Vec<ExprToken>> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit()
+            || (c == '.' && chars.get(i + 1).map_or(false, char::is_ascii_digit))
+        {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            let literal: String = chars[start..i].iter().collect();
+            let token = if literal.contains('.') {
+                literal.parse().map(ExprToken::Float).ok()
+            } else {
+                literal.parse().map(ExprToken::Int).ok()
+            };
+            tokens.push(token.ok_or_else(
+                || failed!(target: Release, "invalid number `{}` in expression", literal),
+            )?);
+        } else if c == '"' || c == '\'' {
+            let mut literal = String::new();
+            i += 1;
+            loop {
+                match chars.get(i) {
+                    None => {
+                        return Err(failed!(
+                            target: Release,
+                            "unterminated string in expression `{}`",
+                            source
+                        ))
+                    }
+                    Some('\\') if i + 1 < chars.len() => {
+                        literal.push(chars[i + 1]);
+                        i += 2;
+                    }
+                    Some(next) if *next == c => {
+                        i += 1;
+                        break;
+                    }
+                    Some(next) => {
+                        literal.push(*next);
+                        i += 1;
+                    }
+                }
+            }
+            tokens.push(ExprToken::String(literal));
+        } else if c == '@' {
+            // Field references end at the first character that can't be part of one. Chunks
+            // containing other characters can be quoted, as in `same_as`.
+            let start = i + 1;
+            i += 1;
+            while i < chars.len() {
+                match chars[i] {
+                    '"' => {
+                        i += 1;
+                        while i < chars.len() && chars[i] != '"' {
+                            i += 1;
+                        }
+                        i += 1;
+                    }
+                    c if c.is_alphanumeric() || c == '_' || c == '.' => i += 1,
+                    _ => break,
+                }
+            }
+            let ref_: String = chars[start..i.min(chars.len())].iter().collect();
+            tokens.push(ExprToken::Ref(ref_.parse().with_context(|| {
+                anyhow!("invalid field reference `@{}` in expression", ref_)
+            })?));
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(ExprToken::Ident(chars[start..i].iter().collect()));
+        } else {
+            let rest: String = chars[i..chars.len().min(i + 2)].iter().collect();
+            let symbol = SYMBOLS
+                .iter()
+                .find(|symbol| rest.starts_with(*symbol))
+                .ok_or_else(|| {
+                    failed!(
+                        target: Release,
+                        "unexpected character `{}` in expression `{}`",
+                        c,
+                        source
+                    )
+                })?;
+            i += symbol.len();
+            tokens.push(ExprToken::Symbol(symbol));
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct ExprParser {
+    tokens: Vec<ExprToken>,
+    position: usize,
+    refs: Vec<FieldRef>,
+}
+
+impl ExprParser {
+    fn peek(&self) -> Option<&ExprToken> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Result<ExprToken> {
+        let token = self
+            .tokens
+            .get(self.position)
+            .cloned()
+            .ok_or_else(|| failed!(target: Release, "unexpected end of expression"))?;
+        self.position += 1;
+        Ok(token)
+    }
+
+    fn eat(&mut self, symbol: &str) -> bool {
+        if matches!(self.peek(), Some(ExprToken::Symbol(s)) if *s == symbol) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, symbol: &str) -> Result<()> {
+        match self.next()? {
+            ExprToken::Symbol(s) if s == symbol => Ok(()),
+            token => Err(failed!(
+                target: Release,
+                "expected `{}` in expression, found `{}`",
+                symbol,
+                token
+            )),
+        }
+    }
+
+    fn parse_expr(&mut self) -> Result<Expr> {
+        self.parse_binary(0)
+    }
+
+    /// Parses a chain of binary operations, from the loosest binding (`||`) to the tightest
+    /// (`*`, `/` and `%`).
+    fn parse_binary(&mut self, level: usize) -> Result<Expr> {
+        const LEVELS: [&[(&str, BinaryOp)]; 5] = [
+            &[("||", BinaryOp::Or)],
+            &[("&&", BinaryOp::And)],
+            &[
+                ("==", BinaryOp::Eq),
+                ("!=", BinaryOp::Ne),
+                ("<=", BinaryOp::Le),
+                (">=", BinaryOp::Ge),
+                ("<", BinaryOp::Lt),
+                (">", BinaryOp::Gt),
+            ],
+            &[("+", BinaryOp::Add), ("-", BinaryOp::Sub)],
+            &[
+                ("*", BinaryOp::Mul),
+                ("/", BinaryOp::Div),
+                ("%", BinaryOp::Rem),
+            ],
+        ];
+
+        if level == LEVELS.len() {
+            return self.parse_unary();
+        }
+
+        let mut lhs = self.parse_binary(level + 1)?;
+        while let Some((_, op)) = LEVELS[level]
+            .iter()
+            .find(|(symbol, _)| matches!(self.peek(), Some(ExprToken::Symbol(s)) if s == symbol))
+        {
+            self.position += 1;
+            let rhs = self.parse_binary(level + 1)?;
+            lhs = Expr::Binary(*op, Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr> {
+        if self.eat("-") {
+            Ok(Expr::Unary(UnaryOp::Neg, Box::new(self.parse_unary()?)))
+        } else if self.eat("!") {
+            Ok(Expr::Unary(UnaryOp::Not, Box::new(self.parse_unary()?)))
+        } else {
+            self.parse_primary()
+        }
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr> {
+        match self.next()? {
+            ExprToken::Int(i) => Ok(Expr::Int(i)),
+            ExprToken::Float(x) => Ok(Expr::Float(x)),
+            ExprToken::String(s) => Ok(Expr::String(s)),
+            ExprToken::Ref(ref_) => {
+                let index = match self.refs.iter().position(|r| *r == ref_) {
+                    Some(index) => index,
+                    None => {
+                        self.refs.push(ref_);
+                        self.refs.len() - 1
+                    }
+                };
+                Ok(Expr::Ref(index))
+            }
+            ExprToken::Symbol("(") => {
+                let expr = self.parse_expr()?;
+                self.expect(")")?;
+                Ok(expr)
+            }
+            ExprToken::Ident(ident) => match ident.as_str() {
+                "true" => Ok(Expr::Bool(true)),
+                "false" => Ok(Expr::Bool(false)),
+                "null" => Ok(Expr::Null),
+                "now" if !matches!(self.peek(), Some(ExprToken::Symbol("("))) => {
+                    Ok(Expr::Call(Function::Now, Vec::new()))
+                }
+                name => {
+                    let function = Function::from_name(name).ok_or_else(|| {
+                        failed!(
+                            target: Release,
+                            "unknown function `{}` in expression: references to other fields must start with `@`",
+                            name
+                        )
+                    })?;
+
+                    self.expect("(")?;
+
+                    let mut args = Vec::new();
+                    if !self.eat(")") {
+                        loop {
+                            args.push(self.parse_expr()?);
+                            if self.eat(")") {
+                                break;
+                            }
+                            self.expect(",")?;
+                        }
+                    }
+
+                    let (min, max) = function.arity();
+                    if args.len() < min || args.len() > max {
+                        return Err(failed!(
+                            target: Release,
+                            "wrong number of arguments to `{}`: got {}",
+                            name,
+                            args.len()
+                        ));
+                    }
+
+                    Ok(Expr::Call(function, args))
+                }
+            },
+            token => Err(failed!(
+                target: Release,
+                "unexpected `{}` in expression",
+                token
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_precedence() {
+        let content = ExprContent::parse("@a.price * (1 - @a.discount) + 2 * @a.price").unwrap();
+        assert_eq!(content.refs().len(), 2);
+        assert_eq!(
+            *content.expr(),
+            Expr::Binary(
+                BinaryOp::Add,
+                Box::new(Expr::Binary(
+                    BinaryOp::Mul,
+                    Box::new(Expr::Ref(0)),
+                    Box::new(Expr::Binary(
+                        BinaryOp::Sub,
+                        Box::new(Expr::Int(1)),
+                        Box::new(Expr::Ref(1))
+                    ))
+                )),
+                Box::new(Expr::Binary(
+                    BinaryOp::Mul,
+                    Box::new(Expr::Int(2)),
+                    Box::new(Expr::Ref(0))
+                ))
+            )
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(ExprContent::parse("price * 2").is_err());
+        assert!(ExprContent::parse("round(1, 2, 3)").is_err());
+        assert!(ExprContent::parse("(1 + 2").is_err());
+        assert!(ExprContent::parse("1 + 2)").is_err());
+        assert!(ExprContent::parse("\"unterminated").is_err());
+        assert!(ExprContent::parse("1 # 2").is_err());
+    }
+
+    #[test]
+    fn serde() {
+        let content: Content = serde_json::from_value(serde_json::json!({
+            "type": "expr",
+            "expr": "year(now) - year(@users.content.birth_date)",
+            "now": "2021-06-01T12:00:00+0000"
+        }))
+        .unwrap();
+        assert_eq!(
+            serde_json::to_value(&content).unwrap(),
+            serde_json::json!({
+                "type": "expr",
+                "expr": "year(now) - year(@users.content.birth_date)",
+                "now": "2021-06-01T12:00:00+0000"
+            })
+        );
+
+        assert!(serde_json::from_value::<Content>(serde_json::json!({
+            "type": "expr",
+            "expr": "year(now)",
+            "now": "2021-06-01"
+        }))
+        .is_err());
+    }
+}
@@ -0,0 +1,65 @@
+use super::prelude::*;
+use super::SameAsSample;
+
+use crate::schema::FieldRef;
+
+use std::collections::BTreeMap;
+
+/// Picks one row of another collection and projects some of its fields, so that denormalised
+/// fields all come from the same row rather than from one `same_as` each.
+///
+/// When it is a field of an object, the projected fields are fields of that object instead.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Hash)]
+#[serde(deny_unknown_fields)]
+pub struct LookupContent {
+    /// The rows to pick from, usually the `content` of a collection.
+    #[serde(rename = "ref")]
+    pub ref_: FieldRef,
+    /// The name of each projected field and the field of the row it is projected from.
+    pub fields: BTreeMap<String, String>,
+    /// If set, rows are picked at random among every row generated so far instead of in the order
+    /// they were generated, as for `same_as`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sample: Option<SameAsSample>,
+}
+
+impl LookupContent {
+    /// The name of each projected field and a reference to the field it is projected from.
+    pub fn projected(&self) -> impl Iterator<Item = (&String, FieldRef)> + '_ {
+        self.fields
+            .iter()
+            .map(|(name, field)| (name, self.ref_.child(field)))
+    }
+}
+
+impl Compile for LookupContent {
+    fn compile<'a, C: Compiler<'a>>(&'a self, mut compiler: C) -> Result<Graph> {
+        if self.fields.is_empty() {
+            return Err(anyhow!(
+                "a `lookup` must project at least one field of the rows of `{}`",
+                self.ref_
+            ));
+        }
+
+        let mut row = compiler.get(self.ref_.clone())?;
+        if let Some(sample) = &self.sample {
+            if let SameAsSample::Weighted(weights) = sample {
+                if weights.parent().as_ref() != Some(&self.ref_) {
+                    return Err(anyhow!(
+                        "the weights `{}` of a `lookup` must be a field of the rows of `{}`",
+                        weights,
+                        self.ref_
+                    ));
+                }
+            }
+            row = sample.compile(&mut compiler, row)?;
+        }
+
+        let fields = self
+            .fields
+            .iter()
+            .map(|(name, field)| (name.clone(), field.clone()))
+            .collect();
+        Ok(Graph::Lookup(LookupNode::new(row, fields)))
+    }
+}
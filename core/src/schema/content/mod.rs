@@ -24,7 +24,10 @@ mod r#bool;
 pub use self::r#bool::BoolContent;
 
 mod number;
-pub use number::{number_content, NumberContent, NumberContentKind, NumberKindExt, RangeStep};
+pub use number::{
+    number_content, Exponential, Gamma, LogNormal, Normal, NumberContent, NumberContentKind,
+    NumberKindExt, RangeStep, Zipf,
+};
 
 mod string;
 pub use string::{
@@ -64,9 +67,22 @@ pub use unique::{UniqueAlgorithm, UniqueContent};
 pub mod hidden;
 pub use hidden::HiddenContent;
 
+pub mod expr;
+pub use expr::{BinaryOp, Expr, ExprContent, Function, UnaryOp};
+
+pub mod switch;
+pub use switch::{CaseContent, SwitchContent};
+
+pub mod lookup;
+pub use lookup::LookupContent;
+
+mod span;
+pub use span::ContentError;
+
 use prelude::*;
 
 use super::{FieldRef, Namespace};
+use crate::graph::{SampleNode, Sampling};
 
 pub trait Find<C> {
     fn find<I, R>(&self, reference: I) -> Result<&C>
@@ -101,6 +117,35 @@ pub trait Find<C> {
 pub struct SameAsContent {
     #[serde(rename = "ref")]
     pub ref_: FieldRef,
+    /// If set, values are picked at random among every value generated so far for `ref_`
+    /// instead of being copied in the order they were generated.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sample: Option<SameAsSample>,
+}
+
+/// How a [`SameAsContent`] picks the values it copies from the field it refers to.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SameAsSample {
+    /// Every value is equally likely to be picked.
+    Uniform,
+    /// Values are skewed towards the first ones generated, following a Zipf distribution over
+    /// their rank with the given exponent.
+    Zipf { exponent: f64 },
+    /// Values are picked with a probability proportional to the value of the given field of the
+    /// same element, which must be a sibling of the field referred to.
+    Weighted(FieldRef),
+}
+
+impl Hash for SameAsSample {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            Self::Uniform => {}
+            Self::Zipf { exponent } => exponent.to_bits().hash(state),
+            Self::Weighted(weights) => weights.hash(state),
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Hash)]
@@ -219,35 +264,38 @@ macro_rules! content {
                             out.insert(key, value);
                         }
 
-                        if out.is_empty() {
-                            out.insert("type".to_string(), serde_json::Value::String("empty".to_string()));
-                        } else if out.len() == 1 && out.contains_key("type") {
-                            paste! {
-                            match out.get("type").unwrap().as_str() {
-                                $(
-                                    Some(stringify!([<$name:snake>])) => generator_field_error!($name, $msg),
-                                )*
-                                None | Some(_) => {}
-                            }
+                        let node = span::Node::enter(out.remove(span::SPAN_KEY));
+                        node.exit((|| -> Result<Self::Value, A::Error> {
+                            if out.is_empty() {
+                                out.insert("type".to_string(), serde_json::Value::String("empty".to_string()));
+                            } else if out.len() == 1 && out.contains_key("type") {
+                                paste! {
+                                match out.get("type").unwrap().as_str() {
+                                    $(
+                                        Some(stringify!([<$name:snake>])) => generator_field_error!($name, $msg),
+                                    )*
+                                    None | Some(_) => {}
+                                }
+                                }
                             }
-                        }
 
-                        for key in out.keys() {
-                            if let Some(parent) = UNEXPECTED.get(key as &str) {
-                                let parents = parent.iter().map(|p| format!("`{}`", p)).collect::<Vec<String>>().join(", ");
-                                return Err(A::Error::custom(format!("`{}` is expected to be a field of {}", key, parents)));
+                            for key in out.keys() {
+                                if let Some(parent) = UNEXPECTED.get(key as &str) {
+                                    let parents = parent.iter().map(|p| format!("`{}`", p)).collect::<Vec<String>>().join(", ");
+                                    return Err(A::Error::custom(format!("`{}` is expected to be a field of {}", key, parents)));
+                                }
                             }
-                        }
 
-                        let __ContentWithLabels { labels, content } = __ContentWithLabels::deserialize(out.into_deserializer()).map_err(A::Error::custom)?;
-                        match content {
-                            $(
-                                __Content::$name(inner) => {
-                                    let inner_as_content = Content::$name(inner);
-                                    labels.try_wrap(inner_as_content)
-                                },
-                            )+
-                        }
+                            let __ContentWithLabels { labels, content } = __ContentWithLabels::deserialize(out.into_deserializer()).map_err(A::Error::custom)?;
+                            match content {
+                                $(
+                                    __Content::$name(inner) => {
+                                        let inner_as_content = Content::$name(inner);
+                                        labels.try_wrap(inner_as_content)
+                                    },
+                                )+
+                            }
+                        })())
                     }
 
                     fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
@@ -277,7 +325,7 @@ macro_rules! content {
                     {
                         if let Some(s) = v.strip_prefix("@") {
                             let ref_ = FieldRef::deserialize(s.into_deserializer())?;
-                            Ok(Content::SameAs(SameAsContent { ref_ }))
+                            Ok(Content::SameAs(SameAsContent { ref_, sample: None }))
                         } else {
                             Ok(Content::String(StringContent::Constant(ConstantContent::from(v.to_string()))))
                         }
@@ -316,15 +364,26 @@ content! {
         Unique(UniqueContent) => "missing a `content` field",
         Datasource(DatasourceContent) => "missing a `path` field",
         Hidden(HiddenContent) => "missing a `content` field",
+        Expr(ExprContent) => "missing an `expr` field",
+        Switch(SwitchContent) => "missing an `on` and `cases` field",
+        Lookup(LookupContent) => "missing a `ref` and `fields` field",
         Empty(EmptyContent) => None,
     }
 }
 
 impl Content {
+    /// Deserializes content from JSON text, locating any error at the node it was raised for
+    /// rather than at the end of the outermost node like `serde_json::from_str` does.
+    pub fn from_json_str(text: &str) -> std::result::Result<Self, ContentError> {
+        span::from_json_str(text)
+    }
+
     pub fn from_value_wrapped_in_array(value: &Value) -> Self {
         Content::Array(ArrayContent {
             length: Box::new(Content::from(&Value::from(1))),
             content: Box::new(value.into()),
+            size: None,
+            parent: None,
         })
     }
 
@@ -377,7 +436,7 @@ impl Content {
 
     pub fn is_scalar(&self, ns: &Namespace) -> Result<bool> {
         match self {
-            Self::Array(_) | Self::Object(_) => Ok(false),
+            Self::Array(_) | Self::Object(_) | Self::Lookup(_) => Ok(false),
             Self::SameAs(same_as) => ns.get_s_node(&same_as.ref_)?.is_scalar(ns),
             Self::OneOf(one_of) => {
                 for variant in &one_of.variants {
@@ -387,6 +446,14 @@ impl Content {
                 }
                 Ok(true)
             }
+            Self::Switch(switch) => {
+                for content in switch.iter() {
+                    if !content.is_scalar(ns)? {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            }
             Self::Unique(unique) => unique.content.is_scalar(ns),
             _ => Ok(true),
         }
@@ -424,6 +491,22 @@ impl Content {
             Self::Unique(unique_content) => unique_content.content.accepts(value),
             Self::Hidden(_) => Ok(()),
             Self::SameAs(_) => Ok(()),
+            Self::Expr(_) => Ok(()),
+            Self::Lookup(_) => Ok(()),
+            Self::Switch(switch_content) => {
+                if switch_content
+                    .iter()
+                    .any(|content| content.accepts(value).is_ok())
+                {
+                    Ok(())
+                } else {
+                    Err(failed!(
+                        target: Release,
+                        "no case of this will accept: {}",
+                        value
+                    ))
+                }
+            }
             Self::OneOf(one_of_content) => {
                 let res: Vec<_> = one_of_content
                     .iter()
@@ -466,7 +549,7 @@ impl Content {
                     )),
                 },
                 Value::String(_) => match self {
-                    Self::String(_) => Ok(()),
+                    Self::String(_) | Self::DateTime(_) => Ok(()),
                     _ => Err(failed!(
                         target: Release,
                         "expecting: '{}', found: 'string'",
@@ -509,6 +592,9 @@ impl Content {
             Content::Series(content) => format!("series::{}", content.kind()),
             Content::Unique(_) => "unique".to_string(),
             Content::Hidden(_) => "hidden".to_string(),
+            Content::Expr(_) => "expr".to_string(),
+            Content::Switch(_) => "switch".to_string(),
+            Content::Lookup(_) => "lookup".to_string(),
             Content::Datasource(_) => "datasource".to_string(),
             Content::Empty(_) => "empty".to_string(),
         }
@@ -533,13 +619,15 @@ impl<'r> From<&'r Value> for Content {
             // TODO not sure what the correct behaviour is here
             Value::Null => Content::Null(NullContent),
             Value::Bool(_) => Content::Bool(BoolContent::default()),
-            Value::String(_) => Content::String(StringContent::default()),
+            Value::String(string) => super::infer_string(string),
             Value::Array(arr) => {
                 let length = arr.len();
                 let one_of_content = arr.iter().collect();
                 Content::Array(ArrayContent {
                     length: Box::new(Content::from(&Value::from(length as u64))),
                     content: Box::new(Content::OneOf(one_of_content)),
+                    size: None,
+                    parent: None,
                 })
             }
             Value::Object(obj) => {
@@ -639,6 +727,9 @@ impl Compile for Content {
             Self::Series(series_content) => series_content.compile(compiler),
             Self::Unique(unique_content) => unique_content.compile(compiler),
             Self::Hidden(hidden_content) => hidden_content.compile(compiler),
+            Self::Expr(expr_content) => expr_content.compile(compiler),
+            Self::Switch(switch_content) => switch_content.compile(compiler),
+            Self::Lookup(lookup_content) => lookup_content.compile(compiler),
             Self::Null(_) => Ok(Graph::null()),
             Self::Datasource(datasource) => datasource.compile(compiler),
             Self::Empty(_) => Err(anyhow!("unexpected empty object")),
@@ -648,7 +739,58 @@ impl Compile for Content {
 
 impl Compile for SameAsContent {
     fn compile<'a, C: Compiler<'a>>(&'a self, mut compiler: C) -> Result<Graph> {
-        compiler.get(self.ref_.clone())
+        let reference = compiler.get(self.ref_.clone())?;
+        match &self.sample {
+            None => Ok(reference),
+            Some(sample) => {
+                if let SameAsSample::Weighted(weights) = sample {
+                    if weights.parent() != self.ref_.parent() {
+                        return Err(anyhow!(
+                            "the weights `{}` of a `same_as` must be a sibling of the field `{}` it refers to",
+                            weights,
+                            self.ref_
+                        ));
+                    }
+                }
+                sample.compile(&mut compiler, reference)
+            }
+        }
+    }
+}
+
+impl SameAsSample {
+    /// Makes `reference`, the graph returned for a reference by [`Compiler::get`], pick its
+    /// values at random as this says.
+    pub(crate) fn compile<'a, C: Compiler<'a>>(
+        &self,
+        compiler: &mut C,
+        reference: Graph,
+    ) -> Result<Graph> {
+        let sampling = match self {
+            Self::Uniform => Sampling::Uniform,
+            Self::Zipf { exponent } => {
+                if !exponent.is_finite() || *exponent < 0.0 {
+                    return Err(anyhow!(
+                        "the zipf exponent of a sample must be a non-negative number, found {}",
+                        exponent
+                    ));
+                }
+                Sampling::zipf(*exponent)
+            }
+            Self::Weighted(weights) => {
+                match compiler.get(weights.clone())?.into_recorded_values() {
+                    Ok(weights) => Sampling::weighted(weights),
+                    // Only the references of a graph being built can be read from, not those
+                    // returned while looking for its dependencies
+                    Err(_) => return Ok(reference),
+                }
+            }
+        };
+
+        match reference.into_recorded_values() {
+            Ok(values) => Ok(Graph::Sample(SampleNode::new(values, sampling))),
+            Err(reference) => Ok(reference),
+        }
     }
 }
 
@@ -1008,6 +1150,27 @@ pub mod tests {
         });
     }
 
+    #[test]
+    fn same_as_sample() {
+        let schema: Content = schema!({
+            "type": "same_as",
+            "ref": "users.content.id",
+            "sample": {
+                "weighted": "users.content.weight"
+            }
+        });
+
+        assert_eq!(
+            schema,
+            Content::SameAs(SameAsContent {
+                ref_: "users.content.id".parse().unwrap(),
+                sample: Some(SameAsSample::Weighted(
+                    "users.content.weight".parse().unwrap()
+                )),
+            })
+        );
+    }
+
     #[test]
     #[should_panic(expected = "`one_of` generator is missing a `variants` field")]
     fn one_of_missing_subtype() {
@@ -4,6 +4,7 @@ use std::hash::{Hash, Hasher};
 use super::Categorical;
 
 use crate::graph::number::{RandomF32, RandomI32, RandomU32};
+use crate::graph::{BoundedDistribution, BoundedNumber, NumberDistribution};
 use serde::{
     de::{Deserialize, Deserializer},
     ser::Serializer,
@@ -372,6 +373,116 @@ macro_rules! derive_hash {
 
 derive_hash!(i32, u32, i64, u64, f32, f64);
 
+/// Declares the parameters of a distribution variant of [`NumberContent`].
+///
+/// All distributions share the optional `low` and `high` bounds. Samples
+/// falling outside of `[low, high]` are redrawn (i.e. the distribution is
+/// truncated) unless `clamp` is set, in which case they are moved to the
+/// nearest bound.
+macro_rules! distribution_content {
+    {
+        $(
+            $(#[$attr:meta])*
+            $name:ident {
+                $($param:ident: $param_ty:ty,)*
+            } => |$this:ident| $build:expr,
+        )*
+    } => {
+        $(
+            $(#[$attr])*
+            #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+            #[serde(deny_unknown_fields)]
+            pub struct $name {
+                $(pub $param: $param_ty,)*
+                #[serde(default)]
+                #[serde(skip_serializing_if = "Option::is_none")]
+                pub low: Option<f64>,
+                #[serde(default)]
+                #[serde(skip_serializing_if = "Option::is_none")]
+                pub high: Option<f64>,
+                #[serde(default)]
+                #[serde(skip_serializing_if = "std::ops::Not::not")]
+                pub clamp: bool,
+            }
+
+            #[allow(clippy::derive_hash_xor_eq)]
+            impl Hash for $name {
+                fn hash<H: Hasher>(&self, state: &mut H) {
+                    $(HashParam::hash_param(&self.$param, state);)*
+                    self.low.map(f64::to_bits).hash(state);
+                    self.high.map(f64::to_bits).hash(state);
+                    self.clamp.hash(state);
+                }
+            }
+
+            impl $name {
+                pub fn to_distribution(&self) -> Result<NumberDistribution> {
+                    let $this = self;
+                    $build
+                }
+
+                pub fn bounded<N: BoundedNumber>(&self) -> Result<BoundedDistribution<N>> {
+                    BoundedDistribution::new(self.to_distribution()?, self.low, self.high, self.clamp)
+                }
+
+                /// Distribution parameters do not depend on the subtype so this never fails.
+                pub fn upcast(self, to: NumberContentKind) -> NumberContent {
+                    match to {
+                        NumberContentKind::U64 => number_content::U64::$name(self).into(),
+                        NumberContentKind::I64 => number_content::I64::$name(self).into(),
+                        NumberContentKind::F64 => number_content::F64::$name(self).into(),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+trait HashParam {
+    fn hash_param<H: Hasher>(&self, state: &mut H);
+}
+
+impl HashParam for f64 {
+    fn hash_param<H: Hasher>(&self, state: &mut H) {
+        self.to_bits().hash(state)
+    }
+}
+
+impl HashParam for u64 {
+    fn hash_param<H: Hasher>(&self, state: &mut H) {
+        self.hash(state)
+    }
+}
+
+distribution_content! {
+    /// A normal (Gaussian) distribution.
+    Normal {
+        mean: f64,
+        std_dev: f64,
+    } => |this| NumberDistribution::normal(this.mean, this.std_dev),
+    /// A log-normal distribution, parameterised by the mean `mu` and the
+    /// standard deviation `sigma` of the underlying normal distribution.
+    LogNormal {
+        mu: f64,
+        sigma: f64,
+    } => |this| NumberDistribution::log_normal(this.mu, this.sigma),
+    /// An exponential distribution with rate (or inverse scale) `rate`.
+    Exponential {
+        rate: f64,
+    } => |this| NumberDistribution::exponential(this.rate),
+    /// A gamma distribution with shape `shape` and scale `scale`.
+    Gamma {
+        shape: f64,
+        scale: f64,
+    } => |this| NumberDistribution::gamma(this.shape, this.scale),
+    /// A Zipf distribution over the ranks `1..=n`, where the frequency of a
+    /// rank is inversely proportional to the rank to the power `exponent`.
+    Zipf {
+        n: u64,
+        exponent: f64,
+    } => |this| NumberDistribution::zipf(this.n, this.exponent),
+}
+
 number_content!(
     #[derive(PartialEq, Hash)]
     u32[is_u32, default_u32_range] as U32 {
@@ -379,6 +490,11 @@ number_content!(
         Categorical(Categorical<u32>),
         Constant(u32),
         Id(crate::schema::Id<u32>),
+        Normal(crate::schema::Normal),
+        LogNormal(crate::schema::LogNormal),
+        Exponential(crate::schema::Exponential),
+        Gamma(crate::schema::Gamma),
+        Zipf(crate::schema::Zipf),
     },
     #[derive(PartialEq, Hash)]
     u64[is_u64, default_u64_range] as U64 {
@@ -386,6 +502,11 @@ number_content!(
         Categorical(Categorical<u64>),
         Constant(u64),
         Id(crate::schema::Id<u64>),
+        Normal(crate::schema::Normal),
+        LogNormal(crate::schema::LogNormal),
+        Exponential(crate::schema::Exponential),
+        Gamma(crate::schema::Gamma),
+        Zipf(crate::schema::Zipf),
     },
     #[derive(PartialEq, Hash)]
     i32[is_i32, default_i32_range] as I32 {
@@ -393,6 +514,11 @@ number_content!(
         Categorical(Categorical<i32>),
         Constant(i32),
         Id(crate::schema::Id<i32>),
+        Normal(crate::schema::Normal),
+        LogNormal(crate::schema::LogNormal),
+        Exponential(crate::schema::Exponential),
+        Gamma(crate::schema::Gamma),
+        Zipf(crate::schema::Zipf),
     },
     #[derive(PartialEq, Hash)]
     i64[is_i64, default_i64_range] as I64 {
@@ -400,14 +526,29 @@ number_content!(
         Categorical(Categorical<i64>),
         Constant(i64),
         Id(crate::schema::Id<i64>),
+        Normal(crate::schema::Normal),
+        LogNormal(crate::schema::LogNormal),
+        Exponential(crate::schema::Exponential),
+        Gamma(crate::schema::Gamma),
+        Zipf(crate::schema::Zipf),
     },
     f64[is_f64, default_f64_range] as F64 {
         Range(RangeStep<f64>),
         Constant(f64),
+        Normal(crate::schema::Normal),
+        LogNormal(crate::schema::LogNormal),
+        Exponential(crate::schema::Exponential),
+        Gamma(crate::schema::Gamma),
+        Zipf(crate::schema::Zipf),
     },
     f32[is_f32, default_f32_range] as F32 {
         Range(RangeStep<f32>),
         Constant(f32),
+        Normal(crate::schema::Normal),
+        LogNormal(crate::schema::LogNormal),
+        Exponential(crate::schema::Exponential),
+        Gamma(crate::schema::Gamma),
+        Zipf(crate::schema::Zipf),
     },
 );
 
@@ -425,6 +566,15 @@ impl Compile for NumberContent {
                         let gen = Incrementing::new_at(id.start_at.unwrap_or(1));
                         RandomU64::incrementing(gen)
                     }
+                    number_content::U64::Normal(dist) => RandomU64::distribution(dist.bounded()?),
+                    number_content::U64::LogNormal(dist) => {
+                        RandomU64::distribution(dist.bounded()?)
+                    }
+                    number_content::U64::Exponential(dist) => {
+                        RandomU64::distribution(dist.bounded()?)
+                    }
+                    number_content::U64::Gamma(dist) => RandomU64::distribution(dist.bounded()?),
+                    number_content::U64::Zipf(dist) => RandomU64::distribution(dist.bounded()?),
                 };
                 random_u64.into()
             }
@@ -438,6 +588,15 @@ impl Compile for NumberContent {
                     number_content::I64::Id(id) => {
                         RandomI64::incrementing(Incrementing::new_at(id.start_at.unwrap_or(1)))
                     }
+                    number_content::I64::Normal(dist) => RandomI64::distribution(dist.bounded()?),
+                    number_content::I64::LogNormal(dist) => {
+                        RandomI64::distribution(dist.bounded()?)
+                    }
+                    number_content::I64::Exponential(dist) => {
+                        RandomI64::distribution(dist.bounded()?)
+                    }
+                    number_content::I64::Gamma(dist) => RandomI64::distribution(dist.bounded()?),
+                    number_content::I64::Zipf(dist) => RandomI64::distribution(dist.bounded()?),
                 };
                 random_i64.into()
             }
@@ -445,6 +604,15 @@ impl Compile for NumberContent {
                 let random_f64 = match f64_content {
                     number_content::F64::Range(range) => RandomF64::range(*range)?,
                     number_content::F64::Constant(val) => RandomF64::constant(*val),
+                    number_content::F64::Normal(dist) => RandomF64::distribution(dist.bounded()?),
+                    number_content::F64::LogNormal(dist) => {
+                        RandomF64::distribution(dist.bounded()?)
+                    }
+                    number_content::F64::Exponential(dist) => {
+                        RandomF64::distribution(dist.bounded()?)
+                    }
+                    number_content::F64::Gamma(dist) => RandomF64::distribution(dist.bounded()?),
+                    number_content::F64::Zipf(dist) => RandomF64::distribution(dist.bounded()?),
                 };
                 random_f64.into()
             }
@@ -458,6 +626,15 @@ impl Compile for NumberContent {
                     number_content::U32::Id(id) => {
                         RandomU32::incrementing(Incrementing::new_at(id.start_at.unwrap_or(1)))
                     }
+                    number_content::U32::Normal(dist) => RandomU32::distribution(dist.bounded()?),
+                    number_content::U32::LogNormal(dist) => {
+                        RandomU32::distribution(dist.bounded()?)
+                    }
+                    number_content::U32::Exponential(dist) => {
+                        RandomU32::distribution(dist.bounded()?)
+                    }
+                    number_content::U32::Gamma(dist) => RandomU32::distribution(dist.bounded()?),
+                    number_content::U32::Zipf(dist) => RandomU32::distribution(dist.bounded()?),
                 };
                 random_u32.into()
             }
@@ -471,6 +648,15 @@ impl Compile for NumberContent {
                     number_content::I32::Id(id) => {
                         RandomI32::incrementing(Incrementing::new_at(id.start_at.unwrap_or(1)))
                     }
+                    number_content::I32::Normal(dist) => RandomI32::distribution(dist.bounded()?),
+                    number_content::I32::LogNormal(dist) => {
+                        RandomI32::distribution(dist.bounded()?)
+                    }
+                    number_content::I32::Exponential(dist) => {
+                        RandomI32::distribution(dist.bounded()?)
+                    }
+                    number_content::I32::Gamma(dist) => RandomI32::distribution(dist.bounded()?),
+                    number_content::I32::Zipf(dist) => RandomI32::distribution(dist.bounded()?),
                 };
                 random_i32.into()
             }
@@ -478,6 +664,15 @@ impl Compile for NumberContent {
                 let random_f32 = match f32_content {
                     number_content::F32::Range(range) => RandomF32::range(*range)?,
                     number_content::F32::Constant(val) => RandomF32::constant(*val),
+                    number_content::F32::Normal(dist) => RandomF32::distribution(dist.bounded()?),
+                    number_content::F32::LogNormal(dist) => {
+                        RandomF32::distribution(dist.bounded()?)
+                    }
+                    number_content::F32::Exponential(dist) => {
+                        RandomF32::distribution(dist.bounded()?)
+                    }
+                    number_content::F32::Gamma(dist) => RandomF32::distribution(dist.bounded()?),
+                    number_content::F32::Zipf(dist) => RandomF32::distribution(dist.bounded()?),
                 };
                 random_f32.into()
             }
@@ -610,6 +805,11 @@ impl number_content::U64 {
                 target: Release,
                 "cannot upcast an id number subtype: only unsigned integers are supported"
             )),
+            Self::Normal(dist) => Ok(dist.upcast(to)),
+            Self::LogNormal(dist) => Ok(dist.upcast(to)),
+            Self::Exponential(dist) => Ok(dist.upcast(to)),
+            Self::Gamma(dist) => Ok(dist.upcast(to)),
+            Self::Zipf(dist) => Ok(dist.upcast(to)),
         }
     }
 }
@@ -631,6 +831,11 @@ impl number_content::I64 {
                 }
             },
             Self::Id(id) => id.upcast(to),
+            Self::Normal(dist) => Ok(dist.upcast(to)),
+            Self::LogNormal(dist) => Ok(dist.upcast(to)),
+            Self::Exponential(dist) => Ok(dist.upcast(to)),
+            Self::Gamma(dist) => Ok(dist.upcast(to)),
+            Self::Zipf(dist) => Ok(dist.upcast(to)),
         }
     }
 }
@@ -650,61 +855,58 @@ impl number_content::F64 {
                 )),
                 NumberContentKind::F64 => Ok(self.into()),
             },
+            Self::Normal(dist) => Ok(dist.upcast(to)),
+            Self::LogNormal(dist) => Ok(dist.upcast(to)),
+            Self::Exponential(dist) => Ok(dist.upcast(to)),
+            Self::Gamma(dist) => Ok(dist.upcast(to)),
+            Self::Zipf(dist) => Ok(dist.upcast(to)),
         }
     }
 }
 
-impl Hash for number_content::F32 {
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        match self {
-            Self::Range(range) => range.hash(state),
-            Self::Constant(constant) => constant.to_bits().hash(state),
-        }
-    }
-}
-
-impl PartialEq for number_content::F32 {
-    fn eq(&self, other: &number_content::F32) -> bool {
-        match self {
-            Self::Range(range) => match other {
-                Self::Range(o_range) => range == o_range,
-                _ => false,
-            },
-            Self::Constant(constant) => match other {
-                Self::Constant(o_constant) => constant == o_constant,
-                _ => false,
-            },
-        }
-    }
-}
+macro_rules! float_number_content_impls {
+    {$($as:ident),*} => {
+        $(
+            impl Hash for number_content::$as {
+                fn hash<H: Hasher>(&self, state: &mut H) {
+                    std::mem::discriminant(self).hash(state);
+                    match self {
+                        Self::Range(range) => range.hash(state),
+                        Self::Constant(constant) => constant.to_bits().hash(state),
+                        Self::Normal(dist) => dist.hash(state),
+                        Self::LogNormal(dist) => dist.hash(state),
+                        Self::Exponential(dist) => dist.hash(state),
+                        Self::Gamma(dist) => dist.hash(state),
+                        Self::Zipf(dist) => dist.hash(state),
+                    }
+                }
+            }
 
-impl Hash for number_content::F64 {
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        match self {
-            Self::Range(range) => range.hash(state),
-            Self::Constant(constant) => constant.to_bits().hash(state),
-        }
-    }
+            impl PartialEq for number_content::$as {
+                fn eq(&self, other: &number_content::$as) -> bool {
+                    match (self, other) {
+                        (Self::Range(range), Self::Range(o_range)) => range == o_range,
+                        (Self::Constant(constant), Self::Constant(o_constant)) => constant == o_constant,
+                        (Self::Normal(dist), Self::Normal(o_dist)) => dist == o_dist,
+                        (Self::LogNormal(dist), Self::LogNormal(o_dist)) => dist == o_dist,
+                        (Self::Exponential(dist), Self::Exponential(o_dist)) => dist == o_dist,
+                        (Self::Gamma(dist), Self::Gamma(o_dist)) => dist == o_dist,
+                        (Self::Zipf(dist), Self::Zipf(o_dist)) => dist == o_dist,
+                        _ => false,
+                    }
+                }
+            }
+        )*
+    };
 }
 
-impl PartialEq for number_content::F64 {
-    fn eq(&self, other: &number_content::F64) -> bool {
-        match self {
-            Self::Range(range) => match other {
-                Self::Range(o_range) => range == o_range,
-                _ => false,
-            },
-            Self::Constant(constant) => match other {
-                Self::Constant(o_constant) => constant == o_constant,
-                _ => false,
-            },
-        }
-    }
-}
+float_number_content_impls!(F32, F64);
 
 #[cfg(test)]
 pub mod tests {
     use super::*;
+    use crate::graph::Value as GraphValue;
+    use synth_gen::value::Number as GraphNumber;
 
     use num::One;
 
@@ -753,4 +955,92 @@ pub mod tests {
         test_i32 -> "i32" I32: i32,
         test_i64 -> "i64" I64: i64,
     );
+
+    #[test]
+    fn test_distribution_truncated() {
+        let mut generator = generator!({
+            "type": "number",
+            "normal": {
+                "mean": 40,
+                "std_dev": 12,
+                "low": 18,
+                "high": 99
+            }
+        });
+        let mut rng = crate::tests::rng();
+        for _ in 0..1000 {
+            match generator.complete(&mut rng).unwrap() {
+                GraphValue::Number(GraphNumber::U32(age)) => assert!((18..=99).contains(&age)),
+                otherwise => panic!("expected a u32, found {:?}", otherwise),
+            }
+        }
+    }
+
+    #[test]
+    fn test_distribution_clamped() {
+        let mut generator = generator!({
+            "type": "number",
+            "subtype": "f64",
+            "exponential": {
+                "rate": 0.001,
+                "high": 10.5,
+                "clamp": true
+            }
+        });
+        let mut rng = crate::tests::rng();
+        let samples = (0..100)
+            .map(|_| match generator.complete(&mut rng).unwrap() {
+                GraphValue::Number(GraphNumber::F64(latency)) => latency.into_inner(),
+                otherwise => panic!("expected a f64, found {:?}", otherwise),
+            })
+            .collect::<Vec<_>>();
+        assert!(samples.iter().all(|latency| (0.0..=10.5).contains(latency)));
+        assert!(samples.iter().any(|latency| *latency == 10.5));
+    }
+
+    #[test]
+    fn test_distribution_invalid() {
+        assert!(try_generator!({
+            "type": "number",
+            "normal": {
+                "mean": 0,
+                "std_dev": -1
+            }
+        })
+        .is_err());
+
+        assert!(try_generator!({
+            "type": "number",
+            "zipf": {
+                "n": 10,
+                "exponent": 1,
+                "low": 8,
+                "high": 2
+            }
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn test_distribution_upcast() {
+        let normal: NumberContent = serde_json::from_value(json!({
+            "subtype": "u64",
+            "normal": {
+                "mean": 1,
+                "std_dev": 1
+            }
+        }))
+        .unwrap();
+        let mut upcasted = normal.clone();
+        OptionalMergeStrategy::default()
+            .try_merge(&mut upcasted, &"-1.5".parse().unwrap())
+            .unwrap();
+        match (normal, upcasted) {
+            (
+                NumberContent::U64(number_content::U64::Normal(normal)),
+                NumberContent::F64(number_content::F64::Normal(upcasted)),
+            ) => assert_eq!(normal, upcasted),
+            otherwise => panic!("unexpected upcast {:?}", otherwise),
+        }
+    }
 }
@@ -6,13 +6,19 @@ use serde::{
 use std::borrow::Cow;
 use std::collections::BTreeMap;
 
-const RESERVED_FIELDS: [&str; 2] = ["type", "skip_when_null"];
+const RESERVED_FIELDS: [&str; 3] = ["type", "skip_when_null", "unique_together"];
 
 #[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Hash)]
 pub struct ObjectContent {
     #[serde(default)]
     #[serde(skip_serializing_if = "std::ops::Not::not")]
     pub skip_when_null: bool,
+    /// Sets of fields whose values, taken together, are never generated twice, like the
+    /// multi-column unique keys of SQL: an object repeating the values of a set is generated
+    /// again, unless one of them is `null`.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unique_together: Vec<Vec<String>>,
     #[serde(flatten)]
     #[serde(serialize_with = "normalize_keys")]
     #[serde(deserialize_with = "denormalize_keys")]
@@ -80,6 +86,13 @@ impl ObjectContent {
                 }
             } else if matches!(v, Content::Hidden(_)) {
                 // ok, hidden is not expected to appear
+            } else if let Content::Lookup(lookup) = v {
+                // the fields of a lookup appear in its place
+                for name in lookup.fields.keys() {
+                    if !obj.contains_key(name) {
+                        return Err(failed!(target: Release, "could not find field: '{}'", name));
+                    }
+                }
             } else {
                 let json_value = obj
                     .get(k)
@@ -91,7 +104,7 @@ impl ObjectContent {
 
         // Then check if fields contains all the json keys
         for (k, _) in obj {
-            if !self.fields.contains_key(k) {
+            if !self.fields.contains_key(k) && !self.is_projected(k) {
                 return Err(failed!(
                     target: Release,
                     "field '{}' is not recognized in the schema",
@@ -103,6 +116,15 @@ impl ObjectContent {
         Ok(())
     }
 
+    /// Whether `field` is one of the fields projected by a `lookup` field of this object, which
+    /// appear in its place.
+    pub fn is_projected(&self, field: &str) -> bool {
+        self.fields.values().any(|v| match v {
+            Content::Lookup(lookup) => lookup.fields.contains_key(field),
+            _ => false,
+        })
+    }
+
     pub fn iter(&self) -> impl Iterator<Item = (&String, &Content)> {
         self.fields.iter()
     }
@@ -155,6 +177,40 @@ impl Find<Content> for ObjectContent {
 
 impl Compile for ObjectContent {
     fn compile<'a, C: Compiler<'a>>(&'a self, mut compiler: C) -> Result<Graph> {
+        // The fields projected by a lookup appear in its place, so they can't share the name of
+        // another field of the object
+        let mut names = self
+            .iter()
+            .filter(|(_, field)| !matches!(field, Content::Lookup(_) | Content::Hidden(_)))
+            .map(|(name, _)| (name.as_str(), None))
+            .collect::<HashMap<_, _>>();
+        for (name, field) in self.iter() {
+            if let Content::Lookup(lookup) = field {
+                for projected in lookup.fields.keys() {
+                    match names.insert(projected.as_str(), Some(name)) {
+                        Some(Some(other)) => {
+                            return Err(failed!(
+                                target: Release,
+                                "the lookups '{}' and '{}' both project a field named '{}'",
+                                other,
+                                name,
+                                projected
+                            ))
+                        }
+                        Some(None) => {
+                            return Err(failed!(
+                                target: Release,
+                                "the lookup '{}' projects a field named '{}', which is already a field of the object",
+                                name,
+                                projected
+                            ))
+                        }
+                        None => {}
+                    }
+                }
+            }
+        }
+
         let object_node = self
             .iter()
             .map(|(name, field)| {
@@ -165,11 +221,42 @@ impl Compile for ObjectContent {
                         .map(|graph| KeyValueOrNothing::sometimes(name, graph, false))
                 } else {
                     compiler.build(name, field).map(|graph| {
-                        KeyValueOrNothing::always(name, graph, matches!(field, Content::Hidden(_)))
+                        let key_value = KeyValueOrNothing::always(
+                            name,
+                            graph,
+                            matches!(field, Content::Hidden(_)),
+                        );
+                        if matches!(field, Content::Lookup(_)) {
+                            key_value.flattened()
+                        } else {
+                            key_value
+                        }
                     })
                 }
             })
             .collect::<Result<ObjectNode>>()?;
-        Ok(Graph::Object(object_node))
+
+        if self.unique_together.is_empty() {
+            return Ok(Graph::Object(object_node));
+        }
+
+        for fields in &self.unique_together {
+            if fields.is_empty() {
+                return Err(failed!(
+                    target: Release,
+                    "a set of fields of 'unique_together' cannot be empty"
+                ));
+            }
+            for field in fields {
+                if !self.is_projected(field) {
+                    self.get(field)
+                        .context("in a set of fields of 'unique_together'")?;
+                }
+            }
+        }
+        Ok(Graph::Unique(UniqueNode::together(
+            Graph::Object(object_node),
+            self.unique_together.clone(),
+        )))
     }
 }
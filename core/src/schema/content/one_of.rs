@@ -76,7 +76,7 @@ impl<'t> FromIterator<&'t Value> for OneOfContent {
         let mut out = Self {
             variants: Vec::new(),
         };
-        let strategy = OptionalMergeStrategy;
+        let strategy = OptionalMergeStrategy::default();
         iter.into_iter()
             .for_each(|value| out.insert_with(strategy, value));
         out
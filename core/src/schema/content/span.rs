@@ -0,0 +1,415 @@
+//! Locating the errors raised while deserializing [`Content`](super::Content) in the JSON text it
+//! is read from.
+//!
+//! The `Content` deserializer buffers the fields of a node into a map before deserializing them, so
+//! errors coming from `serde_json` only point at the end of the outermost node. Instead, the text is
+//! parsed into a [`Value`](serde_json::Value) whose nodes hold the index of their span under
+//! [`SPAN_KEY`], which is carried along the buffered fields. Each node is deserialized within a
+//! [`Node`], which records the span of the innermost node an error is raised at.
+
+use super::Content;
+
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::cell::RefCell;
+use std::fmt;
+
+/// An error raised while deserializing [`Content`](super::Content) from JSON text, located at the
+/// innermost node it was raised for.
+#[derive(Debug)]
+pub struct ContentError {
+    path: Vec<String>,
+    line: usize,
+    column: usize,
+    message: String,
+}
+
+impl ContentError {
+    /// The field names and array indices leading to the node from the root, empty for the root
+    /// itself or if the text isn't valid JSON.
+    pub fn path(&self) -> &[String] {
+        &self.path
+    }
+
+    /// The line of the node (or of the syntax error), starting at 1.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// The column of the node (or of the syntax error) in characters, starting at 1.
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    /// The error without its location.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for ContentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)?;
+        if !self.path.is_empty() {
+            write!(f, " at `{}`,", self.path.join("."))?;
+        }
+        write!(f, " at line {} column {}", self.line, self.column)
+    }
+}
+
+impl std::error::Error for ContentError {}
+
+thread_local! {
+    /// The spans of the text being deserialized by [`from_json_str`], if any.
+    static SPANS: RefCell<Spans> = RefCell::new(Spans::default());
+}
+
+/// The key holding the index of the span of an object in the values parsed by
+/// [`from_json_str`], which can't be the name of a field in the text.
+pub(super) const SPAN_KEY: &str = "\u{0}span";
+
+#[derive(Default)]
+struct Spans {
+    /// The path and byte offset of each node of the text.
+    nodes: Vec<(Vec<String>, usize)>,
+    /// The nodes errors were raised at, innermost first.
+    failed: Vec<usize>,
+}
+
+/// A node being deserialized, which errors raised while deserializing it are located at unless
+/// one of its descendants raised them.
+pub(super) struct Node {
+    span: Option<usize>,
+    failed: usize,
+}
+
+impl Node {
+    /// Enters the node with the span held by the [`SPAN_KEY`] of its fields, if any.
+    pub(super) fn enter(span: Option<Value>) -> Self {
+        Self {
+            span: span
+                .and_then(|span| span.as_u64())
+                .map(|span| span as usize),
+            failed: SPANS.with(|spans| spans.borrow().failed.len()),
+        }
+    }
+
+    /// Leaves the node with the result of deserializing it.
+    pub(super) fn exit<T, E>(self, result: Result<T, E>) -> Result<T, E> {
+        SPANS.with(|spans| {
+            let mut spans = spans.borrow_mut();
+            match (&result, self.span) {
+                // The errors of the descendants of the node were recovered from
+                (Ok(_), _) => spans.failed.truncate(self.failed),
+                (Err(_), Some(span)) if spans.failed.len() == self.failed => {
+                    spans.failed.push(span)
+                }
+                (Err(_), _) => {}
+            }
+        });
+        result
+    }
+}
+
+pub(super) fn from_json_str(text: &str) -> Result<Content, ContentError> {
+    serde_json::from_str::<Value>(text).map_err(|err| {
+        let (line, column) = (err.line(), err.column());
+        let message = err.to_string();
+        let message = message
+            .trim_end_matches(&format!(" at line {} column {}", line, column))
+            .to_string();
+        ContentError {
+            path: Vec::new(),
+            line,
+            column,
+            message,
+        }
+    })?;
+
+    let mut parser = Parser {
+        cursor: Cursor { text, offset: 0 },
+        nodes: Vec::new(),
+    };
+    let value = parser
+        .value(&mut Vec::new())
+        .expect("the text has been checked to be valid JSON");
+
+    let previous = SPANS.with(|spans| {
+        spans.replace(Spans {
+            nodes: parser.nodes,
+            failed: Vec::new(),
+        })
+    });
+    let result = Content::deserialize(&value);
+    let spans = SPANS.with(|spans| spans.replace(previous));
+
+    let err = match result {
+        Ok(content) => return Ok(content),
+        Err(err) => err,
+    };
+    let (path, offset) = match spans.failed.first() {
+        Some(&span) => spans.nodes[span].clone(),
+        None => (Vec::new(), text.len() - text.trim_start().len()),
+    };
+    let (line, column) = line_column(text, offset);
+    Err(ContentError {
+        path,
+        line,
+        column,
+        message: err.to_string(),
+    })
+}
+
+fn line_column(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map(|index| index + 1).unwrap_or(0);
+    (line, before[line_start..].chars().count() + 1)
+}
+
+/// Parses valid JSON text into a [`Value`], marking the nodes in it with their spans.
+struct Parser<'t> {
+    cursor: Cursor<'t>,
+    nodes: Vec<(Vec<String>, usize)>,
+}
+
+impl<'t> Parser<'t> {
+    fn value(&mut self, path: &mut Vec<String>) -> Option<Value> {
+        self.cursor.skip_whitespace();
+        let start = self.cursor.offset;
+        match self.cursor.peek()? {
+            b'{' => {
+                let mut map = Map::new();
+                self.cursor.offset += 1;
+                while !self.cursor.close(b'}')? {
+                    self.cursor.skip_whitespace();
+                    let key_start = self.cursor.offset;
+                    self.cursor.skip_string()?;
+                    let key: String =
+                        serde_json::from_str(&self.cursor.text[key_start..self.cursor.offset])
+                            .ok()?;
+                    self.cursor.expect(b':')?;
+                    path.push(key);
+                    let value = self.value(path)?;
+                    map.insert(path.pop()?, value);
+                }
+                // Only objects with a `type` are nodes, anything else (like the `range` of a
+                // number) is part of its parent
+                if map.get("type").map_or(false, Value::is_string) {
+                    map.insert(SPAN_KEY.to_string(), Value::from(self.nodes.len()));
+                    self.nodes.push((path.clone(), start));
+                }
+                Some(Value::Object(map))
+            }
+            b'[' => {
+                let mut values = Vec::new();
+                self.cursor.offset += 1;
+                while !self.cursor.close(b']')? {
+                    path.push(values.len().to_string());
+                    values.push(self.value(path)?);
+                    path.pop();
+                }
+                Some(Value::Array(values))
+            }
+            _ => {
+                self.cursor.skip_value()?;
+                serde_json::from_str(&self.cursor.text[start..self.cursor.offset]).ok()
+            }
+        }
+    }
+}
+
+struct Cursor<'t> {
+    text: &'t str,
+    offset: usize,
+}
+
+impl<'t> Cursor<'t> {
+    fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.offset).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.offset += 1;
+        }
+    }
+
+    fn expect(&mut self, byte: u8) -> Option<()> {
+        self.skip_whitespace();
+        if self.peek() == Some(byte) {
+            self.offset += 1;
+            Some(())
+        } else {
+            None
+        }
+    }
+
+    /// Moves past the comma or the `close` bracket following the elements of an object or array
+    /// read so far, returning whether the bracket was reached.
+    fn close(&mut self, close: u8) -> Option<bool> {
+        self.skip_whitespace();
+        match self.peek()? {
+            b',' => {
+                self.offset += 1;
+                Some(false)
+            }
+            byte if byte == close => {
+                self.offset += 1;
+                Some(true)
+            }
+            _ => Some(false),
+        }
+    }
+
+    fn skip_string(&mut self) -> Option<()> {
+        if self.peek()? != b'"' {
+            return None;
+        }
+        self.offset += 1;
+        loop {
+            match self.peek()? {
+                b'\\' => self.offset += 2,
+                b'"' => {
+                    self.offset += 1;
+                    return Some(());
+                }
+                _ => self.offset += 1,
+            }
+        }
+    }
+
+    fn skip_value(&mut self) -> Option<()> {
+        match self.peek()? {
+            b'"' => self.skip_string(),
+            open @ (b'{' | b'[') => {
+                let close = if open == b'{' { b'}' } else { b']' };
+                self.offset += 1;
+                self.skip_whitespace();
+                if self.peek()? == close {
+                    self.offset += 1;
+                    return Some(());
+                }
+                loop {
+                    if open == b'{' {
+                        self.skip_string()?;
+                        self.expect(b':')?;
+                        self.skip_whitespace();
+                    }
+                    self.skip_value()?;
+                    self.skip_whitespace();
+                    match self.peek()? {
+                        b',' => {
+                            self.offset += 1;
+                            self.skip_whitespace();
+                        }
+                        byte if byte == close => {
+                            self.offset += 1;
+                            return Some(());
+                        }
+                        _ => return None,
+                    }
+                }
+            }
+            _ => {
+                while !matches!(
+                    self.peek(),
+                    None | Some(b',' | b'}' | b']' | b' ' | b'\t' | b'\n' | b'\r')
+                ) {
+                    self.offset += 1;
+                }
+                Some(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    const SCHEMA: &str = r#"{
+    "type": "array",
+    "length": { "type": "number", "constant": 3 },
+    "content": {
+        "type": "object",
+        "id": { "type": "number", "id": {} },
+        "name\"s": { "type": "string", "categorical": { "}]": 1, "x\\": 2 } },
+        "tags": {
+            "type": "one_of",
+            "variants": [
+                { "type": "null" },
+                {
+                    "type": "number",
+                    "low": 1
+                }
+            ]
+        }
+    }
+}"#;
+
+    #[test]
+    fn spans_of_nodes() {
+        let mut parser = Parser {
+            cursor: Cursor {
+                text: SCHEMA,
+                offset: 0,
+            },
+            nodes: Vec::new(),
+        };
+        let value = parser.value(&mut Vec::new()).unwrap();
+        assert_eq!(value["content"]["name\"s"]["categorical"]["x\\"], 2);
+        assert!(value["content"]["tags"]["variants"][1][SPAN_KEY].is_u64());
+        assert!(value["content"]["id"]["id"].as_object().unwrap().is_empty());
+
+        let at = |path: &[&str]| {
+            parser
+                .nodes
+                .iter()
+                .find(|(node, _)| node.iter().eq(path.iter()))
+                .map(|(_, offset)| line_column(SCHEMA, *offset))
+        };
+        assert_eq!(at(&[]), Some((1, 1)));
+        assert_eq!(at(&["length"]), Some((3, 15)));
+        assert_eq!(at(&["content", "tags", "variants", "1"]), Some((12, 17)));
+        // Objects without a `type` are part of their parent
+        assert_eq!(at(&["content", "id", "id"]), None);
+        assert_eq!(at(&["content", "name\"s", "categorical"]), None);
+    }
+
+    #[test]
+    fn error_at_innermost_node() {
+        let err = Content::from_json_str(SCHEMA).unwrap_err();
+        assert_eq!(err.path(), ["content", "tags", "variants", "1"]);
+        assert_eq!((err.line(), err.column()), (12, 17));
+        assert_eq!(
+            err.to_string(),
+            "`low` is expected to be a field of `range` at `content.tags.variants.1`, at line 12 column 17"
+        );
+    }
+
+    #[test]
+    fn error_at_weighted_variant() {
+        let err = Content::from_json_str(
+            r#"{
+                "type": "one_of",
+                "variants": [
+                    { "weight": 1.0, "type": "null" },
+                    { "weight": 1.0, "type": "string", "date_time": {} }
+                ]
+            }"#,
+        )
+        .unwrap_err();
+        assert_eq!(err.path(), ["variants", "1"]);
+        assert_eq!((err.line(), err.column()), (5, 21));
+        assert!(err.message().starts_with("unknown variant `date_time`"));
+    }
+
+    #[test]
+    fn error_at_syntax_error() {
+        let err = Content::from_json_str("{\n  \"type\": \"null\",\n}").unwrap_err();
+        assert!(err.path().is_empty());
+        assert_eq!((err.line(), err.column()), (3, 1));
+        assert_eq!(err.to_string(), "trailing comma at line 3 column 1");
+    }
+}
@@ -0,0 +1,88 @@
+use std::hash::{Hash, Hasher};
+
+use super::prelude::*;
+
+use crate::graph::SwitchNode;
+use crate::schema::FieldRef;
+
+/// Picks the content to generate depending on the value generated by the field at `on`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Hash)]
+#[serde(deny_unknown_fields)]
+pub struct SwitchContent {
+    pub on: FieldRef,
+    pub cases: Vec<CaseContent>,
+    #[serde(default)]
+    pub default: Box<Content>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct CaseContent {
+    pub when: Value,
+    pub content: Box<Content>,
+}
+
+#[allow(clippy::derive_hash_xor_eq)]
+impl Hash for CaseContent {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.when.to_string().hash(state);
+        self.content.hash(state);
+    }
+}
+
+impl SwitchContent {
+    pub fn iter(&self) -> impl Iterator<Item = &Content> {
+        self.cases
+            .iter()
+            .map(|case| case.content.as_ref())
+            .chain(std::iter::once(self.default.as_ref()))
+    }
+}
+
+impl Compile for SwitchContent {
+    fn compile<'a, C: Compiler<'a>>(&'a self, mut compiler: C) -> Result<Graph> {
+        let on = compiler.get(self.on.clone())?;
+        let cases = self
+            .cases
+            .iter()
+            .enumerate()
+            .map(|(idx, case)| {
+                compiler
+                    .build(&idx.to_string(), &case.content)
+                    .map(|graph| (case.when.clone(), graph))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let default = compiler.build("default", &self.default)?;
+        Ok(Graph::Switch(SwitchNode::new(on, cases, default)))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn default_is_null() {
+        let content: Content = schema!({
+            "type": "switch",
+            "on": "orders.content.status",
+            "cases": [
+                {
+                    "when": "shipped",
+                    "content": {
+                        "type": "bool",
+                        "constant": true
+                    }
+                }
+            ]
+        });
+        match content {
+            Content::Switch(switch) => {
+                assert_eq!(switch.on, "orders.content.status".parse().unwrap());
+                assert_eq!(switch.cases[0].when, serde_json::json!("shipped"));
+                assert!(switch.default.is_null());
+            }
+            otherwise => panic!("expected a switch, found {}", otherwise),
+        }
+    }
+}
@@ -1,14 +1,22 @@
 #![allow(clippy::derivable_impls)]
 
 use crate::compile::Compile;
-use crate::graph::UniqueNode;
+use crate::graph::permutation::{Pattern, Space};
+use crate::graph::{PermutationNode, UniqueNode};
+use crate::schema::{number_content, NumberContent, RangeStep, StringContent};
 use crate::{Compiler, Content, Graph};
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use synth_gen::value::Number;
 
 #[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Hash)]
 pub enum UniqueAlgorithm {
-    Hash { retries: Option<usize> },
+    Hash {
+        retries: Option<usize>,
+    },
+    /// Goes through a random permutation of the values of integer ranges and simple patterns,
+    /// which needs no memory of the values generated and never retries.
+    Permutation,
 }
 
 #[allow(clippy::derivable_impls)]
@@ -26,11 +34,90 @@ pub struct UniqueContent {
     pub content: Box<Content>,
 }
 
+impl UniqueContent {
+    /// The values of `content` the permutation algorithm goes through, or `None` for the
+    /// contents generating unique values already.
+    fn permuted_space(&self) -> Result<Option<Space>> {
+        let space = match self.content.as_ref() {
+            Content::Number(NumberContent::U32(number_content::U32::Range(range))) => {
+                integers(range, u32::MIN, u32::MAX, |n| Number::from(n as u32))?
+            }
+            Content::Number(NumberContent::U64(number_content::U64::Range(range))) => {
+                integers(range, u64::MIN, u64::MAX, |n| Number::from(n as u64))?
+            }
+            Content::Number(NumberContent::I32(number_content::I32::Range(range))) => {
+                integers(range, i32::MIN, i32::MAX, |n| Number::from(n as i32))?
+            }
+            Content::Number(NumberContent::I64(number_content::I64::Range(range))) => {
+                integers(range, i64::MIN, i64::MAX, |n| Number::from(n as i64))?
+            }
+            Content::Number(NumberContent::U32(number_content::U32::Id(_)))
+            | Content::Number(NumberContent::U64(number_content::U64::Id(_)))
+            | Content::Number(NumberContent::I32(number_content::I32::Id(_)))
+            | Content::Number(NumberContent::I64(number_content::I64::Id(_))) => return Ok(None),
+            Content::String(StringContent::Pattern(pattern)) => {
+                Space::Pattern(Pattern::parse(&pattern.to_string())?)
+            }
+            other => {
+                return Err(anyhow!(
+                    "the permutation algorithm of `unique` only supports integer ranges, ids and patterns, found a '{}'",
+                    other.kind()
+                ))
+            }
+        };
+        Ok(Some(space))
+    }
+}
+
+/// The integers of `range`, whose bounds default to `min` and `max`.
+fn integers<N>(range: &RangeStep<N>, min: N, max: N, to_number: fn(i128) -> Number) -> Result<Space>
+where
+    N: Copy + Into<i128>,
+{
+    let low: i128 = range.low.unwrap_or(min).into();
+    let high: i128 = range.high.unwrap_or(max).into();
+    let step: i128 = range.step.map_or(1, Into::into);
+    if step <= 0 {
+        return Err(anyhow!(
+            "integer range 'step'={} is invalid, use a positive value instead",
+            step
+        ));
+    }
+
+    let delta = high - low;
+    let first = if range.include_low { 0 } else { 1 };
+    let last = if delta % step != 0 || range.include_high {
+        delta / step
+    } else {
+        delta / step - 1
+    };
+    if low > high || last < first {
+        return Err(anyhow!(
+            "integer range low={} high={} step={} is empty",
+            low,
+            high,
+            step
+        ));
+    }
+
+    Ok(Space::Integers {
+        low: low + first * step,
+        step,
+        size: (last - first + 1) as u128,
+        to_number,
+    })
+}
+
 impl Compile for UniqueContent {
     fn compile<'a, C: Compiler<'a>>(&'a self, compiler: C) -> Result<Graph> {
-        let graph = self.content.compile(compiler)?;
         let node = match self.algorithm {
-            UniqueAlgorithm::Hash { retries } => UniqueNode::hash(graph, retries),
+            UniqueAlgorithm::Hash { retries } => {
+                UniqueNode::hash(self.content.compile(compiler)?, retries)
+            }
+            UniqueAlgorithm::Permutation => match self.permuted_space()? {
+                Some(space) => UniqueNode::Permutation(PermutationNode::new(space)),
+                None => return self.content.compile(compiler),
+            },
         };
         Ok(Graph::Unique(node))
     }
@@ -0,0 +1,311 @@
+//! Turns the string and integer fields of a collection which only ever take a few distinct
+//! values, like a `status` or a `country`, into categoricals of the values seen, so that they are
+//! generated with the same values and frequencies.
+//!
+//! The distinct values of the fields are counted by [`Cardinality::observe`] alongside the merge
+//! of the values into the content of their collection, and the fields found to have few distinct
+//! values are converted by [`Cardinality::apply`] once all the values have been seen.
+use std::collections::{BTreeMap, HashMap};
+
+use serde_json::Value;
+
+use crate::schema::{
+    number_content, Categorical, CategoricalType, Content, NumberContent, StringContent,
+};
+
+/// The default largest number of distinct values of a field for it to be turned into a
+/// categorical.
+pub const DEFAULT_CATEGORICAL_THRESHOLD: usize = 10;
+
+/// A step from a node to one of its children.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(super) enum Segment {
+    Field(String),
+    Element,
+}
+
+/// The distinct values seen at a node with how many times each was seen.
+#[derive(Debug)]
+enum Distinct {
+    Strings(BTreeMap<String, u64>),
+    Unsigned(BTreeMap<u64, u64>),
+    Signed(BTreeMap<i64, u64>),
+    /// More distinct values than the threshold, floats, or values of different types: the node
+    /// is left as it is.
+    Many,
+}
+
+impl Distinct {
+    fn len(&self) -> usize {
+        match self {
+            Self::Strings(seen) => seen.len(),
+            Self::Unsigned(seen) => seen.len(),
+            Self::Signed(seen) => seen.len(),
+            Self::Many => usize::MAX,
+        }
+    }
+
+    fn push(&mut self, value: &Value) {
+        match (&mut *self, value) {
+            (Self::Strings(seen), Value::String(string)) => push(seen, string.clone()),
+            (Self::Unsigned(seen), Value::Number(number)) if number.is_u64() => {
+                push(seen, number.as_u64().unwrap())
+            }
+            (Self::Unsigned(seen), Value::Number(number)) if number.is_i64() => {
+                // A negative number: the node is a signed integer from now on
+                let signed = std::mem::take(seen)
+                    .into_iter()
+                    .map(|(value, count)| Some((i64::try_from(value).ok()?, count)))
+                    .collect::<Option<_>>();
+                *self = match signed {
+                    Some(signed) => Self::Signed(signed),
+                    None => Self::Many,
+                };
+                self.push(value)
+            }
+            (Self::Signed(seen), Value::Number(number)) if number.is_i64() => {
+                push(seen, number.as_i64().unwrap())
+            }
+            (Self::Many, _) => {}
+            _ => *self = Self::Many,
+        }
+    }
+}
+
+fn push<T: Ord>(seen: &mut BTreeMap<T, u64>, value: T) {
+    *seen.entry(value).or_default() += 1;
+}
+
+/// Counts the distinct values of the string and integer fields of a collection, keeping at most
+/// `threshold` of them for each field.
+#[derive(Debug)]
+pub struct Cardinality {
+    threshold: usize,
+    nodes: HashMap<Vec<Segment>, (Distinct, u64)>,
+}
+
+impl Cardinality {
+    pub fn new(threshold: usize) -> Self {
+        Self {
+            threshold,
+            nodes: HashMap::new(),
+        }
+    }
+
+    /// Counts the values of the collection in `value`.
+    pub fn observe(&mut self, value: &Value) {
+        self.observe_at(&mut Vec::new(), value)
+    }
+
+    fn observe_at(&mut self, path: &mut Vec<Segment>, value: &Value) {
+        match value {
+            Value::Null | Value::Bool(_) => {}
+            Value::Object(object) => {
+                for (key, value) in object {
+                    path.push(Segment::Field(key.clone()));
+                    self.observe_at(path, value);
+                    path.pop();
+                }
+            }
+            Value::Array(values) => {
+                path.push(Segment::Element);
+                for value in values {
+                    self.observe_at(path, value);
+                }
+                path.pop();
+            }
+            Value::String(_) | Value::Number(_) => {
+                let (distinct, total) = self.nodes.entry(path.clone()).or_insert_with(|| {
+                    let distinct = match value {
+                        Value::String(_) => Distinct::Strings(BTreeMap::new()),
+                        Value::Number(number) if number.is_u64() => {
+                            Distinct::Unsigned(BTreeMap::new())
+                        }
+                        Value::Number(number) if number.is_i64() => {
+                            Distinct::Signed(BTreeMap::new())
+                        }
+                        _ => Distinct::Many,
+                    };
+                    (distinct, 0)
+                });
+                distinct.push(value);
+                *total += 1;
+                if distinct.len() > self.threshold {
+                    *distinct = Distinct::Many;
+                }
+            }
+        }
+    }
+
+    /// Turns the nodes of `content` with few distinct values into categoricals of the values
+    /// observed.
+    pub fn apply(&self, content: &mut Content) {
+        self.apply_at(&mut Vec::new(), content)
+    }
+
+    fn apply_at(&self, path: &mut Vec<Segment>, content: &mut Content) {
+        match content {
+            Content::Object(object_content) => {
+                for (key, field) in object_content.fields.iter_mut() {
+                    path.push(Segment::Field(key.clone()));
+                    self.apply_at(path, field);
+                    path.pop();
+                }
+            }
+            Content::Array(array_content) => {
+                path.push(Segment::Element);
+                self.apply_at(path, &mut array_content.content);
+                path.pop();
+            }
+            Content::OneOf(one_of_content) => {
+                for variant in one_of_content.variants.iter_mut() {
+                    self.apply_at(path, &mut variant.content);
+                }
+            }
+            Content::String(_) | Content::DateTime(_) | Content::Number(_) => {
+                if let Some(replacement) = self.categorical_at(path, content) {
+                    debug!(
+                        "turning a '{}' node with few distinct values into a categorical",
+                        content.kind()
+                    );
+                    *content = replacement;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// The categorical replacing `content` at `path`, if it has few distinct values which repeat
+    /// on average.
+    fn categorical_at(&self, path: &[Segment], content: &Content) -> Option<Content> {
+        let (distinct, total) = self.nodes.get(path)?;
+        let len = distinct.len();
+        if len > self.threshold || *total < 2 * len as u64 {
+            return None;
+        }
+
+        match (content, distinct) {
+            (Content::String(_) | Content::DateTime(_), Distinct::Strings(seen)) => Some(
+                Content::String(StringContent::Categorical(categorical(seen))),
+            ),
+            (Content::Number(NumberContent::U64(_)), Distinct::Unsigned(seen)) => Some(
+                Content::Number(number_content::U64::Categorical(categorical(seen)).into()),
+            ),
+            (Content::Number(NumberContent::I64(_)), Distinct::Signed(seen)) => Some(
+                Content::Number(number_content::I64::Categorical(categorical(seen)).into()),
+            ),
+            (Content::Number(NumberContent::I64(_)), Distinct::Unsigned(seen)) => {
+                let seen = seen
+                    .iter()
+                    .map(|(value, count)| Some((i64::try_from(*value).ok()?, *count)))
+                    .collect::<Option<_>>()?;
+                Some(Content::Number(
+                    number_content::I64::Categorical(categorical(&seen)).into(),
+                ))
+            }
+            _ => None,
+        }
+    }
+}
+
+fn categorical<T: CategoricalType>(seen: &BTreeMap<T, u64>) -> Categorical<T> {
+    Categorical {
+        seen: seen.clone(),
+        total: seen.values().sum(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::schema::{MergeStrategy, ObjectContent, OptionalMergeStrategy};
+
+    fn infer(values: Value, threshold: usize) -> ObjectContent {
+        let mut content = Content::from_value_wrapped_in_array(&values[0]);
+        OptionalMergeStrategy::default()
+            .try_merge(&mut content, &values)
+            .unwrap();
+
+        let mut cardinality = Cardinality::new(threshold);
+        cardinality.observe(&values);
+        cardinality.apply(&mut content);
+
+        match content {
+            Content::Array(array_content) => match *array_content.content {
+                Content::Object(object_content) => object_content,
+                otherwise => panic!("unexpected content: {}", otherwise.kind()),
+            },
+            otherwise => panic!("unexpected content: {}", otherwise.kind()),
+        }
+    }
+
+    #[test]
+    fn few_distinct_values() {
+        let orders = infer(
+            json!([
+                { "id": 1, "status": "paid", "quantity": 1 },
+                { "id": 2, "status": "paid", "quantity": 2 },
+                { "id": 3, "status": "shipped", "quantity": -1 },
+                { "id": 4, "status": "paid", "quantity": 2 },
+                { "id": 5, "status": "shipped", "quantity": 1 },
+                { "id": 6, "status": "paid", "quantity": 2 }
+            ]),
+            DEFAULT_CATEGORICAL_THRESHOLD,
+        );
+
+        assert_eq!(
+            orders.fields["status"],
+            Content::String(StringContent::Categorical(
+                serde_json::from_value(json!({ "paid": 4, "shipped": 2 })).unwrap()
+            ))
+        );
+        assert_eq!(
+            orders.fields["quantity"],
+            Content::Number(
+                number_content::I64::Categorical(
+                    serde_json::from_value(json!({ "-1": 1, "1": 2, "2": 3 })).unwrap()
+                )
+                .into()
+            )
+        );
+        // Every id is different
+        assert_eq!(orders.fields["id"].kind(), "number::U64::Range");
+    }
+
+    #[test]
+    fn above_threshold() {
+        let orders = infer(
+            json!([
+                { "status": "paid" },
+                { "status": "paid" },
+                { "status": "shipped" },
+                { "status": "shipped" },
+                { "status": "cancelled" },
+                { "status": "cancelled" }
+            ]),
+            2,
+        );
+        assert_ne!(orders.fields["status"].kind(), "string::categorical");
+
+        let orders = infer(json!([{ "status": "paid" }, { "status": "paid" }]), 0);
+        assert_ne!(orders.fields["status"].kind(), "string::categorical");
+    }
+
+    #[test]
+    fn nullable_fields() {
+        let users = infer(
+            json!([
+                { "country": "FR" },
+                { "country": null },
+                { "country": "FR" },
+                { "country": "DE" },
+                { "country": "DE" }
+            ]),
+            DEFAULT_CATEGORICAL_THRESHOLD,
+        );
+
+        let country = users.fields["country"].as_nullable().unwrap();
+        assert_eq!(country.kind(), "string::categorical");
+    }
+}
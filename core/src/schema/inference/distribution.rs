@@ -0,0 +1,380 @@
+//! Fits a distribution to the numbers of a collection, so that fields like an age or a price are
+//! generated around the values they were seen with rather than uniformly across their range.
+//!
+//! The moments of the numbers (and of their logarithms) are accumulated by
+//! [`DistributionFit::observe`] alongside the merge of the values into the content of their
+//! collection. Once all the values have been seen, [`DistributionFit::apply`] fits a normal, a
+//! log-normal and an exponential distribution to each number range and keeps the one with the
+//! lowest [Akaike information criterion](https://en.wikipedia.org/wiki/Akaike_information_criterion),
+//! if it is lower than the one of the uniform distribution of the range. The fitted distribution
+//! is bounded by the smallest and largest values seen.
+//!
+//! Every choice is logged at the `info` level, with the criterion of each candidate, so that it
+//! can be reviewed.
+use std::collections::HashMap;
+use std::f64::consts::PI;
+
+use serde_json::Value;
+
+use super::cardinality::Segment;
+use crate::schema::{number_content, Content, Exponential, LogNormal, Normal, NumberContent};
+
+/// The smallest number of values a distribution is fitted to. Below it, ranges are left uniform.
+pub const MIN_FIT_VALUES: u64 = 30;
+
+/// The mean and the sum of the squared deviations from the mean of a stream of numbers, updated
+/// with Welford's algorithm.
+#[derive(Debug, Default)]
+struct Moments {
+    count: u64,
+    mean: f64,
+    m2: f64,
+}
+
+impl Moments {
+    fn push(&mut self, value: f64) {
+        self.count += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value - self.mean);
+    }
+
+    fn variance(&self) -> f64 {
+        self.m2 / self.count as f64
+    }
+}
+
+/// The numbers seen at a node.
+#[derive(Debug)]
+struct Sample {
+    values: Moments,
+    /// The moments of the logarithms of the values, as long as they are all positive.
+    logs: Option<Moments>,
+    min: f64,
+    max: f64,
+}
+
+impl Default for Sample {
+    fn default() -> Self {
+        Self {
+            values: Moments::default(),
+            logs: Some(Moments::default()),
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+        }
+    }
+}
+
+impl Sample {
+    fn push(&mut self, value: f64) {
+        self.values.push(value);
+        if value > 0.0 {
+            if let Some(logs) = self.logs.as_mut() {
+                logs.push(value.ln());
+            }
+        } else {
+            self.logs = None;
+        }
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+    }
+
+    /// The candidate distributions with their Akaike information criterion, starting with the
+    /// uniform distribution of the range.
+    fn candidates(&self) -> Vec<(Candidate, f64)> {
+        let n = self.values.count as f64;
+        let mut candidates = vec![(
+            Candidate::Uniform,
+            4.0 + 2.0 * n * (self.max - self.min).ln(),
+        )];
+
+        let variance = self.values.variance();
+        if variance > 0.0 {
+            candidates.push((
+                Candidate::Normal {
+                    mean: self.values.mean,
+                    std_dev: variance.sqrt(),
+                },
+                4.0 + n * ((2.0 * PI * variance).ln() + 1.0),
+            ));
+        }
+
+        if let Some(logs) = self.logs.as_ref() {
+            let variance = logs.variance();
+            if variance > 0.0 {
+                candidates.push((
+                    Candidate::LogNormal {
+                        mu: logs.mean,
+                        sigma: variance.sqrt(),
+                    },
+                    4.0 + n * ((2.0 * PI * variance).ln() + 1.0) + 2.0 * n * logs.mean,
+                ));
+            }
+        }
+
+        if self.min >= 0.0 && self.values.mean > 0.0 {
+            candidates.push((
+                Candidate::Exponential {
+                    rate: 1.0 / self.values.mean,
+                },
+                2.0 + 2.0 * n * (self.values.mean.ln() + 1.0),
+            ));
+        }
+
+        candidates
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Candidate {
+    Uniform,
+    Normal { mean: f64, std_dev: f64 },
+    LogNormal { mu: f64, sigma: f64 },
+    Exponential { rate: f64 },
+}
+
+impl Candidate {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Uniform => "uniform",
+            Self::Normal { .. } => "normal",
+            Self::LogNormal { .. } => "log_normal",
+            Self::Exponential { .. } => "exponential",
+        }
+    }
+
+    /// The content of this distribution bounded by `[low, high]`, of the same subtype as
+    /// `range`, if it is a range.
+    fn to_content(self, range: &NumberContent, low: f64, high: f64) -> Option<Content> {
+        macro_rules! fitted {
+            ($($kind:ident),*) => {
+                match range {
+                    $(NumberContent::$kind(number_content::$kind::Range(_)) => {
+                        let fitted: number_content::$kind = match self {
+                            Self::Uniform => return None,
+                            Self::Normal { mean, std_dev } => Normal {
+                                mean,
+                                std_dev,
+                                low: Some(low),
+                                high: Some(high),
+                                clamp: false,
+                            }
+                            .into(),
+                            Self::LogNormal { mu, sigma } => LogNormal {
+                                mu,
+                                sigma,
+                                low: Some(low),
+                                high: Some(high),
+                                clamp: false,
+                            }
+                            .into(),
+                            Self::Exponential { rate } => Exponential {
+                                rate,
+                                low: Some(low),
+                                high: Some(high),
+                                clamp: false,
+                            }
+                            .into(),
+                        };
+                        Some(Content::Number(fitted.into()))
+                    })*
+                    _ => None,
+                }
+            };
+        }
+
+        fitted!(U32, U64, I32, I64, F32, F64)
+    }
+}
+
+/// Accumulates the moments of the numbers of a collection to fit distributions to its number
+/// ranges.
+#[derive(Debug, Default)]
+pub struct DistributionFit {
+    nodes: HashMap<Vec<Segment>, Sample>,
+}
+
+impl DistributionFit {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Accumulates the numbers of the collection in `value`.
+    pub fn observe(&mut self, value: &Value) {
+        self.observe_at(&mut Vec::new(), value)
+    }
+
+    fn observe_at(&mut self, path: &mut Vec<Segment>, value: &Value) {
+        match value {
+            Value::Object(object) => {
+                for (key, value) in object {
+                    path.push(Segment::Field(key.clone()));
+                    self.observe_at(path, value);
+                    path.pop();
+                }
+            }
+            Value::Array(values) => {
+                path.push(Segment::Element);
+                for value in values {
+                    self.observe_at(path, value);
+                }
+                path.pop();
+            }
+            Value::Number(number) => {
+                if let Some(number) = number.as_f64() {
+                    self.nodes.entry(path.clone()).or_default().push(number);
+                }
+            }
+            Value::Null | Value::Bool(_) | Value::String(_) => {}
+        }
+    }
+
+    /// Replaces the number ranges of `content` with the distributions fitted to their values,
+    /// where one fits better than the range.
+    pub fn apply(&self, content: &mut Content) {
+        self.apply_at(&mut Vec::new(), content)
+    }
+
+    fn apply_at(&self, path: &mut Vec<Segment>, content: &mut Content) {
+        match content {
+            Content::Object(object_content) => {
+                for (key, field) in object_content.fields.iter_mut() {
+                    path.push(Segment::Field(key.clone()));
+                    self.apply_at(path, field);
+                    path.pop();
+                }
+            }
+            Content::Array(array_content) => {
+                path.push(Segment::Element);
+                self.apply_at(path, &mut array_content.content);
+                path.pop();
+            }
+            Content::OneOf(one_of_content) => {
+                for variant in one_of_content.variants.iter_mut() {
+                    self.apply_at(path, &mut variant.content);
+                }
+            }
+            Content::Number(number_content) => {
+                if let Some(fitted) = self.fit_at(path, number_content) {
+                    *content = fitted;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn fit_at(&self, path: &[Segment], number_content: &NumberContent) -> Option<Content> {
+        let sample = self.nodes.get(path)?;
+        if sample.values.count < MIN_FIT_VALUES || sample.min >= sample.max {
+            return None;
+        }
+        // Only ranges are fitted: categoricals, constants and ids are left as they are
+        if !number_content.kind().ends_with("::Range") {
+            return None;
+        }
+
+        let candidates = sample.candidates();
+        let (best, _) = candidates
+            .iter()
+            .copied()
+            .min_by(|(_, left), (_, right)| left.total_cmp(right))?;
+
+        let criteria = candidates
+            .iter()
+            .map(|(candidate, criterion)| format!("{}: {:.1}", candidate.name(), criterion))
+            .collect::<Vec<_>>()
+            .join(", ");
+        info!(
+            "keeping the {} distribution for `{}` ({} values, {})",
+            best.name(),
+            display_path(path),
+            sample.values.count,
+            criteria
+        );
+
+        best.to_content(number_content, sample.min, sample.max)
+    }
+}
+
+fn display_path(path: &[Segment]) -> String {
+    path.iter()
+        .map(|segment| match segment {
+            Segment::Field(name) => name.as_str(),
+            Segment::Element => "content",
+        })
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::schema::{MergeStrategy, ObjectContent, OptionalMergeStrategy};
+
+    fn infer(values: Vec<Value>) -> ObjectContent {
+        let values = Value::Array(values);
+        let mut content = Content::from_value_wrapped_in_array(&values[0]);
+        OptionalMergeStrategy::default()
+            .try_merge(&mut content, &values)
+            .unwrap();
+
+        let mut fit = DistributionFit::new();
+        fit.observe(&values);
+        fit.apply(&mut content);
+
+        match content {
+            Content::Array(array_content) => match *array_content.content {
+                Content::Object(object_content) => object_content,
+                otherwise => panic!("unexpected content: {}", otherwise.kind()),
+            },
+            otherwise => panic!("unexpected content: {}", otherwise.kind()),
+        }
+    }
+
+    #[test]
+    fn normal() {
+        // Ages following a binomial distribution
+        let mut users = Vec::new();
+        for k in 0..=8u64 {
+            let count = (1..=k).fold(1, |count, i| count * (9 - i) / i);
+            for _ in 0..count {
+                users.push(json!({ "age": 30 + 5 * k }));
+            }
+        }
+        let users = infer(users);
+
+        match &users.fields["age"] {
+            Content::Number(NumberContent::U64(number_content::U64::Normal(normal))) => {
+                assert!((normal.mean - 50.0).abs() < 1e-6);
+                assert!((normal.std_dev - 50.0_f64.sqrt()).abs() < 1e-6);
+                assert_eq!(normal.low, Some(30.0));
+                assert_eq!(normal.high, Some(70.0));
+            }
+            otherwise => panic!("unexpected content: {}", otherwise.kind()),
+        }
+    }
+
+    #[test]
+    fn exponential() {
+        let orders = (0..100)
+            .map(|i| {
+                let quantile = (i as f64 + 0.5) / 100.0;
+                json!({ "delay": -(1.0 - quantile).ln() * 10.0 })
+            })
+            .collect();
+        let orders = infer(orders);
+
+        assert_eq!(orders.fields["delay"].kind(), "number::F64::Exponential");
+    }
+
+    #[test]
+    fn uniform() {
+        let users = infer((1..=100).map(|id| json!({ "id": id })).collect());
+        assert_eq!(users.fields["id"].kind(), "number::U64::Range");
+
+        // Too few values to fit a distribution
+        let users = infer((0..10).map(|i| json!({ "age": 30 + i % 3 })).collect());
+        assert_eq!(users.fields["age"].kind(), "number::U64::Range");
+    }
+}
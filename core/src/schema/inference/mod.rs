@@ -6,6 +6,15 @@ use serde_json::{Map, Number, Value};
 use std::collections::HashSet;
 use std::fmt::Display;
 
+pub mod cardinality;
+pub use cardinality::{Cardinality, DEFAULT_CATEGORICAL_THRESHOLD};
+
+pub mod distribution;
+pub use distribution::DistributionFit;
+
+pub mod string;
+pub use string::infer_string;
+
 pub mod value;
 pub use value::ValueMergeStrategy;
 
@@ -22,17 +31,28 @@ pub trait MergeStrategy<M, C>: std::fmt::Display {
     fn try_merge(self, master: &mut M, candidate: &C) -> Result<()>;
 }
 
-#[derive(Clone, Copy)]
-pub struct OptionalMergeStrategy;
+#[derive(Clone, Copy, Default)]
+pub struct OptionalMergeStrategy {
+    /// Whether the content merged into was declared, like the content of the columns of a
+    /// database, rather than inferred from values. Declared strings, dates and times are not
+    /// widened to accept the values which don't fit them.
+    pub declared: bool,
+}
 
 impl std::fmt::Display for OptionalMergeStrategy {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "OptionalMergeStrategy")
+        write!(f, "OptionalMergeStrategy(declared = {})", self.declared)
     }
 }
 
 impl MergeStrategy<Content, Value> for OptionalMergeStrategy {
     fn try_merge(self, master: &mut Content, candidate: &Value) -> Result<()> {
+        if let Value::String(string) = candidate {
+            if !self.declared {
+                string::widen(master, string);
+            }
+        }
+
         match (master, candidate) {
             // Logical nodes go first
             (Content::SameAs(_), _) => {
@@ -40,32 +60,35 @@ impl MergeStrategy<Content, Value> for OptionalMergeStrategy {
                 Ok(())
             }
             (Content::OneOf(one_of_content), candidate) => {
-                Self.try_merge(one_of_content, candidate)
+                self.try_merge(one_of_content, candidate)
             }
             (Content::Unique(unique_content), candidate) => {
-                Self.try_merge(unique_content, candidate)
+                self.try_merge(unique_content, candidate)
+            }
+            (Content::Hidden(hidden_content), candidate) => {
+                self.try_merge(hidden_content.content.as_mut(), candidate)
             }
             // Non-logical nodes go after
             (Content::Object(master_obj), Value::Object(candidate_obj)) => {
-                Self.try_merge(master_obj, candidate_obj)
+                self.try_merge(master_obj, candidate_obj)
             }
-            (Content::Array(ArrayContent { content, length }), Value::Array(values)) => {
-                Self.try_merge(length.as_mut(), &Value::from(values.len()))?;
+            (Content::Array(ArrayContent { content, length, .. }), Value::Array(values)) => {
+                self.try_merge(length.as_mut(), &Value::from(values.len()))?;
                 values
                     .iter()
-                    .try_for_each(|value| Self.try_merge(content.as_mut(), value))
+                    .try_for_each(|value| self.try_merge(content.as_mut(), value))
             }
             (Content::String(string_content), Value::String(string)) => {
-                Self.try_merge(string_content, string)
+                self.try_merge(string_content, string)
             }
             (Content::DateTime(date_time_content), Value::String(string)) => {
-                Self.try_merge(date_time_content, string)
+                self.try_merge(date_time_content, string)
             }
             (Content::Number(number_content), Value::Number(number)) => {
-                Self.try_merge(number_content, number)
+                self.try_merge(number_content, number)
             }
             (Content::Bool(bool_content), Value::Bool(boolean)) => {
-                Self.try_merge(bool_content, boolean)
+                self.try_merge(bool_content, boolean)
             }
             (Content::Null(_), Value::Null) => Ok(()),
             (master, candidate) => Err(failed!(
@@ -80,7 +103,7 @@ impl MergeStrategy<Content, Value> for OptionalMergeStrategy {
 
 impl MergeStrategy<UniqueContent, Value> for OptionalMergeStrategy {
     fn try_merge(self, master: &mut UniqueContent, candidate: &Value) -> Result<()> {
-        Self.try_merge(&mut *master.content, candidate)
+        self.try_merge(&mut *master.content, candidate)
     }
 }
 
@@ -140,10 +163,11 @@ impl MergeStrategy<ObjectContent, Map<String, Value>> for OptionalMergeStrategy
         master: &mut ObjectContent,
         candidate_obj: &serde_json::Map<String, Value>,
     ) -> Result<()> {
+        // Lookups and the fields they project are left as they are
         let master_keys: HashSet<_> = master
             .iter()
             .filter_map(|(key, value)| {
-                if !value.is_null() {
+                if !value.is_null() && !matches!(value, Content::Lookup(_)) {
                     Some(key.clone())
                 } else {
                     None
@@ -154,7 +178,7 @@ impl MergeStrategy<ObjectContent, Map<String, Value>> for OptionalMergeStrategy
         let candidate_keys: HashSet<_> = candidate_obj
             .iter()
             .filter_map(|(key, value)| {
-                if !value.is_null() {
+                if !value.is_null() && !master.is_projected(key) {
                     Some(key.clone())
                 } else {
                     None
@@ -177,7 +201,7 @@ impl MergeStrategy<ObjectContent, Map<String, Value>> for OptionalMergeStrategy
             // SAFETY: `key` is in both `self` and `candidate_obj`
             let master_value = master.get_mut(key).unwrap();
             let candidate_value = candidate_obj.get(key).unwrap();
-            Self.try_merge(master_value, candidate_value)?;
+            self.try_merge(master_value, candidate_value)?;
         }
 
         Ok(())
@@ -268,6 +292,11 @@ impl MergeStrategy<number_content::U64, u64> for OptionalMergeStrategy {
             number_content::U64::Categorical(cat) => self.try_merge(cat, candidate),
             number_content::U64::Constant(cst) => self.try_merge(cst, candidate),
             number_content::U64::Id(id) => self.try_merge(id, candidate),
+            number_content::U64::Normal(_)
+            | number_content::U64::LogNormal(_)
+            | number_content::U64::Exponential(_)
+            | number_content::U64::Gamma(_)
+            | number_content::U64::Zipf(_) => Ok(()),
         }
     }
 }
@@ -279,6 +308,11 @@ impl MergeStrategy<number_content::I64, i64> for OptionalMergeStrategy {
             number_content::I64::Categorical(cat) => self.try_merge(cat, candidate),
             number_content::I64::Constant(cst) => self.try_merge(cst, candidate),
             I64::Id(id) => self.try_merge(id, candidate),
+            number_content::I64::Normal(_)
+            | number_content::I64::LogNormal(_)
+            | number_content::I64::Exponential(_)
+            | number_content::I64::Gamma(_)
+            | number_content::I64::Zipf(_) => Ok(()),
         }
     }
 }
@@ -288,6 +322,11 @@ impl MergeStrategy<number_content::F64, f64> for OptionalMergeStrategy {
         match master {
             number_content::F64::Range(range) => self.try_merge(range, candidate),
             number_content::F64::Constant(cst) => self.try_merge(cst, candidate),
+            number_content::F64::Normal(_)
+            | number_content::F64::LogNormal(_)
+            | number_content::F64::Exponential(_)
+            | number_content::F64::Gamma(_)
+            | number_content::F64::Zipf(_) => Ok(()),
         }
     }
 }
@@ -299,6 +338,11 @@ impl MergeStrategy<number_content::U32, u32> for OptionalMergeStrategy {
             number_content::U32::Categorical(cat) => self.try_merge(cat, candidate),
             number_content::U32::Constant(cst) => self.try_merge(cst, candidate),
             number_content::U32::Id(id) => self.try_merge(id, candidate),
+            number_content::U32::Normal(_)
+            | number_content::U32::LogNormal(_)
+            | number_content::U32::Exponential(_)
+            | number_content::U32::Gamma(_)
+            | number_content::U32::Zipf(_) => Ok(()),
         }
     }
 }
@@ -310,6 +354,11 @@ impl MergeStrategy<number_content::I32, i32> for OptionalMergeStrategy {
             number_content::I32::Categorical(cat) => self.try_merge(cat, candidate),
             number_content::I32::Constant(cst) => self.try_merge(cst, candidate),
             I32::Id(id) => self.try_merge(id, candidate),
+            number_content::I32::Normal(_)
+            | number_content::I32::LogNormal(_)
+            | number_content::I32::Exponential(_)
+            | number_content::I32::Gamma(_)
+            | number_content::I32::Zipf(_) => Ok(()),
         }
     }
 }
@@ -319,6 +368,11 @@ impl MergeStrategy<number_content::F32, f32> for OptionalMergeStrategy {
         match master {
             number_content::F32::Range(range) => self.try_merge(range, candidate),
             number_content::F32::Constant(cst) => self.try_merge(cst, candidate),
+            number_content::F32::Normal(_)
+            | number_content::F32::LogNormal(_)
+            | number_content::F32::Exponential(_)
+            | number_content::F32::Gamma(_)
+            | number_content::F32::Zipf(_) => Ok(()),
         }
     }
 }
@@ -378,6 +432,7 @@ impl MergeStrategy<NumberContent, Number> for OptionalMergeStrategy {
 #[cfg(test)]
 pub mod tests {
     use super::*;
+    use crate::schema::ChronoValueType;
     use crate::Namespace;
 
     macro_rules! as_array {
@@ -417,7 +472,7 @@ pub mod tests {
             .accepts(&collection_name, &user_no_address_as_array)
             .is_err());
         ns.try_update(
-            OptionalMergeStrategy,
+            OptionalMergeStrategy::default(),
             &collection_name,
             &user_no_address_as_array,
         )
@@ -455,13 +510,13 @@ pub mod tests {
         ns.put_collection_from_json(collection_name.clone(), &user_no_last_name)
             .unwrap();
         ns.try_update(
-            OptionalMergeStrategy,
+            OptionalMergeStrategy::default(),
             &collection_name,
             &user_no_address_as_array,
         )
         .unwrap();
         ns.try_update(
-            OptionalMergeStrategy,
+            OptionalMergeStrategy::default(),
             &collection_name,
             &user_no_address_as_array,
         )
@@ -508,7 +563,7 @@ pub mod tests {
         ns.put_collection_from_json(collection_name.clone(), &user_no_last_name)
             .unwrap();
         ns.try_update(
-            OptionalMergeStrategy,
+            OptionalMergeStrategy::default(),
             &collection_name,
             &user_no_address_as_array,
         )
@@ -524,6 +579,122 @@ pub mod tests {
             .is_err());
     }
 
+    #[test]
+    fn test_merge_lookup_fields() {
+        let mut items: ObjectContent = serde_json::from_value(json!({
+            "quantity": {
+                "type": "number",
+                "subtype": "u64",
+                "range": {
+                    "low": 1,
+                    "high": 10,
+                    "step": 1
+                }
+            },
+            "orders": {
+                "type": "lookup",
+                "ref": "orders.content",
+                "fields": {
+                    "order_id": "id",
+                    "order_date": "date"
+                }
+            }
+        }))
+        .unwrap();
+        let expected = items.clone();
+
+        let item = json!({
+            "quantity": 2,
+            "order_id": 1,
+            "order_date": "2021-01-01"
+        });
+        OptionalMergeStrategy::default()
+            .try_merge(&mut items, item.as_object().unwrap())
+            .unwrap();
+
+        // The projected fields stay with the lookup, which is not made nullable
+        assert_eq!(items.fields["orders"], expected.fields["orders"]);
+        assert_eq!(items.len(), 2);
+        assert!(items.accepts(item.as_object().unwrap()).is_ok());
+    }
+
+    #[test]
+    fn merge_inferred_strings() {
+        let users = json!([
+            {
+                "id": "67e55044-10b1-426f-9247-bb680e5fe0c8",
+                "email": "jane@example.com",
+                "joined": "2021-01-02"
+            },
+            {
+                "id": "d9428888-122b-11e1-b85c-61cd3cbb3210",
+                "email": "not an email",
+                "joined": "2021-03-04"
+            }
+        ]);
+
+        let mut content = Content::from_value_wrapped_in_array(&users[0]);
+        OptionalMergeStrategy::default()
+            .try_merge(&mut content, &users)
+            .unwrap();
+
+        let users = match content {
+            Content::Array(ArrayContent { content, .. }) => match *content {
+                Content::Object(users) => users,
+                otherwise => panic!("unexpected content: {}", otherwise.kind()),
+            },
+            otherwise => panic!("unexpected content: {}", otherwise.kind()),
+        };
+        assert_eq!(users.fields["id"].kind(), "string::uuid");
+        assert_eq!(
+            users.fields["email"],
+            Content::String(StringContent::default())
+        );
+        match &users.fields["joined"] {
+            Content::DateTime(DateTimeContent {
+                format, begin, end, ..
+            }) => {
+                let fmt = ChronoValueFormatter::new(format);
+                assert_eq!(fmt.format(begin.as_ref().unwrap()).unwrap(), "2021-01-02");
+                assert_eq!(fmt.format(end.as_ref().unwrap()).unwrap(), "2021-03-04");
+            }
+            otherwise => panic!("unexpected content: {}", otherwise.kind()),
+        }
+    }
+
+    #[test]
+    fn merge_declared_strings() {
+        // The content of a `timestamptz` column
+        let timestamp = Content::DateTime(DateTimeContent {
+            format: "%Y-%m-%dT%H:%M:%S%z".to_string(),
+            type_: ChronoValueType::DateTime,
+            begin: None,
+            end: None,
+        });
+
+        let mut inferred = timestamp.clone();
+        OptionalMergeStrategy::default()
+            .try_merge(&mut inferred, &json!("tomorrow"))
+            .unwrap();
+        assert_eq!(inferred, Content::String(StringContent::default()));
+
+        let declared = OptionalMergeStrategy { declared: true };
+        let mut content = timestamp;
+        declared
+            .try_merge(&mut content, &json!("2021-01-02T03:04:05+0000"))
+            .unwrap();
+        assert!(declared
+            .try_merge(&mut content, &json!("tomorrow"))
+            .is_err());
+        match &content {
+            Content::DateTime(DateTimeContent { begin, end, .. }) => {
+                assert!(begin.is_some());
+                assert_eq!(begin, end);
+            }
+            otherwise => panic!("unexpected content: {}", otherwise.kind()),
+        }
+    }
+
     #[test]
     fn merge_numbers() {
         let mut master: NumberContent = serde_json::from_value(json!({
@@ -537,7 +708,7 @@ pub mod tests {
         .unwrap();
         let error_margin = f64::EPSILON;
 
-        OptionalMergeStrategy
+        OptionalMergeStrategy::default()
             .try_merge(&mut master, &"15".parse().unwrap())
             .unwrap();
 
@@ -552,10 +723,10 @@ pub mod tests {
             _ => unreachable!(),
         }
 
-        OptionalMergeStrategy
+        OptionalMergeStrategy::default()
             .try_merge(&mut master, &"-10".parse().unwrap())
             .unwrap();
-        OptionalMergeStrategy
+        OptionalMergeStrategy::default()
             .try_merge(&mut master, &"20".parse().unwrap())
             .unwrap();
 
@@ -570,10 +741,10 @@ pub mod tests {
             _ => unreachable!(),
         }
 
-        OptionalMergeStrategy
+        OptionalMergeStrategy::default()
             .try_merge(&mut master, &"-13.6".parse().unwrap())
             .unwrap();
-        OptionalMergeStrategy
+        OptionalMergeStrategy::default()
             .try_merge(&mut master, &"20.6".parse().unwrap())
             .unwrap();
 
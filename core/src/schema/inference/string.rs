@@ -0,0 +1,400 @@
+//! Recognises the kind of the strings seen during inference, so that string fields get a
+//! generator producing values that look like the ones they were inferred from rather than random
+//! alphanumerics.
+//!
+//! A field starts out as the narrowest content recognised for its first value and is widened by
+//! [`widen`] every time a value does not fit it, so that it ends up with the narrowest content
+//! accepting all the values sampled. Contents declared rather than inferred, like those of the
+//! columns of a database, are merged with [`OptionalMergeStrategy::declared`] set so that they are
+//! never widened.
+//!
+//! [`OptionalMergeStrategy::declared`]: super::OptionalMergeStrategy::declared
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use regex::Regex;
+
+use crate::schema::{
+    ChronoValueFormatter, Content, DateTimeContent, FakerContent, RegexContent, StringContent, Uuid,
+};
+
+lazy_static! {
+    static ref UUID_REGEX: Regex =
+        Regex::new("^[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}$")
+            .unwrap();
+    static ref EMAIL_REGEX: Regex =
+        Regex::new(r"^[A-Za-z0-9._%+-]+@[A-Za-z0-9-]+(\.[A-Za-z0-9-]+)*\.[A-Za-z]{2,}$").unwrap();
+    static ref URL_REGEX: Regex =
+        Regex::new(r"^https?://[A-Za-z0-9-]+(\.[A-Za-z0-9-]+)+(:[0-9]{1,5})?(/[^\s]*)?$").unwrap();
+    static ref PHONE_NUMBER_REGEX: Regex = Regex::new(r"^\+?[0-9(][0-9 ().-]{5,}[0-9]$").unwrap();
+}
+
+/// The formats tried, in order, on a string to recognise a date or a time. Formats with
+/// fractional seconds come after those without, so that the format chosen only has a fraction if
+/// the values do.
+const DATE_TIME_FORMATS: &[&str] = &[
+    "%Y-%m-%dT%H:%M:%S%:z",
+    "%Y-%m-%dT%H:%M:%S%.f%:z",
+    "%Y-%m-%dT%H:%M:%SZ",
+    "%Y-%m-%dT%H:%M:%S%.fZ",
+    "%Y-%m-%dT%H:%M:%S",
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%d %H:%M:%S%:z",
+    "%Y-%m-%d %H:%M:%S",
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y-%m-%d",
+    "%Y/%m/%d",
+    "%H:%M:%S",
+];
+
+/// The pattern generating URLs, as there is no faker for them.
+const URL_PATTERN: &str = r"https://(www\.)?[a-z]{3,12}\.(com|org|net|io)(/[a-z0-9]{1,10}){0,3}";
+
+/// Strings whose shape has more runs than this are not learned as a pattern, as they are more
+/// likely free text than a code or an identifier.
+const MAX_SHAPE_RUNS: usize = 8;
+
+/// The narrowest content recognised for a string value.
+pub fn infer_string(value: &str) -> Content {
+    if UUID_REGEX.is_match(value) {
+        return Content::String(StringContent::Uuid(Uuid));
+    }
+
+    if let Some(date_time) = infer_date_time(value) {
+        return Content::DateTime(date_time);
+    }
+
+    if let Some(generator) = FAKERS
+        .iter()
+        .find_map(|(generator, is_match)| is_match(value).then_some(generator))
+    {
+        return Content::String(StringContent::Faker(FakerContent {
+            generator: generator.to_string(),
+            locales: Vec::new(),
+            args: Default::default(),
+        }));
+    }
+
+    if URL_REGEX.is_match(value) {
+        return pattern(URL_PATTERN.to_string());
+    }
+
+    match Shape::of(value) {
+        Some(shape) => pattern(shape.to_pattern()),
+        None => Content::String(StringContent::default()),
+    }
+}
+
+/// Widens `master` so that it accepts the string `value`, if it is a content [`infer_string`]
+/// can produce. Any other content is left as it is.
+pub fn widen(master: &mut Content, value: &str) {
+    let accepts = match master {
+        Content::String(StringContent::Uuid(_)) => UUID_REGEX.is_match(value),
+        Content::String(StringContent::Faker(FakerContent { generator, .. })) => FAKERS
+            .iter()
+            .find(|(known, _)| *known == generator.as_str())
+            .map(|(_, is_match)| is_match(value))
+            .unwrap_or(true),
+        Content::String(StringContent::Pattern(regex)) if regex.to_string() == URL_PATTERN => {
+            URL_REGEX.is_match(value)
+        }
+        Content::String(StringContent::Pattern(regex)) => {
+            match Shape::from_pattern(&regex.to_string()) {
+                Some(master_shape) => {
+                    match Shape::of(value).and_then(|shape| master_shape.union(&shape)) {
+                        Some(shape) => {
+                            *master = pattern(shape.to_pattern());
+                            true
+                        }
+                        None => false,
+                    }
+                }
+                None => true,
+            }
+        }
+        Content::DateTime(DateTimeContent { format, .. }) => {
+            ChronoValueFormatter::new(format).parse(value).is_ok()
+        }
+        _ => true,
+    };
+
+    if !accepts {
+        debug!(
+            "widening a '{}' node to a pattern to accept '{}'",
+            master.kind(),
+            value
+        );
+        *master = Content::String(StringContent::default());
+    }
+}
+
+type Matcher = fn(&str) -> bool;
+
+/// The faker generators recognised, with the strings they are recognised from.
+const FAKERS: &[(&str, Matcher)] = &[
+    ("safe_email", |value| EMAIL_REGEX.is_match(value)),
+    ("ipv4", |value| value.parse::<Ipv4Addr>().is_ok()),
+    ("ipv6", |value| value.parse::<Ipv6Addr>().is_ok()),
+    ("phone_number", is_phone_number),
+];
+
+/// Whether `value` looks like a phone number: digits with separators, as a plain string of
+/// digits is as likely to be a code or an identifier.
+fn is_phone_number(value: &str) -> bool {
+    let digits = value.chars().filter(char::is_ascii_digit).count();
+    let separators = value
+        .chars()
+        .filter(|c| matches!(c, ' ' | '-' | '(' | ')'))
+        .count();
+    PHONE_NUMBER_REGEX.is_match(value)
+        && (7..=15).contains(&digits)
+        && (value.starts_with('+') || separators >= 2)
+}
+
+fn infer_date_time(value: &str) -> Option<DateTimeContent> {
+    DATE_TIME_FORMATS.iter().find_map(|format| {
+        let parsed = ChronoValueFormatter::new(format).parse(value).ok()?;
+        Some(DateTimeContent {
+            format: format.to_string(),
+            type_: parsed.type_(),
+            begin: Some(parsed.clone()),
+            end: Some(parsed),
+        })
+    })
+}
+
+fn pattern(pattern: String) -> Content {
+    match RegexContent::compile(pattern, 32) {
+        Ok(regex) => Content::String(StringContent::Pattern(regex)),
+        Err(err) => {
+            debug!("could not compile an inferred pattern: {}", err);
+            Content::String(StringContent::default())
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CharClass {
+    Upper,
+    Lower,
+    Digit,
+    Literal(char),
+}
+
+impl CharClass {
+    fn of(c: char) -> Self {
+        if c.is_ascii_uppercase() {
+            Self::Upper
+        } else if c.is_ascii_lowercase() {
+            Self::Lower
+        } else if c.is_ascii_digit() {
+            Self::Digit
+        } else {
+            Self::Literal(c)
+        }
+    }
+}
+
+/// A run of characters of the same class, repeated between `min` and `max` times.
+#[derive(Debug, Clone, PartialEq)]
+struct Run {
+    class: CharClass,
+    min: usize,
+    max: usize,
+}
+
+/// The shape of a string as runs of uppercase letters, lowercase letters, digits and literal
+/// characters, like `[A-Z]{2}-[0-9]{4}` for `AB-1234`.
+#[derive(Debug, Clone, PartialEq)]
+struct Shape(Vec<Run>);
+
+impl Shape {
+    fn of(value: &str) -> Option<Self> {
+        let mut runs: Vec<Run> = Vec::new();
+        for class in value.chars().map(CharClass::of) {
+            match runs.last_mut() {
+                Some(run) if run.class == class => {
+                    run.min += 1;
+                    run.max += 1;
+                }
+                _ => runs.push(Run {
+                    class,
+                    min: 1,
+                    max: 1,
+                }),
+            }
+        }
+        if runs.is_empty() || runs.len() > MAX_SHAPE_RUNS {
+            None
+        } else {
+            Some(Self(runs))
+        }
+    }
+
+    /// The shape accepting the strings of both `self` and `other`, if they have the same runs.
+    fn union(&self, other: &Self) -> Option<Self> {
+        if self.0.len() != other.0.len() {
+            return None;
+        }
+        self.0
+            .iter()
+            .zip(other.0.iter())
+            .map(|(left, right)| {
+                (left.class == right.class).then(|| Run {
+                    class: left.class,
+                    min: left.min.min(right.min),
+                    max: left.max.max(right.max),
+                })
+            })
+            .collect::<Option<_>>()
+            .map(Self)
+    }
+
+    fn to_pattern(&self) -> String {
+        let mut pattern = String::new();
+        for Run { class, min, max } in &self.0 {
+            match class {
+                CharClass::Upper => pattern.push_str("[A-Z]"),
+                CharClass::Lower => pattern.push_str("[a-z]"),
+                CharClass::Digit => pattern.push_str("[0-9]"),
+                CharClass::Literal(c) => {
+                    pattern.push_str(&regex_syntax::escape(c.encode_utf8(&mut [0; 4])))
+                }
+            }
+            if min != max {
+                pattern.push_str(&format!("{{{},{}}}", min, max));
+            } else if *min != 1 {
+                pattern.push_str(&format!("{{{}}}", min));
+            }
+        }
+        pattern
+    }
+
+    /// Reads back a pattern written by [`Shape::to_pattern`], or `None` if it is any other
+    /// pattern.
+    fn from_pattern(pattern: &str) -> Option<Self> {
+        let mut runs = Vec::new();
+        let mut rest = pattern;
+        while !rest.is_empty() {
+            let class = if let Some(tail) = rest.strip_prefix("[A-Z]") {
+                rest = tail;
+                CharClass::Upper
+            } else if let Some(tail) = rest.strip_prefix("[a-z]") {
+                rest = tail;
+                CharClass::Lower
+            } else if let Some(tail) = rest.strip_prefix("[0-9]") {
+                rest = tail;
+                CharClass::Digit
+            } else {
+                let mut chars = rest.chars();
+                let c = match chars.next()? {
+                    '\\' => chars
+                        .next()
+                        .filter(|c| regex_syntax::is_meta_character(*c))?,
+                    c if regex_syntax::is_meta_character(c) => return None,
+                    c => c,
+                };
+                rest = chars.as_str();
+                match CharClass::of(c) {
+                    CharClass::Literal(c) => CharClass::Literal(c),
+                    _ => return None,
+                }
+            };
+
+            let (min, max) = match rest.strip_prefix('{') {
+                Some(tail) => {
+                    let end = tail.find('}')?;
+                    rest = &tail[end + 1..];
+                    match tail[..end].split_once(',') {
+                        Some((min, max)) => (min.parse().ok()?, max.parse().ok()?),
+                        None => {
+                            let n = tail[..end].parse().ok()?;
+                            (n, n)
+                        }
+                    }
+                }
+                None => (1, 1),
+            };
+            runs.push(Run { class, min, max });
+        }
+
+        if runs.is_empty() {
+            None
+        } else {
+            Some(Self(runs))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kind_of(value: &str) -> String {
+        infer_string(value).kind()
+    }
+
+    #[test]
+    fn recognises_strings() {
+        assert_eq!(
+            kind_of("67e55044-10b1-426f-9247-bb680e5fe0c8"),
+            "string::uuid"
+        );
+        assert_eq!(kind_of("jane.doe@example.com"), "string::safe_email");
+        assert_eq!(kind_of("192.168.0.1"), "string::ipv4");
+        assert_eq!(kind_of("2001:db8::1"), "string::ipv6");
+        assert_eq!(kind_of("+44 20 7946 0958"), "string::phone_number");
+        assert_eq!(kind_of("(555) 123-4567"), "string::phone_number");
+        assert_eq!(kind_of("2021-03-04T05:06:07+00:00"), "date_time");
+        assert_eq!(kind_of("2021-03-04"), "date_time");
+        assert_eq!(kind_of("https://example.com/a/b"), "string::pattern");
+        assert_eq!(kind_of("1234567"), "string::pattern");
+    }
+
+    #[test]
+    fn infers_date_time_format() {
+        match infer_string("2021-03-04 05:06:07.250") {
+            Content::DateTime(DateTimeContent { format, .. }) => {
+                assert_eq!(format, "%Y-%m-%d %H:%M:%S%.f")
+            }
+            otherwise => panic!("unexpected content: {}", otherwise.kind()),
+        }
+    }
+
+    #[test]
+    fn learns_shapes() {
+        let mut content = infer_string("AB-1234");
+        assert_eq!(content, pattern("[A-Z]{2}\\-[0-9]{4}".to_string()));
+
+        widen(&mut content, "XYZ-12");
+        assert_eq!(content, pattern("[A-Z]{2,3}\\-[0-9]{2,4}".to_string()));
+
+        widen(&mut content, "not a code");
+        assert_eq!(content, Content::String(StringContent::default()));
+    }
+
+    #[test]
+    fn shape_round_trips() {
+        for value in ["AB-1234", "a.b{c}", "x", "Hello, World!"] {
+            let shape = Shape::of(value).unwrap();
+            assert_eq!(Shape::from_pattern(&shape.to_pattern()), Some(shape));
+        }
+
+        assert_eq!(Shape::from_pattern("[a-zA-Z0-9]*"), None);
+        assert_eq!(Shape::from_pattern("(0[1-9]|1[0-2])"), None);
+    }
+
+    #[test]
+    fn widens_on_mismatch() {
+        let mut content = infer_string("67e55044-10b1-426f-9247-bb680e5fe0c8");
+        widen(&mut content, "67e55044-10b1-426f-9247-bb680e5fe0c9");
+        assert_eq!(content, Content::String(StringContent::Uuid(Uuid)));
+        widen(&mut content, "67e55044");
+        assert_eq!(content, Content::String(StringContent::default()));
+
+        let mut content = infer_string("2021-03-04");
+        widen(&mut content, "2021-03-05");
+        assert_eq!(content.kind(), "date_time");
+        widen(&mut content, "tomorrow");
+        assert_eq!(content, Content::String(StringContent::default()));
+    }
+}
@@ -11,7 +11,10 @@ use serde::{
 use serde_json::Value as JsonValue;
 
 pub mod inference;
-pub use inference::{MergeStrategy, OptionalMergeStrategy, ValueMergeStrategy};
+pub use inference::{
+    infer_string, Cardinality, DistributionFit, MergeStrategy, OptionalMergeStrategy,
+    ValueMergeStrategy, DEFAULT_CATEGORICAL_THRESHOLD,
+};
 
 pub mod optionalise;
 
@@ -149,6 +152,12 @@ impl FieldRef {
         Some(parent)
     }
 
+    pub(crate) fn child(&self, field: &str) -> FieldRef {
+        let mut child = self.clone();
+        child.fields.push(field.to_string());
+        child
+    }
+
     pub(crate) fn last(&self) -> String {
         match self.fields.last() {
             Some(field) => field.clone(),
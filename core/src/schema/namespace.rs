@@ -60,7 +60,7 @@ impl Namespace {
     }
 
     pub fn default_try_update(&mut self, name: &str, value: &Value) -> Result<()> {
-        self.try_update(OptionalMergeStrategy, name, value)
+        self.try_update(OptionalMergeStrategy::default(), name, value)
     }
 
     pub fn try_update<M: MergeStrategy<Content, Value>>(
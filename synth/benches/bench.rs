@@ -23,11 +23,12 @@ fn bench_generate_n_to_stdout(size: usize) {
         let args = Args::Generate(GenerateCommand {
             namespace,
             collection: None,
-            size,
+            size: size.into(),
             to: "json:".to_string(),
             seed: Some(0),
             random: false,
             schema: None,
+            truncate: false,
         });
         let output = io::stdout();
         Cli::new().unwrap().run(args, output).await.unwrap()
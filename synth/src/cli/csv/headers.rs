@@ -1,4 +1,4 @@
-use synth_core::schema::content::{ArrayContent, ObjectContent, SameAsContent};
+use synth_core::schema::content::{ArrayContent, LookupContent, ObjectContent, SameAsContent};
 use synth_core::{Content, Namespace};
 
 use super::determine_content_array_max_length;
@@ -42,6 +42,7 @@ impl CsvHeaders {
                 &unique.content,
                 namespace,
             ),
+            Content::Lookup(lookup) => parse_lookup_to_headers(None, lookup, namespace),
             _ => Ok(vec![CsvHeader::ObjectProperty {
                 key: "value".to_string(),
                 parent: None,
@@ -198,6 +199,7 @@ fn parse_content_to_headers(
         Content::OneOf(_) => parse_one_of_to_headers(parent, content, namespace),
         Content::SameAs(same_as) => parse_same_as_to_headers(parent, same_as, namespace),
         Content::Unique(unique) => parse_content_to_headers(parent, &unique.content, namespace),
+        Content::Lookup(lookup) => parse_lookup_to_headers(Some(&parent), lookup, namespace),
         _ => Ok(vec![parent]),
     }
 }
@@ -210,6 +212,12 @@ fn parse_object_to_headers(
     let mut flatterned = Vec::new();
 
     for (field_name, field_content) in &obj.fields {
+        // The fields of a lookup are fields of the object it is in
+        if let Content::Lookup(lookup) = field_content {
+            flatterned.extend(parse_lookup_to_headers(parent, lookup, ns)?);
+            continue;
+        }
+
         flatterned.extend(parse_content_to_headers(
             CsvHeader::ObjectProperty {
                 parent: parent.cloned().map(Box::new),
@@ -223,6 +231,27 @@ fn parse_object_to_headers(
     Ok(flatterned)
 }
 
+fn parse_lookup_to_headers(
+    parent: Option<&CsvHeader>,
+    lookup: &LookupContent,
+    ns: &Namespace,
+) -> Result<Vec<CsvHeader>> {
+    let mut flatterned = Vec::new();
+
+    for (field_name, field_ref) in lookup.projected() {
+        flatterned.extend(parse_content_to_headers(
+            CsvHeader::ObjectProperty {
+                parent: parent.cloned().map(Box::new),
+                key: field_name.clone(),
+            },
+            ns.get_s_node(&field_ref)?,
+            ns,
+        )?);
+    }
+
+    Ok(flatterned)
+}
+
 fn parse_array_to_headers(
     parent: Option<&CsvHeader>,
     array: &ArrayContent,
@@ -367,7 +396,11 @@ mod tests {
                                 }),
                             ))),
                             content: Box::new(Content::Null(NullContent)),
+                            size: None,
+                            parent: None,
                         })),
+                        size: None,
+                        parent: None,
                     }),
                 );
 
@@ -378,6 +411,7 @@ mod tests {
                             VariantContent::new(Content::Null(NullContent)),
                             VariantContent::new(Content::SameAs(SameAsContent {
                                 ref_: FieldRef::new("my_collection.z").unwrap(),
+                                sample: None,
                             })),
                         ],
                     }),
@@ -1,15 +1,17 @@
 mod headers;
 
-use crate::cli::export::{ExportParams, ExportStrategy};
-use crate::sampler::{Sampler, SamplerOutput};
+use crate::cli::export::{ExportOutput, ExportParams, ExportStrategy};
+use crate::sampler::{Sampler, SamplerOutput, DEFAULT_CHUNK_SIZE};
 
 use synth_core::schema::content::{number_content, ArrayContent, NumberContent};
-use synth_core::schema::{MergeStrategy, OptionalMergeStrategy};
+use synth_core::schema::{Cardinality, DistributionFit, MergeStrategy, OptionalMergeStrategy};
 use synth_core::{Content, Namespace, Value};
 use synth_gen::value::Number;
 
 use anyhow::Result;
 
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::path::PathBuf;
 
@@ -21,28 +23,58 @@ pub struct CsvFileExportStrategy {
 }
 
 impl ExportStrategy for CsvFileExportStrategy {
-    fn export(&self, params: ExportParams) -> Result<SamplerOutput> {
-        let generator = Sampler::try_from(&params.namespace)?;
-        let output = generator.sample_seeded(params.collection_name, params.target, params.seed)?;
-
+    fn export(&self, params: ExportParams) -> Result<ExportOutput> {
         if self.to_dir.exists() {
             return Err(anyhow::anyhow!("Output directory already exists"));
         } else {
             std::fs::create_dir_all(&self.to_dir)?;
         }
 
-        match csv_output_from_sampler_ouput(output.clone(), &params.namespace)? {
-            CsvOutput::Namespace(ns) => {
-                for (name, csv) in ns {
-                    std::fs::write(self.to_dir.join(name + ".csv"), csv)?;
+        let generator = Sampler::try_from(&params.namespace)?;
+        let chunks = generator.sample_seeded_chunked(
+            params.collection_name,
+            params.target,
+            params.seed,
+            DEFAULT_CHUNK_SIZE,
+        );
+
+        // One writer per collection, opened (and given its header row) with the first chunk the
+        // collection appears in.
+        let mut writers = HashMap::<String, csv::Writer<std::fs::File>>::new();
+
+        for chunk in chunks {
+            let collections = match chunk? {
+                SamplerOutput::Namespace(key_values) => key_values
+                    .into_iter()
+                    .map(|(name, value)| (name.clone(), name, value))
+                    .collect(),
+                SamplerOutput::Collection(name, value) => {
+                    vec![("collection".to_string(), name, value)]
                 }
+            };
+
+            for (file_name, collection_name, value) in collections {
+                let writer = match writers.entry(file_name) {
+                    Entry::Occupied(entry) => entry.into_mut(),
+                    Entry::Vacant(entry) => {
+                        let path = self.to_dir.join(entry.key().clone() + ".csv");
+                        let mut writer = csv::Writer::from_path(path)?;
+                        write_csv_header(&mut writer, &collection_name, &params.namespace)?;
+                        entry.insert(writer)
+                    }
+                };
+
+                write_csv_records(writer, &collection_name, value, &params.namespace)?;
             }
-            CsvOutput::Collection(csv) => {
-                std::fs::write(self.to_dir.join("collection.csv"), csv)?;
-            }
         }
 
-        Ok(output)
+        let mut bytes = 0;
+        for (file_name, mut writer) in writers {
+            writer.flush()?;
+            bytes += std::fs::metadata(self.to_dir.join(file_name + ".csv"))?.len() as usize;
+        }
+
+        Ok(ExportOutput::Streamed { bytes: Some(bytes) })
     }
 }
 
@@ -50,7 +82,7 @@ impl ExportStrategy for CsvFileExportStrategy {
 pub struct CsvStdoutExportStrategy;
 
 impl ExportStrategy for CsvStdoutExportStrategy {
-    fn export(&self, params: ExportParams) -> Result<SamplerOutput> {
+    fn export(&self, params: ExportParams) -> Result<ExportOutput> {
         let generator = Sampler::try_from(&params.namespace)?;
         let output = generator.sample_seeded(params.collection_name, params.target, params.seed)?;
 
@@ -63,7 +95,7 @@ impl ExportStrategy for CsvStdoutExportStrategy {
             CsvOutput::Collection(csv) => println!("{}", csv),
         }
 
-        Ok(output)
+        Ok(ExportOutput::Sampled(output))
     }
 }
 
@@ -71,6 +103,7 @@ impl ExportStrategy for CsvStdoutExportStrategy {
 pub struct CsvFileImportStrategy {
     pub from_dir: PathBuf,
     pub expect_header_row: bool,
+    pub categorical_threshold: usize,
 }
 
 impl ImportStrategy for CsvFileImportStrategy {
@@ -86,7 +119,11 @@ impl ImportStrategy for CsvFileImportStrategy {
                     .has_headers(self.expect_header_row)
                     .from_path(entry.path())?;
 
-                let collection = import_csv_collection(reader, self.expect_header_row)?;
+                let collection = import_csv_collection(
+                    reader,
+                    self.expect_header_row,
+                    self.categorical_threshold,
+                )?;
 
                 let mut name_string = entry.file_name().into_string().map_err(|_| {
                     anyhow!("Failed to interpret collection name when importing a CSV namespace")
@@ -106,6 +143,7 @@ impl ImportStrategy for CsvFileImportStrategy {
 #[derive(Clone, Debug)]
 pub struct CsvStdinImportStrategy {
     pub expect_header_row: bool,
+    pub categorical_threshold: usize,
 }
 
 impl ImportStrategy for CsvStdinImportStrategy {
@@ -116,17 +154,20 @@ impl ImportStrategy for CsvStdinImportStrategy {
             .from_reader(stdin.lock());
 
         let name = "collection".to_string();
-        import_csv_collection(reader, self.expect_header_row).map(|collection| {
-            let mut namespace = Namespace::new();
-            namespace.put_collection(name, collection).unwrap();
-            namespace
-        })
+        import_csv_collection(reader, self.expect_header_row, self.categorical_threshold).map(
+            |collection| {
+                let mut namespace = Namespace::new();
+                namespace.put_collection(name, collection).unwrap();
+                namespace
+            },
+        )
     }
 }
 
 pub fn import_csv_collection(
     mut reader: csv::Reader<impl std::io::Read>,
     expect_header_row: bool,
+    categorical_threshold: usize,
 ) -> Result<Content> {
     let headers = if expect_header_row {
         Some(headers::CsvHeaders::from_csv_header_record(
@@ -152,8 +193,17 @@ pub fn import_csv_collection(
 
     let mut values = vec![head];
     values.extend(tail.into_iter());
+    let values = serde_json::Value::Array(values);
+
+    OptionalMergeStrategy::default().try_merge(&mut content, &values)?;
+
+    let mut cardinality = Cardinality::new(categorical_threshold);
+    cardinality.observe(&values);
+    cardinality.apply(&mut content);
 
-    OptionalMergeStrategy.try_merge(&mut content, &serde_json::Value::Array(values))?;
+    let mut fit = DistributionFit::new();
+    fit.observe(&values);
+    fit.apply(&mut content);
 
     Ok(content)
 }
@@ -285,34 +335,60 @@ fn csv_output_from_sampler_ouput(
 fn to_csv_string(collection_name: String, value: Value, namespace: &Namespace) -> Result<String> {
     let mut writer = csv::Writer::from_writer(vec![]);
 
-    let collection = namespace.get_collection(&collection_name)?;
+    write_csv_header(&mut writer, &collection_name, namespace)?;
+    write_csv_records(&mut writer, &collection_name, value, namespace)?;
+
+    Ok(String::from_utf8(writer.into_inner()?)?)
+}
+
+fn write_csv_header<W: std::io::Write>(
+    writer: &mut csv::Writer<W>,
+    collection_name: &str,
+    namespace: &Namespace,
+) -> Result<()> {
+    let collection = namespace.get_collection(collection_name)?;
+
+    let content = match collection {
+        Content::Array(array_content) => &array_content.content,
+        non_array => non_array,
+    };
+
+    writer.write_record(&headers::CsvHeaders::from_content(content, namespace)?.to_csv_record())?;
+
+    Ok(())
+}
+
+fn write_csv_records<W: std::io::Write>(
+    writer: &mut csv::Writer<W>,
+    collection_name: &str,
+    value: Value,
+    namespace: &Namespace,
+) -> Result<()> {
+    let collection = namespace.get_collection(collection_name)?;
 
     match (collection, value) {
         (Content::Array(array_content), Value::Array(elements)) => {
             let inner_content: &Content = &array_content.content;
 
-            writer.write_record(
-                &headers::CsvHeaders::from_content(inner_content, namespace)?.to_csv_record(),
-            )?;
-
             for val in elements {
-                let record = synth_val_to_csv_record(val, inner_content, namespace);
+                let record = synth_val_to_csv_record(val, inner_content, namespace)?;
                 writer.write_record(record)?;
             }
         }
         (_, value) => {
-            writer.write_record(
-                &headers::CsvHeaders::from_content(collection, namespace)?.to_csv_record(),
-            )?;
-            writer.write_record(synth_val_to_csv_record(value, collection, namespace))?;
+            writer.write_record(synth_val_to_csv_record(value, collection, namespace)?)?;
         }
     }
 
-    Ok(String::from_utf8(writer.into_inner()?)?)
+    Ok(())
 }
 
-fn synth_val_to_csv_record(val: Value, content: &Content, namespace: &Namespace) -> Vec<String> {
-    match val {
+fn synth_val_to_csv_record(
+    val: Value,
+    content: &Content,
+    namespace: &Namespace,
+) -> Result<Vec<String>> {
+    let record = match val {
         Value::Null(_) => vec![String::new()],
         Value::Bool(b) => vec![b.to_string()],
         Value::Number(n) => {
@@ -333,7 +409,7 @@ fn synth_val_to_csv_record(val: Value, content: &Content, namespace: &Namespace)
                         let inner_content = obj_content.fields.get(&field).unwrap();
 
                         flatterned.extend(
-                            synth_val_to_csv_record(obj_val, inner_content, namespace).into_iter(),
+                            synth_val_to_csv_record(obj_val, inner_content, namespace)?.into_iter(),
                         );
                     }
 
@@ -347,9 +423,9 @@ fn synth_val_to_csv_record(val: Value, content: &Content, namespace: &Namespace)
 
             match content {
                 Content::Array(array_content) => {
-                    let expected_scalar_count = count_scalars_in_content(content, namespace);
+                    let expected_scalar_count = count_scalars_in_content(content, namespace)?;
                     let scalar_count = elements.len()
-                        * count_scalars_in_content(&*array_content.content, namespace);
+                        * count_scalars_in_content(&*array_content.content, namespace)?;
 
                     let null_padding_iter = std::iter::repeat(Value::Null(()))
                         .take(expected_scalar_count - scalar_count);
@@ -359,7 +435,7 @@ fn synth_val_to_csv_record(val: Value, content: &Content, namespace: &Namespace)
                     });
 
                     for itm in iter {
-                        flatterned.extend(itm.into_iter());
+                        flatterned.extend(itm?.into_iter());
                     }
 
                     flatterned
@@ -367,7 +443,9 @@ fn synth_val_to_csv_record(val: Value, content: &Content, namespace: &Namespace)
                 _ => panic!("Schema and generated data don't align"),
             }
         }
-    }
+    };
+
+    Ok(record)
 }
 
 fn determine_content_array_max_length(array_content: &ArrayContent) -> usize {
@@ -389,32 +467,34 @@ fn determine_content_array_max_length(array_content: &ArrayContent) -> usize {
     }
 }
 
-fn count_scalars_in_content(content: &Content, ns: &Namespace) -> usize {
+fn count_scalars_in_content(content: &Content, ns: &Namespace) -> Result<usize> {
     match content {
-        Content::Array(array_content) => {
-            determine_content_array_max_length(array_content)
-                * count_scalars_in_content(&*array_content.content, ns)
-        }
+        Content::Array(array_content) => Ok(determine_content_array_max_length(array_content)
+            * count_scalars_in_content(&*array_content.content, ns)?),
         Content::Object(obj_content) => obj_content
             .iter()
             .map(|(_, x)| count_scalars_in_content(x, ns))
             .sum(),
-        Content::SameAs(same_as) => {
-            count_scalars_in_content(ns.get_s_node(&same_as.ref_).unwrap(), ns)
-        }
+        Content::SameAs(same_as) => count_scalars_in_content(ns.get_s_node(&same_as.ref_)?, ns),
         Content::OneOf(one_of) => one_of
             .variants
             .iter()
             .map(|x| count_scalars_in_content(&x.content, ns))
             .sum(),
         Content::Unique(unique) => count_scalars_in_content(&unique.content, ns),
-        _ => 1,
+        Content::Lookup(lookup) => lookup
+            .projected()
+            .map(|(_, ref_)| count_scalars_in_content(ns.get_s_node(&ref_)?, ns))
+            .sum(),
+        _ => Ok(1),
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::sampler::Target;
+
     #[test]
     fn test_csv_record_to_value() {
         assert_eq!(
@@ -521,7 +601,7 @@ mod tests {
 
         let generator = Sampler::try_from(&ns).unwrap();
         let output = generator
-            .sample_seeded(Some(collection_name), 1, 0)
+            .sample_seeded(Some(collection_name), Target::Total(1), 0)
             .unwrap();
 
         assert_eq!(
@@ -536,6 +616,23 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_count_scalars_of_dangling_lookup() {
+        let content: Content = serde_json::from_value(serde_json::json!({
+            "type": "object",
+            "user": {
+                "type": "lookup",
+                "ref": "users.content",
+                "fields": {
+                    "user_id": "id"
+                }
+            }
+        }))
+        .unwrap();
+
+        assert!(count_scalars_in_content(&content, &Namespace::new()).is_err());
+    }
+
     #[test]
     fn test_csv_str_to_value() {
         assert_eq!(
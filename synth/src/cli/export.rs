@@ -3,33 +3,49 @@ use crate::cli::json::{JsonFileExportStrategy, JsonStdoutExportStrategy};
 use crate::cli::jsonl::{JsonLinesFileExportStrategy, JsonLinesStdoutExportStrategy};
 use crate::cli::mongo::MongoExportStrategy;
 use crate::cli::mysql::MySqlExportStrategy;
+use crate::cli::parquet::ParquetFileExportStrategy;
 use crate::cli::postgres::PostgresExportStrategy;
+use crate::cli::sql::{SqlFileExportStrategy, SqlStdoutExportStrategy};
+use crate::cli::sqlite::SqliteExportStrategy;
 
 use anyhow::{Context, Result};
 
 use std::cell::RefCell;
+use std::collections::BTreeMap;
 use std::convert::TryFrom;
 use std::io::Write;
 use std::path::PathBuf;
 
 use crate::datasource::DataSource;
-use crate::sampler::{Sampler, SamplerOutput};
+use crate::sampler::{Sampler, SamplerOutput, Target, DEFAULT_CHUNK_SIZE};
 use async_std::task;
 use synth_core::{DataSourceParams, Namespace, Value};
 
 use super::map_from_uri_query;
 
 pub(crate) trait ExportStrategy {
-    fn export(&self, params: ExportParams) -> Result<SamplerOutput>;
+    fn export(&self, params: ExportParams) -> Result<ExportOutput>;
+}
+
+// `bytes` is only read by the "telemetry" feature
+#[cfg_attr(not(feature = "telemetry"), allow(dead_code))]
+pub(crate) enum ExportOutput {
+    /// The whole sample was held in memory before being exported.
+    Sampled(SamplerOutput),
+    /// The sample was exported chunk by chunk and never held in memory as a whole. `bytes` is
+    /// the number of bytes written out, if the export strategy writes to a file or stream.
+    Streamed { bytes: Option<usize> },
 }
 
 pub struct ExportParams {
     pub namespace: Namespace,
     /// The name of the single collection to generate from if one is specified (via --collection).
     pub collection_name: Option<String>,
-    pub target: usize,
+    pub target: Target,
     pub seed: u64,
     pub ns_path: PathBuf,
+    /// Whether to empty the collections of a database before exporting into them (via --truncate).
+    pub truncate: bool,
 }
 
 pub(crate) struct ExportStrategyBuilder<'a, W> {
@@ -70,16 +86,39 @@ where
         let query = map_from_uri_query(params.uri.query());
 
         let export_strategy: Box<dyn ExportStrategy> = match scheme.as_str() {
-            "postgres" | "postgresql" => Box::new(PostgresExportStrategy {
-                uri_string: params.uri.to_string(),
-                schema: params.schema,
-            }),
+            "postgres" | "postgresql" => {
+                let load = query.get("load").unwrap_or(&"insert").parse()?;
+
+                // 'load' is not a connection parameter, so it is left out of the URI handed to
+                // the driver
+                let mut uri = params.uri.clone();
+                let connect_query = query
+                    .iter()
+                    .filter(|(key, _)| **key != "load")
+                    .map(|(key, value)| format!("{}={}", key, value))
+                    .collect::<Vec<_>>()
+                    .join("&");
+                if connect_query.is_empty() {
+                    uri.set_query::<&str, _>(None)?;
+                } else {
+                    uri.set_query(Some(connect_query.as_str()))?;
+                }
+
+                Box::new(PostgresExportStrategy {
+                    uri_string: uri.to_string(),
+                    schema: params.schema,
+                    load,
+                })
+            }
             "mongodb" => Box::new(MongoExportStrategy {
                 uri_string: params.uri.to_string(),
             }),
             "mysql" | "mariadb" => Box::new(MySqlExportStrategy {
                 uri_string: params.uri.to_string(),
             }),
+            "sqlite" => Box::new(SqliteExportStrategy {
+                uri_string: params.uri.to_string(),
+            }),
             "json" => {
                 if params.uri.path() == "" {
                     Box::new(JsonStdoutExportStrategy {
@@ -118,9 +157,37 @@ where
                     })
                 }
             }
+            "parquet" => {
+                if params.uri.path() == "" {
+                    return Err(anyhow!(
+                        "Parquet export requires a directory to write to, e.g. 'parquet:output_dir'."
+                    ));
+                }
+                Box::new(ParquetFileExportStrategy {
+                    to_dir: PathBuf::from(params.uri.path().to_string()),
+                })
+            }
+            "sql" => {
+                let dialect = query.get("dialect").unwrap_or(&"postgres").parse()?;
+                let mode = query.get("mode").unwrap_or(&"insert").parse()?;
+
+                if params.uri.path() == "" {
+                    Box::new(SqlStdoutExportStrategy {
+                        dialect,
+                        mode,
+                        writer: RefCell::new(writer),
+                    })
+                } else {
+                    Box::new(SqlFileExportStrategy {
+                        to_file: PathBuf::from(params.uri.path().to_string()),
+                        dialect,
+                        mode,
+                    })
+                }
+            }
             _ => {
                 return Err(anyhow!(
-                    "Export URI scheme not recognised. Was expecting one of 'mongodb', 'postgres', 'mysql', 'mariadb', 'json', 'jsonl' or 'csv'."
+                    "Export URI scheme not recognised. Was expecting one of 'mongodb', 'postgres', 'mysql', 'mariadb', 'sqlite', 'json', 'jsonl', 'csv', 'parquet' or 'sql'."
                 ));
             }
         };
@@ -128,37 +195,102 @@ where
     }
 }
 
+/// Inserts a sample into a database in a single transaction, so that the database is left as it
+/// was if any of it fails to be inserted.
 pub(crate) fn create_and_insert_values<T: DataSource>(
     params: ExportParams,
     datasource: &T,
-) -> Result<SamplerOutput> {
-    let sampler = Sampler::try_from(&params.namespace)?;
-    let sample =
-        sampler.sample_seeded(params.collection_name.clone(), params.target, params.seed)?;
+) -> Result<ExportOutput> {
+    task::block_on(datasource.begin()).context("Failed to start a transaction")?;
 
-    match sample.clone() {
-        SamplerOutput::Collection(name, value) => {
-            insert_data(datasource, name.as_ref(), value)?;
-        }
-        SamplerOutput::Namespace(namespace) => {
-            for (name, value) in namespace.into_iter() {
-                insert_data(datasource, name.as_ref(), value)?;
+    let inserted = match insert_values(params, datasource) {
+        Ok(inserted) => inserted,
+        Err(err) => {
+            if let Err(rollback_err) = task::block_on(datasource.rollback()) {
+                warn!("Failed to roll back the transaction: {}", rollback_err);
             }
+            return Err(err);
         }
     };
 
-    Ok(sample)
+    task::block_on(datasource.commit()).context("Failed to commit the transaction")?;
+
+    for (name, count) in inserted {
+        if count == 0 {
+            println!(
+                "Collection {} generated 0 values. Skipping insertion...",
+                name
+            );
+        }
+    }
+
+    Ok(ExportOutput::Streamed { bytes: None })
+}
+
+fn insert_values<T: DataSource>(
+    params: ExportParams,
+    datasource: &T,
+) -> Result<BTreeMap<String, usize>> {
+    if params.truncate {
+        let collection_names = match &params.collection_name {
+            Some(name) => vec![name.clone()],
+            None => params.namespace.keys().map(str::to_string).collect(),
+        };
+        task::block_on(datasource.truncate(&collection_names))
+            .context("Failed to truncate the collections")?;
+    }
+
+    let sampler = Sampler::try_from(&params.namespace)?;
+    let chunks = sampler.sample_seeded_chunked(
+        params.collection_name,
+        params.target,
+        params.seed,
+        DEFAULT_CHUNK_SIZE,
+    );
+
+    // Each chunk lists its collections in dependency order, so inserting them in that order
+    // keeps foreign keys satisfied from one chunk to the next.
+    let mut inserted = BTreeMap::<String, usize>::new();
+
+    for chunk in chunks {
+        match chunk? {
+            SamplerOutput::Collection(name, value) => {
+                insert_data(datasource, &mut inserted, name, value)?;
+            }
+            SamplerOutput::Namespace(namespace) => {
+                for (name, value) in namespace {
+                    insert_data(datasource, &mut inserted, name, value)?;
+                }
+            }
+        }
+    }
+
+    Ok(inserted)
 }
 
 fn insert_data<T: DataSource>(
     datasource: &T,
-    collection_name: &str,
+    inserted: &mut BTreeMap<String, usize>,
+    collection_name: String,
     collection: Value,
 ) -> Result<()> {
     let to_insert = match collection {
         Value::Array(elems) => elems,
         non_array => vec![non_array],
     };
-    task::block_on(datasource.insert_data(collection_name, &to_insert[..]))
+
+    let count = inserted.entry(collection_name.clone()).or_insert(0);
+
+    if to_insert.is_empty() {
+        return Ok(());
+    }
+
+    if *count == 0 {
+        task::block_on(datasource.check_data(&collection_name, &to_insert[0]))
+            .with_context(|| format!("Failed to check data for collection {}", collection_name))?;
+    }
+    *count += to_insert.len();
+
+    task::block_on(datasource.insert_data(&collection_name, &to_insert[..]))
         .with_context(|| format!("Failed to insert data for collection {}", collection_name))
 }
@@ -1,9 +1,10 @@
+use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::path::PathBuf;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 
-use synth_core::schema::Namespace;
+use synth_core::schema::{Namespace, DEFAULT_CATEGORICAL_THRESHOLD};
 use synth_core::{Content, DataSourceParams};
 
 use crate::cli::csv::{CsvFileImportStrategy, CsvStdinImportStrategy};
@@ -11,7 +12,11 @@ use crate::cli::json::{JsonFileImportStrategy, JsonStdinImportStrategy};
 use crate::cli::jsonl::{JsonLinesFileImportStrategy, JsonLinesStdinImportStrategy};
 use crate::cli::mongo::MongoImportStrategy;
 use crate::cli::mysql::MySqlImportStrategy;
+use crate::cli::parquet::ParquetFileImportStrategy;
 use crate::cli::postgres::PostgresImportStrategy;
+use crate::cli::sql::{SqlFileImportStrategy, SqlStdinImportStrategy};
+use crate::cli::sqlite::SqliteImportStrategy;
+use crate::datasource::relational_datasource::Sampling;
 
 use super::map_from_uri_query;
 
@@ -28,10 +33,10 @@ pub trait ImportStrategy {
     }
 }
 
-impl TryFrom<DataSourceParams<'_>> for Box<dyn ImportStrategy> {
+impl TryFrom<(DataSourceParams<'_>, Sampling)> for Box<dyn ImportStrategy> {
     type Error = anyhow::Error;
 
-    fn try_from(params: DataSourceParams) -> Result<Self, Self::Error> {
+    fn try_from((params, sampling): (DataSourceParams, Sampling)) -> Result<Self, Self::Error> {
         let scheme = params.uri.scheme().as_str().to_lowercase();
         let query = map_from_uri_query(params.uri.query());
 
@@ -39,19 +44,30 @@ impl TryFrom<DataSourceParams<'_>> for Box<dyn ImportStrategy> {
             "postgres" | "postgresql" => Box::new(PostgresImportStrategy {
                 uri_string: params.uri.to_string(),
                 schema: params.schema,
+                sampling,
             }),
             "mongodb" => Box::new(MongoImportStrategy {
                 uri_string: params.uri.to_string(),
             }),
             "mysql" | "mariadb" => Box::new(MySqlImportStrategy {
                 uri_string: params.uri.to_string(),
+                sampling,
+            }),
+            "sqlite" => Box::new(SqliteImportStrategy {
+                uri_string: params.uri.to_string(),
+                sampling,
             }),
             "json" => {
+                let categorical_threshold = categorical_threshold(&query)?;
+
                 if params.uri.path() == "" {
-                    Box::new(JsonStdinImportStrategy)
+                    Box::new(JsonStdinImportStrategy {
+                        categorical_threshold,
+                    })
                 } else {
                     Box::new(JsonFileImportStrategy {
                         from_file: PathBuf::from(params.uri.path().to_string()),
+                        categorical_threshold,
                     })
                 }
             }
@@ -60,15 +76,18 @@ impl TryFrom<DataSourceParams<'_>> for Box<dyn ImportStrategy> {
                     .get("collection_field_name")
                     .unwrap_or(&"type")
                     .to_string();
+                let categorical_threshold = categorical_threshold(&query)?;
 
                 if params.uri.path() == "" {
                     Box::new(JsonLinesStdinImportStrategy {
                         collection_field_name,
+                        categorical_threshold,
                     })
                 } else {
                     Box::new(JsonLinesFileImportStrategy {
                         from_file: PathBuf::from(params.uri.path().to_string()),
                         collection_field_name,
+                        categorical_threshold,
                     })
                 }
             }
@@ -79,19 +98,46 @@ impl TryFrom<DataSourceParams<'_>> for Box<dyn ImportStrategy> {
                     .get("header_row")
                     .map(|x| *x != "false")
                     .unwrap_or(true);
+                let categorical_threshold = categorical_threshold(&query)?;
 
                 if params.uri.path() == "" {
-                    Box::new(CsvStdinImportStrategy { expect_header_row })
+                    Box::new(CsvStdinImportStrategy {
+                        expect_header_row,
+                        categorical_threshold,
+                    })
                 } else {
                     Box::new(CsvFileImportStrategy {
                         from_dir: PathBuf::from(params.uri.path().to_string()),
                         expect_header_row,
+                        categorical_threshold,
+                    })
+                }
+            }
+            "parquet" => {
+                if params.uri.path() == "" {
+                    return Err(anyhow!(
+                        "Parquet import requires a directory to read from, e.g. 'parquet:input_dir'."
+                    ));
+                }
+                Box::new(ParquetFileImportStrategy {
+                    from_dir: PathBuf::from(params.uri.path().to_string()),
+                })
+            }
+            "sql" => {
+                let dialect = query.get("dialect").unwrap_or(&"postgres").parse()?;
+
+                if params.uri.path() == "" {
+                    Box::new(SqlStdinImportStrategy { dialect })
+                } else {
+                    Box::new(SqlFileImportStrategy {
+                        from_file: PathBuf::from(params.uri.path().to_string()),
+                        dialect,
                     })
                 }
             }
             _ => {
                 return Err(anyhow!(
-                    "Import URI scheme not recognised. Was expecting one of 'mongodb', 'postgres', 'mysql', 'mariadb', 'json', 'jsonl', or 'csv'."
+                    "Import URI scheme not recognised. Was expecting one of 'mongodb', 'postgres', 'mysql', 'mariadb', 'sqlite', 'json', 'jsonl', 'csv', 'parquet', or 'sql'."
                 ));
             }
         };
@@ -99,11 +145,23 @@ impl TryFrom<DataSourceParams<'_>> for Box<dyn ImportStrategy> {
     }
 }
 
+/// The `categorical_threshold` parameter of file URIs: the largest number of distinct values of
+/// a field for it to be imported as a categorical.
+fn categorical_threshold(query: &HashMap<&str, &str>) -> Result<usize> {
+    match query.get("categorical_threshold") {
+        Some(threshold) => threshold
+            .parse()
+            .with_context(|| anyhow!("Invalid categorical threshold '{}'", threshold)),
+        None => Ok(DEFAULT_CATEGORICAL_THRESHOLD),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::cli::csv::import_csv_collection;
     use crate::cli::json::import_json;
     use crate::cli::jsonl::import_json_lines;
+    use synth_core::schema::DEFAULT_CATEGORICAL_THRESHOLD;
 
     #[test]
     fn test_json_and_json_lines_import_equivalence() {
@@ -126,15 +184,19 @@ mod tests {
         });
 
         assert_eq!(
-            import_json_lines(json_lines, "type").unwrap(),
-            import_json(json).unwrap()
+            import_json_lines(json_lines, "type", DEFAULT_CATEGORICAL_THRESHOLD).unwrap(),
+            import_json(json, DEFAULT_CATEGORICAL_THRESHOLD).unwrap()
         );
     }
 
     fn json_csv_equiv_assert(csv: &str, json: serde_json::Value) {
-        let from_csv =
-            import_csv_collection(csv::Reader::from_reader(csv.as_bytes()), true).unwrap();
-        let from_json = import_json(json)
+        let from_csv = import_csv_collection(
+            csv::Reader::from_reader(csv.as_bytes()),
+            true,
+            DEFAULT_CATEGORICAL_THRESHOLD,
+        )
+        .unwrap();
+        let from_json = import_json(json, DEFAULT_CATEGORICAL_THRESHOLD)
             .unwrap()
             .get_collection("collection")
             .unwrap()
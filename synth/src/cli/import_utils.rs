@@ -1,18 +1,21 @@
 use crate::datasource::relational_datasource::{
-    get_columns_info, ColumnInfo, ForeignKey, PrimaryKey, SqlxDataSource, ValueWrapper,
+    get_columns_info, ArgumentsOf, ColumnInfo, ForeignKey, PrimaryKey, SampleStrategy, Sampling,
+    SqlxDataSource, UniqueKey, ValueWrapper,
 };
 use crate::datasource::DataSource;
 use anyhow::{Context, Result};
 use async_std::task;
 use log::debug;
 use serde_json::Value;
-use sqlx::{Executor, Row};
+use sqlx::{Executor, IntoArguments, Row};
+use std::collections::BTreeMap;
 use std::convert::TryFrom;
 use synth_core::graph::json::synth_val_to_json;
-use synth_core::schema::content::number_content::U64;
+use synth_core::schema::content::number_content::{self, U64};
 use synth_core::schema::{
-    ArrayContent, FieldRef, NumberContent, ObjectContent, OptionalMergeStrategy, RangeStep,
-    SameAsContent, UniqueContent,
+    ArrayContent, Categorical, DistributionFit, FieldRef, LookupContent, NumberContent,
+    ObjectContent, OptionalMergeStrategy, RangeStep, SameAsContent, SameAsSample, StringContent,
+    UniqueContent,
 };
 use synth_core::{Content, Namespace};
 
@@ -21,26 +24,35 @@ pub(crate) struct Collection {
     pub(crate) collection: Content,
 }
 
-/// Wrapper around `FieldContent` since we cant' impl `TryFrom` on a struct in a non-owned crate
-struct FieldContentWrapper(Content);
-
 pub(crate) fn build_namespace_import<T: DataSource + SqlxDataSource>(
     datasource: &T,
+    sampling: &Sampling,
 ) -> Result<Namespace>
 where
     T: Sync,
     for<'c> &'c mut T::Connection: Executor<'c, Database = T::DB>,
+    for<'q> ArgumentsOf<'q, T::DB>: IntoArguments<'q, T::DB>,
     String: sqlx::Type<T::DB>,
     for<'d> String: sqlx::Decode<'d, T::DB> + sqlx::Encode<'d, T::DB>,
     usize: sqlx::ColumnIndex<<T::DB as sqlx::Database>::Row>,
     PrimaryKey: TryFrom<<T::DB as sqlx::Database>::Row, Error = anyhow::Error>,
     ForeignKey: TryFrom<<T::DB as sqlx::Database>::Row, Error = anyhow::Error>,
+    UniqueKey: TryFrom<<T::DB as sqlx::Database>::Row, Error = anyhow::Error>,
     ValueWrapper: TryFrom<<T::DB as sqlx::Database>::Row, Error = anyhow::Error>,
     ColumnInfo: TryFrom<<T::DB as sqlx::Database>::Row, Error = anyhow::Error>,
 {
     let table_names = task::block_on(get_table_names(datasource))
         .with_context(|| "Failed to get table names".to_string())?;
 
+    for table_name in sampling.tables.keys() {
+        if !table_names.contains(table_name) {
+            return Err(anyhow!(
+                "Cannot set the rows sampled from the table '{}': no such table in the database",
+                table_name
+            ));
+        }
+    }
+
     let mut namespace = Namespace::default();
 
     info!("Building namespace collections...");
@@ -52,8 +64,11 @@ where
     info!("Building namespace foreign keys...");
     populate_namespace_foreign_keys(&mut namespace, datasource)?;
 
+    info!("Building namespace unique keys...");
+    populate_namespace_unique_keys(&mut namespace, &table_names, datasource)?;
+
     info!("Building namespace values...");
-    populate_namespace_values(&mut namespace, &table_names, datasource)?;
+    populate_namespace_values(&mut namespace, &table_names, datasource, sampling)?;
 
     Ok(namespace)
 }
@@ -61,6 +76,7 @@ where
 async fn get_table_names<T: SqlxDataSource>(datasource: &T) -> Result<Vec<String>>
 where
     for<'c> &'c mut T::Connection: Executor<'c, Database = T::DB>,
+    for<'q> ArgumentsOf<'q, T::DB>: IntoArguments<'q, T::DB>,
     String: sqlx::Type<T::DB>,
     for<'d> String: sqlx::Decode<'d, T::DB>,
     usize: sqlx::ColumnIndex<<T::DB as sqlx::Database>::Row>,
@@ -85,6 +101,7 @@ fn populate_namespace_collections<T: SqlxDataSource>(
 ) -> Result<()>
 where
     for<'c> &'c mut T::Connection: Executor<'c, Database = T::DB>,
+    for<'q> ArgumentsOf<'q, T::DB>: IntoArguments<'q, T::DB>,
     String: sqlx::Type<T::DB>,
     for<'d> String: sqlx::Encode<'d, T::DB>,
     ColumnInfo: TryFrom<<T::DB as sqlx::Database>::Row, Error = anyhow::Error>,
@@ -110,6 +127,7 @@ fn populate_namespace_primary_keys<T: SqlxDataSource>(
 ) -> Result<()>
 where
     for<'c> &'c mut T::Connection: Executor<'c, Database = T::DB>,
+    for<'q> ArgumentsOf<'q, T::DB>: IntoArguments<'q, T::DB>,
     String: sqlx::Type<T::DB>,
     for<'d> String: sqlx::Encode<'d, T::DB>,
     PrimaryKey: TryFrom<<T::DB as sqlx::Database>::Row, Error = anyhow::Error>,
@@ -117,45 +135,58 @@ where
     for table_name in table_names.iter() {
         let primary_keys = task::block_on(get_primary_keys(datasource, table_name.to_string()))?;
 
-        if primary_keys.len() > 1 {
-            bail!(
-                "{} primary keys found at collection {}. Synth does not currently support \
-            composite primary keys.",
-                primary_keys.len(),
-                table_name
-            )
-        }
-
-        if let Some(primary_key) = primary_keys.get(0) {
-            let field = FieldRef::new(&format!(
-                "{}.content.{}",
-                table_name, primary_key.column_name
-            ))?;
-            let node = namespace.get_s_node_mut(&field)?;
-            // if the primary key is a number, use an id generator.
-            let pk_node = match node {
-                Content::Number(n) => n.clone().try_transmute_to_id().ok().map(Content::Number),
-                _ => None,
-            };
-
-            *node = pk_node.unwrap_or_else(|| {
-                Content::Unique(UniqueContent {
-                    algorithm: Default::default(),
-                    content: Box::new(node.clone()),
-                })
-            });
+        let columns = primary_keys
+            .into_iter()
+            .map(|primary_key| primary_key.column_name)
+            .collect::<Vec<_>>();
+        if !columns.is_empty() {
+            set_primary_key(namespace, table_name, &columns)?;
         }
     }
 
     Ok(())
 }
 
+/// Turns the content of a primary key column into an id generator if it is a number, or makes it
+/// unique otherwise. The columns of a composite primary key are made unique together instead.
+pub(crate) fn set_primary_key(
+    namespace: &mut Namespace,
+    table_name: &str,
+    columns: &[String],
+) -> Result<()> {
+    let column_name = match columns {
+        [column_name] => column_name,
+        _ => return add_unique_together(namespace, table_name, columns.to_vec()),
+    };
+
+    let field = FieldRef::new(&format!("{}.content.{}", table_name, column_name))?;
+    let node = match namespace.get_s_node_mut(&field)? {
+        Content::Hidden(hidden) => hidden.content.as_mut(),
+        node => node,
+    };
+    // if the primary key is a number, use an id generator.
+    let pk_node = match node {
+        Content::Number(n) => n.clone().try_transmute_to_id().ok().map(Content::Number),
+        _ => None,
+    };
+
+    *node = pk_node.unwrap_or_else(|| {
+        Content::Unique(UniqueContent {
+            algorithm: Default::default(),
+            content: Box::new(node.clone()),
+        })
+    });
+
+    Ok(())
+}
+
 async fn get_primary_keys<T: SqlxDataSource>(
     datasource: &T,
     table_name: String,
 ) -> Result<Vec<PrimaryKey>>
 where
     for<'c> &'c mut T::Connection: Executor<'c, Database = T::DB>,
+    for<'q> ArgumentsOf<'q, T::DB>: IntoArguments<'q, T::DB>,
     String: sqlx::Type<T::DB>,
     for<'d> String: sqlx::Encode<'d, T::DB>,
     PrimaryKey: TryFrom<<T::DB as sqlx::Database>::Row, Error = anyhow::Error>,
@@ -179,25 +210,256 @@ fn populate_namespace_foreign_keys<T: SqlxDataSource>(
 ) -> Result<()>
 where
     for<'c> &'c mut T::Connection: Executor<'c, Database = T::DB>,
+    for<'q> ArgumentsOf<'q, T::DB>: IntoArguments<'q, T::DB>,
     ForeignKey: TryFrom<<T::DB as sqlx::Database>::Row, Error = anyhow::Error>,
 {
-    let foreign_keys = task::block_on(get_foreign_keys(datasource))?;
+    let columns = task::block_on(get_foreign_keys(datasource))?;
+
+    // The columns of each foreign key, which come ordered by foreign key
+    let mut foreign_keys: Vec<Vec<ForeignKey>> = Vec::new();
+    for column in columns {
+        match foreign_keys.last_mut() {
+            Some(fk)
+                if fk[0].from_table == column.from_table
+                    && fk[0].constraint_name == column.constraint_name =>
+            {
+                fk.push(column)
+            }
+            _ => foreign_keys.push(vec![column]),
+        }
+    }
 
     debug!("{} foreign keys found.", foreign_keys.len());
 
     for fk in foreign_keys {
-        let from_field = FieldRef::new(&format!("{}.content.{}", fk.from_table, fk.from_column))?;
-        let to_field = FieldRef::new(&format!("{}.content.{}", fk.to_table, fk.to_column))?;
-        let node = namespace.get_s_node_mut(&from_field)?;
-        *node = Content::SameAs(SameAsContent { ref_: to_field });
+        set_foreign_key(namespace, &fk)?;
+    }
+
+    Ok(())
+}
+
+/// Makes the column a foreign key is on the `same_as` the column it references.
+///
+/// The columns of a composite foreign key are replaced by a `lookup` named after the table they
+/// reference instead, so that all of them point to the same row. Columns which another composite
+/// foreign key already projects are left to it, so a foreign key overlapping one set before only
+/// picks its other columns.
+pub(crate) fn set_foreign_key(namespace: &mut Namespace, fk: &[ForeignKey]) -> Result<()> {
+    if fk.is_empty() {
+        return Err(anyhow!("a foreign key must be on at least one column"));
+    }
+
+    // Generated columns which are referenced are inserted, so that the references hold
+    for column in fk {
+        let field = FieldRef::new(&format!("{}.content.{}", column.to_table, column.to_column))?;
+        if let Ok(node) = namespace.get_s_node_mut(&field) {
+            if let Content::Hidden(hidden) = node {
+                let content = hidden.content.as_ref().clone();
+                *node = content;
+            }
+        }
+    }
+
+    let object = collection_object(namespace, &fk[0].from_table)?;
+
+    // The columns of a composite primary key pick rows at random, so that they make more distinct
+    // combinations than the rows of the tables they reference
+    let sample = object
+        .unique_together
+        .iter()
+        .any(|fields| fk.iter().any(|column| fields.contains(&column.from_column)))
+        .then_some(SameAsSample::Uniform);
+
+    let columns = fk
+        .iter()
+        .filter(|column| !object.is_projected(&column.from_column))
+        .collect::<Vec<_>>();
+
+    let first = match columns.as_slice() {
+        [] => return Ok(()),
+        [column] => {
+            let node = object.get_mut(&column.from_column)?;
+            *node = Content::SameAs(SameAsContent {
+                ref_: FieldRef::new(&format!("{}.content.{}", column.to_table, column.to_column))?,
+                sample,
+            });
+            return Ok(());
+        }
+        [first, ..] => first,
+    };
+
+    let mut fields = BTreeMap::new();
+    for column in columns.iter() {
+        object.get(&column.from_column)?;
+        object.fields.remove(&column.from_column);
+        fields.insert(column.from_column.clone(), column.to_column.clone());
+    }
+
+    let mut name = first.to_table.clone();
+    while object.fields.contains_key(&name) {
+        name.push('_');
+    }
+    object.fields.insert(
+        name,
+        Content::Lookup(LookupContent {
+            ref_: FieldRef::new(&format!("{}.content", first.to_table))?,
+            fields,
+            sample,
+        }),
+    );
+
+    Ok(())
+}
+
+fn populate_namespace_unique_keys<T: SqlxDataSource>(
+    namespace: &mut Namespace,
+    table_names: &[String],
+    datasource: &T,
+) -> Result<()>
+where
+    for<'c> &'c mut T::Connection: Executor<'c, Database = T::DB>,
+    for<'q> ArgumentsOf<'q, T::DB>: IntoArguments<'q, T::DB>,
+    String: sqlx::Type<T::DB>,
+    for<'d> String: sqlx::Encode<'d, T::DB>,
+    UniqueKey: TryFrom<<T::DB as sqlx::Database>::Row, Error = anyhow::Error>,
+{
+    for table_name in table_names.iter() {
+        let unique_keys = task::block_on(get_unique_keys(datasource, table_name.to_string()))?;
+
+        // The columns of each index, which come ordered by index
+        let mut indexes: Vec<(String, Vec<Option<String>>)> = Vec::new();
+        for unique_key in unique_keys {
+            match indexes.last_mut() {
+                Some((index_name, columns)) if *index_name == unique_key.index_name => {
+                    columns.push(unique_key.column_name)
+                }
+                _ => indexes.push((unique_key.index_name, vec![unique_key.column_name])),
+            }
+        }
+
+        debug!(
+            "{} unique keys found at collection {}.",
+            indexes.len(),
+            table_name
+        );
+
+        for (index_name, columns) in indexes {
+            // The values of an expression can be unique without those of its columns being so
+            match columns.into_iter().collect::<Option<Vec<_>>>() {
+                Some(columns) => set_unique_key(namespace, table_name, columns)?,
+                None => debug!("Skipping unique index {} on an expression.", index_name),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Makes the content of the column of a single-column unique key unique (only its non-null values
+/// if it is nullable), or adds the columns of a composite one to the `unique_together` of its
+/// table.
+pub(crate) fn set_unique_key(
+    namespace: &mut Namespace,
+    table_name: &str,
+    columns: Vec<String>,
+) -> Result<()> {
+    if let [column_name] = columns.as_slice() {
+        let object = collection_object(namespace, table_name)?;
+        if object.is_projected(column_name) {
+            // The column comes from a row of the table its composite foreign key references
+            return Ok(());
+        }
+        match object.get_mut(column_name)? {
+            // A unique key allows any number of nulls, so only the other values are made unique
+            Content::OneOf(one_of) if one_of.is_nullable() => one_of
+                .iter_mut()
+                .filter(|content| !content.is_null())
+                .for_each(make_unique),
+            node => make_unique(node),
+        }
+        return Ok(());
+    }
+
+    add_unique_together(namespace, table_name, columns)
+}
+
+fn make_unique(node: &mut Content) {
+    // Ids and references to unique columns are generated without repeats already, and the
+    // values of hidden columns are generated by the database
+    let is_unique = matches!(
+        node,
+        Content::Unique(_)
+            | Content::SameAs(_)
+            | Content::Hidden(_)
+            | Content::Number(NumberContent::U32(number_content::U32::Id(_)))
+            | Content::Number(NumberContent::U64(number_content::U64::Id(_)))
+            | Content::Number(NumberContent::I32(number_content::I32::Id(_)))
+            | Content::Number(NumberContent::I64(number_content::I64::Id(_)))
+    );
+    if !is_unique {
+        *node = Content::Unique(UniqueContent {
+            algorithm: Default::default(),
+            content: Box::new(node.clone()),
+        });
     }
+}
 
+fn add_unique_together(
+    namespace: &mut Namespace,
+    table_name: &str,
+    columns: Vec<String>,
+) -> Result<()> {
+    let object = collection_object(namespace, table_name)?;
+    if !object.unique_together.contains(&columns) {
+        object.unique_together.push(columns);
+    }
     Ok(())
 }
 
+/// The content of the rows of a collection imported from a table.
+fn collection_object<'a>(
+    namespace: &'a mut Namespace,
+    table_name: &str,
+) -> Result<&'a mut ObjectContent> {
+    let collection = FieldRef::new(&format!("{}.content", table_name))?;
+    match namespace.get_s_node_mut(&collection)? {
+        Content::Object(object) => Ok(object),
+        other => Err(anyhow!(
+            "the collection {} is a {} rather than an object",
+            table_name,
+            other
+        )),
+    }
+}
+
+async fn get_unique_keys<T: SqlxDataSource>(
+    datasource: &T,
+    table_name: String,
+) -> Result<Vec<UniqueKey>>
+where
+    for<'c> &'c mut T::Connection: Executor<'c, Database = T::DB>,
+    for<'q> ArgumentsOf<'q, T::DB>: IntoArguments<'q, T::DB>,
+    String: sqlx::Type<T::DB>,
+    for<'d> String: sqlx::Encode<'d, T::DB>,
+    UniqueKey: TryFrom<<T::DB as sqlx::Database>::Row, Error = anyhow::Error>,
+{
+    let query = datasource.get_unique_keys_query();
+    let pool = datasource.get_pool();
+
+    datasource
+        .query(query)
+        .bind(table_name)
+        .fetch_all(&pool)
+        .await?
+        .into_iter()
+        .map(UniqueKey::try_from)
+        .collect()
+}
+
 async fn get_foreign_keys<T: SqlxDataSource>(datasource: &T) -> Result<Vec<ForeignKey>>
 where
     for<'c> &'c mut T::Connection: Executor<'c, Database = T::DB>,
+    for<'q> ArgumentsOf<'q, T::DB>: IntoArguments<'q, T::DB>,
     ForeignKey: TryFrom<<T::DB as sqlx::Database>::Row, Error = anyhow::Error>,
 {
     let query = datasource.get_foreign_keys_query();
@@ -216,37 +478,96 @@ fn populate_namespace_values<T: SqlxDataSource>(
     namespace: &mut Namespace,
     table_names: &[String],
     datasource: &T,
+    sampling: &Sampling,
 ) -> Result<()>
 where
     T: Sync,
     for<'c> &'c mut T::Connection: Executor<'c, Database = T::DB>,
+    for<'q> ArgumentsOf<'q, T::DB>: IntoArguments<'q, T::DB>,
     String: sqlx::Type<T::DB>,
     for<'d> String: sqlx::Encode<'d, T::DB>,
     ValueWrapper: TryFrom<<T::DB as sqlx::Database>::Row, Error = anyhow::Error>,
 {
-    task::block_on(datasource.set_seed())?;
+    if sampling.strategy == SampleStrategy::Random {
+        task::block_on(datasource.set_seed())?;
+    }
 
     for table_name in table_names {
-        let values = task::block_on(get_deterministic_samples(
-            datasource,
-            table_name.to_string(),
-        ))?;
-        let json_values: Vec<Value> = values.into_iter().map(synth_val_to_json).collect();
-        namespace.try_update(OptionalMergeStrategy, table_name, &Value::from(json_values))?;
+        let rows = sampling.rows(table_name);
+        if rows == Some(0) {
+            continue;
+        }
+
+        let values = task::block_on(get_samples(datasource, table_name, sampling.strategy, rows))?;
+        let json_values = Value::from(
+            values
+                .into_iter()
+                .map(synth_val_to_json)
+                .collect::<Vec<Value>>(),
+        );
+        weigh_categoricals(namespace.get_collection_mut(table_name)?, &json_values);
+        // The content of the columns comes from their types, which the values don't widen
+        namespace.try_update(
+            OptionalMergeStrategy { declared: true },
+            table_name,
+            &json_values,
+        )?;
+
+        let mut fit = DistributionFit::new();
+        fit.observe(&json_values);
+        fit.apply(namespace.get_collection_mut(table_name)?);
     }
 
     Ok(())
 }
 
-async fn get_deterministic_samples<T: SqlxDataSource>(
+/// Enum labels and the values of `IN` lists are declared with one occurrence each. The columns
+/// with sampled values forget them, so that the values merged next weigh the labels by how often
+/// they occur in the table.
+fn weigh_categoricals(collection: &mut Content, rows: &Value) {
+    let fields = match collection {
+        Content::Array(array_content) => match array_content.content.as_mut() {
+            Content::Object(object_content) => &mut object_content.fields,
+            _ => return,
+        },
+        _ => return,
+    };
+    let rows = rows.as_array().map(Vec::as_slice).unwrap_or_default();
+
+    for (column, content) in fields.iter_mut() {
+        let sampled = rows
+            .iter()
+            .any(|row| row.get(column).map_or(false, Value::is_string));
+        if let (true, Some(categorical)) = (sampled, string_categorical(content)) {
+            categorical.clear_occurrences();
+        }
+    }
+}
+
+fn string_categorical(content: &mut Content) -> Option<&mut Categorical<String>> {
+    match content {
+        Content::String(StringContent::Categorical(categorical)) => Some(categorical),
+        Content::Unique(unique_content) => string_categorical(&mut unique_content.content),
+        Content::OneOf(one_of_content) => one_of_content
+            .variants
+            .iter_mut()
+            .find_map(|variant| string_categorical(&mut variant.content)),
+        _ => None,
+    }
+}
+
+async fn get_samples<T: SqlxDataSource>(
     datasource: &T,
-    table: String,
+    table_name: &str,
+    strategy: SampleStrategy,
+    rows: Option<usize>,
 ) -> Result<Vec<synth_core::Value>>
 where
     for<'c> &'c mut T::Connection: Executor<'c, Database = T::DB>,
+    for<'q> ArgumentsOf<'q, T::DB>: IntoArguments<'q, T::DB>,
     ValueWrapper: TryFrom<<T::DB as sqlx::Database>::Row, Error = anyhow::Error>,
 {
-    let query = datasource.get_deterministic_samples_query(table);
+    let query = datasource.get_samples_query(table_name, strategy, rows)?;
     let pool = datasource.get_pool();
 
     datasource
@@ -265,14 +586,25 @@ where
         .collect()
 }
 
-impl<T: SqlxDataSource> TryFrom<(&T, Vec<ColumnInfo>)> for Collection {
-    type Error = anyhow::Error;
-
-    fn try_from(columns_meta: (&T, Vec<ColumnInfo>)) -> Result<Self> {
+impl Collection {
+    /// Builds the collection of a table from the information about its columns, where `decode`
+    /// gives the content of the non-null values of a column.
+    pub(crate) fn from_columns<F>(column_infos: Vec<ColumnInfo>, decode: F) -> Result<Self>
+    where
+        F: Fn(&ColumnInfo) -> Result<Content>,
+    {
         let mut collection = ObjectContent::default();
 
-        for column_info in columns_meta.1 {
-            let content = FieldContentWrapper::try_from((columns_meta.0, &column_info))?.0;
+        for column_info in column_infos {
+            let mut content = decode(&column_info)?;
+
+            if column_info.is_nullable {
+                content = content.into_nullable();
+            }
+
+            if column_info.is_generated {
+                content = content.into_hidden();
+            }
 
             collection
                 .fields
@@ -285,21 +617,20 @@ impl<T: SqlxDataSource> TryFrom<(&T, Vec<ColumnInfo>)> for Collection {
                     RangeStep::new(1, 2, 1),
                 )))),
                 content: Box::new(Content::Object(collection)),
+                size: None,
+                parent: None,
             }),
         })
     }
 }
 
-impl<T: SqlxDataSource> TryFrom<(&T, &ColumnInfo)> for FieldContentWrapper {
+impl<T: SqlxDataSource> TryFrom<(&T, Vec<ColumnInfo>)> for Collection {
     type Error = anyhow::Error;
 
-    fn try_from(column_meta: (&T, &ColumnInfo)) -> Result<Self> {
-        let mut content = column_meta.0.decode_to_content(column_meta.1)?;
-
-        if column_meta.1.is_nullable {
-            content = content.into_nullable();
-        }
-
-        Ok(FieldContentWrapper(content))
+    fn try_from(columns_meta: (&T, Vec<ColumnInfo>)) -> Result<Self> {
+        let datasource = columns_meta.0;
+        Collection::from_columns(columns_meta.1, |column_info| {
+            datasource.decode_to_content(column_info)
+        })
     }
 }
@@ -1,8 +1,8 @@
-use crate::cli::export::{ExportParams, ExportStrategy};
+use crate::cli::export::{ExportOutput, ExportParams, ExportStrategy};
 use crate::cli::import::ImportStrategy;
-use crate::sampler::{Sampler, SamplerOutput};
+use crate::sampler::Sampler;
 
-use synth_core::schema::{MergeStrategy, OptionalMergeStrategy};
+use synth_core::schema::{Cardinality, DistributionFit, MergeStrategy, OptionalMergeStrategy};
 use synth_core::{Content, Namespace};
 
 use anyhow::{Context, Result};
@@ -18,13 +18,13 @@ pub struct JsonFileExportStrategy {
 }
 
 impl ExportStrategy for JsonFileExportStrategy {
-    fn export(&self, params: ExportParams) -> Result<SamplerOutput> {
+    fn export(&self, params: ExportParams) -> Result<ExportOutput> {
         let generator = Sampler::try_from(&params.namespace)?;
         let output = generator.sample_seeded(params.collection_name, params.target, params.seed)?;
 
         std::fs::write(&self.from_file, output.clone().into_json().to_string())?;
 
-        Ok(output)
+        Ok(ExportOutput::Sampled(output))
     }
 }
 
@@ -34,45 +34,52 @@ pub struct JsonStdoutExportStrategy<W> {
 }
 
 impl<W: Write> ExportStrategy for JsonStdoutExportStrategy<W> {
-    fn export(&self, params: ExportParams) -> Result<SamplerOutput> {
+    fn export(&self, params: ExportParams) -> Result<ExportOutput> {
         let generator = Sampler::try_from(&params.namespace)?;
         let output = generator.sample_seeded(params.collection_name, params.target, params.seed)?;
 
         writeln!(self.writer.borrow_mut(), "{}", output.clone().into_json())
             .expect("failed to write json output");
 
-        Ok(output)
+        Ok(ExportOutput::Sampled(output))
     }
 }
 
 #[derive(Clone, Debug)]
 pub struct JsonFileImportStrategy {
     pub from_file: PathBuf,
+    pub categorical_threshold: usize,
 }
 
 impl ImportStrategy for JsonFileImportStrategy {
     fn import(&self) -> Result<Namespace> {
-        import_json(serde_json::from_reader(std::fs::File::open(
-            &self.from_file,
-        )?)?)
+        import_json(
+            serde_json::from_reader(std::fs::File::open(&self.from_file)?)?,
+            self.categorical_threshold,
+        )
     }
 }
 
 #[derive(Clone, Debug)]
-pub struct JsonStdinImportStrategy;
+pub struct JsonStdinImportStrategy {
+    pub categorical_threshold: usize,
+}
 
 impl ImportStrategy for JsonStdinImportStrategy {
     fn import(&self) -> Result<Namespace> {
-        import_json(serde_json::from_reader(std::io::stdin())?)
+        import_json(
+            serde_json::from_reader(std::io::stdin())?,
+            self.categorical_threshold,
+        )
     }
 }
 
-pub fn import_json(val: serde_json::Value) -> Result<Namespace> {
+pub fn import_json(val: serde_json::Value, categorical_threshold: usize) -> Result<Namespace> {
     match val {
         serde_json::Value::Object(object) => object
             .into_iter()
             .map(|(name, value)| {
-                collection_from_value(&value)
+                collection_from_value(&value, categorical_threshold)
                     .map(|content| (name.clone(), content))
                     .with_context(|| anyhow!("While importing the collection `{}`", name))
             })
@@ -84,12 +91,24 @@ pub fn import_json(val: serde_json::Value) -> Result<Namespace> {
     }
 }
 
-fn collection_from_value(value: &serde_json::Value) -> Result<Content> {
+fn collection_from_value(
+    value: &serde_json::Value,
+    categorical_threshold: usize,
+) -> Result<Content> {
     match value {
         serde_json::Value::Array(values) => {
             let fst = values.first().unwrap_or(&serde_json::Value::Null);
             let mut as_content = Content::from_value_wrapped_in_array(fst);
-            OptionalMergeStrategy.try_merge(&mut as_content, value)?;
+            OptionalMergeStrategy::default().try_merge(&mut as_content, value)?;
+
+            let mut cardinality = Cardinality::new(categorical_threshold);
+            cardinality.observe(value);
+            cardinality.apply(&mut as_content);
+
+            let mut fit = DistributionFit::new();
+            fit.observe(value);
+            fit.apply(&mut as_content);
+
             Ok(as_content)
         }
         unacceptable => Err(anyhow!(
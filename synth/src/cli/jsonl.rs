@@ -1,9 +1,9 @@
-use crate::cli::export::{ExportParams, ExportStrategy};
+use crate::cli::export::{ExportOutput, ExportParams, ExportStrategy};
 use crate::cli::import::ImportStrategy;
-use crate::sampler::{Sampler, SamplerOutput};
+use crate::sampler::{Sampler, SamplerOutput, DEFAULT_CHUNK_SIZE};
 
 use synth_core::graph::{json::synth_val_to_json, Value};
-use synth_core::schema::{MergeStrategy, OptionalMergeStrategy};
+use synth_core::schema::{Cardinality, DistributionFit, MergeStrategy, OptionalMergeStrategy};
 use synth_core::{Content, Namespace};
 
 use anyhow::{Context, Result};
@@ -21,17 +21,29 @@ pub struct JsonLinesFileExportStrategy {
 }
 
 impl ExportStrategy for JsonLinesFileExportStrategy {
-    fn export(&self, params: ExportParams) -> Result<SamplerOutput> {
+    fn export(&self, params: ExportParams) -> Result<ExportOutput> {
         let generator = Sampler::try_from(&params.namespace)?;
-        let output = generator.sample_seeded(params.collection_name, params.target, params.seed)?;
+        let chunks = generator.sample_seeded_chunked(
+            params.collection_name,
+            params.target,
+            params.seed,
+            DEFAULT_CHUNK_SIZE,
+        );
 
         let mut f = std::io::BufWriter::new(std::fs::File::create(&self.from_file)?);
+        let mut bytes = 0;
 
-        for val in json_lines_from_sampler_output(output.clone(), &self.collection_field_name) {
-            f.write_all((val.to_string() + "\n").as_bytes())?;
+        for chunk in chunks {
+            for val in json_lines_from_sampler_output(chunk?, &self.collection_field_name) {
+                let line = val.to_string() + "\n";
+                bytes += line.len();
+                f.write_all(line.as_bytes())?;
+            }
         }
 
-        Ok(output)
+        f.flush()?;
+
+        Ok(ExportOutput::Streamed { bytes: Some(bytes) })
     }
 }
 
@@ -42,23 +54,35 @@ pub struct JsonLinesStdoutExportStrategy<W> {
 }
 
 impl<W: Write> ExportStrategy for JsonLinesStdoutExportStrategy<W> {
-    fn export(&self, params: ExportParams) -> Result<SamplerOutput> {
+    fn export(&self, params: ExportParams) -> Result<ExportOutput> {
         let generator = Sampler::try_from(&params.namespace)?;
-        let output = generator.sample_seeded(params.collection_name, params.target, params.seed)?;
+        let chunks = generator.sample_seeded_chunked(
+            params.collection_name,
+            params.target,
+            params.seed,
+            DEFAULT_CHUNK_SIZE,
+        );
+
+        let mut bytes = 0;
 
         // TODO: Warn user if the collection field name would overwrite an existing field in a collection.
 
-        for line in json_lines_from_sampler_output(output.clone(), &self.collection_field_name) {
-            writeln!(self.writer.borrow_mut(), "{}", line).expect("failed to write jsonl line");
+        for chunk in chunks {
+            for line in json_lines_from_sampler_output(chunk?, &self.collection_field_name) {
+                let line = line.to_string();
+                bytes += line.len() + 1;
+                writeln!(self.writer.borrow_mut(), "{}", line).expect("failed to write jsonl line");
+            }
         }
 
-        Ok(output)
+        Ok(ExportOutput::Streamed { bytes: Some(bytes) })
     }
 }
 
 pub struct JsonLinesFileImportStrategy {
     pub from_file: PathBuf,
     pub collection_field_name: String,
+    pub categorical_threshold: usize,
 }
 
 impl ImportStrategy for JsonLinesFileImportStrategy {
@@ -69,12 +93,14 @@ impl ImportStrategy for JsonLinesFileImportStrategy {
                 .map(|line| serde_json::from_str(&line.unwrap()))
                 .collect::<serde_json::Result<Vec<serde_json::Value>>>()?,
             &self.collection_field_name,
+            self.categorical_threshold,
         )
     }
 }
 
 pub struct JsonLinesStdinImportStrategy {
     pub collection_field_name: String,
+    pub categorical_threshold: usize,
 }
 
 impl ImportStrategy for JsonLinesStdinImportStrategy {
@@ -86,6 +112,7 @@ impl ImportStrategy for JsonLinesStdinImportStrategy {
                 .map(|line| serde_json::from_str(&line.unwrap()))
                 .collect::<serde_json::Result<Vec<serde_json::Value>>>()?,
             &self.collection_field_name,
+            self.categorical_threshold,
         )
     }
 }
@@ -93,6 +120,7 @@ impl ImportStrategy for JsonLinesStdinImportStrategy {
 pub fn import_json_lines(
     json_lines: Vec<serde_json::Value>,
     collection_field_name: &str,
+    categorical_threshold: usize,
 ) -> Result<Namespace> {
     let mut collection_names_to_values: HashMap<Option<String>, Vec<serde_json::Value>> =
         HashMap::new();
@@ -127,7 +155,7 @@ pub fn import_json_lines(
         .map(|(name, values)| {
             let name_or_default = name.unwrap_or_else(|| "collection".to_string());
 
-            collection_from_values_jsonl(values)
+            collection_from_values_jsonl(values, categorical_threshold)
                 .and_then(|content| Ok((name_or_default.parse()?, content)))
                 .with_context(|| anyhow!("While importing the collection '{}'", name_or_default))
         })
@@ -193,9 +221,22 @@ fn synth_val_to_jsonl(val: Value) -> Vec<serde_json::Value> {
 
 /// Create a collection (`Content`) from a set of Serde JSON values that were all generated originally from the same
 /// collection.
-fn collection_from_values_jsonl(values: Vec<serde_json::Value>) -> Result<Content> {
+fn collection_from_values_jsonl(
+    values: Vec<serde_json::Value>,
+    categorical_threshold: usize,
+) -> Result<Content> {
     let fst = values.first().unwrap_or(&serde_json::Value::Null);
     let mut as_content = Content::from_value_wrapped_in_array(fst);
-    OptionalMergeStrategy.try_merge(&mut as_content, &serde_json::Value::Array(values))?;
+    let values = serde_json::Value::Array(values);
+    OptionalMergeStrategy::default().try_merge(&mut as_content, &values)?;
+
+    let mut cardinality = Cardinality::new(categorical_threshold);
+    cardinality.observe(&values);
+    cardinality.apply(&mut as_content);
+
+    let mut fit = DistributionFit::new();
+    fit.observe(&values);
+    fit.apply(&mut as_content);
+
     Ok(as_content)
 }
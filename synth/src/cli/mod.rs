@@ -6,24 +6,33 @@ mod json;
 mod jsonl;
 mod mongo;
 mod mysql;
+mod parquet;
 mod postgres;
+mod sql;
+mod sqlite;
 mod store;
+mod validate;
 
 use crate::cli::export::ExportParams;
 use crate::cli::import::ImportStrategy;
 use crate::cli::store::Store;
+use crate::cli::validate::validate_namespace;
+use crate::datasource::relational_datasource::{SampleStrategy, Sampling};
+use crate::sampler::Target;
 use crate::version::print_version_message;
 
 use anyhow::{Context, Result};
 use rand::RngCore;
 use serde::Serialize;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::convert::{TryFrom, TryInto};
 use std::io::Write;
 use std::iter::FromIterator;
 use std::path::PathBuf;
+use std::str::FromStr;
 use structopt::clap::AppSettings;
 use structopt::StructOpt;
+use synth_core::schema::{ArrayContent, Content, Namespace};
 use synth_core::DataSourceParams;
 use uriparse::URI;
 
@@ -84,6 +93,7 @@ impl<'w> Cli {
             Args::Init { .. } => Ok(()),
             Args::Generate(cmd) => self.generate(cmd, writer),
             Args::Import(cmd) => self.import(cmd),
+            Args::Validate(cmd) => self.validate(cmd, writer),
             #[cfg(feature = "telemetry")]
             Args::Telemetry(cmd) => self.telemetry(cmd, writer),
             Args::Version => {
@@ -121,12 +131,21 @@ impl<'w> Cli {
         // TODO: If ns exists and no collection: break
         // If collection and ns exists and collection exists: break
 
-        let import_strategy: Box<dyn ImportStrategy> = DataSourceParams {
+        let params = DataSourceParams {
             uri: URI::try_from(cmd.from.as_str())
                 .with_context(|| format!("Parsing import URI '{}'", cmd.from))?,
             schema: cmd.schema,
-        }
-        .try_into()?;
+        };
+        let sampling = Sampling {
+            strategy: cmd.sample_strategy,
+            rows: if cmd.no_values {
+                Some(0)
+            } else {
+                cmd.sample_rows.rows
+            },
+            tables: cmd.sample_rows.tables,
+        };
+        let import_strategy: Box<dyn ImportStrategy> = (params, sampling).try_into()?;
 
         if let Some(collection) = cmd.collection {
             if self.store.collection_exists(&cmd.namespace, &collection) {
@@ -197,12 +216,15 @@ impl<'w> Cli {
 
         let seed = Self::derive_seed(cmd.random, cmd.seed)?;
 
+        let target = cmd.size.target(&namespace)?;
+
         let params = ExportParams {
             namespace,
             collection_name: cmd.collection,
-            target: cmd.size,
+            target,
             seed,
             ns_path: cmd.namespace.clone(),
+            truncate: cmd.truncate,
         };
 
         export_strategy
@@ -211,6 +233,35 @@ impl<'w> Cli {
 
         Ok(())
     }
+
+    fn validate<W: Write>(&self, cmd: ValidateCommand, mut writer: W) -> Result<()> {
+        let errors = validate_namespace(&self.store, &cmd.namespace).context(format!(
+            "Unable to open the namespace \"{}\"",
+            cmd.namespace.display()
+        ))?;
+
+        match cmd.format {
+            ValidateFormat::Text => {
+                for error in &errors {
+                    writeln!(writer, "{}", error)?;
+                }
+            }
+            ValidateFormat::Json => {
+                serde_json::to_writer_pretty(&mut writer, &errors)?;
+                writeln!(writer)?;
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow!(
+                "Found {} error(s) in the namespace \"{}\"",
+                errors.len(),
+                cmd.namespace.display()
+            ))
+        }
+    }
 }
 
 // The serialization of this enum is used for telemetry when synth panics and we want our logs to
@@ -232,6 +283,8 @@ pub enum Args {
     Generate(GenerateCommand),
     #[structopt(about = "Import data from an external source")]
     Import(ImportCommand),
+    #[structopt(about = "Check a namespace for errors without generating data")]
+    Validate(ValidateCommand),
     #[cfg(feature = "telemetry")]
     #[structopt(about = "Toggle anonymous usage data collection")]
     Telemetry(TelemetryCommand),
@@ -256,8 +309,12 @@ pub struct GenerateCommand {
     #[structopt(long, help = "The specific collection from which to generate")]
     #[serde(skip)]
     pub collection: Option<String>,
-    #[structopt(long, help = "the number of samples", default_value = "1")]
-    pub size: usize,
+    #[structopt(
+        long,
+        help = "The number of samples: either a number of values to generate across all collections, or sizes per collection overriding the `size` of their collection file (e.g. 'users=100,orders=5000'). When generating collections by size, a single number alongside them (e.g. '10,users=100') is the size of the collections without one.",
+        default_value = "1"
+    )]
+    pub size: Size,
     #[structopt(
         long,
         help = "The URI into which data will be generated. Can be a file-based URI scheme to output data to the filesystem or stdout ('json:', 'jsonl:' and 'csv:' allow outputting JSON, JSON Lines and CSV data respectively) or can be a database URI to write data directly to some database (supports Postgres, MongoDB, and MySQL). Defaults to writing JSON data to stdout. [example: jsonl:/tmp/generation_output]",
@@ -281,6 +338,115 @@ pub struct GenerateCommand {
     )]
     #[serde(skip)]
     pub schema: Option<String>,
+    #[structopt(
+        long,
+        help = "(Databases only) Empty the tables of the generated collections before inserting into them. Postgres also empties the tables referring to them (TRUNCATE ... CASCADE)."
+    )]
+    pub truncate: bool,
+}
+
+/// The value of `--size`.
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct Size {
+    number: Option<usize>,
+    #[serde(skip)]
+    collections: BTreeMap<String, usize>,
+}
+
+impl Size {
+    /// Generates each collection until it reaches its size if any collection has one, either
+    /// from `--size` or from the `size` of its collection file, or else until the total number of
+    /// values reaches the number given to `--size`.
+    fn target(&self, namespace: &Namespace) -> Result<Target> {
+        let mut sizes = BTreeMap::new();
+
+        for (name, content) in namespace.iter() {
+            if let Content::Array(ArrayContent {
+                size: Some(size), ..
+            }) = content
+            {
+                if *size == 0 {
+                    return Err(anyhow!(
+                        "The size of the collection '{}' must be a positive number",
+                        name
+                    ));
+                }
+                sizes.insert(name.to_string(), *size);
+            }
+        }
+
+        for (name, size) in &self.collections {
+            if !namespace.collection_exists(name) {
+                return Err(anyhow!(
+                    "Cannot set the size of the collection '{}': no such collection in the namespace",
+                    name
+                ));
+            }
+            sizes.insert(name.clone(), *size);
+        }
+
+        let number = self.number.unwrap_or(1);
+        if sizes.is_empty() {
+            Ok(Target::Total(number))
+        } else {
+            Ok(Target::Collections {
+                sizes,
+                default: number,
+            })
+        }
+    }
+}
+
+impl From<usize> for Size {
+    fn from(number: usize) -> Self {
+        Self {
+            number: Some(number),
+            collections: BTreeMap::new(),
+        }
+    }
+}
+
+impl FromStr for Size {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut size = Self::default();
+
+        for part in s.split(',').map(str::trim) {
+            match part.split_once('=') {
+                Some((name, number)) => {
+                    let number = number.trim().parse().with_context(|| {
+                        anyhow!("Invalid size '{}' for the collection '{}'", number, name)
+                    })?;
+                    if number == 0 {
+                        return Err(anyhow!(
+                            "The size of the collection '{}' must be a positive number",
+                            name
+                        ));
+                    }
+                    if size
+                        .collections
+                        .insert(name.trim().to_string(), number)
+                        .is_some()
+                    {
+                        return Err(anyhow!(
+                            "The size of the collection '{}' is given twice",
+                            name
+                        ));
+                    }
+                }
+                None if size.number.is_none() => {
+                    size.number = Some(
+                        part.parse()
+                            .with_context(|| anyhow!("Invalid size '{}'", part))?,
+                    );
+                }
+                None => return Err(anyhow!("Only one size can be given without a collection")),
+            }
+        }
+
+        Ok(size)
+    }
 }
 
 #[derive(StructOpt, Serialize)]
@@ -310,6 +476,116 @@ pub struct ImportCommand {
     )]
     #[serde(skip)]
     pub schema: Option<String>,
+    #[structopt(
+        long,
+        help = "(Databases only) The number of rows sampled from each table to infer values from, or 'all'. Tables can also be given their own number of rows (e.g. '10000,countries=all').",
+        default_value = "10"
+    )]
+    #[serde(skip)]
+    pub sample_rows: SampleRows,
+    #[structopt(
+        long,
+        help = "(Databases only) How rows are sampled from tables: 'random' (the default), 'head' for the first rows returned, or 'tablesample' (Postgres only) for rows from random pages.",
+        default_value = "random"
+    )]
+    #[serde(skip)]
+    pub sample_strategy: SampleStrategy,
+    #[structopt(
+        long,
+        help = "(Databases only) Only import the structure of tables, without sampling values from the tables not given a number of rows in --sample-rows."
+    )]
+    #[serde(skip)]
+    pub no_values: bool,
+}
+
+/// The value of `--sample-rows`, where `None` stands for all the rows of a table.
+#[derive(Debug, PartialEq)]
+pub struct SampleRows {
+    rows: Option<usize>,
+    tables: BTreeMap<String, Option<usize>>,
+}
+
+impl FromStr for SampleRows {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let parse_rows = |rows: &str| -> Result<Option<usize>> {
+            match rows.trim() {
+                "all" => Ok(None),
+                rows => rows
+                    .parse()
+                    .map(Some)
+                    .with_context(|| anyhow!("Invalid number of rows '{}'", rows)),
+            }
+        };
+
+        let mut rows = None;
+        let mut tables = BTreeMap::new();
+
+        for part in s.split(',').map(str::trim) {
+            match part.split_once('=') {
+                Some((name, number)) => {
+                    if tables
+                        .insert(name.trim().to_string(), parse_rows(number)?)
+                        .is_some()
+                    {
+                        return Err(anyhow!(
+                            "The number of rows of the table '{}' is given twice",
+                            name
+                        ));
+                    }
+                }
+                None if rows.is_none() => rows = Some(parse_rows(part)?),
+                None => {
+                    return Err(anyhow!(
+                        "Only one number of rows can be given without a table"
+                    ))
+                }
+            }
+        }
+
+        Ok(Self {
+            rows: rows.unwrap_or(Some(10)),
+            tables,
+        })
+    }
+}
+
+#[derive(StructOpt, Serialize)]
+pub struct ValidateCommand {
+    #[structopt(
+        help = "The namespace directory from which to read schema files",
+        parse(from_os_str)
+    )]
+    #[serde(skip)]
+    pub namespace: PathBuf,
+    #[structopt(
+        long,
+        help = "The format in which to report errors: 'text' for one error per line or 'json' for a JSON array of errors",
+        default_value = "text"
+    )]
+    pub format: ValidateFormat,
+}
+
+#[derive(Serialize)]
+pub enum ValidateFormat {
+    Text,
+    Json,
+}
+
+impl FromStr for ValidateFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            _ => Err(anyhow!(
+                "Format '{}' not recognised. Was expecting one of 'text' or 'json'.",
+                s
+            )),
+        }
+    }
 }
 
 #[cfg(feature = "telemetry")]
@@ -326,6 +602,7 @@ pub enum TelemetryCommand {
 #[cfg(test)]
 pub mod tests {
     use super::*;
+    use crate::sampler::{Sampler, SamplerOutput};
 
     #[test]
     fn test_derive_seed() {
@@ -334,4 +611,191 @@ pub mod tests {
         assert!(Cli::derive_seed(true, Some(5)).is_err());
         assert!(Cli::derive_seed(true, None).is_ok());
     }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!("10".parse::<Size>().unwrap(), Size::from(10));
+        assert_eq!(
+            "users=100, orders=5000".parse::<Size>().unwrap(),
+            Size {
+                number: None,
+                collections: BTreeMap::from([
+                    ("users".to_string(), 100),
+                    ("orders".to_string(), 5000)
+                ]),
+            }
+        );
+        assert_eq!(
+            "10,users=100".parse::<Size>().unwrap(),
+            Size {
+                number: Some(10),
+                collections: BTreeMap::from([("users".to_string(), 100)]),
+            }
+        );
+        assert!("10,20".parse::<Size>().is_err());
+        assert!("users=0".parse::<Size>().is_err());
+        assert!("users=1,users=2".parse::<Size>().is_err());
+        assert!("users=many".parse::<Size>().is_err());
+    }
+
+    #[test]
+    fn test_parse_sample_rows() {
+        assert_eq!(
+            "10000".parse::<SampleRows>().unwrap(),
+            SampleRows {
+                rows: Some(10000),
+                tables: BTreeMap::new(),
+            }
+        );
+        assert_eq!(
+            "all, countries=100".parse::<SampleRows>().unwrap(),
+            SampleRows {
+                rows: None,
+                tables: BTreeMap::from([("countries".to_string(), Some(100))]),
+            }
+        );
+        assert_eq!(
+            "countries=all".parse::<SampleRows>().unwrap(),
+            SampleRows {
+                rows: Some(10),
+                tables: BTreeMap::from([("countries".to_string(), None)]),
+            }
+        );
+        assert!("10,20".parse::<SampleRows>().is_err());
+        assert!("countries=1,countries=2".parse::<SampleRows>().is_err());
+        assert!("countries=many".parse::<SampleRows>().is_err());
+    }
+
+    #[test]
+    fn test_size_target() {
+        let namespace: Namespace = serde_json::from_value(serde_json::json!({
+            "users": {
+                "type": "array",
+                "length": 1,
+                "size": 100,
+                "content": { "type": "null" }
+            },
+            "orders": {
+                "type": "array",
+                "length": 1,
+                "content": { "type": "null" }
+            }
+        }))
+        .unwrap();
+
+        assert_eq!(
+            "orders=5000"
+                .parse::<Size>()
+                .unwrap()
+                .target(&namespace)
+                .unwrap(),
+            Target::Collections {
+                sizes: BTreeMap::from([("users".to_string(), 100), ("orders".to_string(), 5000)]),
+                default: 1,
+            }
+        );
+        assert_eq!(
+            "users=10"
+                .parse::<Size>()
+                .unwrap()
+                .target(&namespace)
+                .unwrap(),
+            Target::Collections {
+                sizes: BTreeMap::from([("users".to_string(), 10)]),
+                default: 1,
+            }
+        );
+        assert!("items=10"
+            .parse::<Size>()
+            .unwrap()
+            .target(&namespace)
+            .is_err());
+
+        let namespace: Namespace = serde_json::from_value(serde_json::json!({
+            "orders": {
+                "type": "array",
+                "length": 1,
+                "content": { "type": "null" }
+            }
+        }))
+        .unwrap();
+        assert_eq!(
+            Size::from(20).target(&namespace).unwrap(),
+            Target::Total(20)
+        );
+    }
+
+    #[test]
+    fn test_size_generated() {
+        let namespace: Namespace = serde_json::from_value(serde_json::json!({
+            "users": {
+                "type": "array",
+                "length": 3,
+                "size": 6,
+                "content": {
+                    "type": "object",
+                    "id": { "type": "number", "id": {} }
+                }
+            },
+            "posts": {
+                "type": "array",
+                "length": 5,
+                "content": {
+                    "type": "object",
+                    "user_id": { "type": "same_as", "ref": "users.content.id" }
+                }
+            },
+            "tags": {
+                "type": "array",
+                "length": 4,
+                "content": { "type": "null" }
+            }
+        }))
+        .unwrap();
+        let target = "10,posts=52"
+            .parse::<Size>()
+            .unwrap()
+            .target(&namespace)
+            .unwrap();
+
+        // Small chunks, so that collections reach their size in different chunks
+        let chunks = Sampler::try_from(&namespace)
+            .unwrap()
+            .sample_seeded_chunked(None, target, 0, 20)
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert!(chunks.len() > 1);
+
+        let mut generated = BTreeMap::<String, usize>::new();
+        for chunk in chunks {
+            match chunk {
+                SamplerOutput::Namespace(key_values) => {
+                    let mut names: Vec<_> =
+                        key_values.iter().map(|(name, _)| name.as_str()).collect();
+                    names.sort_unstable();
+                    assert_eq!(names, ["posts", "tags", "users"]);
+                    for (name, value) in key_values {
+                        match value {
+                            synth_core::Value::Array(values) => {
+                                *generated.entry(name).or_default() += values.len()
+                            }
+                            otherwise => panic!("expected an array, found {:?}", otherwise),
+                        }
+                    }
+                }
+                SamplerOutput::Collection(_, _) => panic!("expected a namespace"),
+            }
+        }
+
+        // The size of `users` is a multiple of its length, so it is reached exactly even though
+        // it is only cut between two generations
+        assert_eq!(
+            generated,
+            BTreeMap::from([
+                ("users".to_string(), 6),
+                ("posts".to_string(), 52),
+                ("tags".to_string(), 10),
+            ])
+        );
+    }
 }
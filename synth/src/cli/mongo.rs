@@ -1,4 +1,4 @@
-use crate::cli::export::{ExportParams, ExportStrategy};
+use crate::cli::export::{ExportOutput, ExportParams, ExportStrategy};
 use crate::cli::import::ImportStrategy;
 use crate::sampler::{Sampler, SamplerOutput};
 use anyhow::Result;
@@ -13,8 +13,8 @@ use synth_core::graph::prelude::number_content::I64;
 use synth_core::graph::prelude::{ChronoValue, Number, NumberContent, ObjectContent, RangeStep};
 use synth_core::schema::number_content::F64;
 use synth_core::schema::{
-    ArrayContent, BoolContent, Categorical, ChronoValueType, DateTimeContent, RegexContent,
-    StringContent,
+    infer_string, ArrayContent, BoolContent, Categorical, ChronoValueType, DateTimeContent,
+    RegexContent, StringContent,
 };
 use synth_core::{Content, Namespace, Value};
 
@@ -107,7 +107,7 @@ fn bson_to_content(bson: &Bson) -> Content {
             *d + 1.,
             0.1,
         )))),
-        Bson::String(_) => Content::String(StringContent::default()),
+        Bson::String(string) => infer_string(string),
         Bson::Array(array) => {
             let length = Content::Number(NumberContent::U64(U64::Constant(array.len() as u64)));
             let content_iter = array.iter().map(bson_to_content);
@@ -115,6 +115,8 @@ fn bson_to_content(bson: &Bson) -> Content {
             Content::Array(ArrayContent {
                 length: Box::new(length),
                 content: Box::new(Content::OneOf(content_iter.collect())),
+                size: None,
+                parent: None,
             })
         }
         Bson::Document(doc) => doc_to_content(doc),
@@ -152,7 +154,7 @@ fn bson_to_content(bson: &Bson) -> Content {
 }
 
 impl ExportStrategy for MongoExportStrategy {
-    fn export(&self, params: ExportParams) -> Result<SamplerOutput> {
+    fn export(&self, params: ExportParams) -> Result<ExportOutput> {
         let mut client = Client::with_uri_str(&self.uri_string)?;
         let sampler = Sampler::try_from(&params.namespace)?;
         let sample =
@@ -170,7 +172,7 @@ impl ExportStrategy for MongoExportStrategy {
             }
         }?;
 
-        Ok(sample)
+        Ok(ExportOutput::Sampled(sample))
     }
 }
 
@@ -1,9 +1,9 @@
-use crate::cli::export::{create_and_insert_values, ExportParams, ExportStrategy};
+use crate::cli::export::{create_and_insert_values, ExportOutput, ExportParams, ExportStrategy};
 use crate::cli::import::ImportStrategy;
 use crate::cli::import_utils::build_namespace_import;
 use crate::datasource::mysql_datasource::MySqlDataSource;
+use crate::datasource::relational_datasource::Sampling;
 use crate::datasource::DataSource;
-use crate::sampler::SamplerOutput;
 use anyhow::Result;
 use synth_core::schema::Namespace;
 
@@ -13,7 +13,7 @@ pub struct MySqlExportStrategy {
 }
 
 impl ExportStrategy for MySqlExportStrategy {
-    fn export(&self, params: ExportParams) -> Result<SamplerOutput> {
+    fn export(&self, params: ExportParams) -> Result<ExportOutput> {
         let datasource = MySqlDataSource::new(&self.uri_string)?;
 
         create_and_insert_values(params, &datasource)
@@ -23,12 +23,13 @@ impl ExportStrategy for MySqlExportStrategy {
 #[derive(Clone, Debug)]
 pub struct MySqlImportStrategy {
     pub uri_string: String,
+    pub sampling: Sampling,
 }
 
 impl ImportStrategy for MySqlImportStrategy {
     fn import(&self) -> Result<Namespace> {
         let datasource = MySqlDataSource::new(&self.uri_string)?;
 
-        build_namespace_import(&datasource)
+        build_namespace_import::<MySqlDataSource>(&datasource, &self.sampling)
     }
 }
@@ -0,0 +1,296 @@
+mod reader;
+mod schema;
+mod writer;
+
+use crate::cli::export::{ExportOutput, ExportParams, ExportStrategy};
+use crate::cli::import::ImportStrategy;
+use crate::sampler::{Sampler, SamplerOutput, DEFAULT_CHUNK_SIZE};
+
+use synth_core::{Namespace, Value};
+
+use anyhow::Result;
+
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::path::PathBuf;
+
+use reader::import_parquet_collection;
+use schema::{Collection, Column};
+use writer::ParquetWriter;
+
+#[derive(Clone, Debug)]
+pub struct ParquetFileExportStrategy {
+    pub to_dir: PathBuf,
+}
+
+impl ExportStrategy for ParquetFileExportStrategy {
+    fn export(&self, params: ExportParams) -> Result<ExportOutput> {
+        if self.to_dir.exists() {
+            return Err(anyhow::anyhow!("Output directory already exists"));
+        } else {
+            std::fs::create_dir_all(&self.to_dir)?;
+        }
+
+        // The types of the columns which depend on the values generated (e.g. those of `expr`
+        // fields) are settled from every row, so the data is generated a first time to observe
+        // them. It is generated again from the same seed when writing it.
+        let mut observed = HashMap::<String, Column>::new();
+        let is_dynamic = params
+            .namespace
+            .iter()
+            .filter(|(name, _)| {
+                params
+                    .collection_name
+                    .as_ref()
+                    .map_or(true, |collection_name| name == collection_name)
+            })
+            .map(|(_, content)| Collection::from_content(content, &params.namespace))
+            .collect::<Result<Vec<_>>>()?
+            .iter()
+            .any(Collection::is_dynamic);
+        if is_dynamic {
+            for chunk in sample_chunks(&params)? {
+                for (file_name, _, rows) in chunk? {
+                    let column = Collection::observe(&rows);
+                    let column = match observed.remove(&file_name) {
+                        Some(previous) => previous.unify(column),
+                        None => column,
+                    };
+                    observed.insert(file_name, column);
+                }
+            }
+        }
+
+        // One writer per collection, created with the first chunk the collection appears in.
+        let mut writers = HashMap::<String, ParquetWriter>::new();
+
+        for chunk in sample_chunks(&params)? {
+            for (file_name, collection_name, rows) in chunk? {
+                let writer = match writers.entry(file_name) {
+                    Entry::Occupied(entry) => entry.into_mut(),
+                    Entry::Vacant(entry) => {
+                        let content = params.namespace.get_collection(&collection_name)?;
+                        let mut collection = Collection::from_content(content, &params.namespace)?;
+                        match observed.get(entry.key()) {
+                            Some(column) => collection.resolve(column),
+                            None => collection.resolve(&Collection::observe(&rows)),
+                        }
+
+                        let path = self.to_dir.join(entry.key().clone() + ".parquet");
+                        entry.insert(ParquetWriter::create(&path, collection)?)
+                    }
+                };
+
+                writer.write(&rows)?;
+            }
+        }
+
+        let mut bytes = 0;
+        for (file_name, writer) in writers {
+            writer.close()?;
+            bytes += std::fs::metadata(self.to_dir.join(file_name + ".parquet"))?.len() as usize;
+        }
+
+        Ok(ExportOutput::Streamed { bytes: Some(bytes) })
+    }
+}
+
+/// The rows of each collection in the chunks generated for `params`, along with the name of the
+/// file they are written to and the name of their collection.
+fn sample_chunks(
+    params: &ExportParams,
+) -> Result<impl Iterator<Item = Result<Vec<(String, String, Vec<Value>)>>>> {
+    let generator = Sampler::try_from(&params.namespace)?;
+    let chunks = generator.sample_seeded_chunked(
+        params.collection_name.clone(),
+        params.target.clone(),
+        params.seed,
+        DEFAULT_CHUNK_SIZE,
+    );
+
+    Ok(chunks.map(|chunk| -> Result<Vec<_>> {
+        let collections = match chunk? {
+            SamplerOutput::Namespace(key_values) => key_values
+                .into_iter()
+                .map(|(name, value)| (name.clone(), name, value))
+                .collect::<Vec<_>>(),
+            SamplerOutput::Collection(name, value) => {
+                vec![("collection".to_string(), name, value)]
+            }
+        };
+
+        Ok(collections
+            .into_iter()
+            .map(|(file_name, collection_name, value)| {
+                let rows = match value {
+                    Value::Array(rows) => rows,
+                    non_array => vec![non_array],
+                };
+                (file_name, collection_name, rows)
+            })
+            .collect())
+    }))
+}
+
+#[derive(Clone, Debug)]
+pub struct ParquetFileImportStrategy {
+    pub from_dir: PathBuf,
+}
+
+impl ImportStrategy for ParquetFileImportStrategy {
+    fn import(&self) -> Result<Namespace> {
+        let mut namespace = Namespace::new();
+
+        for entry in std::fs::read_dir(&self.from_dir)? {
+            let entry = entry?;
+            let path = entry.path();
+
+            // Other files, like the `_SUCCESS` markers or `.crc` checksums written alongside
+            // Parquet files by some tools, aren't collections
+            if entry.file_type()?.is_file()
+                && path
+                    .extension()
+                    .map_or(false, |extension| extension == "parquet")
+            {
+                let collection = import_parquet_collection(&path)?;
+
+                let name_string = path
+                    .file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .ok_or_else(|| {
+                        anyhow!(
+                            "Failed to interpret collection name when importing a Parquet namespace"
+                        )
+                    })?
+                    .to_string();
+
+                namespace.put_collection(name_string, collection)?;
+            }
+        }
+
+        Ok(namespace)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sampler::Target;
+    use synth_core::schema::{ChronoValueType, NumberContent, StringContent};
+    use synth_core::Content;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_parquet_export_and_import() {
+        let namespace: Namespace = serde_json::from_value(serde_json::json!({
+            "users": {
+                "type": "array",
+                "length": 20,
+                "content": {
+                    "type": "object",
+                    "id": { "type": "number", "subtype": "i64", "id": {} },
+                    "name": { "type": "string", "pattern": "[a-z]{4}" },
+                    "double_id": { "type": "expr", "expr": "@users.content.id * 2" },
+                    "joined": {
+                        "type": "date_time",
+                        "format": "%Y-%m-%d",
+                        "subtype": "naive_date",
+                        "begin": "2021-01-01",
+                        "end": "2021-12-31"
+                    },
+                    "score": {
+                        "type": "one_of",
+                        "variants": [
+                            { "type": "null" },
+                            { "type": "number", "range": { "low": 0.0, "high": 1.0 } }
+                        ]
+                    },
+                    "address": {
+                        "type": "object",
+                        "street": { "type": "string", "pattern": "[a-z]{8}" },
+                        "tags": {
+                            "type": "array",
+                            "length": { "type": "number", "range": { "low": 0, "high": 3 } },
+                            "content": { "type": "bool", "frequency": 0.5 }
+                        }
+                    }
+                }
+            },
+            "levels": {
+                "type": "array",
+                "length": 5,
+                "content": { "type": "number", "subtype": "u32", "range": { "low": 0, "high": 10 } }
+            }
+        }))
+        .unwrap();
+
+        let dir = tempdir().unwrap();
+        let to_dir = dir.path().join("out");
+        ParquetFileExportStrategy {
+            to_dir: to_dir.clone(),
+        }
+        .export(ExportParams {
+            namespace,
+            collection_name: None,
+            target: Target::Total(1),
+            seed: 0,
+            ns_path: PathBuf::new(),
+            truncate: false,
+        })
+        .unwrap();
+        // Not a collection
+        std::fs::write(to_dir.join("_SUCCESS"), "").unwrap();
+
+        let imported = ParquetFileImportStrategy { from_dir: to_dir }
+            .import()
+            .unwrap();
+
+        let users = match imported.get_collection("users").unwrap() {
+            Content::Array(array) => match array.content.as_ref() {
+                Content::Object(object) => object.clone(),
+                otherwise => panic!("expected an object, found {}", otherwise),
+            },
+            otherwise => panic!("expected an array, found {}", otherwise),
+        };
+        assert!(matches!(
+            users.fields["id"],
+            Content::Number(NumberContent::I64(_))
+        ));
+        assert!(matches!(
+            users.fields["double_id"],
+            Content::Number(NumberContent::I64(_))
+        ));
+        assert!(matches!(
+            users.fields["name"],
+            Content::String(StringContent::Categorical(_))
+        ));
+        match &users.fields["joined"] {
+            Content::DateTime(date_time) => {
+                assert_eq!(date_time.format, "%Y-%m-%d");
+                assert_eq!(date_time.type_, ChronoValueType::NaiveDate);
+            }
+            otherwise => panic!("expected a date_time, found {}", otherwise),
+        }
+        assert!(matches!(users.fields["score"], Content::OneOf(_)));
+        match &users.fields["address"] {
+            Content::Object(address) => match &address.fields["tags"] {
+                Content::Array(tags) => {
+                    assert!(matches!(tags.content.as_ref(), Content::Bool(_)))
+                }
+                otherwise => panic!("expected an array, found {}", otherwise),
+            },
+            otherwise => panic!("expected an object, found {}", otherwise),
+        }
+
+        assert!(!imported.collection_exists("_SUCCESS"));
+
+        match imported.get_collection("levels").unwrap() {
+            Content::Array(array) => assert!(matches!(
+                array.content.as_ref(),
+                Content::Number(NumberContent::U32(_))
+            )),
+            otherwise => panic!("expected an array, found {}", otherwise),
+        }
+    }
+}
@@ -0,0 +1,396 @@
+use anyhow::{Context, Result};
+
+use parquet::column::reader::{ColumnReader, ColumnReaderImpl};
+use parquet::data_type::{ByteArray, DataType, Int96};
+use parquet::file::reader::FileReader;
+use parquet::file::serialized_reader::SerializedFileReader;
+
+use synth_core::schema::{DistributionFit, MergeStrategy, OptionalMergeStrategy};
+use synth_core::Content;
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use serde_json::Value;
+
+use std::fs::File;
+use std::path::Path;
+
+use super::schema::{Collection, Column, ColumnKind, Leaf, Unit};
+
+/// The number of rows read from a file to infer the content of its collection. Whole row groups
+/// are read until at least this many rows are available.
+const SAMPLE_SIZE: usize = 1000;
+
+const BATCH_SIZE: usize = 1024;
+
+/// Infers the content of a collection from the schema of the Parquet file at `path` and a
+/// sample of its rows.
+pub(super) fn import_parquet_collection(path: &Path) -> Result<Content> {
+    let file =
+        File::open(path).with_context(|| format!("Failed to open file {}", path.display()))?;
+    let reader = SerializedFileReader::new(file)?;
+    let metadata = reader.metadata();
+
+    let collection = Collection::from_parquet_schema(metadata.file_metadata().schema())?;
+    let schema = metadata.file_metadata().schema_descr();
+    let leaves = collection
+        .columns
+        .iter()
+        .flat_map(Column::leaves)
+        .collect::<Vec<_>>();
+
+    let mut rows = Vec::new();
+    for idx in 0..reader.num_row_groups() {
+        if rows.len() >= SAMPLE_SIZE {
+            break;
+        }
+
+        let row_group = reader.get_row_group(idx)?;
+        let mut cursors = leaves
+            .iter()
+            .enumerate()
+            .map(|(i, leaf)| {
+                let descriptor = schema.column(i);
+                read_leaf(
+                    row_group.get_column_reader(i)?,
+                    *leaf,
+                    descriptor.max_def_level(),
+                    descriptor.max_rep_level(),
+                )
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        for _ in 0..row_group.metadata().num_rows() {
+            let mut cursors = &mut cursors[..];
+            let mut fields = serde_json::Map::new();
+            for column in &collection.columns {
+                let (head, tail) = cursors.split_at_mut(column.num_leaves());
+                fields.insert(column.name.clone(), assemble(column, 0, 0, head)?);
+                cursors = tail;
+            }
+            rows.push(Value::Object(fields));
+        }
+    }
+    rows.truncate(SAMPLE_SIZE);
+
+    if collection.wrapped {
+        rows = rows
+            .into_iter()
+            .map(|mut row| row[super::schema::VALUE_COLUMN].take())
+            .collect();
+    }
+
+    let rows = Value::Array(rows);
+    let mut content = collection.to_content();
+    OptionalMergeStrategy::default().try_merge(&mut content, &rows)?;
+
+    let mut fit = DistributionFit::new();
+    fit.observe(&rows);
+    fit.apply(&mut content);
+
+    Ok(content)
+}
+
+/// The definition and repetition levels and the (non-null) values of a leaf column in a row
+/// group, as they are consumed by [`assemble`].
+struct LeafCursor {
+    definitions: Vec<i16>,
+    repetitions: Vec<i16>,
+    values: std::vec::IntoIter<Value>,
+    position: usize,
+}
+
+impl LeafCursor {
+    fn peek(&self) -> Option<(i16, i16)> {
+        self.definitions
+            .get(self.position)
+            .map(|definition| (*definition, self.repetitions[self.position]))
+    }
+
+    fn skip(&mut self) {
+        self.position += 1;
+    }
+
+    fn next_value(&mut self) -> Result<Value> {
+        self.position += 1;
+        self.values
+            .next()
+            .ok_or_else(|| anyhow!("a Parquet column has fewer values than levels"))
+    }
+}
+
+/// Rebuilds the value of `column` from its leaves (the record assembly of the Dremel paper).
+fn assemble(
+    column: &Column,
+    definition: i16,
+    depth: i16,
+    cursors: &mut [LeafCursor],
+) -> Result<Value> {
+    let found = cursors[0]
+        .peek()
+        .ok_or_else(|| anyhow!("a Parquet column has fewer rows than its row group"))?
+        .0;
+
+    let definition = if column.optional {
+        if found <= definition {
+            cursors.iter_mut().for_each(LeafCursor::skip);
+            return Ok(Value::Null);
+        }
+        definition + 1
+    } else {
+        definition
+    };
+
+    match &column.kind {
+        ColumnKind::Leaf(_) | ColumnKind::Dynamic | ColumnKind::Null => cursors[0].next_value(),
+        ColumnKind::Struct(fields) => {
+            let mut object = serde_json::Map::new();
+            let mut cursors = cursors;
+            for field in fields {
+                let (head, tail) = cursors.split_at_mut(field.num_leaves());
+                object.insert(
+                    field.name.clone(),
+                    assemble(field, definition, depth, head)?,
+                );
+                cursors = tail;
+            }
+            Ok(Value::Object(object))
+        }
+        ColumnKind::List(element) => {
+            if found <= definition {
+                cursors.iter_mut().for_each(LeafCursor::skip);
+                return Ok(Value::Array(Vec::new()));
+            }
+
+            let mut elements = Vec::new();
+            loop {
+                elements.push(assemble(element, definition + 1, depth + 1, cursors)?);
+                match cursors[0].peek() {
+                    Some((_, repetition)) if repetition == depth + 1 => continue,
+                    _ => break,
+                }
+            }
+            Ok(Value::Array(elements))
+        }
+    }
+}
+
+fn read_leaf(
+    reader: ColumnReader,
+    leaf: Leaf,
+    max_definition: i16,
+    max_repetition: i16,
+) -> Result<LeafCursor> {
+    let read = |(definitions, repetitions, values): (Vec<i16>, Vec<i16>, Vec<Value>)| {
+        Ok(LeafCursor {
+            definitions,
+            repetitions,
+            values: values.into_iter(),
+            position: 0,
+        })
+    };
+
+    match reader {
+        ColumnReader::BoolColumnReader(mut reader) => read(read_all(
+            &mut reader,
+            max_definition,
+            max_repetition,
+            Value::Bool,
+        )?),
+        ColumnReader::Int32ColumnReader(mut reader) => read(read_all(
+            &mut reader,
+            max_definition,
+            max_repetition,
+            |i| from_integer(leaf, i as i64),
+        )?),
+        ColumnReader::Int64ColumnReader(mut reader) => read(read_all(
+            &mut reader,
+            max_definition,
+            max_repetition,
+            |i| from_integer(leaf, i),
+        )?),
+        ColumnReader::Int96ColumnReader(mut reader) => read(read_all(
+            &mut reader,
+            max_definition,
+            max_repetition,
+            |i: Int96| from_timestamp(leaf, i.to_i64() * 1_000),
+        )?),
+        ColumnReader::FloatColumnReader(mut reader) => read(read_all(
+            &mut reader,
+            max_definition,
+            max_repetition,
+            |f| from_float(f as f64),
+        )?),
+        ColumnReader::DoubleColumnReader(mut reader) => read(read_all(
+            &mut reader,
+            max_definition,
+            max_repetition,
+            from_float,
+        )?),
+        ColumnReader::ByteArrayColumnReader(mut reader) => read(read_all(
+            &mut reader,
+            max_definition,
+            max_repetition,
+            |b: ByteArray| from_bytes(leaf, b.data()),
+        )?),
+        ColumnReader::FixedLenByteArrayColumnReader(mut reader) => read(read_all(
+            &mut reader,
+            max_definition,
+            max_repetition,
+            |b| from_bytes(leaf, b.data()),
+        )?),
+    }
+}
+
+/// Reads all of the levels and values of a column chunk.
+fn read_all<T: DataType, F: Fn(T::T) -> Value>(
+    reader: &mut ColumnReaderImpl<T>,
+    max_definition: i16,
+    max_repetition: i16,
+    to_value: F,
+) -> Result<(Vec<i16>, Vec<i16>, Vec<Value>)> {
+    let mut definitions = Vec::new();
+    let mut repetitions = Vec::new();
+    let mut values = Vec::new();
+
+    let mut definition_batch = vec![0; BATCH_SIZE];
+    let mut repetition_batch = vec![0; BATCH_SIZE];
+    let mut value_batch = vec![T::T::default(); BATCH_SIZE];
+    loop {
+        let (values_read, levels_read) = reader.read_batch(
+            BATCH_SIZE,
+            Some(&mut definition_batch),
+            Some(&mut repetition_batch),
+            &mut value_batch,
+        )?;
+        if values_read == 0 && levels_read == 0 {
+            break;
+        }
+
+        // Levels are only decoded for columns which have some, otherwise every entry is a value
+        // at level 0
+        let entries = if max_definition == 0 && max_repetition == 0 {
+            values_read
+        } else {
+            levels_read
+        };
+        definitions.extend_from_slice(&definition_batch[..entries]);
+        repetitions.extend_from_slice(&repetition_batch[..entries]);
+        values.extend(value_batch.drain(..values_read).map(&to_value));
+        value_batch.resize(BATCH_SIZE, T::T::default());
+    }
+
+    Ok((definitions, repetitions, values))
+}
+
+fn from_integer(leaf: Leaf, i: i64) -> Value {
+    match leaf {
+        Leaf::Int {
+            bits,
+            signed: false,
+        } if bits <= 32 => Value::from(i as u32),
+        Leaf::Int { signed: false, .. } => Value::from(i as u64),
+        Leaf::Decimal { scale } => from_float(i as f64 / 10f64.powi(scale)),
+        Leaf::Date => {
+            let date = NaiveDate::from_ymd(1970, 1, 1) + chrono::Duration::days(i);
+            format_date_time(leaf, date.and_hms(0, 0, 0))
+        }
+        Leaf::Time { unit } => {
+            let micros = to_micros(unit, i);
+            let time = NaiveTime::from_num_seconds_from_midnight_opt(
+                (micros / 1_000_000) as u32,
+                (micros % 1_000_000) as u32 * 1_000,
+            );
+            match time {
+                Some(time) => {
+                    format_date_time(leaf, NaiveDate::from_ymd(1970, 1, 1).and_time(time))
+                }
+                None => Value::Null,
+            }
+        }
+        Leaf::Timestamp { unit, .. } => from_timestamp(leaf, to_micros(unit, i)),
+        _ => Value::from(i),
+    }
+}
+
+fn from_bytes(leaf: Leaf, bytes: &[u8]) -> Value {
+    match leaf {
+        Leaf::Decimal { scale } => from_float(decimal_from_bytes(bytes) / 10f64.powi(scale)),
+        _ => Value::String(String::from_utf8_lossy(bytes).into_owned()),
+    }
+}
+
+/// The unscaled value of a decimal stored as a big-endian two's complement integer of any width.
+fn decimal_from_bytes(bytes: &[u8]) -> f64 {
+    let negative = bytes.first().map_or(false, |byte| byte & 0x80 != 0);
+    // The magnitude of a negative number is one more than its complement
+    let magnitude = bytes.iter().fold(0f64, |magnitude, byte| {
+        let byte = if negative { !byte } else { *byte };
+        magnitude * 256.0 + byte as f64
+    });
+    if negative {
+        -(magnitude + 1.0)
+    } else {
+        magnitude
+    }
+}
+
+fn from_timestamp(leaf: Leaf, micros: i64) -> Value {
+    let date_time = NaiveDateTime::from_timestamp_opt(
+        micros.div_euclid(1_000_000),
+        micros.rem_euclid(1_000_000) as u32 * 1_000,
+    );
+    match date_time {
+        Some(date_time) => format_date_time(leaf, date_time),
+        None => Value::Null,
+    }
+}
+
+fn format_date_time(leaf: Leaf, date_time: NaiveDateTime) -> Value {
+    let format = match leaf.date_time_format() {
+        Some((format, _)) => format,
+        None => return Value::Null,
+    };
+    Value::String(
+        match leaf {
+            Leaf::Timestamp { utc: true, .. } | Leaf::Int96 => {
+                DateTime::<Utc>::from_utc(date_time, Utc).format(format)
+            }
+            _ => date_time.format(format),
+        }
+        .to_string(),
+    )
+}
+
+fn to_micros(unit: Unit, i: i64) -> i64 {
+    match unit {
+        Unit::Millis => i * 1_000,
+        Unit::Micros => i,
+        Unit::Nanos => i.div_euclid(1_000),
+    }
+}
+
+fn from_float(f: f64) -> Value {
+    serde_json::Number::from_f64(f)
+        .map(Value::Number)
+        .unwrap_or(Value::Null)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decimals_from_bytes() {
+        let decimal = Leaf::Decimal { scale: 2 };
+        assert_eq!(
+            from_bytes(decimal, &12345i64.to_be_bytes()),
+            serde_json::json!(123.45)
+        );
+        assert_eq!(from_bytes(decimal, &[0xff, 0x85]), serde_json::json!(-1.23));
+        assert_eq!(
+            from_bytes(decimal, &(-12345i128).to_be_bytes()),
+            serde_json::json!(-123.45)
+        );
+        assert_eq!(from_bytes(Leaf::Bytes, b"abc"), serde_json::json!("abc"));
+    }
+}
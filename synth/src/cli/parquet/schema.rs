@@ -0,0 +1,903 @@
+use anyhow::Result;
+
+use parquet::basic::{
+    ConvertedType, DateType, IntType, ListType, LogicalType, Repetition, StringType, TimeType,
+    TimeUnit, TimestampType, Type as PhysicalType,
+};
+use parquet::schema::types::{Type, TypePtr};
+use parquet_format::MicroSeconds;
+
+use synth_core::graph::prelude::content::number_content;
+use synth_core::schema::{
+    ArrayContent, BoolContent, Categorical, ChronoValue, ChronoValueType, DateTimeContent,
+    NumberContent, ObjectContent, RangeStep, StringContent,
+};
+use synth_core::{Content, Namespace, Value};
+use synth_gen::value::Number;
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// Name of the column holding the elements of collections that aren't arrays of objects.
+pub(super) const VALUE_COLUMN: &str = "value";
+
+/// A column of a Parquet file, possibly nested.
+#[derive(Clone, Debug, PartialEq)]
+pub(super) struct Column {
+    pub name: String,
+    pub optional: bool,
+    pub kind: ColumnKind,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(super) enum ColumnKind {
+    Leaf(Leaf),
+    Struct(Vec<Column>),
+    /// A standard 3-level Parquet list of `element`.
+    List(Box<Column>),
+    /// A column whose type can only be known from the values generated for it (e.g. `expr` or
+    /// `series`).
+    Dynamic,
+    /// A column that is always null.
+    Null,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(super) enum Leaf {
+    Bool,
+    Int {
+        bits: i8,
+        signed: bool,
+    },
+    Float,
+    Double,
+    /// Written as UTF8. Values of another type end up in a `String` column when the types of a
+    /// column disagree, in which case they are written as JSON.
+    String,
+    Bytes,
+    Decimal {
+        scale: i32,
+    },
+    Date,
+    Time {
+        unit: Unit,
+    },
+    Timestamp {
+        unit: Unit,
+        utc: bool,
+    },
+    /// Legacy nanosecond timestamps.
+    Int96,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(super) enum Unit {
+    Millis,
+    Micros,
+    Nanos,
+}
+
+/// The columns of the file of a collection.
+#[derive(Clone, Debug, PartialEq)]
+pub(super) struct Collection {
+    pub columns: Vec<Column>,
+    /// Whether the elements of the collection are written whole to a single `value` column,
+    /// rather than one column per field of an object.
+    pub wrapped: bool,
+}
+
+impl Collection {
+    pub fn from_content(content: &Content, namespace: &Namespace) -> Result<Self> {
+        let content = match content {
+            Content::Array(ArrayContent { content, .. }) => content,
+            non_array => non_array,
+        };
+
+        match Column::from_content(VALUE_COLUMN, content, namespace)? {
+            Column {
+                optional: false,
+                kind: ColumnKind::Struct(fields),
+                ..
+            } if !fields.is_empty() => Ok(Self {
+                columns: fields,
+                wrapped: false,
+            }),
+            column => Ok(Self {
+                columns: vec![column],
+                wrapped: true,
+            }),
+        }
+    }
+
+    pub fn from_parquet_schema(schema: &Type) -> Result<Self> {
+        let columns = schema
+            .get_fields()
+            .iter()
+            .map(|field| Column::from_parquet_type(field))
+            .collect::<Result<Vec<_>>>()?;
+        let wrapped = matches!(&columns[..], [column] if column.name == VALUE_COLUMN);
+        Ok(Self { columns, wrapped })
+    }
+
+    /// Whether the type of some columns can only be known from the values generated for them.
+    pub fn is_dynamic(&self) -> bool {
+        self.columns.iter().any(Column::is_dynamic)
+    }
+
+    /// The column of the values of `rows`, to be unified with the columns of the other rows
+    /// generated for the collection and passed to [`resolve`](Self::resolve).
+    pub fn observe(rows: &[Value]) -> Column {
+        rows.iter()
+            .map(|row| Column::from_value(VALUE_COLUMN, row))
+            .fold(Column::new(VALUE_COLUMN, ColumnKind::Null), Column::unify)
+    }
+
+    /// Settles the type of the `Dynamic` columns from `observed`, the column of all of the rows
+    /// generated.
+    pub fn resolve(&mut self, observed: &Column) {
+        if self.wrapped {
+            self.columns[0].resolve(observed);
+        } else {
+            for column in self.columns.iter_mut() {
+                column.resolve(&observed.field(&column.name));
+            }
+        }
+    }
+
+    pub fn schema(&self) -> Result<TypePtr> {
+        let schema = Type::group_type_builder("schema")
+            .with_fields(
+                &mut self
+                    .columns
+                    .iter()
+                    .map(Column::to_parquet_type)
+                    .collect::<Result<_>>()?,
+            )
+            .build()?;
+        Ok(Arc::new(schema))
+    }
+
+    /// The content of the collection, to be refined with the rows sampled from the file.
+    pub fn to_content(&self) -> Content {
+        let content = if self.wrapped {
+            self.columns[0].to_content()
+        } else {
+            Content::Object(ObjectContent {
+                fields: self
+                    .columns
+                    .iter()
+                    .map(|column| (column.name.clone(), column.to_content()))
+                    .collect(),
+                ..Default::default()
+            })
+        };
+
+        Content::Array(ArrayContent {
+            length: Box::new(Content::Number(NumberContent::U64(
+                number_content::U64::Range(RangeStep::new(1, 2, 1)),
+            ))),
+            content: Box::new(content),
+            size: None,
+            parent: None,
+        })
+    }
+}
+
+impl Column {
+    fn new(name: &str, kind: ColumnKind) -> Self {
+        Self {
+            name: name.to_string(),
+            optional: false,
+            kind,
+        }
+    }
+
+    pub fn from_content(name: &str, content: &Content, namespace: &Namespace) -> Result<Self> {
+        let kind = match content {
+            Content::Null(_) => {
+                return Ok(Self {
+                    optional: true,
+                    ..Self::new(name, ColumnKind::Null)
+                })
+            }
+            Content::Bool(_) => ColumnKind::Leaf(Leaf::Bool),
+            Content::Number(number) => ColumnKind::Leaf(match number {
+                NumberContent::U32(_) => Leaf::Int {
+                    bits: 32,
+                    signed: false,
+                },
+                NumberContent::U64(_) => Leaf::Int {
+                    bits: 64,
+                    signed: false,
+                },
+                NumberContent::I32(_) => Leaf::Int {
+                    bits: 32,
+                    signed: true,
+                },
+                NumberContent::I64(_) => Leaf::Int {
+                    bits: 64,
+                    signed: true,
+                },
+                NumberContent::F32(_) => Leaf::Float,
+                NumberContent::F64(_) => Leaf::Double,
+            }),
+            Content::String(_) => ColumnKind::Leaf(Leaf::String),
+            Content::DateTime(date_time) => ColumnKind::Leaf(match date_time.type_ {
+                ChronoValueType::NaiveDate => Leaf::Date,
+                ChronoValueType::NaiveTime => Leaf::Time { unit: Unit::Micros },
+                ChronoValueType::NaiveDateTime => Leaf::Timestamp {
+                    unit: Unit::Micros,
+                    utc: false,
+                },
+                ChronoValueType::DateTime => Leaf::Timestamp {
+                    unit: Unit::Micros,
+                    utc: true,
+                },
+            }),
+            Content::Object(object) => {
+                let mut columns = Vec::new();
+                for (field, content) in object.iter() {
+                    match content {
+                        Content::Hidden(_) => {}
+                        // The fields of a lookup are fields of the object it is in
+                        Content::Lookup(lookup) => {
+                            for (name, ref_) in lookup.projected() {
+                                columns.push(Self::from_content(
+                                    name,
+                                    namespace.get_s_node(&ref_)?,
+                                    namespace,
+                                )?);
+                            }
+                        }
+                        content => columns.push(Self::from_content(field, content, namespace)?),
+                    }
+                }
+                ColumnKind::Struct(columns)
+            }
+            Content::Lookup(lookup) => ColumnKind::Struct(
+                lookup
+                    .projected()
+                    .map(|(name, ref_)| {
+                        Self::from_content(name, namespace.get_s_node(&ref_)?, namespace)
+                    })
+                    .collect::<Result<_>>()?,
+            ),
+            Content::Array(array) => ColumnKind::List(Box::new(Self::from_content(
+                "element",
+                &array.content,
+                namespace,
+            )?)),
+            Content::OneOf(one_of) => {
+                return one_of
+                    .iter()
+                    .map(|variant| Self::from_content(name, variant, namespace))
+                    .reduce(|left, right| Ok(left?.unify(right?)))
+                    .unwrap_or_else(|| Ok(Self::new(name, ColumnKind::Null)))
+            }
+            Content::Switch(switch) => {
+                return switch
+                    .iter()
+                    .map(|branch| Self::from_content(name, branch, namespace))
+                    .reduce(|left, right| Ok(left?.unify(right?)))
+                    .expect("a switch always has a default")
+            }
+            Content::SameAs(same_as) => {
+                return Self::from_content(name, namespace.get_s_node(&same_as.ref_)?, namespace)
+            }
+            Content::Unique(unique) => return Self::from_content(name, &unique.content, namespace),
+            Content::Hidden(hidden) => return Self::from_content(name, &hidden.content, namespace),
+            Content::Series(_) | Content::Expr(_) | Content::Datasource(_) | Content::Empty(_) => {
+                ColumnKind::Dynamic
+            }
+        };
+        Ok(Self::new(name, kind))
+    }
+
+    fn from_value(name: &str, value: &Value) -> Self {
+        let kind = match value {
+            Value::Null(_) => {
+                return Self {
+                    optional: true,
+                    ..Self::new(name, ColumnKind::Null)
+                }
+            }
+            Value::Bool(_) => ColumnKind::Leaf(Leaf::Bool),
+            Value::Number(number) => ColumnKind::Leaf(match number {
+                Number::I8(_) | Number::I16(_) | Number::I32(_) => Leaf::Int {
+                    bits: 32,
+                    signed: true,
+                },
+                Number::U8(_) | Number::U16(_) | Number::U32(_) => Leaf::Int {
+                    bits: 32,
+                    signed: false,
+                },
+                Number::I64(_) | Number::I128(_) => Leaf::Int {
+                    bits: 64,
+                    signed: true,
+                },
+                Number::U64(_) | Number::U128(_) => Leaf::Int {
+                    bits: 64,
+                    signed: false,
+                },
+                Number::F32(_) => Leaf::Float,
+                Number::F64(_) => Leaf::Double,
+            }),
+            Value::String(_) => ColumnKind::Leaf(Leaf::String),
+            Value::DateTime(date_time) => ColumnKind::Leaf(match date_time.value {
+                ChronoValue::NaiveDate(_) => Leaf::Date,
+                ChronoValue::NaiveTime(_) => Leaf::Time { unit: Unit::Micros },
+                ChronoValue::NaiveDateTime(_) => Leaf::Timestamp {
+                    unit: Unit::Micros,
+                    utc: false,
+                },
+                ChronoValue::DateTime(_) => Leaf::Timestamp {
+                    unit: Unit::Micros,
+                    utc: true,
+                },
+            }),
+            Value::Object(fields) => ColumnKind::Struct(
+                fields
+                    .iter()
+                    .map(|(field, value)| Self::from_value(field, value))
+                    .collect(),
+            ),
+            Value::Array(elements) => ColumnKind::List(Box::new(
+                elements
+                    .iter()
+                    .map(|element| Self::from_value("element", element))
+                    .reduce(Self::unify)
+                    .unwrap_or_else(|| Self::new("element", ColumnKind::Null)),
+            )),
+        };
+        Self::new(name, kind)
+    }
+
+    /// The narrowest column that can hold the values of both `self` and `other`.
+    pub fn unify(self, other: Self) -> Self {
+        let optional = self.optional || other.optional;
+        let kind = match (self.kind, other.kind) {
+            (ColumnKind::Null, kind) | (kind, ColumnKind::Null) => kind,
+            (left, right) if left == right => left,
+            (ColumnKind::Dynamic, _) | (_, ColumnKind::Dynamic) => ColumnKind::Dynamic,
+            (ColumnKind::Leaf(left), ColumnKind::Leaf(right)) => {
+                ColumnKind::Leaf(match (left, right) {
+                    (
+                        Leaf::Int {
+                            bits: left_bits,
+                            signed: left_signed,
+                        },
+                        Leaf::Int {
+                            bits: right_bits,
+                            signed: right_signed,
+                        },
+                    ) => Leaf::Int {
+                        bits: left_bits.max(right_bits),
+                        signed: left_signed || right_signed,
+                    },
+                    (Leaf::Int { .. } | Leaf::Float | Leaf::Double, Leaf::Float | Leaf::Double)
+                    | (Leaf::Float | Leaf::Double, Leaf::Int { .. }) => Leaf::Double,
+                    _ => Leaf::String,
+                })
+            }
+            (ColumnKind::Struct(left), ColumnKind::Struct(right)) => {
+                let mut fields = left
+                    .into_iter()
+                    .map(|field| (field.name.clone(), Some(field)))
+                    .collect::<Vec<_>>();
+                for field in right {
+                    match fields.iter_mut().find(|(name, _)| *name == field.name) {
+                        Some((_, existing)) => {
+                            *existing = existing.take().map(|existing| existing.unify(field))
+                        }
+                        None => fields.push((
+                            field.name.clone(),
+                            Some(Self {
+                                optional: true,
+                                ..field
+                            }),
+                        )),
+                    }
+                }
+                ColumnKind::Struct(fields.into_iter().filter_map(|(_, field)| field).collect())
+            }
+            (ColumnKind::List(left), ColumnKind::List(right)) => {
+                ColumnKind::List(Box::new(left.unify(*right)))
+            }
+            _ => ColumnKind::Leaf(Leaf::String),
+        };
+        Self {
+            name: self.name,
+            optional,
+            kind,
+        }
+    }
+
+    /// Settles the type of the `Dynamic` parts of this column from `observed`, the column of all of
+    /// the values generated for it. Columns that only ever see nulls are written as strings.
+    fn resolve(&mut self, observed: &Self) {
+        match &mut self.kind {
+            ColumnKind::Leaf(_) => {}
+            ColumnKind::Dynamic | ColumnKind::Null => {
+                self.optional |= observed.optional;
+                self.kind = match &observed.kind {
+                    ColumnKind::Null => ColumnKind::Leaf(Leaf::String),
+                    kind => kind.clone(),
+                };
+                // A dynamic column may have become a struct or a list with null parts of its
+                // own, like the `element` of an array that was always empty.
+                self.resolve(observed);
+            }
+            ColumnKind::Struct(fields) => {
+                for field in fields {
+                    field.resolve(&observed.field(&field.name));
+                }
+            }
+            ColumnKind::List(element) => match &observed.kind {
+                ColumnKind::List(observed) => element.resolve(observed),
+                _ => element.resolve(&Self::new("element", ColumnKind::Null)),
+            },
+        }
+    }
+
+    /// The column of field `name` among the values of `self`, which is always null if `self`
+    /// isn't a struct or doesn't have that field.
+    fn field(&self, name: &str) -> Self {
+        match &self.kind {
+            ColumnKind::Struct(fields) => fields.iter().find(|field| field.name == name).cloned(),
+            _ => None,
+        }
+        .unwrap_or_else(|| Self::new(name, ColumnKind::Null))
+    }
+
+    fn is_dynamic(&self) -> bool {
+        match &self.kind {
+            ColumnKind::Dynamic => true,
+            ColumnKind::Leaf(_) | ColumnKind::Null => false,
+            ColumnKind::Struct(fields) => fields.iter().any(Self::is_dynamic),
+            ColumnKind::List(element) => element.is_dynamic(),
+        }
+    }
+
+    /// The number of primitive (leaf) columns under this one.
+    pub fn num_leaves(&self) -> usize {
+        match &self.kind {
+            ColumnKind::Leaf(_) | ColumnKind::Dynamic | ColumnKind::Null => 1,
+            ColumnKind::Struct(fields) => fields.iter().map(Self::num_leaves).sum(),
+            ColumnKind::List(element) => element.num_leaves(),
+        }
+    }
+
+    pub fn leaves(&self) -> Vec<Leaf> {
+        match &self.kind {
+            ColumnKind::Leaf(leaf) => vec![*leaf],
+            ColumnKind::Dynamic | ColumnKind::Null => vec![Leaf::String],
+            ColumnKind::Struct(fields) => fields.iter().flat_map(Self::leaves).collect(),
+            ColumnKind::List(element) => element.leaves(),
+        }
+    }
+
+    fn repetition(&self) -> Repetition {
+        if self.optional {
+            Repetition::OPTIONAL
+        } else {
+            Repetition::REQUIRED
+        }
+    }
+
+    pub fn to_parquet_type(&self) -> Result<TypePtr> {
+        let type_ = match &self.kind {
+            ColumnKind::Leaf(leaf) => {
+                let (physical_type, logical_type) = leaf.to_parquet();
+                Type::primitive_type_builder(&self.name, physical_type)
+                    .with_repetition(self.repetition())
+                    .with_logical_type(logical_type)
+                    .build()?
+            }
+            ColumnKind::Dynamic | ColumnKind::Null => {
+                return Self {
+                    kind: ColumnKind::Leaf(Leaf::String),
+                    ..self.clone()
+                }
+                .to_parquet_type()
+            }
+            ColumnKind::Struct(fields) => {
+                if fields.is_empty() {
+                    return Err(anyhow!(
+                        "Parquet does not support empty objects, found one at `{}`",
+                        self.name
+                    ));
+                }
+                Type::group_type_builder(&self.name)
+                    .with_repetition(self.repetition())
+                    .with_fields(
+                        &mut fields
+                            .iter()
+                            .map(Self::to_parquet_type)
+                            .collect::<Result<_>>()?,
+                    )
+                    .build()?
+            }
+            ColumnKind::List(element) => {
+                let list = Type::group_type_builder("list")
+                    .with_repetition(Repetition::REPEATED)
+                    .with_fields(&mut vec![element.to_parquet_type()?])
+                    .build()?;
+                Type::group_type_builder(&self.name)
+                    .with_repetition(self.repetition())
+                    .with_logical_type(Some(LogicalType::LIST(ListType {})))
+                    .with_fields(&mut vec![Arc::new(list)])
+                    .build()?
+            }
+        };
+        Ok(Arc::new(type_))
+    }
+
+    pub fn from_parquet_type(type_: &Type) -> Result<Self> {
+        let info = type_.get_basic_info();
+        let optional = info.has_repetition() && info.repetition() == Repetition::OPTIONAL;
+
+        let kind = if info.has_repetition() && info.repetition() == Repetition::REPEATED {
+            // A legacy list (a repeated field not wrapped in a `LIST` group)
+            let element = Self::from_parquet_type_unrepeated(type_)?;
+            return Ok(Self {
+                name: info.name().to_string(),
+                optional: false,
+                kind: ColumnKind::List(Box::new(Self {
+                    name: "element".to_string(),
+                    ..element
+                })),
+            });
+        } else {
+            Self::from_parquet_type_unrepeated(type_)?.kind
+        };
+
+        Ok(Self {
+            name: info.name().to_string(),
+            optional,
+            kind,
+        })
+    }
+
+    fn from_parquet_type_unrepeated(type_: &Type) -> Result<Self> {
+        let info = type_.get_basic_info();
+
+        if type_.is_primitive() {
+            let leaf = Leaf::from_parquet(type_).ok_or_else(|| {
+                anyhow!(
+                    "Parquet column `{}` has an unsupported type: {} ({})",
+                    info.name(),
+                    type_.get_physical_type(),
+                    info.converted_type()
+                )
+            })?;
+            return Ok(Self::new(info.name(), ColumnKind::Leaf(leaf)));
+        }
+
+        let fields = type_.get_fields();
+        let kind = match info.converted_type() {
+            ConvertedType::LIST if fields.len() == 1 => {
+                let repeated = &fields[0];
+                let element = if repeated.is_group() && repeated.get_fields().len() == 1 {
+                    Self::from_parquet_type(&repeated.get_fields()[0])?
+                } else {
+                    Self::from_parquet_type_unrepeated(repeated)?
+                };
+                ColumnKind::List(Box::new(Self {
+                    name: "element".to_string(),
+                    ..element
+                }))
+            }
+            ConvertedType::MAP | ConvertedType::MAP_KEY_VALUE => {
+                return Err(anyhow!(
+                    "Parquet column `{}` is a map, which is not supported",
+                    info.name()
+                ))
+            }
+            _ => ColumnKind::Struct(
+                fields
+                    .iter()
+                    .map(|field| Self::from_parquet_type(field))
+                    .collect::<Result<_>>()?,
+            ),
+        };
+        Ok(Self::new(info.name(), kind))
+    }
+
+    /// The content generating this column, to be refined with the values sampled from the file.
+    pub fn to_content(&self) -> Content {
+        let content = match &self.kind {
+            ColumnKind::Leaf(leaf) => leaf.to_content(),
+            ColumnKind::Dynamic | ColumnKind::Null => Content::null(),
+            ColumnKind::Struct(fields) => Content::Object(ObjectContent {
+                fields: fields
+                    .iter()
+                    .map(|field| (field.name.clone(), field.to_content()))
+                    .collect::<BTreeMap<_, _>>(),
+                ..Default::default()
+            }),
+            ColumnKind::List(element) => Content::Array(ArrayContent {
+                length: Box::new(Content::Number(NumberContent::U64(
+                    number_content::U64::Range(RangeStep::default()),
+                ))),
+                content: Box::new(element.to_content()),
+                size: None,
+                parent: None,
+            }),
+        };
+
+        if self.optional {
+            content.into_nullable()
+        } else {
+            content
+        }
+    }
+}
+
+impl Leaf {
+    fn to_parquet(self) -> (PhysicalType, Option<LogicalType>) {
+        let micros = || TimeUnit::MICROS(MicroSeconds::new());
+        match self {
+            Self::Bool => (PhysicalType::BOOLEAN, None),
+            Self::Int { bits, signed } => (
+                if bits <= 32 {
+                    PhysicalType::INT32
+                } else {
+                    PhysicalType::INT64
+                },
+                Some(LogicalType::INTEGER(IntType {
+                    bit_width: bits,
+                    is_signed: signed,
+                })),
+            ),
+            Self::Float => (PhysicalType::FLOAT, None),
+            Self::Double | Self::Decimal { .. } => (PhysicalType::DOUBLE, None),
+            Self::String => (
+                PhysicalType::BYTE_ARRAY,
+                Some(LogicalType::STRING(StringType {})),
+            ),
+            Self::Bytes => (PhysicalType::BYTE_ARRAY, None),
+            Self::Date => (PhysicalType::INT32, Some(LogicalType::DATE(DateType {}))),
+            Self::Time { .. } => (
+                PhysicalType::INT64,
+                Some(LogicalType::TIME(TimeType {
+                    is_adjusted_to_u_t_c: false,
+                    unit: micros(),
+                })),
+            ),
+            Self::Timestamp { utc, .. } => (
+                PhysicalType::INT64,
+                Some(LogicalType::TIMESTAMP(TimestampType {
+                    is_adjusted_to_u_t_c: utc,
+                    unit: micros(),
+                })),
+            ),
+            Self::Int96 => (PhysicalType::INT96, None),
+        }
+    }
+
+    fn from_parquet(type_: &Type) -> Option<Self> {
+        // Nanoseconds only exist as a logical type, without a matching converted type
+        if type_.get_physical_type() == PhysicalType::INT64 {
+            match type_.get_basic_info().logical_type() {
+                Some(LogicalType::TIMESTAMP(TimestampType {
+                    unit: TimeUnit::NANOS(_),
+                    is_adjusted_to_u_t_c,
+                })) => {
+                    return Some(Self::Timestamp {
+                        unit: Unit::Nanos,
+                        utc: is_adjusted_to_u_t_c,
+                    })
+                }
+                Some(LogicalType::TIME(TimeType {
+                    unit: TimeUnit::NANOS(_),
+                    ..
+                })) => return Some(Self::Time { unit: Unit::Nanos }),
+                _ => {}
+            }
+        }
+
+        let leaf = match (
+            type_.get_physical_type(),
+            type_.get_basic_info().converted_type(),
+        ) {
+            (PhysicalType::BOOLEAN, _) => Self::Bool,
+            (
+                PhysicalType::INT32
+                | PhysicalType::INT64
+                | PhysicalType::BYTE_ARRAY
+                | PhysicalType::FIXED_LEN_BYTE_ARRAY,
+                ConvertedType::DECIMAL,
+            ) => Self::Decimal {
+                scale: type_.get_scale(),
+            },
+            (PhysicalType::INT32, ConvertedType::DATE) => Self::Date,
+            (PhysicalType::INT32, ConvertedType::TIME_MILLIS) => Self::Time { unit: Unit::Millis },
+            (PhysicalType::INT64, ConvertedType::TIME_MICROS) => Self::Time { unit: Unit::Micros },
+            (PhysicalType::INT64, ConvertedType::TIMESTAMP_MILLIS) => Self::Timestamp {
+                unit: Unit::Millis,
+                utc: Self::is_utc(type_),
+            },
+            (PhysicalType::INT64, ConvertedType::TIMESTAMP_MICROS) => Self::Timestamp {
+                unit: Unit::Micros,
+                utc: Self::is_utc(type_),
+            },
+            (PhysicalType::INT32, converted) => match converted {
+                ConvertedType::NONE | ConvertedType::INT_8 | ConvertedType::INT_16 => Self::Int {
+                    bits: 32,
+                    signed: true,
+                },
+                ConvertedType::INT_32 => Self::Int {
+                    bits: 32,
+                    signed: true,
+                },
+                ConvertedType::UINT_8 | ConvertedType::UINT_16 | ConvertedType::UINT_32 => {
+                    Self::Int {
+                        bits: 32,
+                        signed: false,
+                    }
+                }
+                _ => return None,
+            },
+            (PhysicalType::INT64, converted) => match converted {
+                ConvertedType::NONE | ConvertedType::INT_64 => Self::Int {
+                    bits: 64,
+                    signed: true,
+                },
+                ConvertedType::UINT_64 => Self::Int {
+                    bits: 64,
+                    signed: false,
+                },
+                _ => return None,
+            },
+            (PhysicalType::INT96, _) => Self::Int96,
+            (PhysicalType::FLOAT, _) => Self::Float,
+            (PhysicalType::DOUBLE, _) => Self::Double,
+            (PhysicalType::BYTE_ARRAY, converted) => match converted {
+                ConvertedType::UTF8 | ConvertedType::ENUM | ConvertedType::JSON => Self::String,
+                ConvertedType::NONE | ConvertedType::BSON => Self::Bytes,
+                _ => return None,
+            },
+            (PhysicalType::FIXED_LEN_BYTE_ARRAY, ConvertedType::NONE) => Self::Bytes,
+            _ => return None,
+        };
+        Some(leaf)
+    }
+
+    fn is_utc(type_: &Type) -> bool {
+        match type_.get_basic_info().logical_type() {
+            Some(LogicalType::TIMESTAMP(timestamp)) => timestamp.is_adjusted_to_u_t_c,
+            // Timestamps written before logical types were introduced are always in UTC
+            _ => true,
+        }
+    }
+
+    /// The format of the strings the values of this column are read as, if they are dates.
+    pub fn date_time_format(self) -> Option<(&'static str, ChronoValueType)> {
+        match self {
+            Self::Date => Some(("%Y-%m-%d", ChronoValueType::NaiveDate)),
+            Self::Time { .. } => Some(("%H:%M:%S", ChronoValueType::NaiveTime)),
+            Self::Timestamp { utc: false, .. } => {
+                Some(("%Y-%m-%dT%H:%M:%S", ChronoValueType::NaiveDateTime))
+            }
+            Self::Timestamp { utc: true, .. } | Self::Int96 => {
+                Some(("%Y-%m-%dT%H:%M:%S%z", ChronoValueType::DateTime))
+            }
+            _ => None,
+        }
+    }
+
+    fn to_content(self) -> Content {
+        if let Some((format, type_)) = self.date_time_format() {
+            return Content::DateTime(DateTimeContent {
+                format: format.to_string(),
+                type_,
+                begin: None,
+                end: None,
+            });
+        }
+
+        match self {
+            Self::Bool => Content::Bool(BoolContent::default()),
+            Self::Int { bits, signed } => Content::Number(match (bits <= 32, signed) {
+                (true, true) => {
+                    NumberContent::I32(number_content::I32::Range(RangeStep::default()))
+                }
+                (false, true) => {
+                    NumberContent::I64(number_content::I64::Range(RangeStep::default()))
+                }
+                (true, false) => {
+                    NumberContent::U32(number_content::U32::Range(RangeStep::default()))
+                }
+                (false, false) => {
+                    NumberContent::U64(number_content::U64::Range(RangeStep::default()))
+                }
+            }),
+            Self::Float => Content::Number(NumberContent::F32(number_content::F32::Range(
+                RangeStep::default(),
+            ))),
+            Self::Double | Self::Decimal { .. } => Content::Number(NumberContent::F64(
+                number_content::F64::Range(RangeStep::default()),
+            )),
+            Self::String => Content::String(StringContent::Categorical(Categorical::default())),
+            _ => Content::String(StringContent::default()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parquet_format::NanoSeconds;
+
+    #[test]
+    fn test_leaves_from_parquet() {
+        let decimal = Type::primitive_type_builder("price", PhysicalType::FIXED_LEN_BYTE_ARRAY)
+            .with_length(8)
+            .with_converted_type(ConvertedType::DECIMAL)
+            .with_precision(18)
+            .with_scale(2)
+            .build()
+            .unwrap();
+        assert_eq!(
+            Leaf::from_parquet(&decimal),
+            Some(Leaf::Decimal { scale: 2 })
+        );
+
+        let timestamp = Type::primitive_type_builder("created_at", PhysicalType::INT64)
+            .with_logical_type(Some(LogicalType::TIMESTAMP(TimestampType {
+                is_adjusted_to_u_t_c: true,
+                unit: TimeUnit::NANOS(NanoSeconds::new()),
+            })))
+            .build()
+            .unwrap();
+        assert_eq!(
+            Leaf::from_parquet(&timestamp),
+            Some(Leaf::Timestamp {
+                unit: Unit::Nanos,
+                utc: true
+            })
+        );
+    }
+
+    fn row(total: Number, tags: Vec<Value>) -> Value {
+        Value::Object(
+            vec![
+                ("total".to_string(), Value::Number(total)),
+                ("tags".to_string(), Value::Array(tags)),
+            ]
+            .into_iter()
+            .collect(),
+        )
+    }
+
+    #[test]
+    fn test_resolve_from_every_chunk() {
+        let element = Column::new("element", ColumnKind::Dynamic);
+        let mut collection = Collection {
+            columns: vec![
+                Column::new("total", ColumnKind::Dynamic),
+                Column::new("tags", ColumnKind::List(Box::new(element))),
+            ],
+            wrapped: false,
+        };
+        assert!(collection.is_dynamic());
+
+        // Integers and empty lists at first, then a float and a bool
+        let first = vec![
+            row(Number::I64(1), Vec::new()),
+            row(Number::I64(2), Vec::new()),
+        ];
+        let second = vec![row(Number::from(2.5), vec![Value::Bool(true)])];
+        let observed = Collection::observe(&first).unify(Collection::observe(&second));
+        collection.resolve(&observed);
+
+        assert_eq!(collection.columns[0].kind, ColumnKind::Leaf(Leaf::Double));
+        assert_eq!(
+            collection.columns[1].kind,
+            ColumnKind::List(Box::new(Column::new(
+                "element",
+                ColumnKind::Leaf(Leaf::Bool)
+            )))
+        );
+    }
+}
@@ -0,0 +1,411 @@
+use anyhow::{Context, Result};
+
+use parquet::column::writer::ColumnWriter;
+use parquet::data_type::ByteArray;
+use parquet::file::properties::WriterProperties;
+use parquet::file::writer::{FileWriter, SerializedFileWriter};
+
+use synth_core::graph::json::synth_val_to_json;
+use synth_core::schema::ChronoValue;
+use synth_core::Value;
+use synth_gen::value::Number;
+
+use chrono::{NaiveDate, NaiveTime, Timelike};
+
+use std::fs::File;
+use std::path::Path;
+use std::sync::Arc;
+
+use super::schema::{Collection, Column, ColumnKind, Leaf};
+
+/// Writes the values of one collection to a Parquet file, one row group per call to
+/// [`write`](Self::write).
+pub(super) struct ParquetWriter {
+    writer: SerializedFileWriter<File>,
+    collection: Collection,
+}
+
+impl ParquetWriter {
+    pub fn create(path: &Path, collection: Collection) -> Result<Self> {
+        let schema = collection.schema()?;
+        let properties = Arc::new(WriterProperties::builder().build());
+        let file = File::create(path)
+            .with_context(|| format!("Failed to create file {}", path.display()))?;
+        Ok(Self {
+            writer: SerializedFileWriter::new(file, schema, properties)?,
+            collection,
+        })
+    }
+
+    pub fn write(&mut self, rows: &[Value]) -> Result<()> {
+        if rows.is_empty() {
+            return Ok(());
+        }
+
+        let mut buffers = self
+            .collection
+            .columns
+            .iter()
+            .flat_map(Column::leaves)
+            .map(LeafBuffer::new)
+            .collect::<Vec<_>>();
+
+        for row in rows {
+            let mut buffers = &mut buffers[..];
+            for column in &self.collection.columns {
+                let value = if self.collection.wrapped {
+                    Some(row)
+                } else {
+                    match row {
+                        Value::Object(fields) => fields.get(&column.name),
+                        _ => return Err(anyhow!("expected an object, found {}", row.type_())),
+                    }
+                };
+
+                let (head, tail) = buffers.split_at_mut(column.num_leaves());
+                shred(column, value, 0, 0, 0, head)
+                    .with_context(|| format!("while writing column `{}`", column.name))?;
+                buffers = tail;
+            }
+        }
+
+        let mut row_group = self.writer.next_row_group()?;
+        for buffer in buffers {
+            let column_writer = row_group
+                .next_column()?
+                .ok_or_else(|| anyhow!("the schema has fewer columns than were generated"))?;
+            let column_writer = buffer.write_to(column_writer)?;
+            row_group.close_column(column_writer)?;
+        }
+        self.writer.close_row_group(row_group)?;
+
+        Ok(())
+    }
+
+    pub fn close(mut self) -> Result<()> {
+        self.writer.close()?;
+        Ok(())
+    }
+}
+
+/// Splits `value` into the definition and repetition levels and values of the leaf columns of
+/// `column` (the record shredding of the Dremel paper).
+fn shred(
+    column: &Column,
+    value: Option<&Value>,
+    definition: i16,
+    repetition: i16,
+    depth: i16,
+    buffers: &mut [LeafBuffer],
+) -> Result<()> {
+    let value = value.filter(|value| !matches!(value, Value::Null(_)));
+
+    let (value, definition) = match value {
+        Some(value) if column.optional => (value, definition + 1),
+        Some(value) => (value, definition),
+        None if column.optional => {
+            buffers
+                .iter_mut()
+                .for_each(|buffer| buffer.push_levels(definition, repetition));
+            return Ok(());
+        }
+        None => return Err(anyhow!("`{}` cannot be null", column.name)),
+    };
+
+    match &column.kind {
+        ColumnKind::Leaf(_) | ColumnKind::Dynamic | ColumnKind::Null => {
+            buffers[0].push(value, definition, repetition)
+        }
+        ColumnKind::Struct(fields) => {
+            let object = match value {
+                Value::Object(object) => object,
+                otherwise => {
+                    return Err(anyhow!(
+                        "expected an object at `{}`, found {}",
+                        column.name,
+                        otherwise.type_()
+                    ))
+                }
+            };
+
+            let mut buffers = buffers;
+            for field in fields {
+                let (head, tail) = buffers.split_at_mut(field.num_leaves());
+                shred(
+                    field,
+                    object.get(&field.name),
+                    definition,
+                    repetition,
+                    depth,
+                    head,
+                )?;
+                buffers = tail;
+            }
+            Ok(())
+        }
+        ColumnKind::List(element) => {
+            let elements = match value {
+                Value::Array(elements) => elements,
+                otherwise => {
+                    return Err(anyhow!(
+                        "expected an array at `{}`, found {}",
+                        column.name,
+                        otherwise.type_()
+                    ))
+                }
+            };
+
+            if elements.is_empty() {
+                buffers
+                    .iter_mut()
+                    .for_each(|buffer| buffer.push_levels(definition, repetition));
+                return Ok(());
+            }
+
+            for (idx, element_value) in elements.iter().enumerate() {
+                let repetition = if idx == 0 { repetition } else { depth + 1 };
+                shred(
+                    element,
+                    Some(element_value),
+                    definition + 1,
+                    repetition,
+                    depth + 1,
+                    buffers,
+                )?;
+            }
+            Ok(())
+        }
+    }
+}
+
+enum LeafValues {
+    Bool(Vec<bool>),
+    Int32(Vec<i32>),
+    Int64(Vec<i64>),
+    Float(Vec<f32>),
+    Double(Vec<f64>),
+    Bytes(Vec<ByteArray>),
+}
+
+struct LeafBuffer {
+    leaf: Leaf,
+    values: LeafValues,
+    definitions: Vec<i16>,
+    repetitions: Vec<i16>,
+}
+
+impl LeafBuffer {
+    fn new(leaf: Leaf) -> Self {
+        let values = match leaf {
+            Leaf::Bool => LeafValues::Bool(Vec::new()),
+            Leaf::Int { bits, .. } if bits <= 32 => LeafValues::Int32(Vec::new()),
+            Leaf::Date => LeafValues::Int32(Vec::new()),
+            Leaf::Int { .. } | Leaf::Time { .. } | Leaf::Timestamp { .. } => {
+                LeafValues::Int64(Vec::new())
+            }
+            Leaf::Float => LeafValues::Float(Vec::new()),
+            Leaf::Double | Leaf::Decimal { .. } => LeafValues::Double(Vec::new()),
+            Leaf::String | Leaf::Bytes | Leaf::Int96 => LeafValues::Bytes(Vec::new()),
+        };
+        Self {
+            leaf,
+            values,
+            definitions: Vec::new(),
+            repetitions: Vec::new(),
+        }
+    }
+
+    fn push_levels(&mut self, definition: i16, repetition: i16) {
+        self.definitions.push(definition);
+        self.repetitions.push(repetition);
+    }
+
+    fn push(&mut self, value: &Value, definition: i16, repetition: i16) -> Result<()> {
+        let mismatch = || {
+            anyhow!(
+                "a value of type {} cannot be written to a {:?} column",
+                value.type_(),
+                self.leaf
+            )
+        };
+
+        match (&mut self.values, value) {
+            (LeafValues::Bool(values), Value::Bool(b)) => values.push(*b),
+            (LeafValues::Int32(values), Value::Number(number)) => {
+                values.push(as_i64(number, self.leaf).ok_or_else(mismatch)?? as i32)
+            }
+            (LeafValues::Int64(values), Value::Number(number)) => {
+                values.push(as_i64(number, self.leaf).ok_or_else(mismatch)??)
+            }
+            (LeafValues::Float(values), Value::Number(number)) => {
+                values.push(as_f64(number) as f32)
+            }
+            (LeafValues::Double(values), Value::Number(number)) => values.push(as_f64(number)),
+            (LeafValues::Int32(values), Value::DateTime(date_time)) => match &date_time.value {
+                ChronoValue::NaiveDate(date) => values.push(days_since_epoch(date)),
+                _ => return Err(mismatch()),
+            },
+            (LeafValues::Int64(values), Value::DateTime(date_time)) => {
+                values.push(match (&date_time.value, self.leaf) {
+                    (ChronoValue::NaiveTime(time), Leaf::Time { .. }) => {
+                        micros_since_midnight(time)
+                    }
+                    (ChronoValue::NaiveDateTime(date_time), Leaf::Timestamp { .. }) => {
+                        date_time.timestamp() * 1_000_000
+                            + date_time.timestamp_subsec_micros() as i64
+                    }
+                    (ChronoValue::DateTime(date_time), Leaf::Timestamp { .. }) => {
+                        date_time.timestamp() * 1_000_000
+                            + date_time.timestamp_subsec_micros() as i64
+                    }
+                    _ => return Err(mismatch()),
+                })
+            }
+            (LeafValues::Bytes(values), Value::String(string)) => {
+                values.push(ByteArray::from(string.as_bytes().to_vec()))
+            }
+            (LeafValues::Bytes(values), Value::DateTime(date_time)) => {
+                values.push(ByteArray::from(date_time.format_to_string().into_bytes()))
+            }
+            (LeafValues::Bytes(values), value) if self.leaf == Leaf::String => values.push(
+                ByteArray::from(synth_val_to_json(value.clone()).to_string().into_bytes()),
+            ),
+            _ => return Err(mismatch()),
+        }
+
+        self.push_levels(definition, repetition);
+        Ok(())
+    }
+
+    fn write_to(self, mut column_writer: ColumnWriter) -> Result<ColumnWriter> {
+        let definitions = Some(&self.definitions[..]);
+        let repetitions = Some(&self.repetitions[..]);
+
+        match (&mut column_writer, &self.values) {
+            (ColumnWriter::BoolColumnWriter(writer), LeafValues::Bool(values)) => {
+                writer.write_batch(values, definitions, repetitions)?
+            }
+            (ColumnWriter::Int32ColumnWriter(writer), LeafValues::Int32(values)) => {
+                writer.write_batch(values, definitions, repetitions)?
+            }
+            (ColumnWriter::Int64ColumnWriter(writer), LeafValues::Int64(values)) => {
+                writer.write_batch(values, definitions, repetitions)?
+            }
+            (ColumnWriter::FloatColumnWriter(writer), LeafValues::Float(values)) => {
+                writer.write_batch(values, definitions, repetitions)?
+            }
+            (ColumnWriter::DoubleColumnWriter(writer), LeafValues::Double(values)) => {
+                writer.write_batch(values, definitions, repetitions)?
+            }
+            (ColumnWriter::ByteArrayColumnWriter(writer), LeafValues::Bytes(values)) => {
+                writer.write_batch(values, definitions, repetitions)?
+            }
+            _ => {
+                return Err(anyhow!(
+                    "the schema does not match the {:?} column generated",
+                    self.leaf
+                ))
+            }
+        };
+
+        Ok(column_writer)
+    }
+}
+
+/// The physical value of an integer `number` in a column of `leaf`, or `None` if `number` is not
+/// an integer. Unsigned integers are stored bit for bit, which readers know from the `UINT_32` and
+/// `UINT_64` logical types of their columns, and numbers which don't fit the column are an error.
+fn as_i64(number: &Number, leaf: Leaf) -> Option<Result<i64>> {
+    let integer = match *number {
+        Number::I8(i) => Some(i as i128),
+        Number::I16(i) => Some(i as i128),
+        Number::I32(i) => Some(i as i128),
+        Number::I64(i) => Some(i as i128),
+        Number::I128(i) => Some(i),
+        Number::U8(u) => Some(u as i128),
+        Number::U16(u) => Some(u as i128),
+        Number::U32(u) => Some(u as i128),
+        Number::U64(u) => Some(u as i128),
+        Number::U128(u) => i128::try_from(u).ok(),
+        Number::F32(_) | Number::F64(_) => return None,
+    };
+
+    let (low, high) = match leaf {
+        Leaf::Int {
+            bits,
+            signed: false,
+        } if bits <= 32 => (0, u32::MAX as i128),
+        Leaf::Int { signed: false, .. } => (0, u64::MAX as i128),
+        Leaf::Int { bits, .. } if bits <= 32 => (i32::MIN as i128, i32::MAX as i128),
+        _ => (i64::MIN as i128, i64::MAX as i128),
+    };
+
+    Some(
+        integer
+            .filter(|integer| (low..=high).contains(integer))
+            .map(|integer| integer as i64)
+            .ok_or_else(|| anyhow!("{} does not fit in a {:?} column", number, leaf)),
+    )
+}
+
+fn as_f64(number: &Number) -> f64 {
+    match *number {
+        Number::I8(i) => i as f64,
+        Number::I16(i) => i as f64,
+        Number::I32(i) => i as f64,
+        Number::I64(i) => i as f64,
+        Number::I128(i) => i as f64,
+        Number::U8(u) => u as f64,
+        Number::U16(u) => u as f64,
+        Number::U32(u) => u as f64,
+        Number::U64(u) => u as f64,
+        Number::U128(u) => u as f64,
+        Number::F32(f) => f.into_inner() as f64,
+        Number::F64(f) => f.into_inner(),
+    }
+}
+
+fn days_since_epoch(date: &NaiveDate) -> i32 {
+    date.signed_duration_since(NaiveDate::from_ymd(1970, 1, 1))
+        .num_days() as i32
+}
+
+fn micros_since_midnight(time: &NaiveTime) -> i64 {
+    time.num_seconds_from_midnight() as i64 * 1_000_000 + (time.nanosecond() / 1_000) as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_integers_in_columns() {
+        let unsigned = Leaf::Int {
+            bits: 64,
+            signed: false,
+        };
+        let signed = Leaf::Int {
+            bits: 64,
+            signed: true,
+        };
+        let int = Leaf::Int {
+            bits: 32,
+            signed: true,
+        };
+
+        // Stored bit for bit in a UINT_64 column
+        assert_eq!(
+            as_i64(&Number::U64(u64::MAX), unsigned).unwrap().unwrap(),
+            -1
+        );
+        assert_eq!(as_i64(&Number::I64(-1), signed).unwrap().unwrap(), -1);
+        assert_eq!(as_i64(&Number::U128(42), int).unwrap().unwrap(), 42);
+
+        assert!(as_i64(&Number::U64(u64::MAX), signed).unwrap().is_err());
+        assert!(as_i64(&Number::I64(-1), unsigned).unwrap().is_err());
+        assert!(as_i64(&Number::I128(i128::MAX), signed).unwrap().is_err());
+        assert!(as_i64(&Number::U128(u128::MAX), unsigned).unwrap().is_err());
+        assert!(as_i64(&Number::I64(1 << 40), int).unwrap().is_err());
+        assert!(as_i64(&Number::from(1.5), signed).is_none());
+    }
+}
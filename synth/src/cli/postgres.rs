@@ -1,9 +1,11 @@
-use crate::cli::export::{create_and_insert_values, ExportParams, ExportStrategy};
+use crate::cli::export::{create_and_insert_values, ExportOutput, ExportParams, ExportStrategy};
 use crate::cli::import::ImportStrategy;
 use crate::cli::import_utils::build_namespace_import;
-use crate::datasource::postgres_datasource::{PostgresConnectParams, PostgresDataSource};
+use crate::datasource::postgres_datasource::{
+    PostgresConnectParams, PostgresDataSource, PostgresLoad,
+};
+use crate::datasource::relational_datasource::Sampling;
 use crate::datasource::DataSource;
-use crate::sampler::SamplerOutput;
 use anyhow::Result;
 use synth_core::schema::Namespace;
 
@@ -11,13 +13,15 @@ use synth_core::schema::Namespace;
 pub struct PostgresExportStrategy {
     pub uri_string: String,
     pub schema: Option<String>,
+    pub load: PostgresLoad,
 }
 
 impl ExportStrategy for PostgresExportStrategy {
-    fn export(&self, params: ExportParams) -> Result<SamplerOutput> {
+    fn export(&self, params: ExportParams) -> Result<ExportOutput> {
         let connect_params = PostgresConnectParams {
             uri: self.uri_string.clone(),
             schema: self.schema.clone(),
+            load: self.load,
         };
 
         let datasource = PostgresDataSource::new(&connect_params)?;
@@ -30,6 +34,7 @@ impl ExportStrategy for PostgresExportStrategy {
 pub struct PostgresImportStrategy {
     pub uri_string: String,
     pub schema: Option<String>,
+    pub sampling: Sampling,
 }
 
 impl ImportStrategy for PostgresImportStrategy {
@@ -37,10 +42,11 @@ impl ImportStrategy for PostgresImportStrategy {
         let connect_params = PostgresConnectParams {
             uri: self.uri_string.clone(),
             schema: self.schema.clone(),
+            load: PostgresLoad::Insert,
         };
 
         let datasource = PostgresDataSource::new(&connect_params)?;
 
-        build_namespace_import(&datasource)
+        build_namespace_import::<PostgresDataSource>(&datasource, &self.sampling)
     }
 }
@@ -0,0 +1,936 @@
+use crate::cli::import_utils::{set_foreign_key, set_primary_key, set_unique_key, Collection};
+use crate::datasource::check::{apply_checks, categorical};
+use crate::datasource::relational_datasource::{ColumnInfo, ForeignKey};
+use crate::datasource::{mysql_datasource, postgres_datasource, sqlite_datasource};
+
+use super::SqlDialect;
+
+use anyhow::{Context, Result};
+use log::debug;
+
+use sqlparser::ast::{
+    AlterTableOperation, ColumnDef, ColumnOption, DataType, Expr, Ident, ObjectName, Statement,
+    TableConstraint,
+};
+use sqlparser::dialect::{Dialect, MySqlDialect, PostgreSqlDialect, SQLiteDialect};
+use sqlparser::keywords::Keyword;
+use sqlparser::parser::Parser;
+use sqlparser::tokenizer::{Token, Tokenizer};
+
+use synth_core::{Content, Namespace};
+
+use std::collections::{BTreeMap, HashMap};
+
+impl SqlDialect {
+    fn parser_dialect(&self) -> Box<dyn Dialect> {
+        match self {
+            Self::Postgres => Box::new(PostgreSqlDialect {}),
+            Self::MySql => Box::new(MySqlDialect {}),
+            Self::Sqlite => Box::new(SQLiteDialect {}),
+        }
+    }
+
+    /// Postgres folds unquoted identifiers to lower case, the other databases keep them as they
+    /// are written.
+    fn identifier(&self, ident: &Ident) -> String {
+        match (self, ident.quote_style) {
+            (Self::Postgres, None) => ident.value.to_lowercase(),
+            _ => ident.value.clone(),
+        }
+    }
+
+    fn table_name(&self, name: &ObjectName) -> Result<String> {
+        name.0
+            .last()
+            .map(|ident| self.identifier(ident))
+            .ok_or_else(|| anyhow!("a table name is empty"))
+    }
+
+    /// The type of a column as the database reports it when importing from a live database, so
+    /// that it can be decoded the same way.
+    fn column_type(&self, data_type: &DataType) -> Result<ColumnType> {
+        match self {
+            Self::Postgres => postgres_column_type(data_type),
+            Self::MySql => mysql_column_type(data_type),
+            Self::Sqlite => Ok(sqlite_column_type(data_type)),
+        }
+    }
+
+    fn decode_column(&self, column_info: &ColumnInfo) -> Result<Content> {
+        match self {
+            Self::Postgres => postgres_datasource::decode_column(column_info),
+            Self::MySql => mysql_datasource::decode_column(column_info),
+            Self::Sqlite => sqlite_datasource::decode_column(column_info),
+        }
+    }
+}
+
+struct ColumnType {
+    data_type: String,
+    character_maximum_length: Option<i32>,
+    is_custom_type: bool,
+    /// The values of an enum type, if they are part of the script
+    variants: Option<Vec<String>>,
+}
+
+impl ColumnType {
+    fn new(data_type: &str, character_maximum_length: Option<u64>) -> Self {
+        Self {
+            data_type: data_type.to_string(),
+            character_maximum_length: character_maximum_length.map(|length| length as i32),
+            is_custom_type: false,
+            variants: None,
+        }
+    }
+}
+
+/// The names of the Postgres built in types a column can have, as they appear in `udt_name`.
+const POSTGRES_BUILTIN_TYPES: &[&str] = &[
+    "bool",
+    "bpchar",
+    "bytea",
+    "char",
+    "cidr",
+    "citext",
+    "date",
+    "float4",
+    "float8",
+    "inet",
+    "int2",
+    "int4",
+    "int8",
+    "interval",
+    "json",
+    "jsonb",
+    "macaddr",
+    "money",
+    "name",
+    "numeric",
+    "oid",
+    "regclass",
+    "text",
+    "time",
+    "timestamp",
+    "timestamptz",
+    "timetz",
+    "uuid",
+    "varchar",
+    "xml",
+];
+
+fn postgres_column_type(data_type: &DataType) -> Result<ColumnType> {
+    let column_type = match data_type {
+        DataType::Char(length) => ColumnType::new("bpchar", *length),
+        DataType::Varchar(length) => ColumnType::new("varchar", *length),
+        DataType::Uuid => ColumnType::new("uuid", None),
+        DataType::Clob(_) | DataType::Text | DataType::String => ColumnType::new("text", None),
+        DataType::Binary(_) | DataType::Varbinary(_) | DataType::Blob(_) | DataType::Bytea => {
+            ColumnType::new("bytea", None)
+        }
+        DataType::Decimal(_, _) => ColumnType::new("numeric", None),
+        DataType::Float(Some(precision)) if *precision <= 24 => ColumnType::new("float4", None),
+        DataType::Float(_) | DataType::Double => ColumnType::new("float8", None),
+        DataType::Real => ColumnType::new("float4", None),
+        DataType::TinyInt(_) | DataType::SmallInt(_) => ColumnType::new("int2", None),
+        DataType::Int(_) => ColumnType::new("int4", None),
+        DataType::BigInt(_) => ColumnType::new("int8", None),
+        DataType::Boolean => ColumnType::new("bool", None),
+        DataType::Date => ColumnType::new("date", None),
+        DataType::Time => ColumnType::new("time", None),
+        DataType::Timestamp => ColumnType::new("timestamp", None),
+        DataType::Interval => ColumnType::new("interval", None),
+        DataType::Regclass => ColumnType::new("regclass", None),
+        DataType::Array(element) => {
+            let element = postgres_column_type(element)?;
+            ColumnType::new(&format!("_{}", element.data_type), None)
+        }
+        DataType::Custom(name) => {
+            let name = name.to_string().to_lowercase();
+            let data_type = postgres_type_alias(name.rsplit('.').next().unwrap_or_default());
+            ColumnType {
+                is_custom_type: !POSTGRES_BUILTIN_TYPES
+                    .contains(&data_type.trim_start_matches('_')),
+                ..ColumnType::new(&data_type, None)
+            }
+        }
+        DataType::Enum(_) | DataType::Set(_) => {
+            bail!("{} is not a Postgres data type", data_type)
+        }
+    };
+
+    Ok(column_type)
+}
+
+/// The `udt_name` of Postgres types which aren't parsed as one of the common SQL data types.
+fn postgres_type_alias(name: &str) -> String {
+    match name {
+        "int" | "integer" | "serial" | "serial4" => "int4".to_string(),
+        "smallint" | "smallserial" | "serial2" => "int2".to_string(),
+        "bigint" | "bigserial" | "serial8" => "int8".to_string(),
+        "boolean" => "bool".to_string(),
+        "real" => "float4".to_string(),
+        "decimal" => "numeric".to_string(),
+        "character" => "bpchar".to_string(),
+        _ => match name.strip_prefix('_') {
+            Some(element) => format!("_{}", postgres_type_alias(element)),
+            None => name.to_string(),
+        },
+    }
+}
+
+fn mysql_column_type(data_type: &DataType) -> Result<ColumnType> {
+    let column_type = match data_type {
+        DataType::Char(length) => ColumnType::new("char", *length),
+        DataType::Varchar(length) => ColumnType::new("varchar", *length),
+        DataType::Uuid => ColumnType::new("char", Some(36)),
+        DataType::Clob(_) | DataType::Text | DataType::String => ColumnType::new("text", None),
+        DataType::Binary(length) => ColumnType::new("binary", Some(*length)),
+        DataType::Varbinary(length) => ColumnType::new("varbinary", Some(*length)),
+        DataType::Blob(_) | DataType::Bytea => ColumnType::new("blob", None),
+        DataType::Decimal(_, _) => ColumnType::new("decimal", None),
+        DataType::Float(_) => ColumnType::new("float", None),
+        DataType::Real | DataType::Double => ColumnType::new("double", None),
+        DataType::TinyInt(_) | DataType::Boolean => ColumnType::new("tinyint", None),
+        DataType::SmallInt(_) => ColumnType::new("smallint", None),
+        DataType::Int(_) => ColumnType::new("int", None),
+        DataType::BigInt(_) => ColumnType::new("bigint", None),
+        DataType::Date => ColumnType::new("date", None),
+        DataType::Time => ColumnType::new("time", None),
+        DataType::Timestamp => ColumnType::new("timestamp", None),
+        DataType::Enum(variants) => ColumnType {
+            variants: Some(variants.clone()),
+            ..ColumnType::new("enum", None)
+        },
+        DataType::Set(_) => ColumnType::new("set", None),
+        DataType::Custom(name) => match name.to_string().to_lowercase().as_str() {
+            "bool" => ColumnType::new("tinyint", None),
+            "integer" => ColumnType::new("int", None),
+            other => ColumnType::new(other, None),
+        },
+        DataType::Interval | DataType::Regclass | DataType::Array(_) => {
+            bail!("{} is not a MySQL data type", data_type)
+        }
+    };
+
+    Ok(column_type)
+}
+
+/// SQLite columns are decoded from their declared type, without the size in parentheses.
+fn sqlite_column_type(data_type: &DataType) -> ColumnType {
+    let declared = data_type.to_string();
+    let declared = declared.split('(').next().unwrap_or_default().trim();
+
+    let character_maximum_length = match data_type {
+        DataType::Char(length) | DataType::Varchar(length) => *length,
+        _ => None,
+    };
+
+    ColumnType::new(declared, character_maximum_length)
+}
+
+/// The columns and constraints of a table, collected from the `CREATE TABLE` statement that
+/// creates it and the `ALTER TABLE` statements that follow.
+#[derive(Default)]
+struct Table {
+    columns: Vec<ColumnDef>,
+    constraints: Vec<TableConstraint>,
+}
+
+/// Builds a namespace with one collection per table created by the DDL statements of `script`,
+/// as importing from a database created by running it would. Statements other than `CREATE
+/// TABLE`, `ALTER TABLE` and Postgres' `CREATE TYPE ... AS ENUM` are skipped.
+pub(crate) fn import_ddl(script: &str, dialect: SqlDialect) -> Result<Namespace> {
+    let parser_dialect = dialect.parser_dialect();
+    let tokens = Tokenizer::new(parser_dialect.as_ref(), script)
+        .tokenize()
+        .map_err(|e| anyhow!("Failed to tokenize the SQL script: {}", e.message))?;
+
+    let mut tables = BTreeMap::<String, Table>::new();
+    let mut enums = HashMap::<String, Vec<String>>::new();
+
+    for statement in split_statements(tokens) {
+        if let Some((name, variants)) = parse_enum_type(&statement) {
+            enums.insert(name, variants);
+            continue;
+        }
+
+        let is_table_statement = is_table_statement(&statement);
+        let statement = rewrite_types(statement, dialect);
+        let parsed = Parser::new(statement.clone(), parser_dialect.as_ref()).parse_statement();
+
+        match parsed {
+            Ok(Statement::CreateTable {
+                name,
+                columns,
+                constraints,
+                ..
+            }) => {
+                let table = tables.entry(dialect.table_name(&name)?).or_default();
+                table.columns.extend(columns);
+                table.constraints.extend(constraints);
+            }
+            Ok(Statement::AlterTable { name, operation }) => {
+                let table_name = dialect.table_name(&name)?;
+                let table = tables.get_mut(&table_name).ok_or_else(|| {
+                    anyhow!("Cannot alter table '{}' before it is created", table_name)
+                })?;
+                match operation {
+                    AlterTableOperation::AddConstraint(constraint) => {
+                        table.constraints.push(constraint)
+                    }
+                    AlterTableOperation::AddColumn { column_def } => table.columns.push(column_def),
+                    _ => debug!(
+                        "Skipping statement: ALTER TABLE {} {}",
+                        table_name, operation
+                    ),
+                }
+            }
+            Ok(other) => debug!("Skipping statement: {}", other),
+            Err(e) if is_table_statement => {
+                let text = statement.iter().map(Token::to_string).collect::<String>();
+                return Err(anyhow!("{}", e))
+                    .with_context(|| format!("Failed to parse '{}'", text));
+            }
+            Err(e) => debug!("Skipping statement which could not be parsed: {}", e),
+        }
+    }
+
+    let mut namespace = Namespace::default();
+    let mut primary_keys = Vec::new();
+    let mut foreign_keys = Vec::new();
+    let mut unique_keys = Vec::new();
+
+    for (table_name, table) in &tables {
+        info!("Building {} collection...", table_name);
+
+        let keys = TableKeys::new(table, dialect);
+
+        let column_infos = table
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(idx, column)| {
+                let name = dialect.identifier(&column.name);
+                let column_type = dialect.column_type(&column.data_type)?;
+                Ok(ColumnInfo {
+                    is_nullable: !keys.primary_key.contains(&name)
+                        && !column
+                            .options
+                            .iter()
+                            .any(|option| matches!(option.option, ColumnOption::NotNull)),
+                    column_name: name,
+                    ordinal_position: idx as i32 + 1,
+                    is_custom_type: column_type.is_custom_type,
+                    data_type: column_type.data_type,
+                    character_maximum_length: column_type.character_maximum_length,
+                    column_default: column
+                        .options
+                        .iter()
+                        .find_map(|option| match &option.option {
+                            ColumnOption::Default(expr) => Some(expr.to_string()),
+                            _ => None,
+                        }),
+                    // Enums and checks are applied to the content the column is decoded to below
+                    enum_labels: Vec::new(),
+                    checks: Vec::new(),
+                    is_unsigned: false,
+                    is_generated: false,
+                })
+            })
+            .collect::<Result<Vec<_>>>()
+            .with_context(|| format!("in table '{}'", table_name))?;
+
+        let collection = Collection::from_columns(column_infos, |column_info| {
+            let mut content = dialect.decode_column(column_info)?;
+
+            let column = &table.columns[column_info.ordinal_position as usize - 1];
+            let variants = match &column.data_type {
+                DataType::Custom(name) => enums.get(&dialect.table_name(name)?).cloned(),
+                data_type => dialect.column_type(data_type)?.variants,
+            };
+            if let Some(variants) = variants {
+                content = categorical(variants);
+            }
+
+            apply_checks(
+                &mut content,
+                &column_info.column_name,
+                keys.checks.iter().copied(),
+            );
+
+            Ok(content)
+        })
+        .with_context(|| format!("in table '{}'", table_name))?;
+
+        namespace.put_collection(table_name.clone(), collection.collection)?;
+
+        if !keys.primary_key.is_empty() {
+            primary_keys.push((table_name.clone(), keys.primary_key));
+        }
+
+        for (from_columns, to_table, to_columns) in keys.foreign_keys {
+            foreign_keys.push((table_name.clone(), from_columns, to_table, to_columns));
+        }
+
+        for columns in keys.unique_keys {
+            unique_keys.push((table_name.clone(), columns));
+        }
+    }
+
+    info!("Building namespace primary keys...");
+    for (table_name, columns) in &primary_keys {
+        set_primary_key(&mut namespace, table_name, columns)?;
+    }
+
+    info!("Building namespace foreign keys...");
+    for (from_table, from_columns, to_table, to_columns) in foreign_keys {
+        // A foreign key which doesn't name the columns it references references the primary key
+        let to_columns = if to_columns.is_empty() {
+            primary_keys
+                .iter()
+                .find(|(table_name, _)| *table_name == to_table)
+                .map(|(_, columns)| columns.clone())
+                .ok_or_else(|| {
+                    anyhow!(
+                        "The foreign key on {}.({}) references table '{}', which has no primary key",
+                        from_table,
+                        from_columns.join(", "),
+                        to_table
+                    )
+                })?
+        } else {
+            to_columns
+        };
+        if to_columns.len() != from_columns.len() {
+            bail!(
+                "The foreign key on {}.({}) references {} columns of table '{}'",
+                from_table,
+                from_columns.join(", "),
+                to_columns.len(),
+                to_table
+            )
+        }
+
+        let constraint_name = format!("{}_{}_fkey", from_table, from_columns.join("_"));
+        let fk = from_columns
+            .into_iter()
+            .zip(to_columns)
+            .map(|(from_column, to_column)| ForeignKey {
+                from_table: from_table.clone(),
+                from_column,
+                to_table: to_table.clone(),
+                to_column,
+                constraint_name: constraint_name.clone(),
+            })
+            .collect::<Vec<_>>();
+        set_foreign_key(&mut namespace, &fk)?;
+    }
+
+    info!("Building namespace unique keys...");
+    for (table_name, columns) in unique_keys {
+        set_unique_key(&mut namespace, &table_name, columns)?;
+    }
+
+    Ok(namespace)
+}
+
+/// The keys and checks of a table, from the options of its columns and its table constraints.
+struct TableKeys<'t> {
+    primary_key: Vec<String>,
+    /// The columns of each unique constraint
+    unique_keys: Vec<Vec<String>>,
+    /// The columns of each foreign key, the table it references and the columns it references if
+    /// they are named
+    foreign_keys: Vec<(Vec<String>, String, Vec<String>)>,
+    checks: Vec<&'t Expr>,
+}
+
+impl<'t> TableKeys<'t> {
+    fn new(table: &'t Table, dialect: SqlDialect) -> Self {
+        let mut keys = Self {
+            primary_key: Vec::new(),
+            unique_keys: Vec::new(),
+            foreign_keys: Vec::new(),
+            checks: Vec::new(),
+        };
+
+        for column in &table.columns {
+            let name = dialect.identifier(&column.name);
+            for option in &column.options {
+                match &option.option {
+                    ColumnOption::Unique { is_primary: true } => {
+                        keys.primary_key.push(name.clone())
+                    }
+                    ColumnOption::Unique { is_primary: false } => {
+                        keys.unique_keys.push(vec![name.clone()])
+                    }
+                    ColumnOption::ForeignKey {
+                        foreign_table,
+                        referred_columns,
+                        ..
+                    } => {
+                        if let Ok(to_table) = dialect.table_name(foreign_table) {
+                            keys.foreign_keys.push((
+                                vec![name.clone()],
+                                to_table,
+                                referred_columns
+                                    .iter()
+                                    .map(|c| dialect.identifier(c))
+                                    .collect(),
+                            ));
+                        }
+                    }
+                    ColumnOption::Check(expr) => keys.checks.push(expr),
+                    _ => {}
+                }
+            }
+        }
+
+        for constraint in &table.constraints {
+            match constraint {
+                TableConstraint::Unique {
+                    columns,
+                    is_primary: true,
+                    ..
+                } => keys
+                    .primary_key
+                    .extend(columns.iter().map(|c| dialect.identifier(c))),
+                TableConstraint::Unique {
+                    columns,
+                    is_primary: false,
+                    ..
+                } => keys
+                    .unique_keys
+                    .push(columns.iter().map(|c| dialect.identifier(c)).collect()),
+                TableConstraint::ForeignKey {
+                    columns,
+                    foreign_table,
+                    referred_columns,
+                    ..
+                } => {
+                    if let Ok(to_table) = dialect.table_name(foreign_table) {
+                        keys.foreign_keys.push((
+                            columns.iter().map(|c| dialect.identifier(c)).collect(),
+                            to_table,
+                            referred_columns
+                                .iter()
+                                .map(|c| dialect.identifier(c))
+                                .collect(),
+                        ));
+                    }
+                }
+                TableConstraint::Check { expr, .. } => keys.checks.push(expr),
+            }
+        }
+
+        keys
+    }
+}
+
+/// Splits the tokens of a script into statements, without the semicolons and the whitespace
+/// around them.
+fn split_statements(tokens: Vec<Token>) -> Vec<Vec<Token>> {
+    tokens
+        .split(|token| *token == Token::SemiColon)
+        .map(|statement| {
+            let start = statement
+                .iter()
+                .position(|token| !matches!(token, Token::Whitespace(_)))
+                .unwrap_or(statement.len());
+            let end = statement
+                .iter()
+                .rposition(|token| !matches!(token, Token::Whitespace(_)))
+                .map(|idx| idx + 1)
+                .unwrap_or(start);
+            statement[start..end.max(start)].to_vec()
+        })
+        .filter(|statement| !statement.is_empty())
+        .collect()
+}
+
+fn keywords(statement: &[Token]) -> impl Iterator<Item = Keyword> + '_ {
+    statement.iter().filter_map(|token| match token {
+        Token::Word(word) => Some(word.keyword),
+        Token::Whitespace(_) => None,
+        _ => Some(Keyword::NoKeyword),
+    })
+}
+
+/// Whether `statement` is a `CREATE TABLE` or `ALTER TABLE` statement, which have to be parsed
+/// for the namespace to be complete.
+fn is_table_statement(statement: &[Token]) -> bool {
+    let mut keywords = keywords(statement)
+        .filter(|keyword| !matches!(keyword, Keyword::OR | Keyword::REPLACE | Keyword::TEMPORARY));
+    matches!(
+        (keywords.next(), keywords.next()),
+        (Some(Keyword::CREATE), Some(Keyword::TABLE))
+            | (Some(Keyword::ALTER), Some(Keyword::TABLE))
+    )
+}
+
+/// Parses Postgres' `CREATE TYPE <name> AS ENUM (<values>)`, which isn't supported by the SQL
+/// parser.
+fn parse_enum_type(statement: &[Token]) -> Option<(String, Vec<String>)> {
+    let tokens = statement
+        .iter()
+        .filter(|token| !matches!(token, Token::Whitespace(_)))
+        .collect::<Vec<_>>();
+
+    match tokens.as_slice() {
+        [Token::Word(create), Token::Word(type_), rest @ ..]
+            if create.keyword == Keyword::CREATE && type_.keyword == Keyword::TYPE =>
+        {
+            let as_idx = rest.iter().position(
+                |token| matches!(token, Token::Word(word) if word.keyword == Keyword::AS),
+            )?;
+            let name = match rest.get(as_idx.checked_sub(1)?)? {
+                Token::Word(word) if word.quote_style.is_some() => word.value.clone(),
+                Token::Word(word) => word.value.to_lowercase(),
+                _ => return None,
+            };
+            match rest.get(as_idx + 1)? {
+                Token::Word(word) if word.keyword == Keyword::ENUM => {}
+                _ => return None,
+            }
+            let variants = rest[as_idx + 2..]
+                .iter()
+                .filter_map(|token| match token {
+                    Token::SingleQuotedString(value) => Some(value.clone()),
+                    _ => None,
+                })
+                .collect();
+            Some((name, variants))
+        }
+        _ => None,
+    }
+}
+
+/// Rewrites the Postgres types the SQL parser doesn't support into the names Postgres gives them
+/// internally: `TIMESTAMP WITH TIME ZONE` becomes `timestamptz` and arrays like `integer[]` become
+/// `_integer`.
+fn rewrite_types(statement: Vec<Token>, dialect: SqlDialect) -> Vec<Token> {
+    if dialect != SqlDialect::Postgres {
+        return statement;
+    }
+
+    let mut rewritten: Vec<Token> = Vec::with_capacity(statement.len());
+    for token in statement {
+        match token {
+            Token::RBracket => {
+                let element = rewritten
+                    .iter()
+                    .rposition(|token| !matches!(token, Token::Whitespace(_)));
+                if let Some(idx) = element {
+                    if rewritten[idx] == Token::LBracket {
+                        rewritten.truncate(idx);
+                        let element = rewritten
+                            .iter()
+                            .rposition(|token| !matches!(token, Token::Whitespace(_)));
+                        if let Some(idx) = element {
+                            if let Token::Word(word) = &rewritten[idx] {
+                                rewritten[idx] =
+                                    Token::make_word(&format!("_{}", word.value), None);
+                                continue;
+                            }
+                        }
+                        rewritten.push(Token::LBracket);
+                    }
+                }
+                rewritten.push(Token::RBracket);
+            }
+            Token::Word(word) if word.keyword == Keyword::ZONE => {
+                let words = rewritten
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, token)| !matches!(token, Token::Whitespace(_)))
+                    .map(|(idx, _)| idx)
+                    .collect::<Vec<_>>();
+                let with_time_zone = match words.as_slice() {
+                    [.., type_, with, time] => {
+                        match (&rewritten[*type_], &rewritten[*with], &rewritten[*time]) {
+                            (
+                                Token::Word(type_word),
+                                Token::Word(with_word),
+                                Token::Word(time_word),
+                            ) if with_word.keyword == Keyword::WITH
+                                && time_word.keyword == Keyword::TIME =>
+                            {
+                                match type_word.keyword {
+                                    Keyword::TIMESTAMP => Some((*type_, "timestamptz")),
+                                    Keyword::TIME => Some((*type_, "timetz")),
+                                    _ => None,
+                                }
+                            }
+                            _ => None,
+                        }
+                    }
+                    _ => None,
+                };
+                match with_time_zone {
+                    Some((idx, name)) => {
+                        rewritten.truncate(idx);
+                        rewritten.push(Token::make_word(name, None));
+                    }
+                    None => rewritten.push(Token::Word(word)),
+                }
+            }
+            token => rewritten.push(token),
+        }
+    }
+
+    rewritten
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use synth_core::schema::content::number_content::{F64, I32};
+    use synth_core::schema::{
+        ChronoValueType, FieldRef, LookupContent, NumberContent, SameAsContent, SameAsSample,
+        StringContent,
+    };
+
+    fn field<'a>(namespace: &'a Namespace, path: &str) -> &'a Content {
+        namespace.get_s_node(&FieldRef::new(path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_import_postgres_ddl() {
+        let namespace = import_ddl(
+            r#"
+            CREATE TYPE mood AS ENUM ('sad', 'happy');
+            CREATE TABLE Users (
+                id SERIAL PRIMARY KEY,
+                email VARCHAR(255) NOT NULL UNIQUE,
+                age INTEGER NOT NULL CHECK (age >= 18 AND age < 120),
+                current_mood mood NOT NULL,
+                created_at TIMESTAMP WITH TIME ZONE NOT NULL,
+                tags TEXT[]
+            );
+            CREATE TABLE orders (
+                id bigint NOT NULL,
+                user_id integer NOT NULL,
+                status varchar(10) NOT NULL CHECK (status IN ('new', 'paid'))
+            );
+            CREATE INDEX orders_user ON orders (user_id);
+            ALTER TABLE orders ADD PRIMARY KEY (id);
+            ALTER TABLE orders ADD CONSTRAINT orders_user_fk
+                FOREIGN KEY (user_id) REFERENCES users (id);
+            "#,
+            SqlDialect::Postgres,
+        )
+        .unwrap();
+
+        assert!(matches!(
+            field(&namespace, "users.content.id"),
+            Content::Number(NumberContent::I32(I32::Id(_)))
+        ));
+        match field(&namespace, "users.content.email") {
+            Content::Unique(unique) => assert!(matches!(
+                unique.content.as_ref(),
+                Content::String(StringContent::Pattern(_))
+            )),
+            otherwise => panic!("expected unique content, found {}", otherwise),
+        }
+        match field(&namespace, "users.content.age") {
+            Content::Number(NumberContent::I32(I32::Range(range))) => {
+                assert_eq!(range.low, Some(18));
+                assert_eq!(range.high, Some(120));
+            }
+            otherwise => panic!("expected an i32 range, found {}", otherwise),
+        }
+        assert_eq!(
+            field(&namespace, "users.content.current_mood"),
+            &categorical(vec!["sad".to_string(), "happy".to_string()])
+        );
+        assert!(matches!(
+            field(&namespace, "users.content.created_at"),
+            Content::DateTime(date_time) if date_time.type_ == ChronoValueType::DateTime
+        ));
+        assert!(matches!(
+            field(&namespace, "users.content.tags"),
+            Content::OneOf(_)
+        ));
+        assert_eq!(
+            field(&namespace, "orders.content.user_id"),
+            &Content::SameAs(SameAsContent {
+                ref_: FieldRef::new("users.content.id").unwrap(),
+                sample: None,
+            })
+        );
+        assert_eq!(
+            field(&namespace, "orders.content.status"),
+            &categorical(vec!["new".to_string(), "paid".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_import_sqlite_ddl() {
+        let namespace = import_ddl(
+            "CREATE TABLE users (id INTEGER PRIMARY KEY, name VARCHAR(20));
+            CREATE TABLE orders (
+                id INTEGER PRIMARY KEY,
+                user_id INTEGER NOT NULL REFERENCES users,
+                total NUMERIC NOT NULL CHECK (total > 0 AND total <= 100)
+            );",
+            SqlDialect::Sqlite,
+        )
+        .unwrap();
+
+        assert!(matches!(
+            field(&namespace, "users.content.name"),
+            Content::OneOf(_)
+        ));
+        match field(&namespace, "orders.content.total") {
+            Content::Number(NumberContent::F64(F64::Range(range))) => {
+                // The exclusive lower bound is left out by starting at the next float
+                assert!(range.low.unwrap() > 0.0);
+                assert!(range.low.unwrap() < f64::EPSILON);
+                assert!(range.include_low);
+                assert_eq!(range.high, Some(100.0));
+                assert!(range.include_high);
+            }
+            otherwise => panic!("expected an f64 range, found {}", otherwise),
+        }
+        assert!(matches!(
+            field(&namespace, "orders.content.user_id"),
+            Content::SameAs(_)
+        ));
+    }
+
+    #[test]
+    fn test_import_ddl_with_composite_keys() {
+        let namespace = import_ddl(
+            "CREATE TABLE orders (
+                id INTEGER NOT NULL,
+                region CHAR(2) NOT NULL,
+                code CHAR(4) NOT NULL,
+                PRIMARY KEY (id, region),
+                UNIQUE (region, code)
+            );
+            CREATE TABLE products (id SERIAL PRIMARY KEY);
+            CREATE TABLE order_products (
+                order_id INTEGER NOT NULL,
+                order_region CHAR(2) NOT NULL,
+                product_id INTEGER NOT NULL REFERENCES products (id),
+                PRIMARY KEY (order_id, order_region, product_id),
+                FOREIGN KEY (order_id, order_region) REFERENCES orders (id, region)
+            );",
+            SqlDialect::Postgres,
+        )
+        .unwrap();
+
+        let unique_together = |path| match field(&namespace, path) {
+            Content::Object(object) => object.unique_together.clone(),
+            otherwise => panic!("expected an object, found {}", otherwise),
+        };
+        assert_eq!(
+            unique_together("orders.content"),
+            vec![
+                vec!["id".to_string(), "region".to_string()],
+                vec!["region".to_string(), "code".to_string()]
+            ]
+        );
+        // The columns of a composite unique key don't have to be unique on their own
+        assert!(matches!(
+            field(&namespace, "orders.content.code"),
+            Content::String(_)
+        ));
+        assert_eq!(
+            unique_together("order_products.content"),
+            vec![vec![
+                "order_id".to_string(),
+                "order_region".to_string(),
+                "product_id".to_string()
+            ]]
+        );
+
+        assert_eq!(
+            field(&namespace, "order_products.content.orders"),
+            &Content::Lookup(LookupContent {
+                ref_: FieldRef::new("orders.content").unwrap(),
+                fields: vec![
+                    ("order_id".to_string(), "id".to_string()),
+                    ("order_region".to_string(), "region".to_string()),
+                ]
+                .into_iter()
+                .collect(),
+                sample: Some(SameAsSample::Uniform),
+            })
+        );
+        assert_eq!(
+            field(&namespace, "order_products.content.product_id"),
+            &Content::SameAs(SameAsContent {
+                ref_: FieldRef::new("products.content.id").unwrap(),
+                sample: Some(SameAsSample::Uniform),
+            })
+        );
+        assert!(namespace
+            .get_s_node(&FieldRef::new("order_products.content.order_id").unwrap())
+            .is_err());
+    }
+
+    #[test]
+    fn test_import_ddl_with_overlapping_foreign_keys() {
+        let namespace = import_ddl(
+            "CREATE TABLE tenants (id INTEGER PRIMARY KEY);
+            CREATE TABLE users (
+                tenant_id INTEGER NOT NULL REFERENCES tenants (id),
+                id INTEGER NOT NULL,
+                PRIMARY KEY (tenant_id, id)
+            );
+            CREATE TABLE orgs (
+                tenant_id INTEGER NOT NULL REFERENCES tenants (id),
+                id INTEGER NOT NULL,
+                PRIMARY KEY (tenant_id, id)
+            );
+            CREATE TABLE memberships (
+                tenant_id INTEGER NOT NULL,
+                user_id INTEGER NOT NULL,
+                org_id INTEGER NOT NULL,
+                FOREIGN KEY (tenant_id, user_id) REFERENCES users (tenant_id, id),
+                FOREIGN KEY (tenant_id, org_id) REFERENCES orgs (tenant_id, id),
+                FOREIGN KEY (tenant_id) REFERENCES tenants (id)
+            );",
+            SqlDialect::Postgres,
+        )
+        .unwrap();
+
+        // The tenant comes from the user, and only the org of the second foreign key is left
+        assert_eq!(
+            field(&namespace, "memberships.content.users"),
+            &Content::Lookup(LookupContent {
+                ref_: FieldRef::new("users.content").unwrap(),
+                fields: vec![
+                    ("tenant_id".to_string(), "tenant_id".to_string()),
+                    ("user_id".to_string(), "id".to_string()),
+                ]
+                .into_iter()
+                .collect(),
+                sample: None,
+            })
+        );
+        assert_eq!(
+            field(&namespace, "memberships.content.org_id"),
+            &Content::SameAs(SameAsContent {
+                ref_: FieldRef::new("orgs.content.id").unwrap(),
+                sample: None,
+            })
+        );
+        assert!(namespace
+            .get_s_node(&FieldRef::new("memberships.content.orgs").unwrap())
+            .is_err());
+        assert!(namespace
+            .get_s_node(&FieldRef::new("memberships.content.tenant_id").unwrap())
+            .is_err());
+    }
+
+    #[test]
+    fn test_import_ddl_with_invalid_table() {
+        assert!(import_ddl(
+            "CREATE TABLE users (id INTEGER PRIMARY KEY",
+            SqlDialect::Postgres
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,407 @@
+use anyhow::Result;
+
+use synth_core::graph::json::synth_val_to_json;
+use synth_core::schema::{ChronoValue, ChronoValueAndFormat};
+use synth_core::Value;
+use synth_gen::value::Number;
+
+use chrono::Utc;
+
+use std::collections::BTreeSet;
+use std::io::Write;
+use std::str::FromStr;
+
+use super::SqlDialect;
+
+/// The maximum number of rows inserted by a single `INSERT` statement.
+const INSERT_BATCH_SIZE: usize = 1000;
+
+/// How the rows of a collection are written to a script.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum SqlMode {
+    /// `INSERT INTO ... VALUES` statements
+    Insert,
+    /// Postgres' `COPY ... FROM stdin`, as written by `pg_dump`
+    Copy,
+}
+
+impl FromStr for SqlMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "insert" => Ok(Self::Insert),
+            "copy" => Ok(Self::Copy),
+            _ => Err(anyhow!(
+                "SQL mode '{}' not recognised. Was expecting one of 'insert' or 'copy'.",
+                s
+            )),
+        }
+    }
+}
+
+/// Writes the rows of collections to an SQL script that loads them into a database. The script
+/// runs in a single transaction, so that it either loads every row or none of them.
+pub(crate) struct SqlDumpWriter<W: Write> {
+    writer: W,
+    dialect: SqlDialect,
+    mode: SqlMode,
+    bytes: usize,
+}
+
+impl<W: Write> SqlDumpWriter<W> {
+    pub fn new(writer: W, dialect: SqlDialect, mode: SqlMode) -> Result<Self> {
+        if mode == SqlMode::Copy && dialect != SqlDialect::Postgres {
+            return Err(anyhow!(
+                "The 'copy' mode is only supported by the 'postgres' dialect."
+            ));
+        }
+
+        let mut dump = Self {
+            writer,
+            dialect,
+            mode,
+            bytes: 0,
+        };
+        dump.write("BEGIN;\n")?;
+        Ok(dump)
+    }
+
+    /// Writes the rows of one collection. Collections have to be written in an order where the
+    /// rows a foreign key references are written before it.
+    pub fn write_collection(&mut self, name: &str, value: Value) -> Result<()> {
+        let rows = match value {
+            Value::Array(rows) => rows,
+            non_array => vec![non_array],
+        };
+
+        for batch in rows.chunks(INSERT_BATCH_SIZE) {
+            let objects = batch
+                .iter()
+                .map(|row| match row {
+                    Value::Object(fields) => Ok(fields),
+                    otherwise => Err(anyhow!(
+                        "Only collections of objects can be exported to SQL, but collection '{}' has a value of type {}",
+                        name,
+                        otherwise.type_()
+                    )),
+                })
+                .collect::<Result<Vec<_>>>()?;
+
+            // Fields which are null can be left out of an object, so every row is given the
+            // columns of all of the rows in the batch
+            let columns = objects
+                .iter()
+                .flat_map(|fields| fields.keys())
+                .collect::<BTreeSet<_>>();
+            if columns.is_empty() {
+                continue;
+            }
+
+            let column_list = columns
+                .iter()
+                .map(|column| self.identifier(column))
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            let rows = objects
+                .iter()
+                .map(|fields| {
+                    columns
+                        .iter()
+                        .map(|column| encode(fields.get(*column), self.dialect))
+                        .collect::<Result<Vec<_>>>()
+                })
+                .collect::<Result<Vec<_>>>()?;
+
+            let statement = match self.mode {
+                SqlMode::Insert => {
+                    let values = rows
+                        .into_iter()
+                        .map(|row| {
+                            let row = row
+                                .into_iter()
+                                .map(|value| value.into_literal(self.dialect))
+                                .collect::<Vec<_>>();
+                            format!("({})", row.join(", "))
+                        })
+                        .collect::<Vec<_>>();
+                    format!(
+                        "INSERT INTO {} ({}) VALUES\n{};\n",
+                        self.identifier(name),
+                        column_list,
+                        values.join(",\n")
+                    )
+                }
+                SqlMode::Copy => {
+                    let mut statement = format!(
+                        "COPY {} ({}) FROM stdin;\n",
+                        self.identifier(name),
+                        column_list
+                    );
+                    for row in rows {
+                        let row = row
+                            .into_iter()
+                            .map(Encoded::into_copy_text)
+                            .collect::<Vec<_>>();
+                        statement.push_str(&row.join("\t"));
+                        statement.push('\n');
+                    }
+                    statement.push_str("\\.\n");
+                    statement
+                }
+            };
+
+            self.write(&statement)?;
+        }
+
+        Ok(())
+    }
+
+    /// Ends the transaction of the script and returns the number of bytes written.
+    pub fn finish(mut self) -> Result<usize> {
+        self.write("COMMIT;\n")?;
+        self.writer.flush()?;
+        Ok(self.bytes)
+    }
+
+    fn write(&mut self, s: &str) -> Result<()> {
+        self.writer.write_all(s.as_bytes())?;
+        self.bytes += s.len();
+        Ok(())
+    }
+
+    fn identifier(&self, name: &str) -> String {
+        let quote = match self.dialect {
+            SqlDialect::MySql => '`',
+            SqlDialect::Postgres | SqlDialect::Sqlite => '"',
+        };
+        format!(
+            "{}{}{}",
+            quote,
+            name.replace(quote, &quote.to_string().repeat(2)),
+            quote
+        )
+    }
+}
+
+/// A value as it is written to a script: literals like numbers are written as they are, text is
+/// quoted in `INSERT` statements and escaped in `COPY` data.
+#[derive(Debug, PartialEq)]
+enum Encoded {
+    Null,
+    Literal(String),
+    Text(String),
+}
+
+impl Encoded {
+    fn into_literal(self, dialect: SqlDialect) -> String {
+        match self {
+            Self::Null => "NULL".to_string(),
+            Self::Literal(literal) => literal,
+            Self::Text(text) => {
+                let text = match dialect {
+                    // MySQL treats backslashes in strings as escape characters by default
+                    SqlDialect::MySql => text.replace('\\', "\\\\"),
+                    SqlDialect::Postgres | SqlDialect::Sqlite => text,
+                };
+                format!("'{}'", text.replace('\'', "''"))
+            }
+        }
+    }
+
+    fn into_copy_text(self) -> String {
+        match self {
+            Self::Null => "\\N".to_string(),
+            Self::Literal(literal) => literal,
+            Self::Text(text) => text
+                .replace('\\', "\\\\")
+                .replace('\t', "\\t")
+                .replace('\n', "\\n")
+                .replace('\r', "\\r"),
+        }
+    }
+}
+
+/// Encodes a value the way it is bound to a query when exporting to a database of the dialect
+/// (see the `Encode` implementations of [`Value`]).
+fn encode(value: Option<&Value>, dialect: SqlDialect) -> Result<Encoded> {
+    let value = match value {
+        None | Some(Value::Null(_)) => return Ok(Encoded::Null),
+        Some(value) => value,
+    };
+
+    let encoded = match value {
+        Value::Null(_) => Encoded::Null,
+        Value::Bool(b) => match dialect {
+            SqlDialect::Sqlite => Encoded::Literal((*b as u8).to_string()),
+            SqlDialect::Postgres | SqlDialect::MySql => Encoded::Literal(b.to_string()),
+        },
+        Value::Number(number) => encode_number(*number, dialect)?,
+        Value::String(s) => Encoded::Text(s.clone()),
+        Value::DateTime(ChronoValueAndFormat { value, .. }) => Encoded::Text(match value {
+            ChronoValue::NaiveDate(date) => date.format("%Y-%m-%d").to_string(),
+            ChronoValue::NaiveTime(time) => time.format("%H:%M:%S%.f").to_string(),
+            ChronoValue::NaiveDateTime(date_time) => {
+                date_time.format("%Y-%m-%d %H:%M:%S%.f").to_string()
+            }
+            ChronoValue::DateTime(date_time) => match dialect {
+                SqlDialect::Postgres => date_time.format("%Y-%m-%d %H:%M:%S%.f%:z").to_string(),
+                SqlDialect::MySql => date_time
+                    .with_timezone(&Utc)
+                    .format("%Y-%m-%d %H:%M:%S%.f")
+                    .to_string(),
+                SqlDialect::Sqlite => date_time.to_rfc3339(),
+            },
+        }),
+        Value::Object(_) => Encoded::Text(synth_val_to_json(value.clone()).to_string()),
+        Value::Array(_) => match dialect {
+            SqlDialect::Postgres => Encoded::Text(value.to_postgres_string()),
+            SqlDialect::Sqlite => Encoded::Text(synth_val_to_json(value.clone()).to_string()),
+            SqlDialect::MySql => return Err(anyhow!("MySQL does not support arrays")),
+        },
+    };
+
+    Ok(encoded)
+}
+
+fn encode_number(number: Number, dialect: SqlDialect) -> Result<Encoded> {
+    let encoded = match (number, dialect) {
+        (Number::F32(f), _) => {
+            encode_float(f.into_inner() as f64, f.into_inner().to_string(), dialect)?
+        }
+        (Number::F64(f), _) => encode_float(f.into_inner(), f.into_inner().to_string(), dialect)?,
+        (Number::I128(i), SqlDialect::Sqlite) => Encoded::Text(i.to_string()),
+        (Number::U128(u), SqlDialect::Sqlite) => Encoded::Text(u.to_string()),
+        // Unsigned integers are stored in the signed type of the same size
+        (Number::U8(u), SqlDialect::Postgres | SqlDialect::MySql) => {
+            Encoded::Literal((u as i8).to_string())
+        }
+        (Number::U16(u), SqlDialect::Postgres | SqlDialect::MySql) => {
+            Encoded::Literal((u as i16).to_string())
+        }
+        (Number::U32(u), SqlDialect::Postgres) => Encoded::Literal((u as i32).to_string()),
+        (Number::U64(u), _) => Encoded::Literal((u as i64).to_string()),
+        (integer, _) => Encoded::Literal(integer.to_string()),
+    };
+
+    Ok(encoded)
+}
+
+fn encode_float(f: f64, literal: String, dialect: SqlDialect) -> Result<Encoded> {
+    if f.is_finite() {
+        return Ok(Encoded::Literal(literal));
+    }
+
+    match dialect {
+        SqlDialect::Postgres if f.is_nan() => Ok(Encoded::Text("NaN".to_string())),
+        SqlDialect::Postgres if f > 0.0 => Ok(Encoded::Text("Infinity".to_string())),
+        SqlDialect::Postgres => Ok(Encoded::Text("-Infinity".to_string())),
+        // SQLite stores NaN as NULL and reads numbers too large for a REAL as infinity
+        SqlDialect::Sqlite if f.is_nan() => Ok(Encoded::Null),
+        SqlDialect::Sqlite if f > 0.0 => Ok(Encoded::Literal("9e999".to_string())),
+        SqlDialect::Sqlite => Ok(Encoded::Literal("-9e999".to_string())),
+        SqlDialect::MySql => Err(anyhow!("MySQL does not support the float {}", f)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn rows() -> Value {
+        let mut first = BTreeMap::new();
+        first.insert("id".to_string(), Value::Number(Number::I64(1)));
+        first.insert("name".to_string(), Value::String("O'Brien".to_string()));
+        first.insert("active".to_string(), Value::Bool(true));
+
+        let mut second = BTreeMap::new();
+        second.insert("id".to_string(), Value::Number(Number::I64(2)));
+        second.insert("name".to_string(), Value::String("tab\there".to_string()));
+
+        Value::Array(vec![Value::Object(first), Value::Object(second)])
+    }
+
+    fn dump(dialect: SqlDialect, mode: SqlMode) -> String {
+        let mut out = Vec::new();
+        let mut dump = SqlDumpWriter::new(&mut out, dialect, mode).unwrap();
+        dump.write_collection("users", rows()).unwrap();
+        let bytes = dump.finish().unwrap();
+        assert_eq!(bytes, out.len());
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_dump_insert() {
+        assert_eq!(
+            dump(SqlDialect::Postgres, SqlMode::Insert),
+            "BEGIN;\n\
+            INSERT INTO \"users\" (\"active\", \"id\", \"name\") VALUES\n\
+            (true, 1, 'O''Brien'),\n\
+            (NULL, 2, 'tab\there');\n\
+            COMMIT;\n"
+        );
+        assert_eq!(
+            dump(SqlDialect::Sqlite, SqlMode::Insert),
+            "BEGIN;\n\
+            INSERT INTO \"users\" (\"active\", \"id\", \"name\") VALUES\n\
+            (1, 1, 'O''Brien'),\n\
+            (NULL, 2, 'tab\there');\n\
+            COMMIT;\n"
+        );
+        assert!(dump(SqlDialect::MySql, SqlMode::Insert)
+            .contains("INSERT INTO `users` (`active`, `id`, `name`) VALUES"));
+    }
+
+    #[test]
+    fn test_dump_copy() {
+        assert_eq!(
+            dump(SqlDialect::Postgres, SqlMode::Copy),
+            "BEGIN;\n\
+            COPY \"users\" (\"active\", \"id\", \"name\") FROM stdin;\n\
+            true\t1\tO'Brien\n\
+            \\N\t2\ttab\\there\n\
+            \\.\n\
+            COMMIT;\n"
+        );
+        assert!(SqlDumpWriter::new(Vec::new(), SqlDialect::MySql, SqlMode::Copy).is_err());
+    }
+
+    #[test]
+    fn test_encode() {
+        let encode_in = |value: Value, dialect| encode(Some(&value), dialect).unwrap();
+
+        assert_eq!(
+            encode_in(Value::Number(Number::U64(u64::MAX)), SqlDialect::Postgres),
+            Encoded::Literal("-1".to_string())
+        );
+        assert_eq!(
+            encode_in(Value::Number(Number::I128(1)), SqlDialect::Sqlite),
+            Encoded::Text("1".to_string())
+        );
+        assert_eq!(
+            encode_in(Value::Number(Number::from(f64::NAN)), SqlDialect::Postgres),
+            Encoded::Text("NaN".to_string())
+        );
+        assert_eq!(
+            encode_in(Value::Number(Number::from(f64::NAN)), SqlDialect::Sqlite),
+            Encoded::Null
+        );
+        assert!(encode(
+            Some(&Value::Number(Number::from(f64::INFINITY))),
+            SqlDialect::MySql
+        )
+        .is_err());
+        assert!(encode(Some(&Value::Array(vec![])), SqlDialect::MySql).is_err());
+
+        assert_eq!(
+            Encoded::Text("a\\b".to_string()).into_literal(SqlDialect::MySql),
+            "'a\\\\b'"
+        );
+        assert_eq!(
+            Encoded::Text("a\\b\nc".to_string()).into_copy_text(),
+            "a\\\\b\\nc"
+        );
+    }
+}
@@ -0,0 +1,147 @@
+mod ddl;
+mod dump;
+
+use crate::cli::export::{ExportOutput, ExportParams, ExportStrategy};
+use crate::cli::import::ImportStrategy;
+use crate::sampler::{Sampler, SamplerOutput, DEFAULT_CHUNK_SIZE};
+
+use synth_core::Namespace;
+
+use anyhow::{Context, Result};
+
+use std::cell::RefCell;
+use std::convert::TryFrom;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use dump::SqlDumpWriter;
+pub(crate) use dump::SqlMode;
+
+/// The database an SQL script is written for, which decides how the script is parsed and how the
+/// types of its columns are decoded.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum SqlDialect {
+    Postgres,
+    MySql,
+    Sqlite,
+}
+
+impl FromStr for SqlDialect {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "postgres" | "postgresql" => Ok(Self::Postgres),
+            "mysql" | "mariadb" => Ok(Self::MySql),
+            "sqlite" => Ok(Self::Sqlite),
+            _ => Err(anyhow!(
+                "SQL dialect '{}' not recognised. Was expecting one of 'postgres', 'mysql', \
+                'mariadb' or 'sqlite'.",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SqlFileExportStrategy {
+    pub to_file: PathBuf,
+    pub(crate) dialect: SqlDialect,
+    pub(crate) mode: SqlMode,
+}
+
+impl ExportStrategy for SqlFileExportStrategy {
+    fn export(&self, params: ExportParams) -> Result<ExportOutput> {
+        let file = std::fs::File::create(&self.to_file)
+            .with_context(|| format!("Failed to create file {}", self.to_file.display()))?;
+        let bytes = export_sql(
+            params,
+            std::io::BufWriter::new(file),
+            self.dialect,
+            self.mode,
+        )?;
+
+        Ok(ExportOutput::Streamed { bytes: Some(bytes) })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SqlStdoutExportStrategy<W> {
+    pub(crate) dialect: SqlDialect,
+    pub(crate) mode: SqlMode,
+    pub writer: RefCell<W>,
+}
+
+impl<W: Write> ExportStrategy for SqlStdoutExportStrategy<W> {
+    fn export(&self, params: ExportParams) -> Result<ExportOutput> {
+        let bytes = export_sql(
+            params,
+            &mut *self.writer.borrow_mut(),
+            self.dialect,
+            self.mode,
+        )?;
+
+        Ok(ExportOutput::Streamed { bytes: Some(bytes) })
+    }
+}
+
+/// Writes a script loading the generated collections, returning the number of bytes written.
+/// Chunks list their collections in dependency order, so every row is written after the rows it
+/// references.
+fn export_sql<W: Write>(
+    params: ExportParams,
+    writer: W,
+    dialect: SqlDialect,
+    mode: SqlMode,
+) -> Result<usize> {
+    let mut dump = SqlDumpWriter::new(writer, dialect, mode)?;
+
+    let sampler = Sampler::try_from(&params.namespace)?;
+    let chunks = sampler.sample_seeded_chunked(
+        params.collection_name,
+        params.target,
+        params.seed,
+        DEFAULT_CHUNK_SIZE,
+    );
+
+    for chunk in chunks {
+        match chunk? {
+            SamplerOutput::Collection(name, value) => dump.write_collection(&name, value)?,
+            SamplerOutput::Namespace(collections) => {
+                for (name, value) in collections {
+                    dump.write_collection(&name, value)?;
+                }
+            }
+        }
+    }
+
+    dump.finish()
+}
+
+#[derive(Clone, Debug)]
+pub struct SqlFileImportStrategy {
+    pub from_file: PathBuf,
+    pub(crate) dialect: SqlDialect,
+}
+
+impl ImportStrategy for SqlFileImportStrategy {
+    fn import(&self) -> Result<Namespace> {
+        let script = std::fs::read_to_string(&self.from_file)
+            .with_context(|| format!("Failed to read file {}", self.from_file.display()))?;
+        ddl::import_ddl(&script, self.dialect)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SqlStdinImportStrategy {
+    pub(crate) dialect: SqlDialect,
+}
+
+impl ImportStrategy for SqlStdinImportStrategy {
+    fn import(&self) -> Result<Namespace> {
+        let mut script = String::new();
+        std::io::stdin().read_to_string(&mut script)?;
+        ddl::import_ddl(&script, self.dialect)
+    }
+}
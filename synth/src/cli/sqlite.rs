@@ -0,0 +1,506 @@
+use crate::cli::export::{create_and_insert_values, ExportOutput, ExportParams, ExportStrategy};
+use crate::cli::import::ImportStrategy;
+use crate::cli::import_utils::build_namespace_import;
+use crate::datasource::relational_datasource::Sampling;
+use crate::datasource::sqlite_datasource::SqliteDataSource;
+use crate::datasource::DataSource;
+use anyhow::Result;
+use synth_core::schema::Namespace;
+
+#[derive(Clone, Debug)]
+pub struct SqliteExportStrategy {
+    pub uri_string: String,
+}
+
+impl ExportStrategy for SqliteExportStrategy {
+    fn export(&self, params: ExportParams) -> Result<ExportOutput> {
+        let datasource = SqliteDataSource::new(&self.uri_string)?;
+
+        create_and_insert_values(params, &datasource)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SqliteImportStrategy {
+    pub uri_string: String,
+    pub sampling: Sampling,
+}
+
+impl ImportStrategy for SqliteImportStrategy {
+    fn import(&self) -> Result<Namespace> {
+        let datasource = SqliteDataSource::new(&self.uri_string)?;
+
+        build_namespace_import::<SqliteDataSource>(&datasource, &self.sampling)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::store::Store;
+    use crate::datasource::relational_datasource::{SampleStrategy, SqlxDataSource};
+    use crate::sampler::Target;
+    use async_std::task;
+    use sqlx::Executor;
+    use std::collections::BTreeMap;
+    use std::path::{Path, PathBuf};
+    use synth_core::schema::number_content::I64;
+    use synth_core::schema::{FieldRef, NumberContent, SameAsContent, StringContent};
+    use synth_core::{Content, Value};
+    use synth_gen::value::Number;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_sqlite_import_and_export() {
+        let dir = tempdir().unwrap();
+        let uri_string = format!("sqlite://{}?mode=rwc", dir.path().join("app.db").display());
+
+        let datasource = SqliteDataSource::new(&uri_string).unwrap();
+        task::block_on(async {
+            for query in [
+                "CREATE TABLE users (id INTEGER PRIMARY KEY, name VARCHAR(20) NOT NULL, score REAL)",
+                "CREATE TABLE orders (id INTEGER PRIMARY KEY, user_id INTEGER NOT NULL REFERENCES users, total NUMERIC NOT NULL)",
+                "INSERT INTO users VALUES (1, 'alice', 0.5), (2, 'bob', 1.5), (3, 'carol', NULL)",
+                "INSERT INTO orders VALUES (1, 1, 10.5), (2, 2, 20)",
+            ] {
+                datasource
+                    .execute_query(query.to_string(), vec![])
+                    .await
+                    .unwrap();
+            }
+        });
+
+        let namespace = SqliteImportStrategy {
+            uri_string: uri_string.clone(),
+            sampling: Sampling::default(),
+        }
+        .import()
+        .unwrap();
+
+        let orders = match namespace.get_collection("orders").unwrap() {
+            Content::Array(array) => match array.content.as_ref() {
+                Content::Object(object) => object.clone(),
+                otherwise => panic!("expected an object, found {}", otherwise),
+            },
+            otherwise => panic!("expected an array, found {}", otherwise),
+        };
+        assert!(matches!(
+            orders.fields["id"],
+            Content::Number(NumberContent::I64(_))
+        ));
+        assert!(matches!(orders.fields["user_id"], Content::SameAs(_)));
+        assert!(matches!(
+            orders.fields["total"],
+            Content::Number(NumberContent::F64(_))
+        ));
+
+        let users = match namespace.get_collection("users").unwrap() {
+            Content::Array(array) => match array.content.as_ref() {
+                Content::Object(object) => object.clone(),
+                otherwise => panic!("expected an object, found {}", otherwise),
+            },
+            otherwise => panic!("expected an array, found {}", otherwise),
+        };
+        assert!(matches!(
+            users.fields["name"],
+            Content::String(StringContent::Pattern(_))
+        ));
+        assert!(matches!(users.fields["score"], Content::OneOf(_)));
+
+        task::block_on(async {
+            datasource
+                .execute_query("DELETE FROM orders".to_string(), vec![])
+                .await
+                .unwrap();
+            datasource
+                .execute_query("DELETE FROM users".to_string(), vec![])
+                .await
+                .unwrap();
+        });
+
+        let export = |truncate| {
+            SqliteExportStrategy {
+                uri_string: uri_string.clone(),
+            }
+            .export(ExportParams {
+                namespace: namespace.clone(),
+                collection_name: None,
+                target: Target::Total(5),
+                seed: 0,
+                ns_path: PathBuf::new(),
+                truncate,
+            })
+        };
+        let count_rows = || -> (i64, i64, i64) {
+            task::block_on(
+                sqlx::query_as(
+                    "SELECT (SELECT count(*) FROM users), count(*),
+                        count(*) FILTER (WHERE user_id NOT IN (SELECT id FROM users))
+                        FROM orders",
+                )
+                .fetch_one(&datasource.get_pool()),
+            )
+            .unwrap()
+        };
+
+        export(false).unwrap();
+
+        let exported = count_rows();
+        let (_, orders, orphans) = exported;
+        assert!(orders > 0);
+        assert_eq!(orphans, 0);
+
+        // Only the orders clash with the ones already there, so the generated users are inserted
+        // before the export fails and have to be rolled back
+        task::block_on(async {
+            for query in [
+                "DELETE FROM orders",
+                "UPDATE users SET id = id + 1000",
+                "INSERT INTO orders SELECT 1, min(id), 1.0 FROM users",
+            ] {
+                datasource
+                    .execute_query(query.to_string(), vec![])
+                    .await
+                    .unwrap();
+            }
+        });
+        let before = count_rows();
+        assert!(export(false).is_err());
+        assert_eq!(count_rows(), before);
+
+        export(true).unwrap();
+        assert_eq!(count_rows(), exported);
+    }
+
+    #[test]
+    fn test_sqlite_import_nullable_unique() {
+        let dir = tempdir().unwrap();
+        let uri_string = format!("sqlite://{}?mode=rwc", dir.path().join("app.db").display());
+
+        let datasource = SqliteDataSource::new(&uri_string).unwrap();
+        task::block_on(async {
+            for query in [
+                "CREATE TABLE users (id INTEGER PRIMARY KEY, email TEXT UNIQUE)",
+                "INSERT INTO users VALUES (1, 'alice@example.com'), (2, NULL), (3, NULL)",
+            ] {
+                datasource
+                    .execute_query(query.to_string(), vec![])
+                    .await
+                    .unwrap();
+            }
+        });
+
+        let namespace = SqliteImportStrategy {
+            uri_string,
+            sampling: Sampling::default(),
+        }
+        .import()
+        .unwrap();
+
+        // Any number of rows can have a null email, so only the other emails are unique
+        match namespace.get_s_node(&"users.content.email".parse().unwrap()) {
+            Ok(Content::OneOf(one_of)) => {
+                let variants = one_of.iter().collect::<Vec<_>>();
+                assert_eq!(variants.len(), 2);
+                assert!(variants.iter().any(|content| content.is_null()));
+                assert!(variants
+                    .iter()
+                    .any(|content| matches!(content, Content::Unique(_))));
+            }
+            otherwise => panic!("expected a nullable email, found {:?}", otherwise),
+        }
+    }
+
+    #[test]
+    fn test_sqlite_import_unique_expression() {
+        let dir = tempdir().unwrap();
+        let uri_string = format!("sqlite://{}?mode=rwc", dir.path().join("app.db").display());
+
+        let datasource = SqliteDataSource::new(&uri_string).unwrap();
+        task::block_on(async {
+            for query in [
+                "CREATE TABLE users (id INTEGER PRIMARY KEY, tenant INTEGER NOT NULL, email TEXT NOT NULL)",
+                "CREATE UNIQUE INDEX users_email ON users (tenant, lower(email))",
+            ] {
+                datasource
+                    .execute_query(query.to_string(), vec![])
+                    .await
+                    .unwrap();
+            }
+        });
+
+        let namespace = SqliteImportStrategy {
+            uri_string,
+            sampling: Sampling::default(),
+        }
+        .import()
+        .unwrap();
+
+        // Only the lowercase emails of a tenant are unique, which isn't imported
+        assert!(matches!(
+            namespace.get_s_node(&"users.content.tenant".parse().unwrap()),
+            Ok(Content::Number(_))
+        ));
+        match namespace.get_s_node(&"users.content".parse().unwrap()) {
+            Ok(Content::Object(object)) => assert!(object.unique_together.is_empty()),
+            otherwise => panic!("expected an object, found {:?}", otherwise),
+        }
+    }
+
+    #[test]
+    fn test_sqlite_transactions() {
+        let dir = tempdir().unwrap();
+        let uri_string = format!("sqlite://{}?mode=rwc", dir.path().join("app.db").display());
+        let datasource = SqliteDataSource::new(&uri_string).unwrap();
+
+        let user = |id: i64, name: &str| {
+            Value::Object(BTreeMap::from([
+                ("id".to_string(), Value::Number(Number::I64(id))),
+                ("name".to_string(), Value::String(name.to_string())),
+            ]))
+        };
+        let names = || -> Vec<String> {
+            task::block_on(
+                sqlx::query_scalar("SELECT name FROM users ORDER BY id")
+                    .fetch_all(&datasource.get_pool()),
+            )
+            .unwrap()
+        };
+
+        task::block_on(async {
+            datasource
+                .execute_query(
+                    "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL)".to_string(),
+                    vec![],
+                )
+                .await
+                .unwrap();
+            datasource
+                .insert_data("users", &[user(1, "alice")])
+                .await
+                .unwrap();
+
+            // The rows inserted before an insert fails are rolled back along with it
+            datasource.begin().await.unwrap();
+            datasource
+                .insert_data("users", &[user(2, "bob")])
+                .await
+                .unwrap();
+            assert!(datasource
+                .insert_data("users", &[user(1, "carol")])
+                .await
+                .is_err());
+            datasource.rollback().await.unwrap();
+        });
+        assert_eq!(names(), ["alice"]);
+
+        task::block_on(async {
+            datasource.begin().await.unwrap();
+            datasource.truncate(&["users".to_string()]).await.unwrap();
+            datasource
+                .insert_data("users", &[user(1, "dave")])
+                .await
+                .unwrap();
+            datasource.commit().await.unwrap();
+        });
+        assert_eq!(names(), ["dave"]);
+    }
+
+    #[test]
+    fn test_sqlite_import_sampling() {
+        let dir = tempdir().unwrap();
+        let uri_string = format!("sqlite://{}?mode=rwc", dir.path().join("app.db").display());
+
+        let datasource = SqliteDataSource::new(&uri_string).unwrap();
+        task::block_on(async {
+            for query in [
+                "CREATE TABLE items (id INTEGER PRIMARY KEY, price INTEGER NOT NULL)",
+                "WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < 20)
+                    INSERT INTO items SELECT i, i FROM n",
+            ] {
+                datasource
+                    .execute_query(query.to_string(), vec![])
+                    .await
+                    .unwrap();
+            }
+        });
+
+        let highest_price = |sampling: Sampling| {
+            let namespace = SqliteImportStrategy {
+                uri_string: uri_string.clone(),
+                sampling,
+            }
+            .import()
+            .unwrap();
+            match namespace.get_s_node(&"items.content.price".parse().unwrap()) {
+                Ok(Content::Number(NumberContent::I64(I64::Range(range)))) => range.high,
+                otherwise => panic!("expected a range, found {:?}", otherwise),
+            }
+        };
+
+        assert_eq!(highest_price(Sampling::default()), Some(10));
+        assert_eq!(
+            highest_price(Sampling {
+                strategy: SampleStrategy::Head,
+                rows: Some(5),
+                tables: BTreeMap::new(),
+            }),
+            Some(5)
+        );
+        assert_eq!(
+            highest_price(Sampling {
+                strategy: SampleStrategy::Random,
+                rows: Some(0),
+                tables: BTreeMap::from([("items".to_string(), None)]),
+            }),
+            Some(20)
+        );
+        assert_eq!(
+            highest_price(Sampling {
+                strategy: SampleStrategy::Random,
+                rows: Some(0),
+                tables: BTreeMap::new(),
+            }),
+            None
+        );
+
+        assert!(SqliteImportStrategy {
+            uri_string: uri_string.clone(),
+            sampling: Sampling {
+                strategy: SampleStrategy::Random,
+                rows: Some(10),
+                tables: BTreeMap::from([("orders".to_string(), None)]),
+            },
+        }
+        .import()
+        .is_err());
+        assert!(SqliteImportStrategy {
+            uri_string,
+            sampling: Sampling {
+                strategy: SampleStrategy::TableSample,
+                rows: Some(10),
+                tables: BTreeMap::new(),
+            },
+        }
+        .import()
+        .is_err());
+    }
+
+    const HOSPITAL_SCHEMA: &str =
+        include_str!("../../testing_harness/sqlite/0_hospital_schema.sql");
+    const HOSPITAL_DATA: &str = include_str!("../../testing_harness/mysql/1_hospital_data.sql");
+
+    /// An in-memory database named `name` holding the hospital schema, and its rows if `with_data`
+    /// is set. The database is shared by the connections to it and lasts as long as the data
+    /// source returned is open.
+    fn hospital_database(name: &str, with_data: bool) -> (String, SqliteDataSource) {
+        let uri_string = format!("sqlite:{}?mode=memory", name);
+        let datasource = SqliteDataSource::new(&uri_string).unwrap();
+        task::block_on(async {
+            let pool = datasource.get_pool();
+            pool.execute(HOSPITAL_SCHEMA).await.unwrap();
+            if with_data {
+                pool.execute(HOSPITAL_DATA).await.unwrap();
+            }
+        });
+        (uri_string, datasource)
+    }
+
+    /// The rows of the hospital tables, and those referring to a row which doesn't exist.
+    fn count_hospital_rows(datasource: &SqliteDataSource) -> (i64, i64) {
+        task::block_on(
+            sqlx::query_as(
+                "SELECT (SELECT count(*) FROM hospitals)
+                        + (SELECT count(*) FROM doctors)
+                        + (SELECT count(*) FROM patients),
+                    (SELECT count(*) FROM doctors
+                        WHERE hospital_id NOT IN (SELECT id FROM hospitals))
+                        + (SELECT count(*) FROM patients
+                        WHERE doctor_id NOT IN (SELECT id FROM doctors))",
+            )
+            .fetch_one(&datasource.get_pool()),
+        )
+        .unwrap()
+    }
+
+    fn export_hospitals(uri_string: &str, namespace: Namespace) {
+        SqliteExportStrategy {
+            uri_string: uri_string.to_string(),
+        }
+        .export(ExportParams {
+            namespace,
+            collection_name: None,
+            target: Target::Total(30),
+            seed: 0,
+            ns_path: PathBuf::new(),
+            truncate: false,
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn test_sqlite_harness_generate() {
+        let (uri_string, datasource) = hospital_database("harness_generate", false);
+
+        let master = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("testing_harness")
+            .join("postgres")
+            .join("hospital_master");
+        export_hospitals(&uri_string, Store::init().unwrap().get_ns(master).unwrap());
+
+        let (rows, orphans) = count_hospital_rows(&datasource);
+        assert!(rows > 30);
+        assert_eq!(orphans, 0);
+    }
+
+    #[test]
+    fn test_sqlite_harness_import() {
+        let (uri_string, _datasource) = hospital_database("harness_import", true);
+
+        let namespace = SqliteImportStrategy {
+            uri_string,
+            sampling: Sampling::default(),
+        }
+        .import()
+        .unwrap();
+
+        let field = |path: &str| namespace.get_s_node(&path.parse().unwrap()).unwrap();
+        for table in ["hospitals", "doctors", "patients"] {
+            assert!(matches!(
+                field(&format!("{}.content.id", table)),
+                Content::Number(NumberContent::I64(I64::Id(_)))
+            ));
+        }
+        assert_eq!(
+            field("doctors.content.hospital_id"),
+            &Content::SameAs(SameAsContent {
+                ref_: FieldRef::new("hospitals.content.id").unwrap(),
+                sample: None,
+            })
+        );
+        assert_eq!(
+            field("patients.content.doctor_id"),
+            &Content::SameAs(SameAsContent {
+                ref_: FieldRef::new("doctors.content.id").unwrap(),
+                sample: None,
+            })
+        );
+        for path in [
+            "doctors.content.date_joined",
+            "patients.content.date_joined",
+        ] {
+            assert!(matches!(
+                field(path).as_nullable(),
+                Some(Content::DateTime(_))
+            ));
+        }
+
+        // The imported namespace generates data the schema accepts
+        let (uri_string, datasource) = hospital_database("harness_import_generate", false);
+        export_hospitals(&uri_string, namespace.clone());
+
+        let (rows, orphans) = count_hospital_rows(&datasource);
+        assert!(rows > 30);
+        assert_eq!(orphans, 0);
+    }
+}
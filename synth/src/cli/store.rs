@@ -22,7 +22,7 @@ impl Underlying {
 
 impl Underlying {
     fn parse(&self, text: &str) -> Result<Content> {
-        serde_json::from_str(text).context("Failed to parse collection")
+        Content::from_json_str(text).context("Failed to parse collection")
     }
 }
 
@@ -71,6 +71,31 @@ impl Store {
     pub fn get_ns(&self, ns_path: PathBuf) -> Result<Namespace> {
         let mut ns = Namespace::default();
 
+        for entry in self.collection_entries(&ns_path)? {
+            let (collection_name, content) = self
+                .get_collection(&entry)
+                .with_context(|| anyhow!("at file {}", entry.path().display()))?;
+
+            ns.put_collection(collection_name, content)?;
+        }
+
+        Ok(ns)
+    }
+
+    /// Get the name and path of every collection file of a namespace, without parsing them
+    pub fn get_collection_paths(&self, ns_path: &Path) -> Result<Vec<(String, PathBuf)>> {
+        let mut paths = self
+            .collection_entries(ns_path)?
+            .into_iter()
+            .map(|entry| Ok((Self::collection_name(&entry)?, entry.path())))
+            .collect::<Result<Vec<_>>>()?;
+        paths.sort();
+        Ok(paths)
+    }
+
+    fn collection_entries(&self, ns_path: &Path) -> Result<Vec<DirEntry>> {
+        let mut entries = Vec::new();
+
         for entry in ns_path
             .read_dir()
             .with_context(|| format!("At path {:?}", ns_path))?
@@ -78,16 +103,12 @@ impl Store {
             let entry = entry?;
             if let Some(file_ext) = entry.path().extension() {
                 if file_ext == UNDERLYING.extension() {
-                    let (collection_name, content) = self
-                        .get_collection(&entry)
-                        .with_context(|| anyhow!("at file {}", entry.path().display()))?;
-
-                    ns.put_collection(collection_name, content)?;
+                    entries.push(entry);
                 }
             }
         }
 
-        Ok(ns)
+        Ok(entries)
     }
 
     pub fn save_collection_path(
@@ -126,17 +147,21 @@ impl Store {
     }
 
     fn get_collection(&self, dir_entry: &DirEntry) -> Result<(String, Content)> {
+        let collection_name = Self::collection_name(dir_entry)?;
+        let collection_file_content = std::fs::read_to_string(dir_entry.path())?;
+        let collection = UNDERLYING.parse(&collection_file_content)?;
+
+        Ok((collection_name, collection))
+    }
+
+    fn collection_name(dir_entry: &DirEntry) -> Result<String> {
         let entry_name = dir_entry.file_name();
         let file_name = entry_name.to_str().unwrap();
-        let collection_name = file_name
+        Ok(file_name
             .split('.')
             .next()
             .ok_or_else(|| failed!(target: Debug, "invalid filename {}", file_name))?
-            .to_string();
-        let collection_file_content = std::fs::read_to_string(dir_entry.path())?;
-        let collection = UNDERLYING.parse(&collection_file_content)?;
-
-        Ok((collection_name, collection))
+            .to_string())
     }
 }
 
@@ -14,9 +14,8 @@ use std::path::PathBuf;
 use std::rc::Rc;
 use uuid::Uuid;
 
-use crate::cli::export::{ExportParams, ExportStrategy};
+use crate::cli::export::{ExportOutput, ExportParams, ExportStrategy};
 use crate::cli::{config, GenerateCommand, ImportCommand};
-use crate::sampler::SamplerOutput;
 use crate::utils::META_OS;
 use crate::version::version;
 
@@ -265,18 +264,23 @@ impl<'w> TelemetryExportStrategy<'w> {
         Ok(())
     }
 
-    fn fill_telemetry_post(&self, output: SamplerOutput) -> Result<()> {
-        let j = output.into_json();
-        let s = serde_json::to_string(&j)?;
+    fn fill_telemetry_post(&self, output: &ExportOutput) -> Result<()> {
+        let bytes = match output {
+            ExportOutput::Sampled(sample) => {
+                let j = sample.clone().into_json();
+                Some(serde_json::to_string(&j)?.len())
+            }
+            ExportOutput::Streamed { bytes } => *bytes,
+        };
 
-        self.telemetry_context.borrow_mut().bytes = Some(s.len());
+        self.telemetry_context.borrow_mut().bytes = bytes;
 
         Ok(())
     }
 }
 
 impl<'w> ExportStrategy for TelemetryExportStrategy<'w> {
-    fn export(&self, params: ExportParams) -> Result<SamplerOutput> {
+    fn export(&self, params: ExportParams) -> Result<ExportOutput> {
         Self::fill_telemetry_pre(
             Rc::clone(&self.telemetry_context),
             &params.namespace,
@@ -285,7 +289,7 @@ impl<'w> ExportStrategy for TelemetryExportStrategy<'w> {
         )?;
         let output = self.exporter.export(params)?;
 
-        self.fill_telemetry_post(output.clone())?;
+        self.fill_telemetry_post(&output)?;
 
         Ok(output)
     }
@@ -318,6 +322,7 @@ where
         Args::Init { .. } => "init",
         Args::Generate { .. } => "generate",
         Args::Import { .. } => "import",
+        Args::Validate { .. } => "validate",
         Args::Telemetry(TelemetryCommand::Enable) => "telemetry::enable",
         Args::Telemetry(TelemetryCommand::Disable) => "telemetry::disable",
         Args::Telemetry(TelemetryCommand::Status) => "telemetry::status",
@@ -522,10 +527,10 @@ impl TelemetryClient {
 #[cfg(test)]
 pub mod tests {
     use super::{
-        ExportParams, ExportStrategy, Namespace, SamplerOutput, TelemetryClient, TelemetryContext,
+        ExportOutput, ExportParams, ExportStrategy, Namespace, TelemetryClient, TelemetryContext,
         TelemetryExportStrategy,
     };
-    use crate::sampler::Sampler;
+    use crate::sampler::{Sampler, Target};
     use anyhow::Result;
     use std::cell::RefCell;
     use std::convert::TryFrom;
@@ -544,12 +549,12 @@ pub mod tests {
     pub struct DummyExportStrategy {}
 
     impl ExportStrategy for DummyExportStrategy {
-        fn export(&self, params: ExportParams) -> Result<SamplerOutput> {
+        fn export(&self, params: ExportParams) -> Result<ExportOutput> {
             let generator = Sampler::try_from(&params.namespace)?;
             let output =
                 generator.sample_seeded(params.collection_name, params.target, params.seed)?;
 
-            Ok(output)
+            Ok(ExportOutput::Sampled(output))
         }
     }
 
@@ -605,9 +610,10 @@ pub mod tests {
             .export(ExportParams {
                 namespace: schema,
                 collection_name: None,
-                target: 1,
+                target: Target::Total(1),
                 seed: 500,
                 ns_path: PathBuf::from("/dummy/path"),
+                truncate: false,
             })
             .unwrap();
 
@@ -694,9 +700,10 @@ pub mod tests {
             .export(ExportParams {
                 namespace: schema,
                 collection_name: None,
-                target: 1,
+                target: Target::Total(1),
                 seed: 500,
                 ns_path: PathBuf::from("/dummy/path"),
+                truncate: false,
             })
             .unwrap();
 
@@ -771,9 +778,10 @@ pub mod tests {
             .export(ExportParams {
                 namespace: schema,
                 collection_name: None,
-                target: 1,
+                target: Target::Total(1),
                 seed: 500,
                 ns_path: PathBuf::from("/dummy/path"),
+                truncate: false,
             })
             .unwrap();
 
@@ -848,9 +856,10 @@ pub mod tests {
             .export(ExportParams {
                 namespace: schema,
                 collection_name: "collection-2".parse().ok(),
-                target: 1,
+                target: Target::Total(1),
                 seed: 500,
                 ns_path: PathBuf::from("/dummy/namespace"),
+                truncate: false,
             })
             .unwrap();
 
@@ -0,0 +1,373 @@
+use crate::cli::store::Store;
+
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use synth_core::compile::{Address, FromLink, NamespaceCompiler, Source};
+use synth_core::{Compile, Compiler, Content, Graph, Namespace};
+
+/// An error found in a namespace by `synth validate`.
+#[derive(Debug, Serialize)]
+pub struct ValidationError {
+    /// The collection file the error is in. `None` if the error could not be narrowed down to a
+    /// single collection.
+    pub file: Option<PathBuf>,
+    pub collection: Option<String>,
+    /// The JSON path to the offending node within the collection file, e.g. `content.name`.
+    pub path: Option<String>,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.file {
+            Some(file) => write!(f, "{}", file.display())?,
+            None => write!(f, "<namespace>")?,
+        }
+        if let (Some(line), Some(column)) = (self.line, self.column) {
+            write!(f, ":{}:{}", line, column)?;
+        }
+        if let Some(path) = &self.path {
+            write!(f, ": at `{}`", path)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+/// Loads and compiles every collection of the namespace at `ns_path`, returning all of the errors
+/// found rather than stopping at the first one.
+pub fn validate_namespace(store: &Store, ns_path: &Path) -> Result<Vec<ValidationError>> {
+    let mut errors = Vec::new();
+    let mut namespace = Namespace::new();
+    let mut files = BTreeMap::new();
+    let mut unloaded = BTreeSet::new();
+
+    for (name, file) in store.get_collection_paths(ns_path)? {
+        let text = std::fs::read_to_string(&file)?;
+        let error = |path, line, column, message| ValidationError {
+            file: Some(file.clone()),
+            collection: Some(name.clone()),
+            path,
+            line,
+            column,
+            message,
+        };
+
+        match Content::from_json_str(&text) {
+            Ok(content) => match namespace.put_collection(name.clone(), content) {
+                Ok(()) => {
+                    files.insert(name, file);
+                }
+                Err(err) => {
+                    errors.push(error(None, None, None, format!("{:#}", err)));
+                    unloaded.insert(name);
+                }
+            },
+            Err(err) => {
+                let path = Some(err.path().join(".")).filter(|path| !path.is_empty());
+                let message = err.message().to_string();
+                errors.push(error(path, Some(err.line()), Some(err.column()), message));
+                unloaded.insert(name);
+            }
+        }
+    }
+
+    for (collection, path, err) in compile_errors(&namespace, unloaded) {
+        errors.push(ValidationError {
+            file: collection.as_ref().map(|name| files[name].clone()),
+            collection,
+            path,
+            line: None,
+            column: None,
+            message: format!("{:#}", err),
+        });
+    }
+
+    Ok(errors)
+}
+
+/// Compiles each collection of `namespace` along with the collections it refers to, so that an
+/// error in one collection does not hide the errors of the others.
+///
+/// Errors local to a collection are found first, by crawling it on its own. Its references are then
+/// checked by compiling it along with the collections it refers to, unless one of those has failed
+/// too, or is `unloaded` as it could not be read, so that errors are not repeated down the line.
+fn compile_errors(
+    namespace: &Namespace,
+    unloaded: BTreeSet<String>,
+) -> Vec<(Option<String>, Option<String>, anyhow::Error)> {
+    let err = match NamespaceCompiler::new(namespace).compile() {
+        Ok(_) => return Vec::new(),
+        Err(err) => err,
+    };
+
+    let mut errors = Vec::new();
+    let mut references = BTreeMap::new();
+    let mut broken = unloaded;
+
+    for (name, content) in namespace.iter() {
+        let position = Address::new_root().into_at(name);
+        let mut collections = BTreeSet::new();
+        let mut local_errors = Vec::new();
+        if let Err(err) = content.compile(Crawler {
+            namespace,
+            position: position.clone(),
+            collections: &mut collections,
+            errors: &mut local_errors,
+        }) {
+            if local_errors.is_empty() {
+                local_errors.push((position, err));
+            }
+        }
+
+        if !local_errors.is_empty() {
+            broken.insert(name.to_string());
+        }
+        for (address, err) in local_errors {
+            let path = address.iter().skip(1).collect::<Vec<_>>().join(".");
+            let path = Some(path).filter(|path| !path.is_empty());
+            errors.push((Some(name.to_string()), path, err));
+        }
+        references.insert(name, collections);
+    }
+
+    let closures: BTreeMap<&str, BTreeSet<&str>> = namespace
+        .keys()
+        .map(|name| {
+            let mut closure = BTreeSet::new();
+            let mut visits = vec![name];
+            while let Some(name) = visits.pop() {
+                if closure.insert(name) {
+                    visits.extend(references[name].iter().filter_map(|reference| {
+                        references
+                            .get_key_value(reference.as_str())
+                            .map(|(collection, _)| *collection)
+                    }));
+                }
+            }
+            (name, closure)
+        })
+        .collect();
+
+    let mut results: BTreeMap<&str, Result<Graph>> = closures
+        .iter()
+        .filter(|(_, closure)| {
+            !closure.iter().any(|collection| {
+                broken.contains(*collection) || !references[collection].is_disjoint(&broken)
+            })
+        })
+        .map(|(name, closure)| {
+            let subset: Namespace = namespace
+                .iter()
+                .filter(|(collection, _)| closure.contains(collection))
+                .map(|(collection, content)| (collection.to_string(), content.clone()))
+                .collect();
+            (*name, NamespaceCompiler::new(&subset).compile())
+        })
+        .collect();
+
+    let failed: BTreeSet<&str> = results
+        .iter()
+        .filter(|(_, result)| result.is_err())
+        .map(|(name, _)| *name)
+        .collect();
+
+    errors.extend(
+        failed
+            .iter()
+            .filter(|name| {
+                // Collections in a cycle with this one fail along with it, so they don't count
+                !closures[*name].iter().any(|dependency| {
+                    dependency != *name
+                        && failed.contains(dependency)
+                        && !closures[dependency].contains(*name)
+                })
+            })
+            .filter_map(|name| {
+                results
+                    .remove(name)
+                    .and_then(Result::err)
+                    .map(|err| (Some(name.to_string()), None, err))
+            }),
+    );
+
+    if errors.is_empty() && broken.is_empty() {
+        vec![(None, None, err)]
+    } else {
+        errors
+    }
+}
+
+/// A [`Compiler`](Compiler) which visits every node of a collection without building anything,
+/// recording the collections it refers to and the innermost nodes failing to compile.
+struct Crawler<'a, 'r> {
+    namespace: &'a Namespace,
+    position: Address,
+    collections: &'r mut BTreeSet<String>,
+    errors: &'r mut Vec<(Address, anyhow::Error)>,
+}
+
+impl<'a, 'r> Compiler<'a> for Crawler<'a, 'r> {
+    fn build(&mut self, field: &str, content: &'a Content) -> Result<Graph> {
+        let position = self.position.clone().into_at(field);
+        let num_errors = self.errors.len();
+        let crawler = Crawler {
+            namespace: self.namespace,
+            position: position.clone(),
+            collections: self.collections,
+            errors: self.errors,
+        };
+        if let Err(err) = content.compile(crawler) {
+            if self.errors.len() == num_errors {
+                self.errors.push((position, err));
+            }
+        }
+        // Carry on with a dummy so that the other children get crawled too
+        Ok(Graph::dummy())
+    }
+
+    fn get<S: Into<Address>>(&mut self, target: S) -> Result<Graph> {
+        if let Some(collection) = target.into().iter().next() {
+            self.collections.insert(collection.to_string());
+        }
+        Ok(Graph::dummy())
+    }
+
+    fn content<S: Into<Address>>(&self, field: S) -> Option<&'a Content> {
+        Source::Namespace(self.namespace).find(&field.into()).ok()
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_validate_namespace() -> Result<()> {
+        let dir = tempdir()?;
+        let write = |name: &str, text: &str| std::fs::write(dir.path().join(name), text);
+
+        write(
+            "users.json",
+            r#"{
+                "type": "array",
+                "length": 1,
+                "content": {
+                    "type": "object",
+                    "id": { "type": "number", "id": {} },
+                    "name": {
+                        "type": "one_of",
+                        "variants": [{ "type": "null" }, { "type": "strin" }]
+                    }
+                }
+            }"#,
+        )?;
+        write(
+            "orders.json",
+            r#"{
+                "type": "array",
+                "length": 1,
+                "content": {
+                    "type": "object",
+                    "id": { "type": "number", "id": {} },
+                    "product_id": { "type": "same_as", "ref": "products.content.idd" }
+                }
+            }"#,
+        )?;
+        write(
+            "products.json",
+            r#"{
+                "type": "array",
+                "length": 1,
+                "content": {
+                    "type": "object",
+                    "id": { "type": "number", "id": {} },
+                    "name": { "type": "string", "pattern": "[a-z]+" }
+                }
+            }"#,
+        )?;
+        write(
+            "reviews.json",
+            r#"{
+                "type": "array",
+                "length": 1,
+                "content": {
+                    "type": "object",
+                    "order_id": { "type": "same_as", "ref": "orders.content.id" },
+                    "user_id": { "type": "same_as", "ref": "users.content.id" },
+                    "rating": {
+                        "type": "number",
+                        "range": { "low": 5, "high": 1, "step": 1 }
+                    }
+                }
+            }"#,
+        )?;
+        write(
+            "sessions.json",
+            r#"{
+                "type": "array",
+                "length": 1,
+                "content": {
+                    "type": "object",
+                    "id": { "type": "number", "id": {} },
+                }
+            }"#,
+        )?;
+
+        let errors = validate_namespace(&Store::init()?, dir.path())?;
+        let errors = errors
+            .into_iter()
+            .map(|err| (err.collection, err.path, err.line, err.message))
+            .collect::<Vec<_>>();
+
+        assert_eq!(errors.len(), 4);
+        assert_eq!(errors[0].0.as_deref(), Some("sessions"));
+        assert_eq!(errors[0].2, Some(7));
+        assert!(errors[0].3.starts_with("trailing comma"));
+        assert_eq!(errors[1].0.as_deref(), Some("users"));
+        assert_eq!(errors[1].1.as_deref(), Some("content.name.variants.1"));
+        assert_eq!(errors[1].2, Some(9));
+        assert!(errors[1].3.starts_with("unknown variant `strin`"));
+        assert_eq!(errors[2].0.as_deref(), Some("reviews"));
+        assert_eq!(errors[2].1.as_deref(), Some("content.rating"));
+        assert!(errors[2].3.contains("'low'=5 > 'high'=1"));
+        // The references of `reviews` aren't checked as it has an error of its own
+        assert_eq!(errors[3].0.as_deref(), Some("orders"));
+        assert!(errors[3].3.contains("products.content.idd"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_namespace_cycle() -> Result<()> {
+        let dir = tempdir()?;
+        for (name, other) in [("a", "b"), ("b", "a")] {
+            std::fs::write(
+                dir.path().join(format!("{}.json", name)),
+                format!(
+                    r#"{{
+                        "type": "array",
+                        "length": 1,
+                        "content": {{
+                            "type": "object",
+                            "id": {{ "type": "same_as", "ref": "{}.content.id" }}
+                        }}
+                    }}"#,
+                    other
+                ),
+            )?;
+        }
+
+        let errors = validate_namespace(&Store::init()?, dir.path())?;
+
+        assert_eq!(errors.len(), 2);
+        assert!(errors.iter().all(|err| err.message.contains("cycle")));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,569 @@
+//! Narrowing the content of a column to the values its `CHECK` constraints accept.
+use anyhow::{Context, Result};
+
+use sqlparser::ast::{BinaryOperator, Expr, FunctionArg, FunctionArgExpr, UnaryOperator, Value};
+use sqlparser::dialect::PostgreSqlDialect;
+use sqlparser::keywords::Keyword;
+use sqlparser::parser::Parser;
+use sqlparser::tokenizer::{Token, Tokenizer};
+
+use synth_core::schema::content::number_content;
+use synth_core::schema::{
+    Categorical, CategoricalType, NumberContent, RangeStep, RegexContent, StringContent,
+};
+use synth_core::Content;
+
+use std::ops::{Add, Sub};
+use std::str::FromStr;
+
+/// The class of the characters of the strings text columns are imported as.
+const ALPHANUMERIC: &str = "[a-zA-Z0-9]";
+
+/// Parses a `CHECK` constraint as Postgres describes it with `pg_get_constraintdef`, such as
+/// `CHECK ((age >= 18))`.
+pub(crate) fn parse_postgres_check(definition: &str) -> Result<Expr> {
+    let condition = definition
+        .trim()
+        .trim_end_matches("NOT VALID")
+        .trim()
+        .strip_prefix("CHECK")
+        .with_context(|| format!("'{}' is not a CHECK constraint", definition))?;
+
+    let dialect = PostgreSqlDialect {};
+    let tokens = Tokenizer::new(&dialect, condition)
+        .tokenize()
+        .map_err(|e| anyhow!("Failed to tokenize '{}': {}", definition, e.message))?;
+    Parser::new(rewrite_any_array(tokens), &dialect)
+        .parse_expr()
+        .with_context(|| format!("Failed to parse '{}'", definition))
+}
+
+/// Rewrites the `column = ANY (ARRAY[...])` Postgres describes `column IN (...)` as, since arrays
+/// can't be parsed.
+fn rewrite_any_array(tokens: Vec<Token>) -> Vec<Token> {
+    let tokens = tokens
+        .into_iter()
+        .filter(|token| !matches!(token, Token::Whitespace(_)))
+        .collect::<Vec<_>>();
+
+    let mut rewritten = Vec::new();
+    let mut idx = 0;
+    while idx < tokens.len() {
+        match any_array(&tokens[idx..]) {
+            Some((items, len)) => {
+                rewritten.push(Token::make_keyword("IN"));
+                rewritten.push(Token::LParen);
+                rewritten.extend_from_slice(items);
+                rewritten.push(Token::RParen);
+                idx += len;
+            }
+            None => {
+                rewritten.push(tokens[idx].clone());
+                idx += 1;
+            }
+        }
+    }
+    rewritten
+}
+
+/// The items of the array of an `= ANY (ARRAY[...])` at the start of `tokens`, and how many
+/// tokens it spans. The array can be nested in more parentheses and cast.
+fn any_array(tokens: &[Token]) -> Option<(&[Token], usize)> {
+    match tokens {
+        [Token::Eq, Token::Word(any), Token::LParen, ..] if any.keyword == Keyword::ANY => {}
+        _ => return None,
+    }
+
+    let start = tokens.iter().position(|token| *token == Token::LBracket)? + 1;
+    match &tokens[start - 2] {
+        Token::Word(array) if array.keyword == Keyword::ARRAY => {}
+        _ => return None,
+    }
+    let len = tokens[start..]
+        .iter()
+        .position(|token| *token == Token::RBracket)?;
+
+    let mut depth = 0;
+    for (idx, token) in tokens.iter().enumerate().skip(2) {
+        match token {
+            Token::LParen => depth += 1,
+            Token::RParen => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some((&tokens[start..start + len], idx + 1));
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+pub(crate) fn categorical(variants: Vec<String>) -> Content {
+    let mut categorical = Categorical::default();
+    for variant in variants {
+        categorical.push(variant);
+    }
+    Content::String(StringContent::Categorical(categorical))
+}
+
+fn is_column(expr: &Expr, column: &str) -> bool {
+    match expr {
+        Expr::Identifier(ident) => ident.value.eq_ignore_ascii_case(column),
+        Expr::CompoundIdentifier(idents) => idents
+            .last()
+            .map(|ident| ident.value.eq_ignore_ascii_case(column))
+            .unwrap_or(false),
+        Expr::Nested(expr) | Expr::Cast { expr, .. } => is_column(expr, column),
+        _ => false,
+    }
+}
+
+fn literal(expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::Value(Value::Number(number, _)) => Some(number.to_string()),
+        Expr::Value(Value::SingleQuotedString(string)) => Some(string.clone()),
+        Expr::UnaryOp {
+            op: UnaryOperator::Minus,
+            expr,
+        } => match expr.as_ref() {
+            Expr::Value(Value::Number(number, _)) => Some(format!("-{}", number)),
+            _ => None,
+        },
+        Expr::Nested(expr) | Expr::Cast { expr, .. } => literal(expr),
+        _ => None,
+    }
+}
+
+enum Bound {
+    Low { value: String, inclusive: bool },
+    High { value: String, inclusive: bool },
+}
+
+/// Whether `expr` is the length of `column`.
+fn is_length_of(expr: &Expr, column: &str) -> bool {
+    match expr {
+        Expr::Function(function) => {
+            let is_length = function.name.0.last().map_or(false, |name| {
+                ["length", "char_length", "character_length"]
+                    .iter()
+                    .any(|length| name.value.eq_ignore_ascii_case(length))
+            });
+            match function.args.as_slice() {
+                [FunctionArg::Unnamed(FunctionArgExpr::Expr(arg))] => {
+                    is_length && is_column(arg, column)
+                }
+                _ => false,
+            }
+        }
+        Expr::Nested(expr) => is_length_of(expr, column),
+        _ => false,
+    }
+}
+
+/// Narrows `content` to the values that satisfy the parts of the `CHECK` constraints of `column`
+/// that compare it or its length with literals: comparisons, `BETWEEN` and `IN` lists joined by
+/// `AND`. Other conditions are left out.
+pub(crate) fn apply_checks<'a>(
+    content: &mut Content,
+    column: &str,
+    checks: impl IntoIterator<Item = &'a Expr>,
+) {
+    for check in checks {
+        apply_check(content, column, check);
+    }
+
+    if let Content::Number(number) = content {
+        match number {
+            NumberContent::F32(number_content::F32::Range(range)) => complete_float_range(range),
+            NumberContent::F64(number_content::F64::Range(range)) => complete_float_range(range),
+            _ => {}
+        }
+    }
+}
+
+fn apply_check(content: &mut Content, column: &str, check: &Expr) {
+    match check {
+        Expr::Nested(expr) => apply_check(content, column, expr),
+        Expr::BinaryOp {
+            left,
+            op: BinaryOperator::And,
+            right,
+        } => {
+            apply_check(content, column, left);
+            apply_check(content, column, right);
+        }
+        Expr::BinaryOp { left, op, right } => {
+            let of_length = is_length_of(left, column) || is_length_of(right, column);
+            let (value, op) = if is_column(left, column) || is_length_of(left, column) {
+                (literal(right), op.clone())
+            } else if is_column(right, column) || is_length_of(right, column) {
+                let flipped = match op {
+                    BinaryOperator::Gt => BinaryOperator::Lt,
+                    BinaryOperator::GtEq => BinaryOperator::LtEq,
+                    BinaryOperator::Lt => BinaryOperator::Gt,
+                    BinaryOperator::LtEq => BinaryOperator::GtEq,
+                    other => other.clone(),
+                };
+                (literal(left), flipped)
+            } else {
+                return;
+            };
+
+            if let Some(value) = value {
+                let bound = match op {
+                    BinaryOperator::Gt => Bound::Low {
+                        value,
+                        inclusive: false,
+                    },
+                    BinaryOperator::GtEq => Bound::Low {
+                        value,
+                        inclusive: true,
+                    },
+                    BinaryOperator::Lt => Bound::High {
+                        value,
+                        inclusive: false,
+                    },
+                    BinaryOperator::LtEq => Bound::High {
+                        value,
+                        inclusive: true,
+                    },
+                    _ => return,
+                };
+                if of_length {
+                    narrow_length(content, &bound);
+                } else {
+                    narrow(content, &bound);
+                }
+            }
+        }
+        Expr::Between {
+            expr,
+            negated: false,
+            low,
+            high,
+        } if is_column(expr, column) => {
+            if let Some(value) = literal(low) {
+                narrow(
+                    content,
+                    &Bound::Low {
+                        value,
+                        inclusive: true,
+                    },
+                );
+            }
+            if let Some(value) = literal(high) {
+                narrow(
+                    content,
+                    &Bound::High {
+                        value,
+                        inclusive: true,
+                    },
+                );
+            }
+        }
+        Expr::InList {
+            expr,
+            list,
+            negated: false,
+        } if is_column(expr, column) => {
+            if let Some(values) = list.iter().map(literal).collect::<Option<Vec<_>>>() {
+                restrict(content, values);
+            }
+        }
+        _ => {}
+    }
+}
+
+macro_rules! for_number_ranges {
+    ($number:expr, $range:ident => $body:expr) => {
+        match $number {
+            NumberContent::U32(number_content::U32::Range($range)) => $body,
+            NumberContent::U64(number_content::U64::Range($range)) => $body,
+            NumberContent::I32(number_content::I32::Range($range)) => $body,
+            NumberContent::I64(number_content::I64::Range($range)) => $body,
+            NumberContent::F32(number_content::F32::Range($range)) => $body,
+            NumberContent::F64(number_content::F64::Range($range)) => $body,
+            _ => {}
+        }
+    };
+}
+
+fn narrow(content: &mut Content, bound: &Bound) {
+    if let Content::Number(number) = content {
+        for_number_ranges!(number, range => narrow_range(range, bound));
+
+        // Float ranges can only leave out their lower bound when they have a step, so it is moved
+        // up to the next float instead
+        match number {
+            NumberContent::F32(number_content::F32::Range(range)) => exclude_float_low(range),
+            NumberContent::F64(number_content::F64::Range(range)) => exclude_float_low(range),
+            _ => {}
+        }
+    }
+}
+
+fn narrow_range<N: FromStr + PartialOrd>(range: &mut RangeStep<N>, bound: &Bound) {
+    match bound {
+        Bound::Low { value, inclusive } => {
+            if let Ok(value) = value.parse::<N>() {
+                let narrower = range.low.as_ref().map_or(true, |low| {
+                    value > *low || (value == *low && range.include_low && !inclusive)
+                });
+                if narrower {
+                    range.low = Some(value);
+                    range.include_low = *inclusive;
+                }
+            }
+        }
+        Bound::High { value, inclusive } => {
+            if let Ok(value) = value.parse::<N>() {
+                let narrower = range.high.as_ref().map_or(true, |high| {
+                    value < *high || (value == *high && range.include_high && !inclusive)
+                });
+                if narrower {
+                    range.high = Some(value);
+                    range.include_high = *inclusive;
+                }
+            }
+        }
+    }
+}
+
+/// The float types of number ranges.
+trait Float: Copy + PartialOrd + Add<Output = Self> + Sub<Output = Self> {
+    const ZERO: Self;
+
+    /// The smallest float greater than `self`.
+    fn next_up(self) -> Self;
+
+    /// How far from a bound the other bound of a range is set when it has none: as far as the
+    /// bound is from zero, and at least one.
+    fn span(self) -> Self;
+}
+
+macro_rules! float_impl {
+    ($float:ty) => {
+        impl Float for $float {
+            const ZERO: Self = 0.;
+
+            fn next_up(self) -> Self {
+                if self == 0. {
+                    <$float>::from_bits(1)
+                } else if self > 0. {
+                    <$float>::from_bits(self.to_bits() + 1)
+                } else {
+                    <$float>::from_bits(self.to_bits() - 1)
+                }
+            }
+
+            fn span(self) -> Self {
+                self.abs().max(1.)
+            }
+        }
+    };
+}
+
+float_impl!(f32);
+float_impl!(f64);
+
+fn exclude_float_low<F: Float>(range: &mut RangeStep<F>) {
+    if !range.include_low {
+        range.low = range.low.map(F::next_up);
+        range.include_low = true;
+    }
+}
+
+/// Sets the bounds a float range is left without when it has one. Float ranges otherwise go from
+/// zero to one, which a bound from a check can be beyond, leaving them empty.
+fn complete_float_range<F: Float>(range: &mut RangeStep<F>) {
+    match (range.low, range.high) {
+        (Some(low), None) => range.high = Some(low + low.span()),
+        (None, Some(high)) if high <= F::ZERO => range.low = Some(high - high.span()),
+        _ => {}
+    }
+}
+
+/// Narrows the length of the strings of `content`, if it is the pattern of alphanumeric strings
+/// text columns are imported as.
+fn narrow_length(content: &mut Content, bound: &Bound) {
+    let pattern = match content {
+        Content::String(StringContent::Pattern(pattern)) => pattern.to_string(),
+        _ => return,
+    };
+    let lengths = pattern
+        .strip_prefix(ALPHANUMERIC)
+        .and_then(|lengths| lengths.strip_prefix('{')?.strip_suffix('}'))
+        .and_then(|lengths| lengths.split_once(','));
+    let (mut low, mut high) = match lengths {
+        Some((low, high)) => match (low.trim().parse::<u64>(), high.trim().parse::<u64>()) {
+            (Ok(low), Ok(high)) => (low, high),
+            _ => return,
+        },
+        None => return,
+    };
+
+    match bound {
+        Bound::Low { value, inclusive } => {
+            if let Ok(value) = value.parse::<u64>() {
+                low = low.max(if *inclusive { value } else { value + 1 });
+            }
+        }
+        Bound::High { value, inclusive } => {
+            if let Ok(value) = value.parse::<u64>() {
+                let value = if *inclusive {
+                    Some(value)
+                } else {
+                    value.checked_sub(1)
+                };
+                match value {
+                    Some(value) => high = high.min(value),
+                    None => return,
+                }
+            }
+        }
+    }
+
+    // Text columns without a maximum length are imported with an arbitrary one, which a lower
+    // bound can be above
+    let high = high.max(low);
+    if let Ok(pattern) = RegexContent::pattern(format!("{}{{{},{}}}", ALPHANUMERIC, low, high)) {
+        *content = Content::String(StringContent::Pattern(pattern));
+    }
+}
+
+/// Restricts `content` to one of `values`, if they are all values of its type.
+fn restrict(content: &mut Content, values: Vec<String>) {
+    match content {
+        Content::String(_) => *content = categorical(values),
+        Content::Number(NumberContent::U32(number)) => {
+            if let Some(categorical) = parse_categorical(&values) {
+                *number = number_content::U32::Categorical(categorical);
+            }
+        }
+        Content::Number(NumberContent::U64(number)) => {
+            if let Some(categorical) = parse_categorical(&values) {
+                *number = number_content::U64::Categorical(categorical);
+            }
+        }
+        Content::Number(NumberContent::I32(number)) => {
+            if let Some(categorical) = parse_categorical(&values) {
+                *number = number_content::I32::Categorical(categorical);
+            }
+        }
+        Content::Number(NumberContent::I64(number)) => {
+            if let Some(categorical) = parse_categorical(&values) {
+                *number = number_content::I64::Categorical(categorical);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn parse_categorical<N: CategoricalType + Default>(values: &[String]) -> Option<Categorical<N>> {
+    let mut categorical = Categorical::default();
+    for value in values {
+        categorical.push(value.parse().ok()?);
+    }
+    Some(categorical)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::datasource::postgres_datasource::decode_column;
+    use crate::datasource::relational_datasource::ColumnInfo;
+    use synth_core::schema::number_content::{F64, I32, I64};
+
+    fn column(name: &str, data_type: &str, checks: &[&str]) -> ColumnInfo {
+        ColumnInfo {
+            column_name: name.to_string(),
+            ordinal_position: 1,
+            is_nullable: false,
+            is_custom_type: false,
+            data_type: data_type.to_string(),
+            character_maximum_length: None,
+            column_default: None,
+            enum_labels: Vec::new(),
+            is_unsigned: false,
+            is_generated: false,
+            checks: checks.iter().map(|check| check.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_decode_postgres_constraints() {
+        let age = column("age", "int4", &["CHECK (((age >= 18) AND (age <= 120)))"]);
+        match decode_column(&age).unwrap() {
+            Content::Number(NumberContent::I32(I32::Range(range))) => {
+                assert_eq!(range.low, Some(18));
+                assert_eq!(range.high, Some(120));
+                assert!(range.include_high);
+            }
+            otherwise => panic!("expected an i32 range, found {}", otherwise),
+        }
+
+        let status = column(
+            "status",
+            "varchar",
+            &["CHECK (((status)::text = ANY ((ARRAY['new'::character varying, 'paid'::character varying])::text[])))"],
+        );
+        assert_eq!(
+            decode_column(&status).unwrap(),
+            categorical(vec!["new".to_string(), "paid".to_string()])
+        );
+
+        let mut name = column(
+            "name",
+            "varchar",
+            &["CHECK ((length((name)::text) >= 3)) NOT VALID"],
+        );
+        name.character_maximum_length = Some(20);
+        match decode_column(&name).unwrap() {
+            Content::String(StringContent::Pattern(pattern)) => {
+                assert_eq!(pattern.to_string(), "[a-zA-Z0-9]{3,20}")
+            }
+            otherwise => panic!("expected a pattern, found {}", otherwise),
+        }
+
+        let price = column("price", "numeric", &["CHECK ((price >= (10)::numeric))"]);
+        match decode_column(&price).unwrap() {
+            Content::Number(NumberContent::F64(F64::Range(range))) => {
+                assert_eq!(range.low, Some(10.0));
+                assert!(range.include_low);
+                assert_eq!(range.high, Some(20.0));
+            }
+            otherwise => panic!("expected an f64 range, found {}", otherwise),
+        }
+
+        let rate = column(
+            "rate",
+            "float8",
+            &["CHECK (((rate > (0)::double precision) AND (rate < (0.5)::double precision)))"],
+        );
+        match decode_column(&rate).unwrap() {
+            Content::Number(NumberContent::F64(F64::Range(range))) => {
+                assert!(range.low.unwrap() > 0.0);
+                assert!(range.include_low);
+                assert_eq!(range.high, Some(0.5));
+                assert!(!range.include_high);
+            }
+            otherwise => panic!("expected an f64 range, found {}", otherwise),
+        }
+
+        let mut mood = column("mood", "mood", &[]);
+        mood.is_custom_type = true;
+        mood.enum_labels = vec!["sad".to_string(), "happy".to_string()];
+        assert_eq!(
+            decode_column(&mood).unwrap(),
+            categorical(vec!["sad".to_string(), "happy".to_string()])
+        );
+
+        let mut id = column("id", "int8", &[]);
+        id.column_default = Some("nextval('users_id_seq'::regclass)".to_string());
+        assert!(matches!(
+            decode_column(&id).unwrap(),
+            Content::Number(NumberContent::I64(I64::Id(_)))
+        ));
+    }
+}
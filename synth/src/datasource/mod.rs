@@ -2,9 +2,11 @@ use anyhow::Result;
 use async_trait::async_trait;
 use synth_core::Value;
 
+pub(crate) mod check;
 pub(crate) mod mysql_datasource;
 pub(crate) mod postgres_datasource;
 pub(crate) mod relational_datasource;
+pub(crate) mod sqlite_datasource;
 
 /// This trait encompasses all data source types, whether it's SQL or No-SQL. APIs should be defined
 /// async when possible, delegating to the caller on how to handle it. Data source specific
@@ -17,5 +19,25 @@ pub trait DataSource {
     where
         Self: Sized;
 
+    /// Checks the first value generated for a collection against the data source before any of
+    /// the collection is inserted.
+    async fn check_data(&self, collection_name: &str, first: &Value) -> Result<()>;
+
+    /// Inserts a chunk of values into a collection. Large generations are inserted one chunk at
+    /// a time, so this can be called several times for the same collection.
     async fn insert_data(&self, collection_name: &str, collection: &[Value]) -> Result<()>;
+
+    /// Starts a transaction which the following inserts are made in, until it is ended by
+    /// [`commit`](Self::commit) or [`rollback`](Self::rollback).
+    async fn begin(&self) -> Result<()>;
+
+    async fn commit(&self) -> Result<()>;
+
+    async fn rollback(&self) -> Result<()>;
+
+    /// Removes all of the values of the given collections. Postgres and MySQL also remove the
+    /// values in other collections referring to them, while SQLite defers foreign key checks to
+    /// the end of the transaction, so it fails to commit if values still refer to removed rows by
+    /// then.
+    async fn truncate(&self, collection_names: &[String]) -> Result<()>;
 }
@@ -1,14 +1,18 @@
+use crate::datasource::check::categorical;
 use crate::datasource::relational_datasource::{
-    insert_relational_data, ColumnInfo, ForeignKey, PrimaryKey, SqlxDataSource, ValueWrapper,
+    begin_relational_transaction, check_relational_data, commit_relational_transaction,
+    insert_relational_data, rollback_relational_transaction, truncate_relational_data, ColumnInfo,
+    ForeignKey, PrimaryKey, SampleStrategy, SqlxDataSource, UniqueKey, ValueWrapper,
 };
 use crate::datasource::DataSource;
 use anyhow::{Context, Result};
+use async_std::sync::Mutex;
 use async_std::task;
 use async_trait::async_trait;
 use rust_decimal::prelude::ToPrimitive;
 use rust_decimal::Decimal;
 use sqlx::mysql::{MySqlColumn, MySqlPoolOptions, MySqlRow};
-use sqlx::{Column, MySql, Pool, Row, TypeInfo};
+use sqlx::{Column, MySql, Pool, Row, Transaction, TypeInfo};
 use std::collections::BTreeMap;
 use std::convert::TryFrom;
 use std::prelude::rust_2015::Result::Ok;
@@ -26,6 +30,7 @@ use synth_gen::prelude::*;
 
 pub struct MySqlDataSource {
     pool: Pool<MySql>,
+    transaction: Mutex<Option<Transaction<'static, MySql>>>,
 }
 
 #[async_trait]
@@ -39,18 +44,73 @@ impl DataSource for MySqlDataSource {
                 .connect(connect_params.as_str())
                 .await?;
 
-            Ok::<Self, anyhow::Error>(MySqlDataSource { pool })
+            Ok::<Self, anyhow::Error>(MySqlDataSource {
+                pool,
+                transaction: Mutex::new(None),
+            })
         })
     }
 
+    async fn check_data(&self, collection_name: &str, first: &Value) -> Result<()> {
+        check_relational_data::<Self>(self, collection_name, first).await
+    }
+
     async fn insert_data(&self, collection_name: &str, collection: &[Value]) -> Result<()> {
-        insert_relational_data(self, collection_name, collection).await
+        insert_relational_data::<Self>(self, collection_name, collection).await
+    }
+
+    async fn begin(&self) -> Result<()> {
+        begin_relational_transaction::<Self>(self).await
+    }
+
+    async fn commit(&self) -> Result<()> {
+        commit_relational_transaction::<Self>(self).await
+    }
+
+    async fn rollback(&self) -> Result<()> {
+        rollback_relational_transaction::<Self>(self).await
+    }
+
+    async fn truncate(&self, collection_names: &[String]) -> Result<()> {
+        let table_names = self.with_referencing_tables(collection_names).await?;
+        truncate_relational_data::<Self>(self, &table_names).await
+    }
+}
+
+impl MySqlDataSource {
+    /// Adds to `table_names` every table referring to one of them, directly or through other
+    /// tables, as emptying a table without them would leave their rows referring to nothing.
+    async fn with_referencing_tables(&self, table_names: &[String]) -> Result<Vec<String>> {
+        let query = sqlx::query_as::<_, (String, String)>(
+            r"SELECT table_name, referenced_table_name
+                FROM information_schema.referential_constraints
+                WHERE constraint_schema = DATABASE()",
+        );
+        let references = match self.transaction.lock().await.as_mut() {
+            Some(transaction) => query.fetch_all(&mut **transaction).await?,
+            None => query.fetch_all(&self.pool).await?,
+        };
+
+        let mut table_names = table_names.to_vec();
+        let mut i = 0;
+        while i < table_names.len() {
+            for (from_table, to_table) in &references {
+                if *to_table == table_names[i] && !table_names.contains(from_table) {
+                    info!(
+                        "Also truncating {}, which refers to {}",
+                        from_table, to_table
+                    );
+                    table_names.push(from_table.clone());
+                }
+            }
+            i += 1;
+        }
+        Ok(table_names)
     }
 }
 
 impl SqlxDataSource for MySqlDataSource {
     type DB = MySql;
-    type Arguments = sqlx::mysql::MySqlArguments;
     type Connection = sqlx::mysql::MySqlConnection;
 
     const IDENTIFIER_QUOTE: char = '`';
@@ -63,6 +123,10 @@ impl SqlxDataSource for MySqlDataSource {
         Pool::clone(&self.pool)
     }
 
+    fn get_transaction(&self) -> &Mutex<Option<Transaction<'static, Self::DB>>> {
+        &self.transaction
+    }
+
     fn get_table_names_query(&self) -> &str {
         r"SELECT table_name FROM information_schema.tables
             WHERE table_schema = DATABASE() and table_type = 'BASE TABLE'"
@@ -71,82 +135,142 @@ impl SqlxDataSource for MySqlDataSource {
     fn get_primary_keys_query(&self) -> &str {
         r"SELECT column_name, data_type
             FROM information_schema.columns
-            WHERE table_schema = DATABASE() AND table_name = ? AND column_key = 'PRI'"
+            WHERE table_schema = DATABASE() AND table_name = ? AND column_key = 'PRI'
+            ORDER BY ordinal_position"
     }
 
-    fn get_foreign_keys_query(&self) -> &str {
-        r"SELECT table_name, column_name, referenced_table_name, referenced_column_name
-            FROM information_schema.key_column_usage
-            WHERE referenced_table_schema = DATABASE()"
+    fn get_unique_keys_query(&self) -> &str {
+        r"SELECT index_name, column_name
+            FROM information_schema.statistics
+            WHERE table_schema = DATABASE() AND table_name = ? AND non_unique = 0
+                AND index_name <> 'PRIMARY'
+            ORDER BY index_name, seq_in_index"
     }
 
-    fn get_deterministic_samples_query(&self, table_name: String) -> String {
-        format!("SELECT * FROM {} ORDER BY rand(0.5) LIMIT 10", table_name)
+    fn get_foreign_keys_query(&self) -> &str {
+        r"SELECT table_name, column_name, referenced_table_name, referenced_column_name,
+                constraint_name
+            FROM information_schema.key_column_usage
+            WHERE referenced_table_schema = DATABASE()
+            ORDER BY table_name, constraint_name, ordinal_position"
     }
 
-    fn decode_to_content(&self, column_info: &ColumnInfo) -> Result<Content> {
-        let content = match column_info.data_type.to_lowercase().as_str() {
-            "char" | "varchar" | "text" | "binary" | "varbinary" | "enum" | "set" => {
-                let pattern = "[a-zA-Z0-9]{0, {}}".replace(
-                    "{}",
-                    &format!("{}", column_info.character_maximum_length.unwrap_or(1)),
-                );
-                Content::String(StringContent::Pattern(
-                    RegexContent::pattern(pattern).context("pattern will always compile")?,
-                ))
-            }
-            "int" | "integer" | "tinyint" | "smallint" | "mediumint" | "bigint" => {
-                Content::Number(NumberContent::I64(I64::Range(RangeStep::default())))
-            }
-            "serial" => Content::Number(NumberContent::U64(U64::Range(RangeStep::default()))),
-            "float" | "double" | "numeric" | "decimal" => {
-                Content::Number(NumberContent::F64(F64::Range(RangeStep::default())))
+    fn get_samples_query(
+        &self,
+        table_name: &str,
+        strategy: SampleStrategy,
+        rows: Option<usize>,
+    ) -> Result<String> {
+        let query = match (strategy, rows) {
+            (_, None) => format!("SELECT * FROM `{}`", table_name),
+            (SampleStrategy::TableSample, Some(_)) => {
+                bail!("MySQL doesn't support TABLESAMPLE. Sample with 'random' or 'head' instead.")
             }
-            "timestamp" => Content::DateTime(DateTimeContent {
-                format: "".to_string(), // todo
-                type_: ChronoValueType::NaiveDateTime,
-                begin: None,
-                end: None,
-            }),
-            "date" => Content::DateTime(DateTimeContent {
-                format: "%Y-%m-%d".to_string(),
-                type_: ChronoValueType::NaiveDate,
-                begin: None,
-                end: None,
-            }),
-            "datetime" => Content::DateTime(DateTimeContent {
-                format: "%Y-%m-%d %H:%M:%S".to_string(),
-                type_: ChronoValueType::NaiveDateTime,
-                begin: None,
-                end: None,
-            }),
-            "time" => Content::DateTime(DateTimeContent {
-                format: "%H:%M:%S".to_string(),
-                type_: ChronoValueType::NaiveTime,
-                begin: None,
-                end: None,
-            }),
-            _ => bail!(
-                "We haven't implemented a converter for {}",
-                column_info.data_type
+            (SampleStrategy::Random, Some(rows)) => format!(
+                "SELECT * FROM `{}` ORDER BY rand(0.5) LIMIT {}",
+                table_name, rows
             ),
+            (SampleStrategy::Head, Some(rows)) => {
+                format!("SELECT * FROM `{}` LIMIT {}", table_name, rows)
+            }
         };
+        Ok(query)
+    }
 
-        Ok(content)
+    fn decode_to_content(&self, column_info: &ColumnInfo) -> Result<Content> {
+        decode_column(column_info)
     }
 
     fn get_columns_info_query(&self) -> &str {
         r"SELECT column_name, ordinal_position, is_nullable, data_type,
-            character_maximum_length
+            character_maximum_length, column_type, extra
             FROM information_schema.columns
             WHERE table_name = ? AND table_schema = DATABASE()"
     }
+
+    fn get_truncate_queries(&self, table_names: &[String]) -> Vec<String> {
+        // TRUNCATE would commit the transaction, so rows are deleted instead, with foreign key
+        // checks turned off to not depend on the order the tables are emptied in. The tables
+        // referring to them are emptied too (see `with_referencing_tables`)
+        let mut queries = vec!["SET FOREIGN_KEY_CHECKS = 0".to_string()];
+        queries.extend(
+            table_names
+                .iter()
+                .map(|table_name| format!("DELETE FROM {}", Self::quote_identifier(table_name))),
+        );
+        queries.push("SET FOREIGN_KEY_CHECKS = 1".to_string());
+        queries
+    }
+}
+
+/// Decodes the content of a column from its `data_type` in `information_schema.columns`.
+pub(crate) fn decode_column(column_info: &ColumnInfo) -> Result<Content> {
+    let content = match column_info.data_type.to_lowercase().as_str() {
+        "enum" | "set" => categorical(column_info.enum_labels.clone()),
+        "char" | "varchar" | "text" | "binary" | "varbinary" => {
+            let pattern = "[a-zA-Z0-9]{0, {}}".replace(
+                "{}",
+                &format!("{}", column_info.character_maximum_length.unwrap_or(1)),
+            );
+            Content::String(StringContent::Pattern(
+                RegexContent::pattern(pattern).context("pattern will always compile")?,
+            ))
+        }
+        "int" | "integer" | "tinyint" | "smallint" | "mediumint" | "bigint" => {
+            if column_info.is_unsigned {
+                Content::Number(NumberContent::U64(U64::Range(RangeStep::default())))
+            } else {
+                Content::Number(NumberContent::I64(I64::Range(RangeStep::default())))
+            }
+        }
+        "serial" => Content::Number(NumberContent::U64(U64::Range(RangeStep::default()))),
+        "float" | "double" | "numeric" | "decimal" => {
+            Content::Number(NumberContent::F64(F64::Range(RangeStep::default())))
+        }
+        "timestamp" => Content::DateTime(DateTimeContent {
+            format: "".to_string(), // todo
+            type_: ChronoValueType::NaiveDateTime,
+            begin: None,
+            end: None,
+        }),
+        "date" => Content::DateTime(DateTimeContent {
+            format: "%Y-%m-%d".to_string(),
+            type_: ChronoValueType::NaiveDate,
+            begin: None,
+            end: None,
+        }),
+        "datetime" => Content::DateTime(DateTimeContent {
+            format: "%Y-%m-%d %H:%M:%S".to_string(),
+            type_: ChronoValueType::NaiveDateTime,
+            begin: None,
+            end: None,
+        }),
+        "time" => Content::DateTime(DateTimeContent {
+            format: "%H:%M:%S".to_string(),
+            type_: ChronoValueType::NaiveTime,
+            begin: None,
+            end: None,
+        }),
+        _ => bail!(
+            "We haven't implemented a converter for {}",
+            column_info.data_type
+        ),
+    };
+
+    Ok(content)
 }
 
 impl TryFrom<MySqlRow> for ColumnInfo {
     type Error = anyhow::Error;
 
     fn try_from(row: MySqlRow) -> Result<Self, Self::Error> {
+        let (enum_labels, is_unsigned) = parse_column_type(&row.try_get::<String, usize>(5)?);
+        let extra = row.try_get::<String, usize>(6)?.to_lowercase();
+        // `extra` also holds `DEFAULT_GENERATED` for columns with an expression as their default
+        let is_generated = extra
+            .split_whitespace()
+            .any(|word| word == "auto_increment" || word == "generated");
+
         Ok(ColumnInfo {
             column_name: row.try_get::<String, usize>(0)?,
             ordinal_position: row.try_get::<u32, usize>(1)? as i32,
@@ -154,10 +278,48 @@ impl TryFrom<MySqlRow> for ColumnInfo {
             data_type: row.try_get::<String, usize>(3)?,
             character_maximum_length: extract_column_char_max_len(4, row)?,
             is_custom_type: false,
+            column_default: None,
+            enum_labels,
+            checks: Vec::new(),
+            is_unsigned,
+            is_generated,
         })
     }
 }
 
+/// Reads the labels of an `enum` or the members of a `set` from the `column_type` of a column, like
+/// `enum('a','b')`, and whether the column is `unsigned`, like `int(10) unsigned` ones.
+fn parse_column_type(column_type: &str) -> (Vec<String>, bool) {
+    let lowercase = column_type.to_lowercase();
+    if !lowercase.starts_with("enum(") && !lowercase.starts_with("set(") {
+        let is_unsigned = lowercase.split_whitespace().any(|word| word == "unsigned");
+        return (Vec::new(), is_unsigned);
+    }
+
+    // Labels are quoted, with the quotes in them doubled
+    let mut labels = Vec::new();
+    let mut label = String::new();
+    let mut in_label = false;
+    let mut chars = column_type.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\'' {
+            if in_label {
+                label.push(c);
+            }
+        } else if !in_label {
+            in_label = true;
+        } else if chars.peek() == Some(&'\'') {
+            chars.next();
+            label.push('\'');
+        } else {
+            in_label = false;
+            labels.push(std::mem::take(&mut label));
+        }
+    }
+
+    (labels, false)
+}
+
 /// Extracts a column's max character length. MySql's datatype for max char length is INT, but for
 /// Mariadb it's BIGINT UNSIGNED, so we must try both rust data types when reading the row. We
 /// truncate i64 to i32 in order to fit our internal models and practically, we probably won't be
@@ -182,6 +344,17 @@ impl TryFrom<MySqlRow> for PrimaryKey {
     }
 }
 
+impl TryFrom<MySqlRow> for UniqueKey {
+    type Error = anyhow::Error;
+
+    fn try_from(row: MySqlRow) -> Result<Self, Self::Error> {
+        Ok(UniqueKey {
+            index_name: row.try_get(0)?,
+            column_name: row.try_get(1)?,
+        })
+    }
+}
+
 impl TryFrom<MySqlRow> for ForeignKey {
     type Error = anyhow::Error;
 
@@ -191,6 +364,7 @@ impl TryFrom<MySqlRow> for ForeignKey {
             from_column: row.try_get(1)?,
             to_table: row.try_get(2)?,
             to_column: row.try_get(3)?,
+            constraint_name: row.try_get(4)?,
         })
     }
 }
@@ -215,6 +389,14 @@ fn try_match_value(row: &MySqlRow, column: &MySqlColumn) -> Result<Value> {
         "char" | "varchar" | "text" | "binary" | "varbinary" | "enum" | "set" => {
             Value::String(row.try_get::<String, &str>(column.name())?)
         }
+        "tinyint unsigned" => Value::Number(Number::from(row.try_get::<u8, &str>(column.name())?)),
+        "smallint unsigned" => {
+            Value::Number(Number::from(row.try_get::<u16, &str>(column.name())?))
+        }
+        "mediumint unsigned" | "int unsigned" => {
+            Value::Number(Number::from(row.try_get::<u32, &str>(column.name())?))
+        }
+        "bigint unsigned" => Value::Number(Number::from(row.try_get::<u64, &str>(column.name())?)),
         "tinyint" => Value::Number(Number::from(row.try_get::<i8, &str>(column.name())?)),
         "smallint" => Value::Number(Number::from(row.try_get::<i16, &str>(column.name())?)),
         "mediumint" | "int" | "integer" => {
@@ -256,3 +438,40 @@ fn try_match_value(row: &MySqlRow, column: &MySqlColumn) -> Result<Value> {
 
     Ok(value)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_column_type() {
+        assert_eq!(
+            parse_column_type("enum('small','it''s medium','large, really')"),
+            (
+                vec![
+                    "small".to_string(),
+                    "it's medium".to_string(),
+                    "large, really".to_string()
+                ],
+                false
+            )
+        );
+        assert_eq!(
+            parse_column_type("set('a','b')"),
+            (vec!["a".to_string(), "b".to_string()], false)
+        );
+        assert_eq!(parse_column_type("int(10) unsigned"), (Vec::new(), true));
+        assert_eq!(
+            parse_column_type("bigint unsigned zerofill"),
+            (Vec::new(), true)
+        );
+        assert_eq!(parse_column_type("int(11)"), (Vec::new(), false));
+        assert_eq!(parse_column_type("varchar(255)"), (Vec::new(), false));
+    }
+
+    #[test]
+    fn test_quote_identifier() {
+        assert_eq!(MySqlDataSource::quote_identifier("users"), "`users`");
+        assert_eq!(MySqlDataSource::quote_identifier("my`table"), "`my``table`");
+    }
+}
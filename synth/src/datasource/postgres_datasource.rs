@@ -1,33 +1,73 @@
+use crate::datasource::check::{apply_checks, categorical, parse_postgres_check};
 use crate::datasource::relational_datasource::{
-    insert_relational_data, ColumnInfo, ForeignKey, PrimaryKey, SqlxDataSource, ValueWrapper,
+    begin_relational_transaction, check_relational_data, commit_relational_transaction,
+    get_columns_info, insert_relational_data, rollback_relational_transaction,
+    truncate_relational_data, ColumnInfo, ForeignKey, PrimaryKey, SampleStrategy, SqlxDataSource,
+    UniqueKey, ValueWrapper,
 };
 use crate::datasource::DataSource;
 use anyhow::{Context, Result};
-use async_std::sync::Arc;
+use async_std::sync::{Arc, Mutex};
 use async_std::task;
 use async_trait::async_trait;
 use rust_decimal::prelude::ToPrimitive;
 use rust_decimal::Decimal;
-use sqlx::postgres::{PgColumn, PgPoolOptions, PgRow, PgTypeInfo, PgTypeKind};
-use sqlx::{Column, Executor, Pool, Postgres, Row, TypeInfo};
-use std::collections::BTreeMap;
-use std::convert::TryFrom;
-use synth_core::schema::number_content::{F32, F64, I32, I64};
+use sqlx::postgres::types::{PgInterval, PgMoney, PgRange};
+use sqlx::postgres::{
+    PgArguments, PgColumn, PgConnection, PgCopyIn, PgPoolOptions, PgRow, PgTypeInfo, PgTypeKind,
+};
+use sqlx::{Column, Executor, Pool, Postgres, Row, Transaction, TypeInfo};
+use std::collections::{BTreeMap, HashMap};
+use std::convert::{TryFrom, TryInto};
+use std::net::IpAddr;
+use std::ops::DerefMut;
+use std::str::FromStr;
+use synth_core::graph::json::synth_val_to_json;
+use synth_core::graph::string::FakerArgs;
+use synth_core::schema::number_content::{F32, F64, I32, I64, U32};
 use synth_core::schema::{
-    ArrayContent, BoolContent, Categorical, ChronoValue, ChronoValueAndFormat, ChronoValueType,
-    DateTimeContent, NumberContent, ObjectContent, RangeStep, RegexContent, StringContent, Uuid,
+    ArrayContent, BoolContent, ChronoValue, ChronoValueAndFormat, ChronoValueType, DateTimeContent,
+    FakerContent, NumberContent, ObjectContent, RangeStep, RegexContent, StringContent, Uuid,
 };
 use synth_core::{Content, Value};
+use synth_gen::value::Number;
 
 pub struct PostgresConnectParams {
     pub(crate) uri: String,
     pub(crate) schema: Option<String>,
+    pub(crate) load: PostgresLoad,
+}
+
+/// How generated rows are loaded into tables.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PostgresLoad {
+    /// Batched multi-row `INSERT` statements
+    Insert,
+    /// `COPY ... FROM STDIN` in the text format
+    Copy,
+}
+
+impl FromStr for PostgresLoad {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "insert" => Ok(Self::Insert),
+            "copy" => Ok(Self::Copy),
+            _ => Err(anyhow!(
+                "Load method '{}' not recognised. Was expecting one of 'insert' or 'copy'.",
+                s
+            )),
+        }
+    }
 }
 
 pub struct PostgresDataSource {
     pool: Pool<Postgres>,
     single_thread_pool: Pool<Postgres>,
     schema: String, // consider adding a type schema
+    load: PostgresLoad,
+    transaction: Mutex<Option<Transaction<'static, Postgres>>>,
 }
 
 #[async_trait]
@@ -77,12 +117,39 @@ impl DataSource for PostgresDataSource {
                 pool,
                 single_thread_pool,
                 schema,
+                load: connect_params.load,
+                transaction: Mutex::new(None),
             })
         })
     }
 
+    async fn check_data(&self, collection_name: &str, first: &Value) -> Result<()> {
+        check_relational_data::<Self>(self, collection_name, first).await
+    }
+
     async fn insert_data(&self, collection_name: &str, collection: &[Value]) -> Result<()> {
-        insert_relational_data(self, collection_name, collection).await
+        match self.load {
+            PostgresLoad::Insert => {
+                insert_relational_data::<Self>(self, collection_name, collection).await
+            }
+            PostgresLoad::Copy => self.copy_data(collection_name, collection).await,
+        }
+    }
+
+    async fn begin(&self) -> Result<()> {
+        begin_relational_transaction::<Self>(self).await
+    }
+
+    async fn commit(&self) -> Result<()> {
+        commit_relational_transaction::<Self>(self).await
+    }
+
+    async fn rollback(&self) -> Result<()> {
+        rollback_relational_transaction::<Self>(self).await
+    }
+
+    async fn truncate(&self, collection_names: &[String]) -> Result<()> {
+        truncate_relational_data::<Self>(self, collection_names).await
     }
 }
 
@@ -110,12 +177,172 @@ impl PostgresDataSource {
 
         Ok(())
     }
+
+    /// Loads rows with a single `COPY ... FROM STDIN`, which is much faster than `INSERT`
+    /// statements and isn't limited in the number of values it takes. Falls back to `INSERT`
+    /// statements if a value can't be written in the text format of its column.
+    async fn copy_data(&self, collection_name: &str, collection: &[Value]) -> Result<()> {
+        if collection.is_empty() {
+            return Ok(());
+        }
+
+        let column_infos = get_columns_info::<Self>(self, collection_name.to_string())
+            .await?
+            .into_iter()
+            .map(|column_info| (column_info.column_name.clone(), column_info))
+            .collect::<HashMap<_, _>>();
+
+        let column_names = collection[0]
+            .as_object()
+            .expect("This is always an object (sampler contract)")
+            .keys()
+            .collect::<Vec<_>>();
+
+        let mut data = String::new();
+        for row in collection {
+            let row_obj = row
+                .as_object()
+                .expect("This is always an object (sampler contract)");
+
+            match copy_row(row_obj, &column_names, &column_infos) {
+                Ok(line) => data.push_str(&line),
+                Err(column_name) => {
+                    debug!(
+                        "Column {}.{} can't be copied, inserting the rows instead",
+                        collection_name, column_name
+                    );
+                    return insert_relational_data::<Self>(self, collection_name, collection).await;
+                }
+            }
+        }
+
+        let statement = format!(
+            "COPY {} ({}) FROM STDIN",
+            quote_identifier(collection_name),
+            column_names
+                .iter()
+                .map(|column_name| quote_identifier(column_name))
+                .collect::<Vec<_>>()
+                .join(",")
+        );
+
+        let data = data.into_bytes();
+        let rows = match self.transaction.lock().await.as_mut() {
+            Some(transaction) => {
+                let copy = transaction.copy_in_raw(&statement).await?;
+                send_copy_data(copy, data).await
+            }
+            None => send_copy_data(self.pool.copy_in_raw(&statement).await?, data).await,
+        }
+        .with_context(|| format!("Copying into {} failed", collection_name))?;
+
+        info!("Copied {} rows...", rows);
+        Ok(())
+    }
+}
+
+/// Sends the data of a `COPY ... FROM STDIN`, aborting it if that fails, and returns the number of
+/// rows copied.
+async fn send_copy_data<C: DerefMut<Target = PgConnection>>(
+    mut copy: PgCopyIn<C>,
+    data: Vec<u8>,
+) -> Result<u64> {
+    let sent = copy.send(data).await.map(|_| ());
+    if let Err(err) = sent {
+        copy.abort(err.to_string()).await?;
+        return Err(err.into());
+    }
+
+    Ok(copy.finish().await?)
+}
+
+fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// Writes a row in the text format of `COPY`, with the values of `column_names` in order, or
+/// returns the name of the first column whose value can't be written.
+fn copy_row<'c>(
+    row: &BTreeMap<String, Value>,
+    column_names: &[&'c String],
+    column_infos: &HashMap<String, ColumnInfo>,
+) -> Result<String, &'c String> {
+    let fields = column_names
+        .iter()
+        .map(|column_name| {
+            copy_text(row.get(*column_name), column_infos.get(*column_name)).ok_or(*column_name)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(fields.join("\t") + "\n")
+}
+
+/// Writes a value in the text format `COPY` reads values of the column from, or returns `None` if
+/// it can't be. Enums are read from their labels and `json`/`jsonb` columns from any JSON value.
+fn copy_text(value: Option<&Value>, column_info: Option<&ColumnInfo>) -> Option<String> {
+    let data_type = column_info.map_or("", |info| info.data_type.as_str());
+
+    let value = match value {
+        None | Some(Value::Null(_)) => return Some("\\N".to_string()),
+        Some(value) => value,
+    };
+
+    let text = match value {
+        Value::Null(_) => return Some("\\N".to_string()),
+        value @ (Value::Array(_) | Value::Object(_))
+            if data_type == "json" || data_type == "jsonb" =>
+        {
+            synth_val_to_json(value.clone()).to_string()
+        }
+        value @ Value::Array(_) if data_type.starts_with('_') => value.to_postgres_string(),
+        Value::Array(_) => return None,
+        value @ Value::Object(_) => synth_val_to_json(value.clone()).to_string(),
+        Value::Bool(b) => b.to_string(),
+        Value::Number(number) => match *number {
+            // Unsigned integers are stored in the signed type of the same size
+            Number::U8(u) => (u as i8).to_string(),
+            Number::U16(u) => (u as i16).to_string(),
+            Number::U32(u) => (u as i32).to_string(),
+            Number::U64(u) => (u as i64).to_string(),
+            Number::F32(f) => copy_float(f.into_inner() as f64, f.to_string()),
+            Number::F64(f) => copy_float(f.into_inner(), f.to_string()),
+            integer => integer.to_string(),
+        },
+        Value::String(s) => s.clone(),
+        Value::DateTime(ChronoValueAndFormat { value, .. }) => match value {
+            ChronoValue::NaiveDate(date) => date.format("%Y-%m-%d").to_string(),
+            ChronoValue::NaiveTime(time) => time.format("%H:%M:%S%.f").to_string(),
+            ChronoValue::NaiveDateTime(date_time) => {
+                date_time.format("%Y-%m-%d %H:%M:%S%.f").to_string()
+            }
+            ChronoValue::DateTime(date_time) => {
+                date_time.format("%Y-%m-%d %H:%M:%S%.f%:z").to_string()
+            }
+        },
+    };
+
+    Some(
+        text.replace('\\', "\\\\")
+            .replace('\t', "\\t")
+            .replace('\n', "\\n")
+            .replace('\r', "\\r"),
+    )
+}
+
+fn copy_float(f: f64, literal: String) -> String {
+    if f.is_nan() {
+        "NaN".to_string()
+    } else if f.is_infinite() && f > 0.0 {
+        "Infinity".to_string()
+    } else if f.is_infinite() {
+        "-Infinity".to_string()
+    } else {
+        literal
+    }
 }
 
 #[async_trait]
 impl SqlxDataSource for PostgresDataSource {
     type DB = Postgres;
-    type Arguments = sqlx::postgres::PgArguments;
     type Connection = sqlx::postgres::PgConnection;
 
     const IDENTIFIER_QUOTE: char = '\"';
@@ -128,7 +355,11 @@ impl SqlxDataSource for PostgresDataSource {
         Pool::clone(&self.pool)
     }
 
-    fn query<'q>(&self, query: &'q str) -> sqlx::query::Query<'q, Self::DB, Self::Arguments> {
+    fn get_transaction(&self) -> &Mutex<Option<Transaction<'static, Self::DB>>> {
+        &self.transaction
+    }
+
+    fn query<'q>(&self, query: &'q str) -> sqlx::query::Query<'q, Self::DB, PgArguments> {
         sqlx::query(query).bind(self.schema.clone())
     }
 
@@ -144,24 +375,39 @@ impl SqlxDataSource for PostgresDataSource {
         r"SELECT a.attname, format_type(a.atttypid, a.atttypmod) AS data_type
         FROM pg_index i
         JOIN pg_attribute a ON a.attrelid = i.indrelid AND a.attnum = ANY(i.indkey)
-        WHERE  i.indrelid = cast($2 as regclass) AND i.indisprimary"
+        WHERE  i.indrelid = cast($2 as regclass) AND i.indisprimary
+        ORDER BY array_position(i.indkey::int2[], a.attnum)"
+    }
+
+    fn get_unique_keys_query(&self) -> &str {
+        r"SELECT c.relname AS index_name, a.attname
+        FROM pg_index i
+        JOIN pg_class c ON c.oid = i.indexrelid
+        JOIN pg_attribute a ON a.attrelid = i.indrelid AND a.attnum = ANY(i.indkey)
+        WHERE i.indrelid = cast($2 as regclass) AND i.indisunique AND NOT i.indisprimary
+            AND i.indpred IS NULL AND i.indexprs IS NULL
+        ORDER BY c.relname, array_position(i.indkey::int2[], a.attnum)"
     }
 
     fn get_foreign_keys_query(&self) -> &str {
-        r"SELECT tc.table_name, kcu.column_name, ccu.table_name AS foreign_table_name,
-            ccu.column_name AS foreign_column_name
-            FROM information_schema.table_constraints AS tc
-            JOIN information_schema.key_column_usage AS kcu
-            ON tc.constraint_name = kcu.constraint_name
-            JOIN information_schema.constraint_column_usage AS ccu
-            ON ccu.constraint_name = tc.constraint_name
-            WHERE constraint_type = 'FOREIGN KEY'
-            and tc.table_schema = $1
-            and tc.table_catalog = current_catalog"
+        // constraint_column_usage doesn't pair up the columns of composite foreign keys, so this
+        // unnests the columns of the constraints instead
+        r"SELECT t.relname, a.attname, ft.relname AS foreign_table_name,
+            fa.attname AS foreign_column_name, c.conname
+            FROM pg_constraint c
+            JOIN pg_namespace n ON n.oid = c.connamespace
+            CROSS JOIN LATERAL unnest(c.conkey, c.confkey) WITH ORDINALITY AS k(attnum, fattnum, position)
+            JOIN pg_class t ON t.oid = c.conrelid
+            JOIN pg_attribute a ON a.attrelid = c.conrelid AND a.attnum = k.attnum
+            JOIN pg_class ft ON ft.oid = c.confrelid
+            JOIN pg_attribute fa ON fa.attrelid = c.confrelid AND fa.attnum = k.fattnum
+            WHERE c.contype = 'f'
+            AND n.nspname = $1
+            ORDER BY t.relname, c.conname, k.position"
     }
 
     /// Must use the singled threaded pool when setting this in conjunction with random, called by
-    /// [get_deterministic_samples]. Otherwise, expect endless facepalms (-_Q)
+    /// [get_samples]. Otherwise, expect endless facepalms (-_Q)
     async fn set_seed(&self) -> Result<()> {
         sqlx::query("SELECT setseed(0.5)")
             .execute(&self.single_thread_pool)
@@ -169,111 +415,261 @@ impl SqlxDataSource for PostgresDataSource {
         Ok(())
     }
 
-    fn get_deterministic_samples_query(&self, table_name: String) -> String {
-        format!("SELECT * FROM {} ORDER BY random() LIMIT 10", table_name)
+    fn get_samples_query(
+        &self,
+        table_name: &str,
+        strategy: SampleStrategy,
+        rows: Option<usize>,
+    ) -> Result<String> {
+        let table = quote_identifier(table_name);
+        let query = match (strategy, rows) {
+            (_, None) => format!("SELECT * FROM {}", table),
+            (SampleStrategy::Random, Some(rows)) => {
+                format!("SELECT * FROM {} ORDER BY random() LIMIT {}", table, rows)
+            }
+            (SampleStrategy::Head, Some(rows)) => format!("SELECT * FROM {} LIMIT {}", table, rows),
+            // The share of pages to read is estimated from the number of rows in the statistics
+            // of the table, doubled for the pages to hold enough rows
+            (SampleStrategy::TableSample, Some(rows)) => format!(
+                "SELECT * FROM {0} TABLESAMPLE SYSTEM ((
+                    SELECT least(100, 200.0 * {1} / greatest(reltuples, 1))::real
+                    FROM pg_class WHERE oid = '{2}'::regclass
+                )) REPEATABLE (0) LIMIT {1}",
+                table,
+                rows,
+                table.replace('\'', "''")
+            ),
+        };
+        Ok(query)
     }
 
     fn decode_to_content(&self, column_info: &ColumnInfo) -> Result<Content> {
-        if column_info.is_custom_type {
-            return Ok(Content::String(StringContent::Categorical(
-                Categorical::default(),
-            )));
-        }
+        decode_column(column_info)
+    }
 
-        let content = match column_info.data_type.to_lowercase().as_str() {
-            "bool" => Content::Bool(BoolContent::default()),
-            "oid" => {
-                bail!("OID data type not supported")
-            }
-            "char" | "varchar" | "text" | "citext" | "bpchar" | "name" | "unknown" => {
-                let pattern = "[a-zA-Z0-9]{0, {}}".replace(
-                    "{}",
-                    &format!("{}", column_info.character_maximum_length.unwrap_or(1)),
-                );
-                Content::String(StringContent::Pattern(
-                    RegexContent::pattern(pattern).context("pattern will always compile")?,
-                ))
-            }
-            "int2" => Content::Number(NumberContent::I32(I32::Range(RangeStep::default()))),
-            "int4" => Content::Number(NumberContent::I32(I32::Range(RangeStep::default()))),
-            "int8" => Content::Number(NumberContent::I64(I64::Range(RangeStep::default()))),
-            "float4" => Content::Number(NumberContent::F32(F32::Range(RangeStep::default()))),
-            "float8" => Content::Number(NumberContent::F64(F64::Range(RangeStep::default()))),
-            "numeric" => Content::Number(NumberContent::F64(F64::Range(RangeStep::default()))),
-            "timestamptz" => Content::DateTime(DateTimeContent {
-                format: "%Y-%m-%dT%H:%M:%S%z".to_string(),
-                type_: ChronoValueType::DateTime,
-                begin: None,
-                end: None,
-            }),
-            "timestamp" => Content::DateTime(DateTimeContent {
-                format: "%Y-%m-%dT%H:%M:%S".to_string(),
-                type_: ChronoValueType::NaiveDateTime,
-                begin: None,
-                end: None,
-            }),
-            "date" => Content::DateTime(DateTimeContent {
-                format: "%Y-%m-%d".to_string(),
-                type_: ChronoValueType::NaiveDate,
-                begin: None,
-                end: None,
-            }),
-            "time" => Content::DateTime(DateTimeContent {
-                format: "%H:%M:%S".to_string(),
-                type_: ChronoValueType::NaiveTime,
-                begin: None,
-                end: None,
-            }),
-            "json" | "jsonb" => Content::Object(ObjectContent {
-                skip_when_null: false,
-                fields: BTreeMap::new(),
-            }),
-            "uuid" => Content::String(StringContent::Uuid(Uuid)),
-            _ => {
-                if let Some(data_type) = column_info.data_type.strip_prefix('_') {
-                    let mut column_info = column_info.clone();
-                    column_info.data_type = data_type.to_string();
-
-                    Content::Array(ArrayContent::from_content_default_length(
-                        self.decode_to_content(&column_info)?,
-                    ))
-                } else {
-                    bail!(
-                        "We haven't implemented a converter for {}",
-                        column_info.data_type
+    async fn get_column_casts(&self, collection_name: &str) -> Result<HashMap<String, String>> {
+        let column_infos = get_columns_info::<Self>(self, collection_name.to_string()).await?;
+
+        // Strings are bound as text, which only text-like and enum columns read as it is
+        Ok(column_infos
+            .into_iter()
+            .filter(|column_info| {
+                !column_info.is_custom_type
+                    && !matches!(
+                        column_info.data_type.as_str(),
+                        "char"
+                            | "varchar"
+                            | "text"
+                            | "citext"
+                            | "bpchar"
+                            | "name"
+                            | "unknown"
+                            | "json"
+                            | "jsonb"
+                            | "_json"
+                            | "_jsonb"
                     )
+            })
+            .map(|column_info| {
+                let cast = quote_identifier(&column_info.data_type);
+                (column_info.column_name, cast)
+            })
+            .collect())
+    }
+
+    fn get_function_argument_placeholder(
+        current: usize,
+        index: usize,
+        value: &Value,
+        cast: Option<&str>,
+    ) -> String {
+        let extra = match (value, cast) {
+            (Value::String(_), Some(cast)) => format!("::text::{}", cast),
+            // Arrays are bound as their text representation
+            (Value::Array(_), Some(cast)) => format!("::{}", cast),
+            (Value::Array(_), None) => {
+                let (typ, depth) = value.get_postgres_type();
+                if typ == "unknown" {
+                    "".to_string() // This is currently not supported
+                } else if typ == "jsonb" {
+                    "::jsonb".to_string() // Cannot have an array of jsonb - ie jsonb[]
+                } else {
+                    format!("::{}{}", typ, "[]".repeat(depth))
                 }
             }
+            _ => "".to_string(),
         };
 
-        Ok(content)
+        format!("${}{}", current + index + 1, extra)
+    }
+
+    fn get_columns_info_query(&self) -> &str {
+        // The ordinal position of a column is its attnum
+        r"SELECT c.column_name, c.ordinal_position, c.is_nullable, c.udt_name,
+        c.character_maximum_length, c.data_type, c.column_default,
+        array(
+            SELECT e.enumlabel::text
+            FROM pg_enum e
+            JOIN pg_type t ON t.oid = e.enumtypid
+            JOIN pg_namespace n ON n.oid = t.typnamespace
+            WHERE t.typname = c.udt_name AND n.nspname = c.udt_schema
+            ORDER BY e.enumsortorder
+        ) AS enum_labels,
+        array(
+            SELECT pg_get_constraintdef(k.oid)
+            FROM pg_constraint k
+            WHERE k.conrelid = format('%I.%I', c.table_schema, c.table_name)::regclass
+            AND k.contype = 'c'
+            AND c.ordinal_position::int2 = ANY(k.conkey)
+        ) AS checks
+        FROM information_schema.columns c
+        WHERE c.table_name = $2
+        AND c.table_schema = $1
+        AND c.table_catalog = current_catalog"
+    }
+
+    fn get_truncate_queries(&self, table_names: &[String]) -> Vec<String> {
+        let table_names = table_names
+            .iter()
+            .map(|table_name| quote_identifier(table_name))
+            .collect::<Vec<_>>();
+        vec![format!("TRUNCATE {} CASCADE", table_names.join(", "))]
+    }
+}
+
+/// Decodes the content of a column from its `udt_name`, which names the element type of arrays
+/// prefixed with an underscore.
+pub(crate) fn decode_column(column_info: &ColumnInfo) -> Result<Content> {
+    if column_info.is_custom_type {
+        return Ok(categorical(column_info.enum_labels.clone()));
     }
 
-    fn get_function_argument_placeholder(current: usize, index: usize, value: &Value) -> String {
-        let extra = if let Value::Array(_) = value {
-            let (typ, depth) = value.get_postgres_type();
-            if typ == "unknown" {
-                "".to_string() // This is currently not supported
-            } else if typ == "jsonb" {
-                "::jsonb".to_string() // Cannot have an array of jsonb - ie jsonb[]
+    let mut content = match column_info.data_type.to_lowercase().as_str() {
+        "bool" => Content::Bool(BoolContent::default()),
+        "oid" => Content::Number(NumberContent::U32(U32::Range(RangeStep::default()))),
+        "char" | "varchar" | "text" | "citext" | "bpchar" | "name" | "unknown" => {
+            let pattern = "[a-zA-Z0-9]{0, {}}".replace(
+                "{}",
+                &format!("{}", column_info.character_maximum_length.unwrap_or(1)),
+            );
+            Content::String(StringContent::Pattern(
+                RegexContent::pattern(pattern).context("pattern will always compile")?,
+            ))
+        }
+        "int2" => Content::Number(NumberContent::I32(I32::Range(RangeStep::default()))),
+        "int4" => Content::Number(NumberContent::I32(I32::Range(RangeStep::default()))),
+        "int8" => Content::Number(NumberContent::I64(I64::Range(RangeStep::default()))),
+        "float4" => Content::Number(NumberContent::F32(F32::Range(RangeStep::default()))),
+        "float8" => Content::Number(NumberContent::F64(F64::Range(RangeStep::default()))),
+        "numeric" => Content::Number(NumberContent::F64(F64::Range(RangeStep::default()))),
+        "timestamptz" => Content::DateTime(DateTimeContent {
+            format: "%Y-%m-%dT%H:%M:%S%z".to_string(),
+            type_: ChronoValueType::DateTime,
+            begin: None,
+            end: None,
+        }),
+        "timestamp" => Content::DateTime(DateTimeContent {
+            format: "%Y-%m-%dT%H:%M:%S".to_string(),
+            type_: ChronoValueType::NaiveDateTime,
+            begin: None,
+            end: None,
+        }),
+        "date" => Content::DateTime(DateTimeContent {
+            format: "%Y-%m-%d".to_string(),
+            type_: ChronoValueType::NaiveDate,
+            begin: None,
+            end: None,
+        }),
+        "time" => Content::DateTime(DateTimeContent {
+            format: "%H:%M:%S".to_string(),
+            type_: ChronoValueType::NaiveTime,
+            begin: None,
+            end: None,
+        }),
+        "json" | "jsonb" => Content::Object(ObjectContent {
+            skip_when_null: false,
+            unique_together: Vec::new(),
+            fields: BTreeMap::new(),
+        }),
+        "uuid" => Content::String(StringContent::Uuid(Uuid)),
+        "bytea" => string_pattern(r"\\x([0-9a-f]{2}){0,16}")?,
+        "inet" => string_faker("ipv4"),
+        "cidr" => string_pattern(r"10\.(0|[1-9][0-9]?)\.(0|[1-9][0-9]?)\.0/24")?,
+        "macaddr" | "macaddr8" => string_faker("mac_address"),
+        "interval" => string_pattern("[1-9][0-9]{0,2} (seconds|minutes|hours|days)")?,
+        "money" => string_pattern(r"[0-9]{1,4}\.[0-9]{2}")?,
+        "point" => string_pattern(r"\(-?[0-9]{1,3}\.[0-9]{1,2},-?[0-9]{1,3}\.[0-9]{1,2}\)")?,
+        // The lower bounds of ranges are always below their upper bounds
+        "int4range" | "int8range" => string_pattern(r"\[[0-9]{1,3},[1-9][0-9]{3}\)")?,
+        "numrange" => string_pattern(r"\[[0-9]{1,3}\.[0-9]{2},[1-9][0-9]{3}\.[0-9]{2}\)")?,
+        "daterange" => string_pattern(&format!(r"\[200[0-9]-{0},202[0-9]-{0}\)", DAY_PATTERN))?,
+        "tsrange" | "tstzrange" => string_pattern(&format!(
+            r"\[200[0-9]-{0} {1},202[0-9]-{0} {1}\)",
+            DAY_PATTERN, TIME_PATTERN
+        ))?,
+        _ => {
+            if let Some(data_type) = column_info.data_type.strip_prefix('_') {
+                let mut column_info = column_info.clone();
+                column_info.data_type = data_type.to_string();
+                column_info.column_default = None;
+                column_info.checks = Vec::new();
+
+                Content::Array(ArrayContent::from_content_default_length(decode_column(
+                    &column_info,
+                )?))
             } else {
-                format!("::{}{}", typ, "[]".repeat(depth))
+                bail!(
+                    "We haven't implemented a converter for {}",
+                    column_info.data_type
+                )
             }
-        } else {
-            "".to_string()
-        };
+        }
+    };
 
-        format!("${}{}", current + index + 1, extra)
+    // Columns which default to the next value of a sequence, like `serial` ones, are ids
+    let is_sequence = column_info
+        .column_default
+        .as_deref()
+        .map_or(false, |default| default.starts_with("nextval("));
+    if let (true, Content::Number(number)) = (is_sequence, &content) {
+        if let Ok(id) = number.clone().try_transmute_to_id() {
+            content = Content::Number(id);
+        }
     }
 
-    fn get_columns_info_query(&self) -> &str {
-        r"SELECT column_name, ordinal_position, is_nullable, udt_name,
-        character_maximum_length, data_type
-        FROM information_schema.columns
-        WHERE table_name = $2
-        AND table_schema = $1
-        AND table_catalog = current_catalog"
-    }
+    let checks = column_info
+        .checks
+        .iter()
+        .filter_map(|definition| match parse_postgres_check(definition) {
+            Ok(check) => Some(check),
+            Err(err) => {
+                debug!("Skipping constraint which could not be parsed: {:#}", err);
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+    apply_checks(&mut content, &column_info.column_name, &checks);
+
+    Ok(content)
+}
+
+/// The month and day of a date, which is valid in any year.
+const DAY_PATTERN: &str = "(0[1-9]|1[0-2])-(0[1-9]|1[0-9]|2[0-8])";
+
+const TIME_PATTERN: &str = "([01][0-9]|2[0-3]):[0-5][0-9]:[0-5][0-9]";
+
+fn string_pattern(pattern: &str) -> Result<Content> {
+    Ok(Content::String(StringContent::Pattern(
+        RegexContent::pattern(pattern.to_string()).context("pattern will always compile")?,
+    )))
+}
+
+fn string_faker(generator: &str) -> Content {
+    Content::String(StringContent::Faker(FakerContent {
+        generator: generator.to_string(),
+        locales: Vec::new(),
+        args: FakerArgs::default(),
+    }))
 }
 
 impl TryFrom<PgRow> for ColumnInfo {
@@ -287,6 +683,11 @@ impl TryFrom<PgRow> for ColumnInfo {
             data_type: row.try_get(3)?,
             character_maximum_length: row.try_get(4)?,
             is_custom_type: row.try_get::<String, usize>(5)? == "USER-DEFINED",
+            column_default: row.try_get(6)?,
+            enum_labels: row.try_get(7)?,
+            checks: row.try_get(8)?,
+            is_unsigned: false,
+            is_generated: false,
         })
     }
 }
@@ -302,6 +703,17 @@ impl TryFrom<PgRow> for PrimaryKey {
     }
 }
 
+impl TryFrom<PgRow> for UniqueKey {
+    type Error = anyhow::Error;
+
+    fn try_from(row: PgRow) -> Result<Self, Self::Error> {
+        Ok(UniqueKey {
+            index_name: row.try_get(0)?,
+            column_name: row.try_get(1)?,
+        })
+    }
+}
+
 impl TryFrom<PgRow> for ForeignKey {
     type Error = anyhow::Error;
 
@@ -311,6 +723,7 @@ impl TryFrom<PgRow> for ForeignKey {
             from_column: row.try_get(1)?,
             to_table: row.try_get(2)?,
             to_column: row.try_get(3)?,
+            constraint_name: row.try_get(4)?,
         })
     }
 }
@@ -333,27 +746,32 @@ impl TryFrom<PgRow> for ValueWrapper {
     }
 }
 
+/// Reads the value of a column from its type, or the base type of its domain. Values are read
+/// unchecked since the type they are read as is always matched by name first.
 fn try_match_value(row: &PgRow, column: &PgColumn) -> Result<Value> {
-    if let PgTypeKind::Enum(_) = column.type_info().kind() {
-        let s = row.try_get::<EnumType, &str>(column.name())?;
+    let mut type_info = column.type_info();
+    while let PgTypeKind::Domain(base) = type_info.kind() {
+        type_info = base;
+    }
+
+    if let PgTypeKind::Enum(_) = type_info.kind() {
+        let s = row.try_get_unchecked::<EnumType, &str>(column.name())?;
         return Ok(Value::String(s.into()));
     }
 
-    let value = match column.type_info().name().to_lowercase().as_str() {
-        "bool" => Value::Bool(row.try_get::<bool, &str>(column.name())?),
-        "oid" => {
-            bail!("OID data type not supported for Postgresql")
-        }
+    let value = match type_info.name().to_lowercase().as_str() {
+        "bool" => Value::Bool(row.try_get_unchecked::<bool, &str>(column.name())?),
+        "oid" => Value::Number((row.try_get_unchecked::<i32, &str>(column.name())? as u32).into()),
         "char" | "varchar" | "text" | "citext" | "bpchar" | "name" | "unknown" => {
-            Value::String(row.try_get::<String, &str>(column.name())?)
+            Value::String(row.try_get_unchecked::<String, &str>(column.name())?)
         }
-        "int2" => Value::Number(row.try_get::<i16, &str>(column.name())?.into()),
-        "int4" => Value::Number(row.try_get::<i32, &str>(column.name())?.into()),
-        "int8" => Value::Number(row.try_get::<i64, &str>(column.name())?.into()),
-        "float4" => Value::Number(row.try_get::<f32, &str>(column.name())?.into()),
-        "float8" => Value::Number(row.try_get::<f64, &str>(column.name())?.into()),
+        "int2" => Value::Number(row.try_get_unchecked::<i16, &str>(column.name())?.into()),
+        "int4" => Value::Number(row.try_get_unchecked::<i32, &str>(column.name())?.into()),
+        "int8" => Value::Number(row.try_get_unchecked::<i64, &str>(column.name())?.into()),
+        "float4" => Value::Number(row.try_get_unchecked::<f32, &str>(column.name())?.into()),
+        "float8" => Value::Number(row.try_get_unchecked::<f64, &str>(column.name())?.into()),
         "numeric" => {
-            let as_decimal = row.try_get::<Decimal, &str>(column.name())?;
+            let as_decimal = row.try_get_unchecked::<Decimal, &str>(column.name())?;
 
             if let Some(truncated) = as_decimal.to_f64() {
                 return Ok(Value::Number(truncated.into()));
@@ -361,52 +779,60 @@ fn try_match_value(row: &PgRow, column: &PgColumn) -> Result<Value> {
 
             bail!("Failed to convert Postgresql numeric data type to 64 bit float")
         }
-        "timestampz" => Value::String(row.try_get::<String, &str>(column.name())?),
-        "timestamp" => Value::String(row.try_get::<String, &str>(column.name())?),
+        "timestamptz" => Value::String(
+            row.try_get_unchecked::<chrono::DateTime<chrono::FixedOffset>, &str>(column.name())?
+                .format("%Y-%m-%dT%H:%M:%S%z")
+                .to_string(),
+        ),
+        "timestamp" => Value::String(
+            row.try_get_unchecked::<chrono::NaiveDateTime, &str>(column.name())?
+                .format("%Y-%m-%dT%H:%M:%S")
+                .to_string(),
+        ),
         "date" => Value::String(format!(
             "{}",
-            row.try_get::<chrono::NaiveDate, &str>(column.name())?
+            row.try_get_unchecked::<chrono::NaiveDate, &str>(column.name())?
         )),
         "time" => Value::String(format!(
             "{}",
-            row.try_get::<chrono::NaiveTime, &str>(column.name())?
+            row.try_get_unchecked::<chrono::NaiveTime, &str>(column.name())?
         )),
         "json" | "jsonb" => {
-            let serde_value = row.try_get::<serde_json::Value, &str>(column.name())?;
+            let serde_value = row.try_get_unchecked::<serde_json::Value, &str>(column.name())?;
             serde_json::from_value(serde_value)?
         }
         "char[]" | "varchar[]" | "text[]" | "citext[]" | "bpchar[]" | "name[]" | "unknown[]" => {
             Value::Array(
-                row.try_get::<Vec<String>, &str>(column.name())
+                row.try_get_unchecked::<Vec<String>, &str>(column.name())
                     .map(|vec| vec.iter().map(|s| Value::String(s.to_string())).collect())?,
             )
         }
         "bool[]" => Value::Array(
-            row.try_get::<Vec<bool>, &str>(column.name())
+            row.try_get_unchecked::<Vec<bool>, &str>(column.name())
                 .map(|vec| vec.into_iter().map(Value::Bool).collect())?,
         ),
         "int2[]" => Value::Array(
-            row.try_get::<Vec<i16>, &str>(column.name())
+            row.try_get_unchecked::<Vec<i16>, &str>(column.name())
                 .map(|vec| vec.into_iter().map(|i| Value::Number(i.into())).collect())?,
         ),
         "int4[]" => Value::Array(
-            row.try_get::<Vec<i32>, &str>(column.name())
+            row.try_get_unchecked::<Vec<i32>, &str>(column.name())
                 .map(|vec| vec.into_iter().map(|i| Value::Number(i.into())).collect())?,
         ),
         "int8[]" => Value::Array(
-            row.try_get::<Vec<i64>, &str>(column.name())
+            row.try_get_unchecked::<Vec<i64>, &str>(column.name())
                 .map(|vec| vec.into_iter().map(|i| Value::Number(i.into())).collect())?,
         ),
         "float4[]" => Value::Array(
-            row.try_get::<Vec<f32>, &str>(column.name())
+            row.try_get_unchecked::<Vec<f32>, &str>(column.name())
                 .map(|vec| vec.into_iter().map(|i| Value::Number(i.into())).collect())?,
         ),
         "float8[]" => Value::Array(
-            row.try_get::<Vec<f64>, &str>(column.name())
+            row.try_get_unchecked::<Vec<f64>, &str>(column.name())
                 .map(|vec| vec.into_iter().map(|i| Value::Number(i.into())).collect())?,
         ),
         "numeric[]" => {
-            let vec = row.try_get::<Vec<Decimal>, &str>(column.name())?;
+            let vec = row.try_get_unchecked::<Vec<Decimal>, &str>(column.name())?;
             let result: Result<Vec<Value>, _> = vec
                 .into_iter()
                 .map(|d| {
@@ -421,7 +847,7 @@ fn try_match_value(row: &PgRow, column: &PgColumn) -> Result<Value> {
             Value::Array(result?)
         }
         "timestamp[]" => Value::Array(
-            row.try_get::<Vec<chrono::NaiveDateTime>, &str>(column.name())
+            row.try_get_unchecked::<Vec<chrono::NaiveDateTime>, &str>(column.name())
                 .map(|vec| {
                     vec.into_iter()
                         .map(|d| {
@@ -434,20 +860,22 @@ fn try_match_value(row: &PgRow, column: &PgColumn) -> Result<Value> {
                 })?,
         ),
         "timestamptz[]" => Value::Array(
-            row.try_get::<Vec<chrono::DateTime<chrono::FixedOffset>>, &str>(column.name())
-                .map(|vec| {
-                    vec.into_iter()
-                        .map(|d| {
-                            Value::DateTime(ChronoValueAndFormat {
-                                format: Arc::from("%Y-%m-%dT%H:%M:%S%z".to_owned()),
-                                value: ChronoValue::DateTime(d),
-                            })
+            row.try_get_unchecked::<Vec<chrono::DateTime<chrono::FixedOffset>>, &str>(
+                column.name(),
+            )
+            .map(|vec| {
+                vec.into_iter()
+                    .map(|d| {
+                        Value::DateTime(ChronoValueAndFormat {
+                            format: Arc::from("%Y-%m-%dT%H:%M:%S%z".to_owned()),
+                            value: ChronoValue::DateTime(d),
                         })
-                        .collect()
-                })?,
+                    })
+                    .collect()
+            })?,
         ),
         "date[]" => Value::Array(
-            row.try_get::<Vec<chrono::NaiveDate>, &str>(column.name())
+            row.try_get_unchecked::<Vec<chrono::NaiveDate>, &str>(column.name())
                 .map(|vec| {
                     vec.into_iter()
                         .map(|d| {
@@ -460,7 +888,7 @@ fn try_match_value(row: &PgRow, column: &PgColumn) -> Result<Value> {
                 })?,
         ),
         "time[]" => Value::Array(
-            row.try_get::<Vec<chrono::NaiveTime>, &str>(column.name())
+            row.try_get_unchecked::<Vec<chrono::NaiveTime>, &str>(column.name())
                 .map(|vec| {
                     vec.into_iter()
                         .map(|t| {
@@ -472,10 +900,83 @@ fn try_match_value(row: &PgRow, column: &PgColumn) -> Result<Value> {
                         .collect()
                 })?,
         ),
+        "uuid" => {
+            let hex = hex(row.try_get_unchecked::<&[u8], &str>(column.name())?);
+            if hex.len() != 32 {
+                bail!("Expected 16 bytes for a uuid, got {}", hex.len() / 2)
+            }
+            Value::String(format!(
+                "{}-{}-{}-{}-{}",
+                &hex[..8],
+                &hex[8..12],
+                &hex[12..16],
+                &hex[16..20],
+                &hex[20..]
+            ))
+        }
+        "bytea" => Value::String(format!(
+            "\\x{}",
+            hex(&row.try_get_unchecked::<Vec<u8>, &str>(column.name())?)
+        )),
+        "inet" | "cidr" => Value::String(decode_inet(
+            row.try_get_unchecked::<&[u8], &str>(column.name())?,
+        )?),
+        "macaddr" | "macaddr8" => Value::String(
+            row.try_get_unchecked::<&[u8], &str>(column.name())?
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect::<Vec<_>>()
+                .join(":"),
+        ),
+        "interval" => {
+            let interval = row.try_get_unchecked::<PgInterval, &str>(column.name())?;
+            Value::String(format!(
+                "{} months {} days {} microseconds",
+                interval.months, interval.days, interval.microseconds
+            ))
+        }
+        "money" => Value::String(
+            row.try_get_unchecked::<PgMoney, &str>(column.name())?
+                .to_decimal(2)
+                .to_string(),
+        ),
+        "point" => {
+            let bytes = row.try_get_unchecked::<&[u8], &str>(column.name())?;
+            if bytes.len() != 16 {
+                bail!("Expected 16 bytes for a point, got {}", bytes.len())
+            }
+            let x = f64::from_be_bytes(bytes[..8].try_into()?);
+            let y = f64::from_be_bytes(bytes[8..].try_into()?);
+            Value::String(format!("({},{})", x, y))
+        }
+        "int4range" => Value::String(
+            row.try_get_unchecked::<PgRange<i32>, &str>(column.name())?
+                .to_string(),
+        ),
+        "int8range" => Value::String(
+            row.try_get_unchecked::<PgRange<i64>, &str>(column.name())?
+                .to_string(),
+        ),
+        "numrange" => Value::String(
+            row.try_get_unchecked::<PgRange<Decimal>, &str>(column.name())?
+                .to_string(),
+        ),
+        "daterange" => Value::String(
+            row.try_get_unchecked::<PgRange<chrono::NaiveDate>, &str>(column.name())?
+                .to_string(),
+        ),
+        "tsrange" => Value::String(
+            row.try_get_unchecked::<PgRange<chrono::NaiveDateTime>, &str>(column.name())?
+                .to_string(),
+        ),
+        "tstzrange" => Value::String(
+            row.try_get_unchecked::<PgRange<chrono::DateTime<chrono::Utc>>, &str>(column.name())?
+                .to_string(),
+        ),
         _ => {
             bail!(
                 "Could not convert value. Converter not implemented for {}",
-                column.type_info().name()
+                type_info.name()
             );
         }
     };
@@ -483,6 +984,34 @@ fn try_match_value(row: &PgRow, column: &PgColumn) -> Result<Value> {
     Ok(value)
 }
 
+/// Reads an `inet` or `cidr` from its binary format, which is its address family, the number of
+/// bits of its netmask, whether it is a `cidr`, the length of its address and its address.
+fn decode_inet(bytes: &[u8]) -> Result<String> {
+    let (bits, is_cidr, address) = match bytes {
+        [_, bits, is_cidr, 4, address @ ..] => {
+            let address: [u8; 4] = address.try_into()?;
+            (*bits, *is_cidr != 0, IpAddr::from(address))
+        }
+        [_, bits, is_cidr, 16, address @ ..] => {
+            let address: [u8; 16] = address.try_into()?;
+            (*bits, *is_cidr != 0, IpAddr::from(address))
+        }
+        _ => bail!("Unexpected binary format for an inet or cidr"),
+    };
+
+    // Like Postgres, leave out the netmask of a host unless it is a `cidr`
+    let host_bits = if address.is_ipv4() { 32 } else { 128 };
+    if bits == host_bits && !is_cidr {
+        Ok(address.to_string())
+    } else {
+        Ok(format!("{}/{}", address, bits))
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
 /// Special type for importing enums
 struct EnumType(String);
 
@@ -509,3 +1038,139 @@ impl From<EnumType> for String {
         enum_type.0
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn column_info(data_type: &str) -> ColumnInfo {
+        ColumnInfo {
+            column_name: "column".to_string(),
+            ordinal_position: 1,
+            is_nullable: false,
+            is_custom_type: false,
+            data_type: data_type.to_string(),
+            character_maximum_length: None,
+            column_default: None,
+            enum_labels: Vec::new(),
+            checks: Vec::new(),
+            is_unsigned: false,
+            is_generated: false,
+        }
+    }
+
+    #[test]
+    fn test_decode_column() {
+        for data_type in [
+            "oid",
+            "bytea",
+            "inet",
+            "cidr",
+            "macaddr",
+            "interval",
+            "money",
+            "point",
+            "int4range",
+            "int8range",
+            "numrange",
+            "daterange",
+            "tsrange",
+            "tstzrange",
+            "_inet",
+        ] {
+            assert!(
+                decode_column(&column_info(data_type)).is_ok(),
+                "{} could not be decoded",
+                data_type
+            );
+        }
+
+        assert!(decode_column(&column_info("tsvector")).is_err());
+    }
+
+    #[test]
+    fn test_copy_row() {
+        let mut row = BTreeMap::new();
+        row.insert("id".to_string(), Value::Number(Number::I64(1)));
+        row.insert(
+            "note".to_string(),
+            Value::String("tab\there\nback\\slash".to_string()),
+        );
+        row.insert("deleted_at".to_string(), Value::Null(()));
+        row.insert(
+            "tags".to_string(),
+            Value::Array(vec![
+                Value::String("a\\b".to_string()),
+                Value::String("say \"hi\"".to_string()),
+            ]),
+        );
+        row.insert(
+            "scores".to_string(),
+            Value::Array(vec![
+                Value::Number(Number::I32(1)),
+                Value::Number(Number::I32(2)),
+            ]),
+        );
+        row.insert("big".to_string(), Value::Number(Number::U64(u64::MAX)));
+        row.insert("ratio".to_string(), Value::Number(Number::from(f64::NAN)));
+
+        let column_names = [
+            "id",
+            "note",
+            "deleted_at",
+            "missing",
+            "tags",
+            "scores",
+            "big",
+            "ratio",
+        ]
+        .iter()
+        .map(|column_name| column_name.to_string())
+        .collect::<Vec<_>>();
+        let column_names = column_names.iter().collect::<Vec<_>>();
+        let column_infos = [("tags", "_text"), ("scores", "_int4")]
+            .iter()
+            .map(|(column_name, data_type)| (column_name.to_string(), column_info(data_type)))
+            .collect::<HashMap<_, _>>();
+
+        assert_eq!(
+            copy_row(&row, &column_names, &column_infos).unwrap(),
+            [
+                "1",
+                r"tab\there\nback\\slash",
+                r"\N",
+                r"\N",
+                r#"{"a\\\\b", "say \\"hi\\""}"#,
+                "{1, 2}",
+                "-1",
+                "NaN",
+            ]
+            .join("\t")
+                + "\n"
+        );
+
+        // Arrays are only written to array columns
+        let column_infos = HashMap::new();
+        assert_eq!(
+            copy_row(&row, &column_names, &column_infos).unwrap_err(),
+            "tags"
+        );
+    }
+
+    #[test]
+    fn test_decode_inet() {
+        assert_eq!(
+            decode_inet(&[2, 32, 0, 4, 10, 0, 0, 1]).unwrap(),
+            "10.0.0.1"
+        );
+        assert_eq!(
+            decode_inet(&[2, 24, 1, 4, 10, 0, 0, 0]).unwrap(),
+            "10.0.0.0/24"
+        );
+        assert_eq!(
+            decode_inet(&[3, 128, 1, 16, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]).unwrap(),
+            "::1/128"
+        );
+        assert!(decode_inet(&[2, 32, 0, 4, 10]).is_err());
+    }
+}
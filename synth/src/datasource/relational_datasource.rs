@@ -1,12 +1,16 @@
 use crate::datasource::DataSource;
 use anyhow::Result;
+use async_std::sync::Mutex;
 use async_trait::async_trait;
 use beau_collector::BeauCollector;
 use futures::future::join_all;
 use sqlx::{
-    query::Query, Arguments, Connection, Database, Encode, Executor, IntoArguments, Pool, Type,
+    database::HasArguments, query::Query, Connection, Database, Encode, Executor, IntoArguments,
+    Pool, Transaction, Type,
 };
+use std::collections::{BTreeMap, HashMap};
 use std::convert::TryFrom;
+use std::str::FromStr;
 use synth_core::{Content, Value};
 use synth_gen::value::Number;
 
@@ -22,6 +26,17 @@ pub struct ColumnInfo {
     pub(crate) is_custom_type: bool,
     pub(crate) data_type: String,
     pub(crate) character_maximum_length: Option<i32>,
+    /// The default of the column, as an SQL expression
+    pub(crate) column_default: Option<String>,
+    /// The labels of the enum type of the column in order, or the members of its set type, if it
+    /// has one
+    pub(crate) enum_labels: Vec<String>,
+    /// Whether the column only takes numbers which aren't negative, like `unsigned` MySQL columns
+    pub(crate) is_unsigned: bool,
+    /// Whether the database generates the values of the column, which are then left out of inserts
+    pub(crate) is_generated: bool,
+    /// The definitions of the `CHECK` constraints on the column
+    pub(crate) checks: Vec<String>,
 }
 
 #[allow(dead_code)]
@@ -31,36 +46,114 @@ pub struct PrimaryKey {
     pub(crate) type_name: String,
 }
 
+/// A column of a unique index other than the primary key, in the order of the index.
+#[derive(Debug)]
+pub struct UniqueKey {
+    pub(crate) index_name: String,
+    /// The column of the key part, which is `None` for a part on an expression
+    pub(crate) column_name: Option<String>,
+}
+
+/// A column of a foreign key, in the order of the foreign key.
 #[derive(Debug)]
 pub struct ForeignKey {
     pub(crate) from_table: String,
     pub(crate) from_column: String,
     pub(crate) to_table: String,
     pub(crate) to_column: String,
+    /// Tells apart the foreign keys of a table, the columns of which are consecutive.
+    pub(crate) constraint_name: String,
+}
+
+/// How the rows which values are inferred from are picked from a table.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SampleStrategy {
+    /// Rows picked at random, with a seed where the database has one
+    Random,
+    /// The first rows the database returns, which takes the least time
+    Head,
+    /// Rows from pages picked at random with `TABLESAMPLE`, which doesn't read the whole table
+    TableSample,
+}
+
+impl FromStr for SampleStrategy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "random" => Ok(Self::Random),
+            "head" => Ok(Self::Head),
+            "tablesample" => Ok(Self::TableSample),
+            _ => Err(anyhow!(
+                "Sample strategy '{}' not recognised. Was expecting one of 'random', 'head' or 'tablesample'.",
+                s
+            )),
+        }
+    }
+}
+
+/// How many rows values are inferred from, and how they are picked, for each table.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sampling {
+    pub(crate) strategy: SampleStrategy,
+    /// The number of rows sampled from tables without one in `tables`, or `None` for all rows
+    pub(crate) rows: Option<usize>,
+    pub(crate) tables: BTreeMap<String, Option<usize>>,
+}
+
+impl Sampling {
+    /// The number of rows sampled from a table, or `None` for all its rows.
+    pub(crate) fn rows(&self, table_name: &str) -> Option<usize> {
+        self.tables.get(table_name).copied().unwrap_or(self.rows)
+    }
+}
+
+impl Default for Sampling {
+    fn default() -> Self {
+        Self {
+            strategy: SampleStrategy::Random,
+            rows: Some(10),
+            tables: BTreeMap::new(),
+        }
+    }
 }
 
 /// Wrapper around `Value` since we can't impl `TryFrom` on a struct in a non-owned crate
 #[derive(Debug)]
 pub struct ValueWrapper(pub(crate) Value);
 
+/// The arguments of a query to `DB`. Unlike Postgres and MySQL, SQLite arguments borrow from the
+/// query, hence the lifetime.
+pub type ArgumentsOf<'q, DB> = <DB as HasArguments<'q>>::Arguments;
+
 /// All sqlx databases should define this trait and implement database specific queries in
 /// their own implementations.
 #[async_trait]
 pub trait SqlxDataSource: DataSource {
-    type DB: Database<Arguments = Self::Arguments, Connection = Self::Connection>;
-    type Arguments: for<'q> Arguments<'q, Database = Self::DB> + for<'q> IntoArguments<'q, Self::DB>;
-    type Connection: Connection<Database = Self::DB>;
+    type DB: Database<Connection = Self::Connection>;
+    type Connection: Connection<Database = Self::DB> + 'static;
 
     const IDENTIFIER_QUOTE: char;
 
+    /// Quotes `name` to be used as an identifier, escaping the quotes in it
+    fn quote_identifier(name: &str) -> String {
+        let quote = Self::IDENTIFIER_QUOTE;
+        let escaped = name.replace(quote, &format!("{}{}", quote, quote));
+        format!("{}{}{}", quote, escaped, quote)
+    }
+
     /// Gets a pool to execute queries with
     fn get_pool(&self) -> Pool<Self::DB>;
 
     /// Gets a multithread pool to execute queries with
     fn get_multithread_pool(&self) -> Pool<Self::DB>;
 
+    /// Gets the transaction started by [`begin_relational_transaction`], which queries are
+    /// executed in instead of the pools while it is open
+    fn get_transaction(&self) -> &Mutex<Option<Transaction<'static, Self::DB>>>;
+
     /// Prepare a single query with data source specifics
-    fn query<'q>(&self, query: &'q str) -> Query<'q, Self::DB, Self::Arguments> {
+    fn query<'q>(&self, query: &'q str) -> Query<'q, Self::DB, ArgumentsOf<'q, Self::DB>> {
         sqlx::query(query)
     }
 
@@ -73,33 +166,64 @@ pub trait SqlxDataSource: DataSource {
     /// Get query for foreign keys
     fn get_foreign_keys_query(&self) -> &str;
 
+    /// Get query for the columns of the unique indexes of a table, other than its primary key
+    fn get_unique_keys_query(&self) -> &str;
+
     /// Get query for columns info
     fn get_columns_info_query(&self) -> &str;
 
+    /// Get queries removing all rows of the given tables
+    fn get_truncate_queries(&self, table_names: &[String]) -> Vec<String>;
+
     async fn set_seed(&self) -> Result<()> {
         // Default for sources that don't need to set a seed
         Ok(())
     }
 
-    /// Get query for deterministic values
-    fn get_deterministic_samples_query(&self, table_name: String) -> String;
+    /// Get query for the rows of a table values are sampled from, which are at most `rows` rows
+    /// if given
+    fn get_samples_query(
+        &self,
+        table_name: &str,
+        strategy: SampleStrategy,
+        rows: Option<usize>,
+    ) -> Result<String>;
 
     /// Decodes column to our Content
     fn decode_to_content(&self, column_info: &ColumnInfo) -> Result<Content>;
 
+    /// Get the types the values of the columns of a collection have to be cast to, by column, for
+    /// columns which can't read the values as they are bound
+    async fn get_column_casts(&self, _collection_name: &str) -> Result<HashMap<String, String>> {
+        Ok(HashMap::new())
+    }
+
     /// Get the function arguments for datasource
-    fn get_function_argument_placeholder(_current: usize, _index: usize, _value: &Value) -> String {
+    fn get_function_argument_placeholder(
+        _current: usize,
+        _index: usize,
+        _value: &Value,
+        _cast: Option<&str>,
+    ) -> String {
         "?".to_string()
     }
 
     // Returns extended query string + current index
-    fn extend_parameterised_query(query: &mut String, curr_index: usize, query_params: Vec<Value>) {
+    fn extend_parameterised_query(
+        query: &mut String,
+        curr_index: usize,
+        query_params: Vec<Value>,
+        casts: &[Option<&str>],
+    ) {
         let extend = query_params.len();
 
         query.push('(');
         for (i, param) in query_params.iter().enumerate() {
             query.push_str(&Self::get_function_argument_placeholder(
-                curr_index, i, param,
+                curr_index,
+                i,
+                param,
+                casts.get(i).copied().flatten(),
             ));
             if i != extend - 1 {
                 query.push(',');
@@ -115,6 +239,7 @@ pub trait SqlxDataSource: DataSource {
     ) -> Result<<Self::DB as Database>::QueryResult>
     where
         for<'c> &'c mut Self::Connection: Executor<'c, Database = Self::DB>,
+        for<'q> ArgumentsOf<'q, Self::DB>: IntoArguments<'q, Self::DB>,
         Value: Type<Self::DB>,
         for<'d> Value: Encode<'d, Self::DB>,
     {
@@ -124,37 +249,67 @@ pub trait SqlxDataSource: DataSource {
             query = query.bind(param);
         }
 
-        let result = query.execute(&self.get_multithread_pool()).await?;
+        let result = match self.get_transaction().lock().await.as_mut() {
+            Some(transaction) => query.execute(&mut **transaction).await?,
+            None => query.execute(&self.get_multithread_pool()).await?,
+        };
 
         Ok(result)
     }
 }
 
-pub async fn insert_relational_data<T: SqlxDataSource + Sync>(
+pub async fn begin_relational_transaction<T: SqlxDataSource>(datasource: &T) -> Result<()> {
+    let transaction = datasource.get_multithread_pool().begin().await?;
+    *datasource.get_transaction().lock().await = Some(transaction);
+    Ok(())
+}
+
+pub async fn commit_relational_transaction<T: SqlxDataSource>(datasource: &T) -> Result<()> {
+    if let Some(transaction) = datasource.get_transaction().lock().await.take() {
+        transaction.commit().await?;
+    }
+    Ok(())
+}
+
+pub async fn rollback_relational_transaction<T: SqlxDataSource>(datasource: &T) -> Result<()> {
+    if let Some(transaction) = datasource.get_transaction().lock().await.take() {
+        transaction.rollback().await?;
+    }
+    Ok(())
+}
+
+pub async fn truncate_relational_data<T: SqlxDataSource + Sync>(
     datasource: &T,
-    collection_name: &str,
-    collection: &[Value],
+    table_names: &[String],
 ) -> Result<()>
 where
     for<'c> &'c mut T::Connection: Executor<'c, Database = T::DB>,
-    String: Type<T::DB>,
-    for<'d> String: Encode<'d, T::DB>,
-    ColumnInfo: TryFrom<<T::DB as Database>::Row, Error = anyhow::Error>,
+    for<'q> ArgumentsOf<'q, T::DB>: IntoArguments<'q, T::DB>,
     Value: Type<T::DB>,
     for<'d> Value: Encode<'d, T::DB>,
 {
-    let batch_size = DEFAULT_INSERT_BATCH_SIZE;
-
-    if collection.is_empty() {
-        println!(
-            "Collection {} generated 0 values. Skipping insertion...",
-            collection_name
-        );
-        return Ok(());
+    for query in datasource.get_truncate_queries(table_names) {
+        datasource.execute_query(query, vec![]).await?;
     }
 
+    info!("Truncated {}...", table_names.join(", "));
+    Ok(())
+}
+
+pub async fn check_relational_data<T: SqlxDataSource + Sync>(
+    datasource: &T,
+    collection_name: &str,
+    first: &Value,
+) -> Result<()>
+where
+    for<'c> &'c mut T::Connection: Executor<'c, Database = T::DB>,
+    for<'q> ArgumentsOf<'q, T::DB>: IntoArguments<'q, T::DB>,
+    String: Type<T::DB>,
+    for<'d> String: Encode<'d, T::DB>,
+    ColumnInfo: TryFrom<<T::DB as Database>::Row, Error = anyhow::Error>,
+{
     let column_infos = get_columns_info(datasource, collection_name.to_string()).await?;
-    let first_valueset = collection[0]
+    let first_valueset = first
         .as_object()
         .expect("This is always an object (sampler contract)");
 
@@ -203,12 +358,42 @@ where
         }
     }
 
+    Ok(())
+}
+
+pub async fn insert_relational_data<T: SqlxDataSource + Sync>(
+    datasource: &T,
+    collection_name: &str,
+    collection: &[Value],
+) -> Result<()>
+where
+    for<'c> &'c mut T::Connection: Executor<'c, Database = T::DB>,
+    for<'q> ArgumentsOf<'q, T::DB>: IntoArguments<'q, T::DB>,
+    Value: Type<T::DB>,
+    for<'d> Value: Encode<'d, T::DB>,
+{
+    let batch_size = DEFAULT_INSERT_BATCH_SIZE;
+
+    if collection.is_empty() {
+        return Ok(());
+    }
+
+    let first_valueset = collection[0]
+        .as_object()
+        .expect("This is always an object (sampler contract)");
+
     let column_names = first_valueset
         .keys()
         .map(|k| format!("{}{}{}", T::IDENTIFIER_QUOTE, k, T::IDENTIFIER_QUOTE))
         .collect::<Vec<String>>()
         .join(",");
 
+    let casts = datasource.get_column_casts(collection_name).await?;
+    let column_casts = first_valueset
+        .keys()
+        .map(|k| casts.get(k).map(String::as_str))
+        .collect::<Vec<_>>();
+
     let mut futures = Vec::with_capacity(collection.len());
 
     for rows in collection.chunks(batch_size) {
@@ -226,7 +411,12 @@ where
                 .expect("This is always an object (sampler contract)");
 
             let mut curr_query_params: Vec<Value> = row_obj.values().cloned().collect();
-            T::extend_parameterised_query(&mut query, curr_index, curr_query_params.clone());
+            T::extend_parameterised_query(
+                &mut query,
+                curr_index,
+                curr_query_params.clone(),
+                &column_casts,
+            );
             curr_index += curr_query_params.len();
             query_params.append(&mut curr_query_params);
 
@@ -256,19 +446,20 @@ pub async fn get_columns_info<T: SqlxDataSource>(
 ) -> Result<Vec<ColumnInfo>>
 where
     for<'c> &'c mut T::Connection: Executor<'c, Database = T::DB>,
+    for<'q> ArgumentsOf<'q, T::DB>: IntoArguments<'q, T::DB>,
     String: Type<T::DB>,
     for<'d> String: Encode<'d, T::DB>,
     ColumnInfo: TryFrom<<T::DB as Database>::Row, Error = anyhow::Error>,
 {
-    let query = datasource.get_columns_info_query();
-    let pool = datasource.get_pool();
-
-    datasource
-        .query(query)
-        .bind(table_name)
-        .fetch_all(&pool)
-        .await?
-        .into_iter()
-        .map(ColumnInfo::try_from)
-        .collect()
+    let query = datasource
+        .query(datasource.get_columns_info_query())
+        .bind(table_name);
+
+    // A pool may not have a connection to spare while a transaction is open
+    let rows = match datasource.get_transaction().lock().await.as_mut() {
+        Some(transaction) => query.fetch_all(&mut **transaction).await?,
+        None => query.fetch_all(&datasource.get_pool()).await?,
+    };
+
+    rows.into_iter().map(ColumnInfo::try_from).collect()
 }
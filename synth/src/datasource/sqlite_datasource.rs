@@ -0,0 +1,338 @@
+use crate::datasource::relational_datasource::{
+    begin_relational_transaction, check_relational_data, commit_relational_transaction,
+    insert_relational_data, rollback_relational_transaction, truncate_relational_data, ColumnInfo,
+    ForeignKey, PrimaryKey, SampleStrategy, SqlxDataSource, UniqueKey, ValueWrapper,
+};
+use crate::datasource::DataSource;
+use anyhow::{Context, Result};
+use async_std::sync::Mutex;
+use async_std::task;
+use async_trait::async_trait;
+use sqlx::sqlite::{SqliteColumn, SqlitePoolOptions, SqliteRow};
+use sqlx::{Column, Pool, Row, Sqlite, Transaction, TypeInfo, ValueRef};
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use synth_core::schema::number_content::{F64, I64};
+use synth_core::schema::{
+    BoolContent, ChronoValueType, DateTimeContent, NumberContent, RangeStep, RegexContent,
+    StringContent,
+};
+use synth_core::{Content, Value};
+use synth_gen::prelude::*;
+
+/// SQLite column types are free-form and only determine a column's type affinity, so columns are
+/// decoded using the rules SQLite applies to find that affinity
+/// (https://www.sqlite.org/datatype3.html#determination_of_column_affinity). Date and time
+/// columns are recognised by name before that since SQLite stores them as text or numbers.
+pub struct SqliteDataSource {
+    pool: Pool<Sqlite>,
+    transaction: Mutex<Option<Transaction<'static, Sqlite>>>,
+}
+
+#[async_trait]
+impl DataSource for SqliteDataSource {
+    type ConnectParams = String;
+
+    fn new(connect_params: &Self::ConnectParams) -> Result<Self> {
+        task::block_on(async {
+            // SQLite only allows one writer at a time
+            let pool = SqlitePoolOptions::new()
+                .max_connections(1)
+                .connect(connect_params.as_str())
+                .await?;
+
+            Ok::<Self, anyhow::Error>(SqliteDataSource {
+                pool,
+                transaction: Mutex::new(None),
+            })
+        })
+    }
+
+    async fn check_data(&self, collection_name: &str, first: &Value) -> Result<()> {
+        check_relational_data::<Self>(self, collection_name, first).await
+    }
+
+    async fn insert_data(&self, collection_name: &str, collection: &[Value]) -> Result<()> {
+        insert_relational_data::<Self>(self, collection_name, collection).await
+    }
+
+    async fn begin(&self) -> Result<()> {
+        begin_relational_transaction::<Self>(self).await
+    }
+
+    async fn commit(&self) -> Result<()> {
+        commit_relational_transaction::<Self>(self).await
+    }
+
+    async fn rollback(&self) -> Result<()> {
+        rollback_relational_transaction::<Self>(self).await
+    }
+
+    async fn truncate(&self, collection_names: &[String]) -> Result<()> {
+        truncate_relational_data::<Self>(self, collection_names).await
+    }
+}
+
+impl SqlxDataSource for SqliteDataSource {
+    type DB = Sqlite;
+    type Connection = sqlx::sqlite::SqliteConnection;
+
+    const IDENTIFIER_QUOTE: char = '"';
+
+    fn get_pool(&self) -> Pool<Self::DB> {
+        Pool::clone(&self.pool)
+    }
+
+    fn get_multithread_pool(&self) -> Pool<Self::DB> {
+        Pool::clone(&self.pool)
+    }
+
+    fn get_transaction(&self) -> &Mutex<Option<Transaction<'static, Self::DB>>> {
+        &self.transaction
+    }
+
+    fn get_table_names_query(&self) -> &str {
+        r"SELECT name FROM sqlite_master
+            WHERE type = 'table' AND name NOT LIKE 'sqlite_%'"
+    }
+
+    fn get_primary_keys_query(&self) -> &str {
+        r"SELECT name, type FROM pragma_table_info(?) WHERE pk > 0 ORDER BY pk"
+    }
+
+    fn get_unique_keys_query(&self) -> &str {
+        r#"SELECT l.name, i.name FROM pragma_index_list(?) l JOIN pragma_index_info(l.name) i
+            WHERE l."unique" = 1 AND l.origin <> 'pk' AND l.partial = 0
+            ORDER BY l.name, i.seqno"#
+    }
+
+    fn get_foreign_keys_query(&self) -> &str {
+        // A foreign key without "to" columns references the primary key of the other table
+        r#"SELECT m.name, f."from", f."table",
+                coalesce(f."to", (SELECT p.name FROM pragma_table_info(f."table") p
+                    WHERE p.pk = f.seq + 1)),
+                cast(f.id AS text)
+            FROM sqlite_master m JOIN pragma_foreign_key_list(m.name) f
+            WHERE m.type = 'table'
+            ORDER BY m.name, f.id, f.seq"#
+    }
+
+    fn get_samples_query(
+        &self,
+        table_name: &str,
+        strategy: SampleStrategy,
+        rows: Option<usize>,
+    ) -> Result<String> {
+        // SQLite can't seed random(), so rows are sampled from the start of tables for imports
+        // to be deterministic
+        let query = match (strategy, rows) {
+            (_, None) => format!("SELECT * FROM \"{}\"", table_name),
+            (SampleStrategy::TableSample, Some(_)) => {
+                bail!("SQLite doesn't support TABLESAMPLE. Sample with 'random' or 'head' instead.")
+            }
+            (SampleStrategy::Random | SampleStrategy::Head, Some(rows)) => {
+                format!("SELECT * FROM \"{}\" LIMIT {}", table_name, rows)
+            }
+        };
+        Ok(query)
+    }
+
+    fn decode_to_content(&self, column_info: &ColumnInfo) -> Result<Content> {
+        decode_column(column_info)
+    }
+
+    fn get_columns_info_query(&self) -> &str {
+        r#"SELECT name, cid, "notnull" = 0 AND pk = 0, type FROM pragma_table_info(?)"#
+    }
+
+    fn get_truncate_queries(&self, table_names: &[String]) -> Vec<String> {
+        // SQLite has no TRUNCATE, and checking foreign keys at the end of the transaction lets
+        // the tables be emptied in any order
+        let mut queries = vec!["PRAGMA defer_foreign_keys = ON".to_string()];
+        queries.extend(
+            table_names
+                .iter()
+                .map(|table_name| format!("DELETE FROM {}", Self::quote_identifier(table_name))),
+        );
+        queries
+    }
+}
+
+/// Decodes the content of a column from its declared type, without the size in parentheses.
+pub(crate) fn decode_column(column_info: &ColumnInfo) -> Result<Content> {
+    let data_type = column_info.data_type.to_lowercase();
+    let data_type = data_type.as_str();
+
+    let content = match data_type {
+        "bool" | "boolean" => Content::Bool(BoolContent::default()),
+        "date" => Content::DateTime(DateTimeContent {
+            format: "%Y-%m-%d".to_string(),
+            type_: ChronoValueType::NaiveDate,
+            begin: None,
+            end: None,
+        }),
+        "time" => Content::DateTime(DateTimeContent {
+            format: "%H:%M:%S".to_string(),
+            type_: ChronoValueType::NaiveTime,
+            begin: None,
+            end: None,
+        }),
+        "datetime" | "timestamp" => Content::DateTime(DateTimeContent {
+            format: "%Y-%m-%d %H:%M:%S".to_string(),
+            type_: ChronoValueType::NaiveDateTime,
+            begin: None,
+            end: None,
+        }),
+        _ if data_type.contains("int") => {
+            Content::Number(NumberContent::I64(I64::Range(RangeStep::default())))
+        }
+        _ if data_type.contains("char")
+            || data_type.contains("clob")
+            || data_type.contains("text")
+            || data_type.contains("blob")
+            || data_type.is_empty() =>
+        {
+            let pattern = "[a-zA-Z0-9]{0, {}}".replace(
+                "{}",
+                &format!("{}", column_info.character_maximum_length.unwrap_or(1)),
+            );
+            Content::String(StringContent::Pattern(
+                RegexContent::pattern(pattern).context("pattern will always compile")?,
+            ))
+        }
+        // Both the REAL and NUMERIC affinities
+        _ => Content::Number(NumberContent::F64(F64::Range(RangeStep::default()))),
+    };
+
+    Ok(content)
+}
+
+impl TryFrom<SqliteRow> for ColumnInfo {
+    type Error = anyhow::Error;
+
+    fn try_from(row: SqliteRow) -> Result<Self, Self::Error> {
+        let data_type = row.try_get::<String, usize>(3)?;
+
+        Ok(ColumnInfo {
+            column_name: row.try_get::<String, usize>(0)?,
+            ordinal_position: row.try_get::<i32, usize>(1)? + 1,
+            is_nullable: row.try_get::<bool, usize>(2)?,
+            character_maximum_length: extract_column_char_max_len(&data_type),
+            data_type: data_type
+                .split('(')
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .to_string(),
+            is_custom_type: false,
+            column_default: None,
+            enum_labels: Vec::new(),
+            checks: Vec::new(),
+            is_unsigned: false,
+            is_generated: false,
+        })
+    }
+}
+
+/// Extracts a column's max character length from the size in its declared type, e.g.
+/// `VARCHAR(255)`. SQLite itself does not enforce it.
+fn extract_column_char_max_len(data_type: &str) -> Option<i32> {
+    let start = data_type.find('(')?;
+    let end = data_type.find(')')?;
+    data_type.get(start + 1..end)?.trim().parse().ok()
+}
+
+impl TryFrom<SqliteRow> for PrimaryKey {
+    type Error = anyhow::Error;
+
+    fn try_from(row: SqliteRow) -> Result<Self, Self::Error> {
+        Ok(PrimaryKey {
+            column_name: row.try_get(0)?,
+            type_name: row.try_get(1)?,
+        })
+    }
+}
+
+impl TryFrom<SqliteRow> for UniqueKey {
+    type Error = anyhow::Error;
+
+    fn try_from(row: SqliteRow) -> Result<Self, Self::Error> {
+        Ok(UniqueKey {
+            index_name: row.try_get(0)?,
+            column_name: row.try_get(1)?,
+        })
+    }
+}
+
+impl TryFrom<SqliteRow> for ForeignKey {
+    type Error = anyhow::Error;
+
+    fn try_from(row: SqliteRow) -> Result<Self, Self::Error> {
+        Ok(ForeignKey {
+            from_table: row.try_get(0)?,
+            from_column: row.try_get(1)?,
+            to_table: row.try_get(2)?,
+            to_column: row.try_get(3)?,
+            constraint_name: row.try_get(4)?,
+        })
+    }
+}
+
+impl TryFrom<SqliteRow> for ValueWrapper {
+    type Error = anyhow::Error;
+
+    fn try_from(row: SqliteRow) -> Result<Self, Self::Error> {
+        let mut kv = BTreeMap::new();
+
+        for column in row.columns() {
+            let value = try_match_value(&row, column).unwrap_or(Value::Null(()));
+            kv.insert(column.name().to_string(), value);
+        }
+
+        Ok(ValueWrapper(Value::Object(kv)))
+    }
+}
+
+fn try_match_value(row: &SqliteRow, column: &SqliteColumn) -> Result<Value> {
+    let raw = row.try_get_raw(column.ordinal())?;
+    if raw.is_null() {
+        return Ok(Value::Null(()));
+    }
+
+    // Values are decoded from the storage class they have, which can differ between the rows of
+    // a column, except for the declared types sqlx decodes from text or integers
+    let type_name = match column.type_info().name() {
+        declared @ ("BOOLEAN" | "DATE" | "TIME" | "DATETIME") => declared.to_string(),
+        _ => raw.type_info().name().to_string(),
+    };
+
+    let value = match type_name.as_str() {
+        "TEXT" => Value::String(row.try_get::<String, &str>(column.name())?),
+        "BLOB" => Value::String(
+            String::from_utf8_lossy(&row.try_get::<Vec<u8>, &str>(column.name())?).into_owned(),
+        ),
+        "BOOLEAN" => Value::Bool(row.try_get::<bool, &str>(column.name())?),
+        "INTEGER" => Value::Number(Number::from(row.try_get::<i64, &str>(column.name())?)),
+        "REAL" => Value::Number(Number::from(row.try_get::<f64, &str>(column.name())?)),
+        "DATE" => Value::String(format!(
+            "{}",
+            row.try_get::<chrono::NaiveDate, &str>(column.name())?
+        )),
+        "TIME" => Value::String(format!(
+            "{}",
+            row.try_get::<chrono::NaiveTime, &str>(column.name())?
+        )),
+        "DATETIME" => Value::String(format!(
+            "{}",
+            row.try_get::<chrono::NaiveDateTime, &str>(column.name())?
+        )),
+        _ => {
+            bail!(
+                "Could not convert value. Converter not implemented for {}",
+                column.type_info().name()
+            );
+        }
+    };
+
+    Ok(value)
+}
@@ -1,5 +1,6 @@
 use anyhow::Result;
 use indicatif::{ProgressBar, ProgressStyle};
+use rand::rngs::StdRng;
 use rand::SeedableRng;
 use std::collections::BTreeMap;
 use std::convert::TryFrom;
@@ -7,10 +8,43 @@ use synth_core::graph::json::synth_val_to_json;
 use synth_core::{Graph, Namespace, Value};
 use synth_gen::prelude::*;
 
+/// The number of values [`Sampler::sample_seeded_chunked`] aims to put in each chunk when
+/// streaming a sample to an export strategy.
+pub(crate) const DEFAULT_CHUNK_SIZE: usize = 10_000;
+
 pub(crate) struct Sampler {
     graph: Graph,
 }
 
+/// The number of values to sample from a namespace.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Target {
+    /// Generate the namespace until the number of values across all of its collections reaches
+    /// the given number.
+    Total(usize),
+    /// Generate each collection until its number of values reaches its size in `sizes`, or
+    /// `default` for the collections without one.
+    ///
+    /// A collection referred to by other collections is only ever cut between two generations of
+    /// the namespace, so that every value referring to it is generated, and may therefore end up
+    /// with a few more values than its size.
+    Collections {
+        sizes: BTreeMap<String, usize>,
+        default: usize,
+    },
+}
+
+impl Target {
+    fn of(&self, collection: &str) -> Option<usize> {
+        match self {
+            Self::Total(_) => None,
+            Self::Collections { sizes, default } => {
+                Some(sizes.get(collection).copied().unwrap_or(*default))
+            }
+        }
+    }
+}
+
 #[derive(Clone)]
 pub(crate) enum SamplerOutput {
     Namespace(Vec<(String, Value)>),
@@ -45,12 +79,53 @@ impl Sampler {
     pub(crate) fn sample_seeded(
         self,
         collection_name: Option<String>,
-        target: usize,
+        target: Target,
         seed: u64,
     ) -> Result<SamplerOutput> {
-        let rng = rand::rngs::StdRng::seed_from_u64(seed);
-        let sample_strategy = SampleStrategy::new(collection_name, target);
-        sample_strategy.sample(self.graph, rng)
+        self.sample_seeded_chunked(collection_name, target, seed, usize::MAX)
+            .next()
+            .expect("the first chunk is always sampled")
+    }
+
+    /// Like [`sample_seeded`](Self::sample_seeded), but returns the sample in chunks of roughly
+    /// `chunk_size` values instead of holding all of it in memory at once.
+    ///
+    /// A chunk is only ever cut between two complete generations of the namespace, so the values
+    /// referred to by `same_as` fields always appear in the same chunk or in an earlier one.
+    /// Collections that do not generate an array are only included in the last chunk.
+    pub(crate) fn sample_seeded_chunked(
+        self,
+        collection_name: Option<String>,
+        target: Target,
+        seed: u64,
+        chunk_size: usize,
+    ) -> SamplerChunks {
+        let ordered = self
+            .graph
+            .iter_ordered()
+            .map(|iter| iter.map(|s| s.to_string()).collect())
+            .unwrap_or_else(Vec::new);
+
+        // The length of the progress bar of a `Target::Collections` is only known once the
+        // collections have been generated once
+        let progress_bar = match target {
+            Target::Total(target) => sampler_progress_bar(target as u64),
+            Target::Collections { .. } => sampler_progress_bar(0),
+        };
+
+        SamplerChunks {
+            graph: self.graph,
+            rng: StdRng::seed_from_u64(seed),
+            collection_name,
+            ordered,
+            target,
+            chunk_size,
+            generated: 0,
+            collections: BTreeMap::new(),
+            non_arrays: BTreeMap::new(),
+            progress_bar,
+            done: false,
+        }
     }
 }
 
@@ -63,139 +138,196 @@ impl TryFrom<&Namespace> for Sampler {
     }
 }
 
-enum SampleStrategy {
-    Namespace(NamespaceSampleStrategy),
-    Collection(CollectionSampleStrategy),
+/// This `struct` is created by the
+/// [`sample_seeded_chunked`](Sampler::sample_seeded_chunked) method on [`Sampler`].
+pub(crate) struct SamplerChunks {
+    graph: Graph,
+    rng: StdRng,
+    collection_name: Option<String>,
+    ordered: Vec<String>,
+    target: Target,
+    chunk_size: usize,
+    generated: usize,
+    /// The number of values generated so far for each collection, only kept for a
+    /// [`Target::Collections`].
+    collections: BTreeMap<String, usize>,
+    non_arrays: BTreeMap<String, Value>,
+    progress_bar: ProgressBar,
+    done: bool,
 }
 
-impl SampleStrategy {
-    fn new(collection_name: Option<String>, target: usize) -> Self {
-        match collection_name {
-            None => SampleStrategy::Namespace(NamespaceSampleStrategy { target }),
-            Some(name) => SampleStrategy::Collection(CollectionSampleStrategy { name, target }),
+impl SamplerChunks {
+    fn is_finished(&self) -> bool {
+        match &self.target {
+            Target::Total(target) => self.generated >= *target,
+            Target::Collections { .. } => {
+                !self.collections.is_empty() && self.unfinished().next().is_none()
+            }
         }
     }
 
-    fn sample<R: Rng>(self, model: Graph, rng: R) -> Result<SamplerOutput> {
-        match self {
-            SampleStrategy::Namespace(nss) => Ok(SamplerOutput::Namespace(nss.sample(model, rng)?)),
-            SampleStrategy::Collection(css) => Ok(SamplerOutput::Collection(
-                css.name.clone(),
-                css.sample(model, rng)?,
-            )),
+    /// The collections of a [`Target::Collections`] which have not reached their size yet.
+    fn unfinished(&self) -> impl Iterator<Item = (&str, usize)> {
+        self.collections
+            .iter()
+            .filter_map(|(collection, generated)| {
+                let target = self.target.of(collection)?;
+                Some((collection.as_str(), target)).filter(|_| *generated < target)
+            })
+    }
+
+    fn update_progress_bar(&self) {
+        match &self.target {
+            Target::Total(_) => self.progress_bar.set_position(self.generated as u64),
+            Target::Collections { .. } => {
+                let (mut length, mut position) = (0, 0);
+                for (collection, generated) in &self.collections {
+                    let target = self.target.of(collection).unwrap_or_default();
+                    length += target;
+                    position += target.min(*generated);
+                }
+                self.progress_bar.set_length(length as u64);
+                self.progress_bar.set_position(position as u64);
+            }
         }
     }
-}
 
-struct NamespaceSampleStrategy {
-    target: usize,
-}
+    fn next_chunk(&mut self) -> Result<SamplerOutput> {
+        let mut chunk = BTreeMap::<String, Value>::new();
+        let mut chunk_generated = 0;
+        let mut exhausted = false;
 
-impl NamespaceSampleStrategy {
-    fn sample<R: Rng>(self, model: Graph, mut rng: R) -> Result<Vec<(String, Value)>> {
-        let mut generated = 0;
-        let mut out = BTreeMap::<String, Value>::new();
-        let progress_bar = sampler_progress_bar(self.target as u64);
+        while !self.is_finished() && chunk_generated < self.chunk_size {
+            // We populate `chunk` by walking through the collections in the generated
+            // namespace. We also keep track of the number of `Values` generated
+            // for the progress bar.
+            let round_start = self.generated;
+            let mut next = as_object(self.graph.complete(&mut self.rng)?)?;
 
-        let ordered: Vec<_> = model
-            .iter_ordered()
-            .map(|iter| iter.map(|s| s.to_string()).collect())
-            .unwrap_or_else(Vec::new);
+            if let Some(name) = &self.collection_name {
+                let collection_value = next.remove(name).ok_or_else(|| {
+                    anyhow!("generated namespace does not have a collection '{}'", name)
+                })?;
+                next = BTreeMap::from([(name.clone(), collection_value)]);
+            }
 
-        let mut model = model.aggregate();
+            for (collection, value) in next {
+                // The number of values still to generate for the collection, if it has a size
+                let remaining = match self.target.of(&collection) {
+                    Some(target) => {
+                        let so_far = self.collections.entry(collection.clone()).or_insert(0);
+                        match target.saturating_sub(*so_far) {
+                            0 => {
+                                // Finished collections stay in the chunks, without any value, so
+                                // that every chunk has the same collections
+                                if let Value::Array(_) = value {
+                                    chunk
+                                        .entry(collection)
+                                        .or_insert_with(|| Value::Array(vec![]));
+                                }
+                                continue;
+                            }
+                            remaining => Some(remaining),
+                        }
+                    }
+                    None => None,
+                };
 
-        while generated < self.target {
-            // We populate `out` by walking through the collections in the generated
-            // namespace. We also keep track of the number of `Values` generated
-            // for the progress bar.
-            let round_start = generated;
-            let next = model.complete(&mut rng)?;
-            as_object(next)?
-                .into_iter()
-                .for_each(|(collection, value)| match value {
-                    Value::Array(elements) => {
-                        generated += elements.len();
-
-                        let entry = out
-                            .entry(collection)
+                let generated = match value {
+                    Value::Array(mut elements) => {
+                        if let Some(remaining) = remaining {
+                            if !self.graph.is_referred_to(&collection) {
+                                elements.truncate(remaining);
+                            }
+                        }
+
+                        let generated = elements.len();
+                        chunk_generated += generated;
+
+                        let entry = chunk
+                            .entry(collection.clone())
                             .or_insert_with(|| Value::Array(vec![]));
 
                         if let Value::Array(to_extend) = entry {
                             to_extend.extend(elements);
                         }
+                        generated
                     }
                     non_array => {
-                        generated += 1;
-                        //out[&collection] = non_array;
-                        out.insert(collection, non_array);
+                        self.non_arrays.insert(collection.clone(), non_array);
+                        1
+                    }
+                };
+
+                self.generated += generated;
+                if let Some(remaining) = remaining {
+                    *self.collections.get_mut(&collection).unwrap() += generated;
+                    // The values referring to the collection from now on refer to the values it
+                    // has just generated
+                    if generated >= remaining {
+                        self.graph.freeze(&collection);
                     }
-                });
-            progress_bar.set_position(generated as u64);
-            if round_start == generated {
-                warn!("could not generate {} values: try modifying the schema to generate more data instead of the --size flag", self.target);
+                }
+            }
+
+            self.update_progress_bar();
+            if round_start == self.generated {
+                match (&self.target, &self.collection_name) {
+                    (Target::Total(target), Some(name)) => warn!("could not generate {} values for collection {}: try modifying the schema to generate more instead of using the --size flag", target, name),
+                    (Target::Total(target), None) => warn!("could not generate {} values: try modifying the schema to generate more data instead of the --size flag", target),
+                    (Target::Collections { .. }, _) => {
+                        for (collection, target) in self.unfinished() {
+                            warn!("could not generate {} values for collection {}: try modifying the schema to generate more instead of using the --size flag", target, collection);
+                        }
+                    }
+                }
+                exhausted = true;
                 break;
             }
         }
 
-        progress_bar.finish_and_clear();
+        if exhausted || self.is_finished() {
+            self.done = true;
+            chunk.append(&mut self.non_arrays);
+            self.progress_bar.finish_and_clear();
+        }
 
-        let mut ordered_out = Vec::new();
+        Ok(match &self.collection_name {
+            Some(name) => SamplerOutput::Collection(
+                name.clone(),
+                chunk.remove(name).unwrap_or_else(|| Value::Array(vec![])),
+            ),
+            None => {
+                let mut ordered_chunk = Vec::new();
 
-        for name in ordered {
-            let value = out.remove(&name).unwrap();
-            ordered_out.push((name, value));
-        }
+                for name in &self.ordered {
+                    if let Some(value) = chunk.remove(name) {
+                        ordered_chunk.push((name.clone(), value));
+                    }
+                }
 
-        ordered_out.extend(out.into_iter());
+                ordered_chunk.extend(chunk.into_iter());
 
-        Ok(ordered_out)
+                SamplerOutput::Namespace(ordered_chunk)
+            }
+        })
     }
 }
 
-struct CollectionSampleStrategy {
-    name: String,
-    target: usize,
-}
+impl Iterator for SamplerChunks {
+    type Item = Result<SamplerOutput>;
 
-impl CollectionSampleStrategy {
-    fn sample<R: Rng>(self, model: Graph, mut rng: R) -> Result<Value> {
-        let mut out = Value::Array(vec![]);
-        let mut generated = 0;
-        let progress_bar = sampler_progress_bar(self.target as u64);
-
-        let mut model = model.aggregate();
-
-        while generated < self.target {
-            let round_start = generated;
-            let next = model.complete(&mut rng)?;
-            let collection_value = as_object(next)?.remove(&self.name).ok_or_else(|| {
-                anyhow!(
-                    "generated namespace does not have a collection '{}'",
-                    self.name
-                )
-            })?;
-            match collection_value {
-                Value::Array(vec) => {
-                    generated += vec.len();
-                    if let Value::Array(to_extend) = &mut out {
-                        to_extend.extend(vec);
-                    }
-                }
-                non_array => {
-                    generated += 1;
-                    out = non_array;
-                }
-            }
-            progress_bar.set_position(generated as u64);
-            if round_start == generated {
-                warn!("could not generate {} values for collection {}: try modifying the schema to generate more instead of using the --size flag", self.target, self.name);
-                break;
-            }
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
         }
 
-        progress_bar.finish_and_clear();
-
-        Ok(out)
+        let chunk = self.next_chunk();
+        if chunk.is_err() {
+            self.done = true;
+            self.progress_bar.finish_and_clear();
+        }
+        Some(chunk)
     }
 }
 
@@ -208,3 +340,328 @@ fn as_object(sample: Value) -> Result<BTreeMap<String, Value>> {
         )),
     }
 }
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    fn namespace() -> Namespace {
+        serde_json::from_value::<synth_core::schema::Content>(serde_json::json!({
+            "type": "object",
+            "users": {
+                "type": "array",
+                "length": 3,
+                "content": {
+                    "type": "object",
+                    "id": {
+                        "type": "number",
+                        "id": {}
+                    }
+                }
+            },
+            "posts": {
+                "type": "array",
+                "length": 5,
+                "content": {
+                    "type": "object",
+                    "user_id": {
+                        "type": "same_as",
+                        "ref": "users.content.id"
+                    }
+                }
+            }
+        }))
+        .unwrap()
+        .into_namespace()
+        .unwrap()
+    }
+
+    fn collection<'a>(output: &'a SamplerOutput, name: &str) -> &'a Vec<Value> {
+        match output {
+            SamplerOutput::Namespace(key_values) => {
+                match &key_values.iter().find(|(key, _)| key == name).unwrap().1 {
+                    Value::Array(elements) => elements,
+                    _ => panic!("collection {} is not an array", name),
+                }
+            }
+            SamplerOutput::Collection(_, _) => panic!("expected a namespace"),
+        }
+    }
+
+    #[test]
+    fn test_sample_seeded_chunked() {
+        let ns = namespace();
+
+        let whole = Sampler::try_from(&ns)
+            .unwrap()
+            .sample_seeded(None, Target::Total(200), 7)
+            .unwrap();
+        let chunks = Sampler::try_from(&ns)
+            .unwrap()
+            .sample_seeded_chunked(None, Target::Total(200), 7, 20)
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+
+        assert!(chunks.len() > 1);
+
+        let mut users = Vec::new();
+        let mut posts = Vec::new();
+
+        for chunk in &chunks {
+            // Collections are ordered so that references are always generated first
+            match chunk {
+                SamplerOutput::Namespace(key_values) => {
+                    let names: Vec<_> = key_values.iter().map(|(name, _)| name.as_str()).collect();
+                    assert_eq!(names, vec!["users", "posts"]);
+                }
+                SamplerOutput::Collection(_, _) => panic!("expected a namespace"),
+            }
+
+            users.extend(collection(chunk, "users").iter().cloned());
+            assert!(collection(chunk, "users").len() < 20 + 3);
+
+            for post in collection(chunk, "posts") {
+                let user_id = post.as_object().unwrap().get("user_id").unwrap();
+                assert!(users
+                    .iter()
+                    .any(|user| user.as_object().unwrap().get("id").unwrap() == user_id));
+                posts.push(post.clone());
+            }
+        }
+
+        assert_eq!(&users, collection(&whole, "users"));
+        assert_eq!(&posts, collection(&whole, "posts"));
+    }
+
+    #[test]
+    fn test_sample_seeded_collections() {
+        let ns = namespace();
+
+        let target = Target::Collections {
+            sizes: BTreeMap::from([("users".to_string(), 6), ("posts".to_string(), 52)]),
+            default: 1,
+        };
+        let chunks = Sampler::try_from(&ns)
+            .unwrap()
+            .sample_seeded_chunked(None, target, 7, 20)
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+
+        let mut users = Vec::new();
+        let mut posts = Vec::new();
+
+        for chunk in &chunks {
+            users.extend(collection(chunk, "users").iter().cloned());
+            posts.extend(collection(chunk, "posts").iter().cloned());
+        }
+
+        // `users` is referred to by `posts`, so is cut between two generations
+        assert_eq!(users.len(), 6);
+        assert_eq!(posts.len(), 52);
+
+        for post in &posts {
+            let user_id = post.as_object().unwrap().get("user_id").unwrap();
+            assert!(users
+                .iter()
+                .any(|user| user.as_object().unwrap().get("id").unwrap() == user_id));
+        }
+    }
+
+    #[test]
+    fn test_sample_seeded_cardinality() {
+        let ns = serde_json::from_value::<synth_core::schema::Content>(serde_json::json!({
+            "type": "object",
+            "users": {
+                "type": "array",
+                "length": 4,
+                "content": {
+                    "type": "object",
+                    "id": {
+                        "type": "number",
+                        "id": {}
+                    },
+                    "weight": {
+                        "type": "number",
+                        "range": {
+                            "low": 1,
+                            "high": 10,
+                            "step": 1
+                        }
+                    }
+                }
+            },
+            "posts": {
+                "type": "array",
+                "length": 1,
+                "parent": "users.content.id",
+                "content": {
+                    "type": "object",
+                    "user_id": "@users.content.id"
+                }
+            },
+            "likes": {
+                "type": "array",
+                "length": 10,
+                "content": {
+                    "type": "object",
+                    "uniform": {
+                        "type": "same_as",
+                        "ref": "users.content.id",
+                        "sample": "uniform"
+                    },
+                    "zipf": {
+                        "type": "same_as",
+                        "ref": "users.content.id",
+                        "sample": {
+                            "zipf": {
+                                "exponent": 1.5
+                            }
+                        }
+                    },
+                    "weighted": {
+                        "type": "same_as",
+                        "ref": "users.content.id",
+                        "sample": {
+                            "weighted": "users.content.weight"
+                        }
+                    }
+                }
+            }
+        }))
+        .unwrap()
+        .into_namespace()
+        .unwrap();
+
+        let output = Sampler::try_from(&ns)
+            .unwrap()
+            .sample_seeded(None, Target::Total(3), 7)
+            .unwrap();
+
+        let users = collection(&output, "users");
+        let ids: Vec<_> = users
+            .iter()
+            .map(|user| user.as_object().unwrap().get("id").unwrap())
+            .collect();
+
+        // Exactly one post per user
+        let posts = collection(&output, "posts");
+        assert_eq!(posts.len(), users.len());
+        for (post, id) in posts.iter().zip(&ids) {
+            assert_eq!(post.as_object().unwrap().get("user_id").unwrap(), *id);
+        }
+
+        for like in collection(&output, "likes") {
+            for field in ["uniform", "zipf", "weighted"] {
+                let user_id = like.as_object().unwrap().get(field).unwrap();
+                assert!(ids.contains(&user_id));
+            }
+        }
+    }
+
+    #[test]
+    fn test_sample_seeded_lookup() {
+        let ns = serde_json::from_value::<synth_core::schema::Content>(serde_json::json!({
+            "type": "object",
+            "users": {
+                "type": "array",
+                "length": 4,
+                "content": {
+                    "type": "object",
+                    "id": {
+                        "type": "number",
+                        "id": {}
+                    },
+                    "email": {
+                        "type": "string",
+                        "faker": {
+                            "generator": "safe_email"
+                        }
+                    }
+                }
+            },
+            "orders": {
+                "type": "array",
+                "length": 10,
+                "content": {
+                    "type": "object",
+                    "id": {
+                        "type": "number",
+                        "id": {}
+                    },
+                    "user": {
+                        "type": "lookup",
+                        "ref": "users.content",
+                        "fields": {
+                            "user_id": "id",
+                            "user_email": "email"
+                        },
+                        "sample": "uniform"
+                    }
+                }
+            }
+        }))
+        .unwrap()
+        .into_namespace()
+        .unwrap();
+
+        let output = Sampler::try_from(&ns)
+            .unwrap()
+            .sample_seeded(None, Target::Total(30), 7)
+            .unwrap();
+
+        let users = collection(&output, "users");
+        let orders = collection(&output, "orders");
+        assert!(!orders.is_empty());
+
+        for order in orders {
+            let order = order.as_object().unwrap();
+            assert!(!order.contains_key("user"));
+            assert!(users.iter().any(|user| {
+                let user = user.as_object().unwrap();
+                user.get("id") == order.get("user_id")
+                    && user.get("email") == order.get("user_email")
+            }));
+        }
+    }
+
+    #[test]
+    fn test_lookup_field_collision() {
+        let ns = serde_json::from_value::<synth_core::schema::Content>(serde_json::json!({
+            "type": "object",
+            "users": {
+                "type": "array",
+                "length": 4,
+                "content": {
+                    "type": "object",
+                    "id": {
+                        "type": "number",
+                        "id": {}
+                    }
+                }
+            },
+            "orders": {
+                "type": "array",
+                "length": 10,
+                "content": {
+                    "type": "object",
+                    "user_id": {
+                        "type": "number",
+                        "id": {}
+                    },
+                    "user": {
+                        "type": "lookup",
+                        "ref": "users.content",
+                        "fields": {
+                            "user_id": "id"
+                        }
+                    }
+                }
+            }
+        }))
+        .unwrap()
+        .into_namespace()
+        .unwrap();
+
+        assert!(Sampler::try_from(&ns).is_err());
+    }
+}
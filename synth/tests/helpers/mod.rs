@@ -9,8 +9,9 @@ pub async fn generate(namespace: &str) -> Result<String> {
         random: false,
         schema: None,
         seed: Some(5),
-        size: 10,
+        size: 10.into(),
         to: "json:".to_string(),
+        truncate: false,
     }))
     .await
 }